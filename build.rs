@@ -1,4 +1,12 @@
-#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+#[cfg(all(
+    feature = "ffi",
+    any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    )
+))]
 fn build_ffi() {
     #[cfg(target_os = "macos")]
     let platform_file = "ffi/mac_os.cc";
@@ -6,13 +14,105 @@ fn build_ffi() {
     #[cfg(target_os = "freebsd")]
     let platform_file = "ffi/freebsd.cc";
 
+    #[cfg(target_os = "openbsd")]
+    let platform_file = "ffi/openbsd.cc";
+
+    #[cfg(target_os = "netbsd")]
+    let platform_file = "ffi/netbsd.cc";
+
     cxx_build::bridge("src/lib.rs")
         .flag_if_supported("-std=c++17")
         .file(platform_file)
         .compile("libpromptr");
+
+    // For the Wi-Fi lookup
+    #[cfg(target_os = "macos")]
+    {
+        println!("cargo:rustc-link-lib=framework=CoreFoundation");
+        println!("cargo:rustc-link-lib=framework=SystemConfiguration");
+    }
+}
+
+/// Records details that show up in `promptr version --verbose`
+fn build_info() {
+    if let Ok(target) = std::env::var("TARGET") {
+        println!("cargo:rustc-env=PROMPTR_TARGET={}", target);
+    }
+
+    let commit = std::process::Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=PROMPTR_GIT_COMMIT={}", commit);
+    }
+}
+
+/// Pulls the one line summary of each segment out of the first paragraph of its module docs so
+/// `registry` doesn't need a second copy.  `The `Clock` segment shows the time` becomes `Shows
+/// the time`.
+fn segment_docs() {
+    let mut docs = String::new();
+
+    let mut paths = std::fs::read_dir("src/segment")
+        .expect("Couldn't list the segment modules")
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let source = std::fs::read_to_string(&path).expect("Couldn't read a segment module");
+
+        let summary = source
+            .lines()
+            .map_while(|line| line.strip_prefix("//!"))
+            .map(str::trim)
+            .take_while(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // Modules without a segment of their own don't get a summary
+        let summary = match summary
+            .strip_prefix("The `")
+            .and_then(|rest| rest.split_once("` segment "))
+        {
+            Some((_, summary)) => summary,
+            None => continue,
+        };
+
+        let mut chars = summary.chars();
+        let summary = match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+            None => continue,
+        };
+
+        let module = path.file_stem().unwrap().to_string_lossy();
+        docs.push_str(&format!(
+            "#[allow(non_upper_case_globals)]\npub const {}: &str = {:?};\n",
+            module, summary
+        ));
+    }
+
+    let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("segment_docs.rs");
+    std::fs::write(out, docs).expect("Couldn't write the segment summaries");
 }
 
 fn main() {
-    #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+    build_info();
+    segment_docs();
+
+    #[cfg(all(
+        feature = "ffi",
+        any(
+            target_os = "macos",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        )
+    ))]
     build_ffi();
 }
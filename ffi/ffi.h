@@ -3,3 +3,5 @@
 #include "rust/cxx.h"
 
 rust::String get_process_name(int64_t pid);
+int64_t get_parent_pid(int64_t pid);
+rust::String get_wifi_ssid(rust::Str interface);
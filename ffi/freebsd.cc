@@ -1,10 +1,19 @@
 #include <sys/param.h>
+#include <sys/ioctl.h>
 #include <sys/queue.h>
 #include <sys/socket.h>
+#include <sys/sockio.h>
 #include <sys/sysctl.h>
 
+#include <net/if.h>
+#include <net/if_dl.h>
+#include <net80211/ieee80211.h>
+#include <net80211/ieee80211_ioctl.h>
+
+#include <ifaddrs.h>
 #include <libprocstat.h>
 #include <stdio.h>
+#include <string.h>
 #include <strings.h>
 #include <unistd.h>
 
@@ -45,3 +54,76 @@ rust::String get_process_name(int64_t pid) {
 
     return ret_str;
 }
+
+int64_t get_parent_pid(int64_t pid) {
+    if (pid > std::numeric_limits<pid_t>::max()) {
+        return -1;
+    }
+
+    struct procstat *prstat = procstat_open_sysctl();
+    unsigned int count = 0;
+    struct kinfo_proc *kp = procstat_getprocs(prstat, KERN_PROC_PID, pid, &count);
+
+    int64_t ppid = -1;
+
+    if (count > 0) {
+        ppid = kp->ki_ppid;
+        procstat_freeprocs(prstat, kp);
+    }
+
+    procstat_close(prstat);
+
+    return ppid;
+}
+
+static std::string ssid_of(int sock, const char *name) {
+    char ssid[IEEE80211_NWID_LEN + 1];
+    bzero(ssid, sizeof(ssid));
+
+    struct ieee80211req ireq;
+    bzero(&ireq, sizeof(ireq));
+    strlcpy(ireq.i_name, name, sizeof(ireq.i_name));
+    ireq.i_type = IEEE80211_IOC_SSID;
+    // The network we're associated with rather than one from the configured list
+    ireq.i_val = -1;
+    ireq.i_data = ssid;
+    ireq.i_len = IEEE80211_NWID_LEN;
+
+    // Not a wireless interface
+    if (ioctl(sock, SIOCG80211, &ireq) < 0 || ireq.i_len <= 0) {
+        return std::string();
+    }
+
+    return std::string(ssid, ireq.i_len);
+}
+
+rust::String get_wifi_ssid(rust::Str interface) {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        return std::string();
+    }
+
+    std::string ssid;
+
+    if (!interface.empty()) {
+        ssid = ssid_of(sock, std::string(interface).c_str());
+    } else {
+        // Try each interface in turn, only wireless ones will answer
+        struct ifaddrs *ifaddrs = NULL;
+
+        if (getifaddrs(&ifaddrs) == 0) {
+            // Every interface has exactly one AF_LINK address
+            for (struct ifaddrs *ifa = ifaddrs; ifa != NULL && ssid.empty(); ifa = ifa->ifa_next) {
+                if (ifa->ifa_addr != NULL && ifa->ifa_addr->sa_family == AF_LINK && (ifa->ifa_flags & IFF_UP)) {
+                    ssid = ssid_of(sock, ifa->ifa_name);
+                }
+            }
+
+            freeifaddrs(ifaddrs);
+        }
+    }
+
+    close(sock);
+
+    return ssid;
+}
@@ -1,4 +1,7 @@
+#include <CoreFoundation/CoreFoundation.h>
+#include <SystemConfiguration/SystemConfiguration.h>
 #include <libproc.h>
+#include <sys/proc_info.h>
 #include <strings.h>
 #include <unistd.h>
 
@@ -26,3 +29,90 @@ rust::String get_process_name(int64_t pid) {
         return std::string();
     }
 }
+
+int64_t get_parent_pid(int64_t pid) {
+    if (pid > std::numeric_limits<pid_t>::max()) {
+        return -1;
+    }
+
+    struct proc_bsdinfo info;
+    bzero(&info, sizeof(info));
+
+    int len = proc_pidinfo(pid, PROC_PIDTBSDINFO, 0, &info, PROC_PIDTBSDINFO_SIZE);
+
+    if (len == PROC_PIDTBSDINFO_SIZE) {
+        return info.pbi_ppid;
+    } else {
+        return -1;
+    }
+}
+
+static std::string from_cf_string(CFStringRef str) {
+    char buf[BUF_LEN];
+    bzero(buf, BUF_LEN);
+
+    if (str != NULL && CFStringGetCString(str, buf, BUF_LEN, kCFStringEncodingUTF8)) {
+        return std::string(buf);
+    } else {
+        return std::string();
+    }
+}
+
+// The BSD name (e.g. en0) of the first Wi-Fi interface
+static std::string wifi_interface() {
+    std::string name;
+
+    CFArrayRef interfaces = SCNetworkInterfaceCopyAll();
+    if (interfaces == NULL) {
+        return name;
+    }
+
+    for (CFIndex i = 0; i < CFArrayGetCount(interfaces) && name.empty(); i++) {
+        SCNetworkInterfaceRef interface = (SCNetworkInterfaceRef)CFArrayGetValueAtIndex(interfaces, i);
+        CFStringRef type = SCNetworkInterfaceGetInterfaceType(interface);
+
+        if (type != NULL && CFEqual(type, kSCNetworkInterfaceTypeIEEE80211)) {
+            name = from_cf_string(SCNetworkInterfaceGetBSDName(interface));
+        }
+    }
+
+    CFRelease(interfaces);
+
+    return name;
+}
+
+// Newer versions of macOS leave the SSID out unless we've been granted location access, in which
+// case there's nothing to show
+rust::String get_wifi_ssid(rust::Str interface) {
+    std::string name = interface.empty() ? wifi_interface() : std::string(interface);
+    if (name.empty()) {
+        return std::string();
+    }
+
+    SCDynamicStoreRef store = SCDynamicStoreCreate(NULL, CFSTR("promptr"), NULL, NULL);
+    if (store == NULL) {
+        return std::string();
+    }
+
+    CFStringRef key = CFStringCreateWithFormat(NULL, NULL, CFSTR("State:/Network/Interface/%s/AirPort"), name.c_str());
+    CFPropertyListRef airport = SCDynamicStoreCopyValue(store, key);
+
+    std::string ssid;
+
+    if (airport != NULL) {
+        if (CFGetTypeID(airport) == CFDictionaryGetTypeID()) {
+            CFTypeRef value = CFDictionaryGetValue((CFDictionaryRef)airport, CFSTR("SSID_STR"));
+
+            if (value != NULL && CFGetTypeID(value) == CFStringGetTypeID()) {
+                ssid = from_cf_string((CFStringRef)value);
+            }
+        }
+
+        CFRelease(airport);
+    }
+
+    CFRelease(key);
+    CFRelease(store);
+
+    return ssid;
+}
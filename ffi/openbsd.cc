@@ -0,0 +1,107 @@
+#include <sys/types.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <sys/sockio.h>
+#include <sys/sysctl.h>
+
+#include <net/if.h>
+#include <net/if_dl.h>
+#include <net80211/ieee80211.h>
+#include <net80211/ieee80211_ioctl.h>
+
+#include <ifaddrs.h>
+#include <string.h>
+#include <strings.h>
+#include <unistd.h>
+
+#include <limits>
+
+#include "rust/cxx.h"
+
+rust::String get_process_name(int64_t pid) {
+    if (pid > std::numeric_limits<pid_t>::max()) {
+        // Should probably print an error or something
+        return std::string();
+    }
+
+    struct kinfo_proc kp;
+    size_t len = sizeof(kp);
+    bzero(&kp, len);
+
+    int mib[6] = { CTL_KERN, KERN_PROC, KERN_PROC_PID, (int)pid, sizeof(struct kinfo_proc), 1 };
+
+    // Our parent process died?
+    if (sysctl(mib, 6, &kp, &len, NULL, 0) == -1 || len == 0) {
+        return std::string();
+    }
+
+    return std::string(kp.p_comm);
+}
+
+int64_t get_parent_pid(int64_t pid) {
+    if (pid > std::numeric_limits<pid_t>::max()) {
+        return -1;
+    }
+
+    struct kinfo_proc kp;
+    size_t len = sizeof(kp);
+    bzero(&kp, len);
+
+    int mib[6] = { CTL_KERN, KERN_PROC, KERN_PROC_PID, (int)pid, sizeof(struct kinfo_proc), 1 };
+
+    if (sysctl(mib, 6, &kp, &len, NULL, 0) == -1 || len == 0) {
+        return -1;
+    }
+
+    return kp.p_ppid;
+}
+
+static std::string ssid_of(int sock, const char *name) {
+    struct ieee80211_nwid nwid;
+    bzero(&nwid, sizeof(nwid));
+
+    struct ifreq ifr;
+    bzero(&ifr, sizeof(ifr));
+    strlcpy(ifr.ifr_name, name, sizeof(ifr.ifr_name));
+    ifr.ifr_data = (caddr_t)&nwid;
+
+    // Not a wireless interface
+    if (ioctl(sock, SIOCG80211NWID, (caddr_t)&ifr) < 0) {
+        return std::string();
+    }
+
+    size_t len = nwid.i_len > IEEE80211_NWID_LEN ? IEEE80211_NWID_LEN : nwid.i_len;
+
+    return std::string((const char *)nwid.i_nwid, len);
+}
+
+rust::String get_wifi_ssid(rust::Str interface) {
+    int sock = socket(AF_INET, SOCK_DGRAM, 0);
+    if (sock < 0) {
+        return std::string();
+    }
+
+    std::string ssid;
+
+    if (!interface.empty()) {
+        ssid = ssid_of(sock, std::string(interface).c_str());
+    } else {
+        // Try each interface in turn, only wireless ones will answer
+        struct ifaddrs *ifaddrs = NULL;
+
+        if (getifaddrs(&ifaddrs) == 0) {
+            // Every interface has exactly one AF_LINK address
+            for (struct ifaddrs *ifa = ifaddrs; ifa != NULL && ssid.empty(); ifa = ifa->ifa_next) {
+                if (ifa->ifa_addr != NULL && ifa->ifa_addr->sa_family == AF_LINK && (ifa->ifa_flags & IFF_UP)) {
+                    ssid = ssid_of(sock, ifa->ifa_name);
+                }
+            }
+
+            freeifaddrs(ifaddrs);
+        }
+    }
+
+    close(sock);
+
+    return ssid;
+}
@@ -0,0 +1,218 @@
+//! A loadable `bash` builtin that renders the prompt inside the shell.  Running `promptr prompt`
+//! from `PROMPT_COMMAND` costs a fork and an exec every time the prompt is drawn, the builtin
+//! skips both.
+//!
+//! Build it and point `PROMPTR_BUILTIN` at the library before loading `promptr`, the loader
+//! enables the builtin and falls back to the `promptr` binary if that doesn't work out:
+//!
+//! ```sh
+//! cargo build --release -p promptr-bash
+//! export PROMPTR_BUILTIN=/path/to/target/release/libpromptr_bash.so
+//! source <(promptr load)
+//! ```
+//!
+//! The builtin is called `promptr_render` so it doesn't shadow the `promptr` binary.  It takes
+//! one option, `-v VAR`, which assigns the prompt to `VAR` instead of printing it.
+//!
+//! `bash` has to be built with support for loadable builtins, which is the default, and the
+//! library has to be built with the same `promptr` version as the binary that generated the
+//! loader.
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::io::Write;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::{env, ptr};
+
+use libpromptr::ansi::EscapeStyle;
+use libpromptr::prompt;
+use libpromptr::shell::{active_marker, Shell, ACTIVE_VAR};
+
+/// A word in the argument list, see `command.h` in the `bash` sources
+#[repr(C)]
+pub struct WordDesc {
+    word: *mut c_char,
+    flags: c_int,
+}
+
+/// The arguments a builtin is called with
+#[repr(C)]
+pub struct WordList {
+    next: *mut WordList,
+    word: *mut WordDesc,
+}
+
+/// What `enable -f` looks for, named after the builtin with a `_struct` suffix.  See `builtins.h`
+/// in the `bash` sources.
+#[repr(C)]
+pub struct Builtin {
+    name: *const c_char,
+    function: Option<unsafe extern "C" fn(*mut WordList) -> c_int>,
+    flags: c_int,
+    long_doc: *const *const c_char,
+    short_doc: *const c_char,
+    handle: *mut c_char,
+}
+
+/// A line of help text, the list ends with a null pointer
+#[repr(transparent)]
+pub struct DocLine(*const c_char);
+
+// Only ever points at string literals
+unsafe impl Sync for DocLine {}
+
+const BUILTIN_ENABLED: c_int = 0x01;
+
+const EXECUTION_SUCCESS: c_int = 0;
+const EXECUTION_FAILURE: c_int = 1;
+const EX_USAGE: c_int = 258;
+
+static LONG_DOC: [DocLine; 5] = [
+    DocLine(c"Render the prompt without starting the promptr binary.".as_ptr() as _),
+    DocLine(c"".as_ptr() as _),
+    DocLine(c"Prints the prompt the same way `promptr prompt` would.  With -v the".as_ptr() as _),
+    DocLine(c"prompt is assigned to VAR instead.".as_ptr() as _),
+    DocLine(ptr::null()),
+];
+
+/// Read by `bash` when the builtin is enabled, `bash` fills in `handle` itself
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static mut promptr_render_struct: Builtin = Builtin {
+    name: c"promptr_render".as_ptr() as _,
+    function: Some(promptr_render),
+    flags: BUILTIN_ENABLED,
+    long_doc: &LONG_DOC as *const DocLine as *const *const c_char,
+    short_doc: c"promptr_render [-v var]".as_ptr() as _,
+    handle: ptr::null_mut(),
+};
+
+type BindVariable = unsafe extern "C" fn(*const c_char, *mut c_char, c_int) -> *mut libc::c_void;
+
+/// Collects the arguments `bash` handed us
+///
+/// ## Safety
+///
+/// `list` has to be null or a well formed list from `bash`
+unsafe fn words(mut list: *const WordList) -> Vec<String> {
+    let mut words = vec![];
+
+    while !list.is_null() {
+        let word = (*list).word;
+        if !word.is_null() && !(*word).word.is_null() {
+            words.push(CStr::from_ptr((*word).word).to_string_lossy().into_owned());
+        }
+        list = (*list).next;
+    }
+
+    words
+}
+
+/// Returns the environment `bash` would hand a command it started right now, including the
+/// assignments in front of the builtin.  The process environment doesn't always keep up.
+fn export_env() -> HashMap<String, String> {
+    // Looked up at runtime since the symbols only exist inside of bash
+    let (make, env) = unsafe {
+        (
+            libc::dlsym(libc::RTLD_DEFAULT, c"maybe_make_export_env".as_ptr() as _),
+            libc::dlsym(libc::RTLD_DEFAULT, c"export_env".as_ptr() as _),
+        )
+    };
+
+    if make.is_null() || env.is_null() {
+        return env::vars().collect();
+    }
+
+    let make: unsafe extern "C" fn() = unsafe { std::mem::transmute(make) };
+    let mut vars = HashMap::new();
+
+    unsafe {
+        make();
+
+        let mut entry = *(env as *const *const *const c_char);
+        while !entry.is_null() && !(*entry).is_null() {
+            let var = CStr::from_ptr(*entry).to_string_lossy();
+            if let Some((name, value)) = var.split_once('=') {
+                vars.insert(name.to_string(), value.to_string());
+            }
+            entry = entry.add(1);
+        }
+    }
+
+    vars
+}
+
+/// Assigns `value` to the shell variable `name` with `bash`'s own `bind_variable`
+fn bind_variable(name: &str, value: &str) -> libpromptr::Result<()> {
+    let name = std::ffi::CString::new(name)?;
+    let value = std::ffi::CString::new(value)?;
+
+    let symbol = unsafe { libc::dlsym(libc::RTLD_DEFAULT, c"bind_variable".as_ptr() as _) };
+    if symbol.is_null() {
+        anyhow::bail!("couldn't find bind_variable, is this bash?");
+    }
+
+    // bash makes its own copy of the value
+    let bind: BindVariable = unsafe { std::mem::transmute(symbol) };
+    match unsafe { bind(name.as_ptr(), value.as_ptr() as *mut c_char, 0) }.is_null() {
+        true => anyhow::bail!("couldn't assign {:?}", name),
+        false => Ok(()),
+    }
+}
+
+fn run(args: Vec<String>) -> libpromptr::Result<c_int> {
+    let var = match args.as_slice() {
+        [] => None,
+        [flag, var] if flag == "-v" => Some(var.clone()),
+        _ => {
+            eprintln!("promptr_render: usage: promptr_render [-v var]");
+            return Ok(EX_USAGE);
+        }
+    };
+
+    // The marker has to come after we've looked at the environment, this shell is the one
+    // rendering so it'd otherwise look like a nested render
+    let vars = export_env();
+
+    let escape_style = match vars.get("PROMPTR_ESCAPE") {
+        Some(style) => style.parse()?,
+        None => EscapeStyle::Bash,
+    };
+
+    env::set_var(ACTIVE_VAR, active_marker());
+
+    let rendered = prompt::render(Shell::Bash, escape_style, vars);
+
+    let result = rendered.and_then(|rendered| {
+        match &var {
+            Some(var) => bind_variable(var, &rendered.text)?,
+            None => {
+                let mut stdout = std::io::stdout();
+                stdout.write_all(rendered.text.as_bytes())?;
+                stdout.flush()?;
+            }
+        }
+        rendered.finish();
+        Ok(())
+    });
+
+    // Otherwise every command this shell starts from now on would think it's part of a render
+    env::remove_var(ACTIVE_VAR);
+
+    result.map(|_| EXECUTION_SUCCESS)
+}
+
+/// The builtin itself, a panic mustn't take the shell down with it
+unsafe extern "C" fn promptr_render(list: *mut WordList) -> c_int {
+    let args = words(list);
+
+    match panic::catch_unwind(AssertUnwindSafe(|| run(args))) {
+        Ok(Ok(status)) => status,
+        Ok(Err(err)) => {
+            eprintln!("Error in promptr: {:?}", err);
+            EXECUTION_FAILURE
+        }
+        Err(_) => EXECUTION_FAILURE,
+    }
+}
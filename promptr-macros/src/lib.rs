@@ -10,6 +10,7 @@ pub fn only_serialize_non_default(input: TokenStream) -> TokenStream {
     let name_string = name.to_string();
 
     let mut serialize_fields = vec![];
+    let mut serialize_all_fields = vec![];
 
     if let syn::Data::Struct(st) = input.data {
         for field in st.fields.iter() {
@@ -19,7 +20,10 @@ pub fn only_serialize_non_default(input: TokenStream) -> TokenStream {
                     if self.#ident != default.#ident {
                         state.serialize_field(#ident_s, &self.#ident)?
                     }
-                })
+                });
+                serialize_all_fields.push(quote! {
+                    state.serialize_field(#ident_s, &crate::All(&self.#ident))?
+                });
             }
         }
     }
@@ -42,6 +46,20 @@ pub fn only_serialize_non_default(input: TokenStream) -> TokenStream {
                 state.end()
             }
         }
+
+        impl crate::SerializeAll for #name
+        {
+            fn serialize_all<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer + Sized,
+            {
+                let mut state = serializer.serialize_struct(#name_string, #serialize_count)?;
+
+                #(#serialize_all_fields;)*
+
+                state.end()
+            }
+        }
     };
 
     TokenStream::from(quoted)
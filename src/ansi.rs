@@ -1,7 +1,12 @@
 //! Odds and ends to wrangle ANSI color live here.
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt::{self, Display};
+use std::str::FromStr;
+use std::time::Duration;
 
+use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 
 /// Colors that can be used with an [`AnsiCommand`]
@@ -12,6 +17,12 @@ use serde::{Deserialize, Serialize};
 /// { "bg": 240 }
 /// ```
 ///
+/// The first 16 and a few others have names, see [`COLOR_NAMES`].  Case doesn't matter and
+/// dashes or spaces work as well as underscores:
+/// ```json
+/// { "fg": "bright_white", "bg": "Orange" }
+/// ```
+///
 /// A 24-bit color is represented by a struct like so:
 /// ```json
 /// {
@@ -22,13 +33,133 @@ use serde::{Deserialize, Serialize};
 ///     }
 /// }
 /// ```
-#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Serialize)]
+///
+/// A segment's foreground can be set to `"auto"` to pick black or white, whichever is easier to
+/// read on the segment's background:
+/// ```json
+/// { "fg": "auto", "bg": 220 }
+/// ```
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum Color {
     /// Color from the ANSI 256-color palette
+    #[serde(deserialize_with = "deserialize_numbered")]
     Numbered(u8),
     /// 24-bit "true" color
     Rgb { r: u8, g: u8, b: u8 },
+    /// Contrasts with the background, see [`Color::contrasting`].  Anywhere other than a
+    /// segment's foreground this is the terminal's default color.
+    #[serde(
+        serialize_with = "serialize_auto",
+        deserialize_with = "deserialize_auto"
+    )]
+    Auto,
+}
+
+/// Written as `"auto"` in the configuration file
+const AUTO: &str = "auto";
+
+/// Palette colors that can be given by name instead of number
+pub const COLOR_NAMES: &[(&str, u8)] = &[
+    ("black", 0),
+    ("red", 1),
+    ("green", 2),
+    ("yellow", 3),
+    ("blue", 4),
+    ("magenta", 5),
+    ("cyan", 6),
+    ("white", 7),
+    ("bright_black", 8),
+    ("bright_red", 9),
+    ("bright_green", 10),
+    ("bright_yellow", 11),
+    ("bright_blue", 12),
+    ("bright_magenta", 13),
+    ("bright_cyan", 14),
+    ("bright_white", 15),
+    ("gray", 8),
+    ("grey", 8),
+    ("brown", 94),
+    ("purple", 93),
+    ("orange", 208),
+    ("pink", 218),
+];
+
+/// Takes a palette number or one of the [`COLOR_NAMES`]
+fn deserialize_numbered<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrName {
+        Number(u8),
+        Name(String),
+    }
+
+    match NumberOrName::deserialize(deserializer)? {
+        NumberOrName::Number(n) => Ok(n),
+        NumberOrName::Name(name) => match Color::named(&name) {
+            Some(Color::Numbered(n)) => Ok(n),
+            _ => Err(serde::de::Error::custom(format!("unknown color: {}", name))),
+        },
+    }
+}
+
+fn serialize_auto<S: serde::Serializer>(serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(AUTO)
+}
+
+fn deserialize_auto<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<(), D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        AUTO => Ok(()),
+        other => Err(serde::de::Error::custom(format!(
+            "unknown color: {}",
+            other
+        ))),
+    }
+}
+
+/// How many colors the terminal can draw.  Colors the terminal can't handle are swapped for the
+/// closest one it can, see [`Color::downgrade`].
+///
+/// ## Usage in a configuration file
+/// ```json
+/// { "color_depth": "256" }
+/// ```
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ColorDepth {
+    /// Guess based on the environment, see [`ColorDepth::resolve`]
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// 24-bit "true" color, nothing is changed
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    /// The 256-color palette
+    #[serde(rename = "256")]
+    Palette256,
+    /// The 16 system colors, for the Linux console and friends
+    #[serde(rename = "16")]
+    Palette16,
+}
+
+/// Whether the terminal draws dark text on a light background or the other way around, see
+/// [`Background::detect`]
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Background {
+    Light,
+    Dark,
+}
+
+/// Text attributes that can be applied to a segment or part of one, see
+/// [`Segment::with_style`](`crate::segment::Segment::with_style`) and
+/// [`Span`](`crate::segment::Span`).  Not every terminal draws italics.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextStyle {
+    Bold,
+    Dim,
+    Italic,
+    Underline,
 }
 
 /// ANSI SGR (Select Graphic Rendition) commands
@@ -45,19 +176,299 @@ pub enum AnsiCommand {
     DefaultBackgroundColor = 49,
     DefaultColorAndStyle = 0,
     BoldOn = 1,
+    DimOn = 2,
+    ItalicOn = 3,
+    UnderlineOn = 4,
+    /// Turns off both bold and dim
     BoldOff = 22,
-    // UnderlineOn = 4,
-    // UnderlineOff = 24,
+    ItalicOff = 23,
+    UnderlineOff = 24,
+}
+
+/// OSC (Operating System Command) sequences
+///
+/// See also: <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Operating-System-Commands>
+#[derive(Copy, Clone, Debug)]
+#[repr(u16)]
+pub enum OscCommand {
+    SetIconAndTitle = 0,
+    SetTitle = 2,
+    // file://hostname/path
+    CurrentDirectory = 7,
+    // iTerm2 style notification
+    Notify = 9,
+    // FinalTerm / semantic prompt markers
+    SemanticPrompt = 133,
+    // rxvt-unicode style notification, also understood by a number of VTE based terminals
+    NotifyExtended = 777,
 }
 
-/// Writes an ANSI escape sequence out to a `String`
+/// How escape sequences are written out for whoever is consuming the rendered prompt
 ///
-/// **TODO** Figure out how multi-shell support should work
-pub fn escape<S: Into<Option<String>>>(cmd: AnsiCommand, args: S) -> String {
+/// Each [`Shell`](`crate::shell::Shell`) has a natural style, this can be overridden with
+/// `promptr prompt --escape <style>` or by setting `PROMPTR_ESCAPE`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EscapeStyle {
+    /// `\[...\]` with control characters spelled out, for `PS1` in bash
+    Bash,
+    /// `%{...%}`, for zsh and tcsh
+    Zsh,
+    /// Bare escape sequences for consumers that measure the prompt themselves
+    Raw,
+    /// tmux format strings (e.g. `#[fg=colour250]`) for use in the status line
+    Tmux,
+}
+
+impl EscapeStyle {
+    /// Marks `raw` as taking up no room on screen.  Control characters are spelled out for
+    /// consumers that prefer it.
+    ///
+    /// tmux can't pass arbitrary escape sequences through its status line so they're dropped.
+    pub fn non_printing(self, raw: &str) -> String {
+        match self {
+            Self::Bash => format!(
+                r"\[{}\]",
+                raw.replace('\\', r"\\")
+                    .replace('\x1b', r"\e")
+                    .replace('\x07', r"\a")
+            ),
+            Self::Zsh => format!("%{{{}%}}", raw),
+            Self::Raw => raw.to_string(),
+            Self::Tmux => String::new(),
+        }
+    }
+
+    /// Prepares segment text for a consumer that isn't the shell the prompt was built for.
+    ///
+    /// The default theme uses `bash` prompt escapes in a couple of places (e.g. `\$`) so those
+    /// are translated.  See [`Shell::escape_text`](`crate::shell::Shell::escape_text`) for the
+    /// shell specific version.
+    pub fn escape_text<'a>(self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Bash => Cow::Borrowed(text),
+            // % introduces a prompt sequence
+            Self::Zsh => Cow::Owned(text.replace("\\$", "$").replace('%', "%%")),
+            Self::Raw => Cow::Owned(text.replace("\\$", "$")),
+            // # introduces a format string
+            Self::Tmux => Cow::Owned(text.replace("\\$", "$").replace('#', "##")),
+        }
+    }
+
+    /// Starts a new line of the prompt.  tmux's status line only has the one line so the prompt's
+    /// lines are put side by side.
+    pub fn newline(self) -> &'static str {
+        match self {
+            Self::Bash => r"\n",
+            Self::Zsh | Self::Raw => "\n",
+            Self::Tmux => " ",
+        }
+    }
+
+    /// Protects text that didn't come from the theme, like the output of a command, so it's
+    /// shown as is.  `bash` expands `PS1` again after decoding the prompt escapes, so a `$(…)` or
+    /// backtick in there would otherwise run every time the prompt is drawn.
+    pub fn escape_literal<'a>(self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Bash if text.contains(['\\', '$', '`']) => Cow::Owned(
+                text.replace('\\', r"\\\\")
+                    .replace('$', r"\\$")
+                    .replace('`', r"\\`"),
+            ),
+            _ => Cow::Borrowed(text),
+        }
+    }
+}
+
+impl ColorDepth {
+    /// Returns a concrete color depth, guessing from the environment if we were asked to.
+    ///
+    /// `COLORTERM` is the only reliable sign of 24-bit color, after that `TERM` says whether the
+    /// 256-color palette is there.  Terminals we don't recognize are assumed to handle anything.
+    pub fn resolve(self, env: &HashMap<String, String>) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+
+        if let Some("truecolor" | "24bit") = env.get("COLORTERM").map(String::as_str) {
+            return Self::TrueColor;
+        }
+
+        match env.get("TERM_PROGRAM").map(String::as_str) {
+            Some("iTerm.app") | Some("WezTerm") | Some("vscode") => return Self::TrueColor,
+            Some("Apple_Terminal") => return Self::Palette256,
+            _ => {}
+        }
+
+        match env.get("TERM").map(String::as_str) {
+            Some(term) if term.ends_with("-direct") => Self::TrueColor,
+            Some(term) if term.contains("256color") => Self::Palette256,
+            Some("linux" | "vt100" | "vt220" | "xterm" | "screen" | "ansi" | "dumb") => {
+                Self::Palette16
+            }
+            _ => Self::TrueColor,
+        }
+    }
+}
+
+impl FromStr for EscapeStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "raw" => Ok(Self::Raw),
+            "tmux" => Ok(Self::Tmux),
+            other => Err(anyhow!("Unknown escape style: {}", other)),
+        }
+    }
+}
+
+impl Background {
+    /// Asks the terminal for its background color
+    pub const QUERY: &'static str = "\x1b]11;?\x07";
+
+    /// Asks for the primary device attributes.  Every terminal answers this one, and answers in
+    /// order, so it's sent after [`Self::QUERY`] to mark the end of the reply.
+    pub const DEVICE_ATTRIBUTES: &'static str = "\x1b[c";
+
+    /// Returns what's written to the terminal to find out its background, [`Self::QUERY`] then
+    /// [`Self::DEVICE_ATTRIBUTES`] wrapped for tmux or screen if need be, see [`passthrough`]
+    pub fn query(env: &HashMap<String, String>) -> String {
+        passthrough(env, &format!("{}{}", Self::QUERY, Self::DEVICE_ATTRIBUTES))
+    }
+
+    /// Works out the background from `COLORFGBG` if the terminal sets it, otherwise asks the
+    /// terminal with [`Self::query`].  Terminals that don't answer within `timeout` are left
+    /// alone, `None` means there's no telling.
+    pub fn detect(env: &HashMap<String, String>, timeout: Duration) -> Option<Self> {
+        if let Some(value) = env.get("COLORFGBG") {
+            return Self::from_colorfgbg(value);
+        }
+
+        Self::from_reply(&crate::platform::query_terminal(
+            &Self::query(env),
+            timeout,
+        )?)
+    }
+
+    /// Reads `COLORFGBG`, e.g. `15;0`.  The last field is the background as a number from the
+    /// 16 system colors, the greys and white are light and the rest dark.
+    pub fn from_colorfgbg(value: &str) -> Option<Self> {
+        match value.rsplit(';').next()?.parse::<u8>().ok()? {
+            0..=6 | 8 => Some(Self::Dark),
+            7 | 9..=15 => Some(Self::Light),
+            _ => None,
+        }
+    }
+
+    /// Reads the terminal's answer to [`Self::QUERY`], e.g. `\x1b]11;rgb:ffff/ffff/dddd\x07`.
+    /// Each channel has anywhere from one to four hex digits.
+    pub fn from_reply(reply: &str) -> Option<Self> {
+        let (_, rgb) = reply.split_once("rgb:")?;
+        let rgb = rgb.trim_end_matches(['\x07', '\x1b', '\\']);
+
+        let channels = rgb
+            .split('/')
+            .map(|channel| {
+                let max = 16f64.powi(channel.len() as i32) - 1.0;
+                match channel.len() {
+                    1..=4 => Some(u16::from_str_radix(channel, 16).ok()? as f64 / max),
+                    _ => None,
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        match channels.as_slice() {
+            [r, g, b] if 0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5 => Some(Self::Light),
+            [_, _, _] => Some(Self::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// Writes an ANSI escape sequence out to a `String`, marked as non-printing in the manner `style`
+/// expects.
+pub fn escape<S: Into<Option<String>>>(style: EscapeStyle, cmd: AnsiCommand, args: S) -> String {
     let args = args.into();
-    match args {
-        Some(args) => format!(r"\[\e[{};{}m\]", cmd, args),
-        None => format!(r"\[\e[{}m\]", cmd),
+
+    if style == EscapeStyle::Tmux {
+        return tmux_format(cmd, args.as_deref());
+    }
+
+    let body = match args {
+        Some(args) => format!("[{};{}m", cmd, args),
+        None => format!("[{}m", cmd),
+    };
+
+    style.non_printing(&format!("\x1b{}", body))
+}
+
+/// Translates an SGR command into a tmux format string
+fn tmux_format(cmd: AnsiCommand, args: Option<&str>) -> String {
+    let attribute = match cmd {
+        AnsiCommand::SetFgColor => format!("fg={}", tmux_color(args)),
+        AnsiCommand::SetBgColor => format!("bg={}", tmux_color(args)),
+        AnsiCommand::DefaultForegroundColor => "fg=default".into(),
+        AnsiCommand::DefaultBackgroundColor => "bg=default".into(),
+        AnsiCommand::DefaultColorAndStyle => "default".into(),
+        AnsiCommand::BoldOn => "bold".into(),
+        AnsiCommand::DimOn => "dim".into(),
+        AnsiCommand::ItalicOn => "italics".into(),
+        AnsiCommand::UnderlineOn => "underscore".into(),
+        AnsiCommand::BoldOff => "nobold,nodim".into(),
+        AnsiCommand::ItalicOff => "noitalics".into(),
+        AnsiCommand::UnderlineOff => "nounderscore".into(),
+    };
+
+    format!("#[{}]", attribute)
+}
+
+/// Translates the arguments produced by [`Color`]'s [`Display`] impl into a tmux color
+fn tmux_color(args: Option<&str>) -> String {
+    let parts = args.unwrap_or_default().split(';').collect::<Vec<_>>();
+
+    let parse = |part: &str| part.parse::<u8>().unwrap_or_default();
+
+    match parts.as_slice() {
+        ["5", n] => format!("colour{}", parse(n)),
+        ["2", r, g, b] => format!("#{:02x}{:02x}{:02x}", parse(r), parse(g), parse(b)),
+        _ => "default".into(),
+    }
+}
+
+/// Writes an OSC sequence out to a `String`, marked as non-printing in the manner `style` expects.
+///
+/// See [`osc_sequence`] for the details.
+pub fn osc(
+    style: EscapeStyle,
+    env: &HashMap<String, String>,
+    cmd: OscCommand,
+    args: &str,
+) -> String {
+    style.non_printing(&osc_sequence(env, cmd, args))
+}
+
+/// Writes a bare OSC sequence out to a `String`, suitable for writing directly to the terminal.
+///
+/// See [`passthrough`] for the details.
+pub fn osc_sequence(env: &HashMap<String, String>, cmd: OscCommand, args: &str) -> String {
+    passthrough(env, &format!("\x1b]{};{}\x07", cmd, args))
+}
+
+/// Wraps `sequence` so it makes it through to the terminal.
+///
+/// GNU screen and tmux swallow sequences they don't understand so we wrap them in a DCS
+/// passthrough if `$TMUX` or `$STY` are set.  tmux additionally needs `allow-passthrough` turned
+/// on as of 3.3.
+pub fn passthrough(env: &HashMap<String, String>, sequence: &str) -> String {
+    if env.contains_key("TMUX") {
+        // Every ESC inside the passthrough needs to be doubled up
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else if env.contains_key("STY") {
+        format!("\x1bP{}\x1b\\", sequence)
+    } else {
+        sequence.to_string()
     }
 }
 
@@ -67,29 +478,167 @@ impl Display for Color {
         match self {
             Self::Numbered(n) => write!(f, "5;{}", n),
             Self::Rgb { r, g, b } => write!(f, "2;{};{};{}", r, g, b),
+            // set_fg and set_bg never get here
+            Self::Auto => write!(f, "5;15"),
         }
     }
 }
 
 impl Color {
-    pub fn set_fg(&self) -> String {
-        escape(AnsiCommand::SetFgColor, self.to_string())
+    /// Returns the palette color called `name`, see [`COLOR_NAMES`]
+    pub fn named(name: &str) -> Option<Self> {
+        let name = name.to_lowercase().replace(['-', ' '], "_");
+
+        COLOR_NAMES
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, n)| Self::Numbered(*n))
     }
 
-    pub fn set_bg(&self) -> String {
-        escape(AnsiCommand::SetBgColor, self.to_string())
+    pub fn set_fg(&self, style: EscapeStyle) -> String {
+        match self {
+            Self::Auto => Self::reset_fg(style),
+            _ => escape(style, AnsiCommand::SetFgColor, self.to_string()),
+        }
     }
 
-    pub fn reset_colors() -> String {
-        escape(AnsiCommand::DefaultColorAndStyle, None)
+    pub fn set_bg(&self, style: EscapeStyle) -> String {
+        match self {
+            Self::Auto => Self::reset_bg(style),
+            _ => escape(style, AnsiCommand::SetBgColor, self.to_string()),
+        }
+    }
+
+    /// Returns the red, green, and blue components, or `None` for [`Color::Auto`].  Palette colors
+    /// are converted using the xterm defaults.
+    pub fn to_rgb(self) -> Option<(u8, u8, u8)> {
+        // The first 16 vary from terminal to terminal, these are xterm's
+        const SYSTEM: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 0, 0),
+            (0, 205, 0),
+            (205, 205, 0),
+            (0, 0, 238),
+            (205, 0, 205),
+            (0, 205, 205),
+            (229, 229, 229),
+            (127, 127, 127),
+            (255, 0, 0),
+            (0, 255, 0),
+            (255, 255, 0),
+            (92, 92, 255),
+            (255, 0, 255),
+            (0, 255, 255),
+            (255, 255, 255),
+        ];
+        const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+        match self {
+            Self::Numbered(n @ 0..=15) => Some(SYSTEM[n as usize]),
+            Self::Numbered(n @ 16..=231) => {
+                let n = (n - 16) as usize;
+                Some((CUBE[n / 36], CUBE[(n / 6) % 6], CUBE[n % 6]))
+            }
+            Self::Numbered(n) => {
+                let gray = 8 + 10 * (n - 232);
+                Some((gray, gray, gray))
+            }
+            Self::Rgb { r, g, b } => Some((r, g, b)),
+            Self::Auto => None,
+        }
+    }
+
+    /// Returns the closest color that can be drawn at `depth`.  The first 16 colors are left
+    /// alone as every terminal has them, even if they look different everywhere.
+    pub fn downgrade(self, depth: ColorDepth) -> Self {
+        let candidates = match (depth, self) {
+            (ColorDepth::Auto | ColorDepth::TrueColor, _) | (_, Self::Auto) => return self,
+            (_, Self::Numbered(0..=15)) => return self,
+            (ColorDepth::Palette256, Self::Numbered(_)) => return self,
+            // The system colors vary too much to be picked for a specific shade
+            (ColorDepth::Palette256, Self::Rgb { .. }) => 16..=255,
+            (ColorDepth::Palette16, _) => 0..=15,
+        };
+
+        let (r, g, b) = self.to_rgb().expect("only Auto has no RGB value");
+        let distance = |n: &u8| {
+            let (r2, g2, b2) = Self::Numbered(*n).to_rgb().expect("palette colors are RGB");
+            let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+            d(r, r2) + d(g, g2) + d(b, b2)
+        };
+
+        candidates
+            .min_by_key(distance)
+            .map(Self::Numbered)
+            .unwrap_or(self)
     }
 
-    pub fn reset_bg() -> String {
-        escape(AnsiCommand::DefaultBackgroundColor, None)
+    /// Returns black or white, whichever stands out more against `self`
+    pub fn contrasting(self) -> Self {
+        let (r, g, b) = match self.to_rgb() {
+            Some(rgb) => rgb,
+            None => return Self::Auto,
+        };
+
+        // Perceived brightness, see ITU-R BT.601
+        let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+
+        match luma > 128 * 1000 {
+            true => Self::Numbered(0),
+            false => Self::Numbered(15),
+        }
+    }
+
+    /// Returns the color `amount` of the way from `self` to `other`, where zero is `self` and
+    /// one is `other`.  The channels are mixed in RGB.  `None` if either is
+    /// [`Auto`](`Self::Auto`).
+    pub fn interpolate(self, other: Self, amount: f64) -> Option<Self> {
+        let (r1, g1, b1) = self.to_rgb()?;
+        let (r2, g2, b2) = other.to_rgb()?;
+
+        let amount = amount.clamp(0.0, 1.0);
+        let mix =
+            |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * amount).round() as u8;
+
+        Some(Self::Rgb {
+            r: mix(r1, r2),
+            g: mix(g1, g2),
+            b: mix(b1, b2),
+        })
     }
 
-    pub fn reset_fg() -> String {
-        escape(AnsiCommand::DefaultForegroundColor, None)
+    pub fn reset_colors(style: EscapeStyle) -> String {
+        escape(style, AnsiCommand::DefaultColorAndStyle, None)
+    }
+
+    pub fn reset_bg(style: EscapeStyle) -> String {
+        escape(style, AnsiCommand::DefaultBackgroundColor, None)
+    }
+
+    pub fn reset_fg(style: EscapeStyle) -> String {
+        escape(style, AnsiCommand::DefaultForegroundColor, None)
+    }
+}
+
+impl TextStyle {
+    /// Returns the escape sequence that turns the style on
+    pub fn on(self, style: EscapeStyle) -> String {
+        match self {
+            Self::Bold => escape(style, AnsiCommand::BoldOn, None),
+            Self::Dim => escape(style, AnsiCommand::DimOn, None),
+            Self::Italic => escape(style, AnsiCommand::ItalicOn, None),
+            Self::Underline => escape(style, AnsiCommand::UnderlineOn, None),
+        }
+    }
+
+    /// Returns the escape sequence that turns the style back off.  Bold and dim share one, so
+    /// turning either off turns off both.
+    pub fn off(self, style: EscapeStyle) -> String {
+        match self {
+            Self::Bold | Self::Dim => escape(style, AnsiCommand::BoldOff, None),
+            Self::Italic => escape(style, AnsiCommand::ItalicOff, None),
+            Self::Underline => escape(style, AnsiCommand::UnderlineOff, None),
+        }
     }
 }
 
@@ -98,3 +647,9 @@ impl Display for AnsiCommand {
         write!(f, "{}", *self as u8)
     }
 }
+
+impl Display for OscCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", *self as u16)
+    }
+}
@@ -1,18 +1,27 @@
 use anyhow::{anyhow, Result};
-use clap::{Args, Parser, Subcommand};
-use directories::ProjectDirs;
+use clap::{ArgEnum, Args, Parser, Subcommand};
 use itertools::Itertools;
 use serde_json::from_reader as json_from_reader;
 
-use std::collections::HashMap;
 use std::env;
 use std::fs::{self, File};
-use std::path::PathBuf;
-
-use libpromptr::ansi::Color;
-use libpromptr::segment::{self, Segment, ToSegment};
-use libpromptr::shell::Shell;
-use libpromptr::{ApplicationState, PromptrConfig, SegmentConfig, Separator};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use libpromptr::ansi::EscapeStyle;
+use libpromptr::bundle::Bundle;
+use libpromptr::crash;
+use libpromptr::glyphs::{self, GlyphSet};
+use libpromptr::lint;
+use libpromptr::prompt::{
+    self, apply_glyph_set, apply_schedule, config_dir, load_config, select_theme,
+};
+use libpromptr::redact::Redactor;
+use libpromptr::registry::{self, Lookup};
+use libpromptr::schedule;
+use libpromptr::shell::{self, active_marker, Shell, ACTIVE_VAR};
+use libpromptr::stats;
+use libpromptr::PromptrConfig;
 
 /// promptr is a colorful, rusty prompt generator for bash.
 #[derive(Parser)]
@@ -44,8 +53,60 @@ enum Commands {
     /// From a bash instance run: source <(promptr load)
     Load,
 
+    /// Add the line that loads promptr to the shell's startup file
+    ///
+    /// The line is marked with a comment so running this again doesn't add it twice, and
+    /// `--remove` takes it back out.  Defaults to `~/.bashrc` for bash.
+    InstallHook(SubCmdInstallHookArgs),
+
     /// This subcommand generates the prompt displayed by the command shell.  Don't call directly
-    Prompt,
+    ///
+    /// With `--format json` the rendered segments, including any structured metadata, are printed
+    /// as JSON for use by scripts.  That's a list with one entry per line of the prompt, each a list
+    /// of segments.
+    Prompt(SubCmdPromptArgs),
+
+    /// Check that the prompt will draw properly in this terminal
+    ///
+    /// Shows a sample of the prompt drawn with each glyph set and asks which one looks right, the
+    /// answer is saved as the glyph_set in the configuration file.  Run this if the prompt shows
+    /// boxes or question marks.
+    Doctor,
+
+    /// Check the configuration file for mistakes
+    ///
+    /// Besides making sure the file loads this warns about unreadable color combinations in the
+    /// theme, unknown segments or arguments, the same segment listed twice in a row, settings for
+    /// segments that weren't compiled in, redact patterns that don't compile, and separators the
+    /// selected glyph set can't draw.  Nothing is rendered along the way.
+    Validate,
+
+    /// Print how long recent prompts took to render
+    ///
+    /// Reports the median and 95th percentile render times for each segment in the Prometheus text
+    /// format.  Nothing is recorded unless `latency_history` is set in the configuration.
+    Stats,
+
+    /// Inspect plugins without putting them in the prompt
+    ///
+    /// Plugins are programs that print segments as JSON, see the `plugins` configuration stanza.
+    #[clap(subcommand)]
+    Plugins(PluginCommands),
+
+    /// Try out, pick, and share themes
+    ///
+    /// Themes are the base theme, the named themes in the configuration, and the files under
+    /// themes/ in the configuration directory.  A bundle is a theme along with the glyph set it
+    /// was designed for and the features it needs, in a versioned format that can be passed
+    /// around and applied with one command.
+    #[clap(subcommand)]
+    Theme(ThemeCommands),
+
+    /// Print version information
+    ///
+    /// With `--verbose` this also prints which optional segments were compiled in, the git
+    /// commit, the target, and the version of any bundled libraries.
+    Version(SubCmdVersionArgs),
 }
 
 #[doc(hidden)]
@@ -55,102 +116,513 @@ struct SubCmdDumpSegmentArgs {
 }
 
 #[doc(hidden)]
-fn config_dir() -> Result<PathBuf> {
-    let project_dirs = ProjectDirs::from("com", "inferiorhumanorgans", "promptr")
-        .ok_or_else(|| anyhow!("couldn't create ProjectDirs"))?;
-    let config_dir = project_dirs.config_dir();
+#[derive(Args, Debug, PartialEq)]
+struct SubCmdPromptArgs {
+    #[clap(long, arg_enum, default_value = "shell")]
+    format: OutputFormat,
+
+    /// How to write out escape sequences: bash, zsh, raw, or tmux.  Defaults to $PROMPTR_ESCAPE
+    /// or whatever suits the current shell.
+    #[clap(long)]
+    escape: Option<EscapeStyle>,
+
+    /// Leave out colors and every other escape sequence, the segments are separated by ` | `.
+    /// Setting $NO_COLOR does the same.
+    #[clap(long)]
+    plain: bool,
+}
 
-    if let Err(error) = fs::metadata(config_dir) {
-        match error.kind() {
-            std::io::ErrorKind::NotFound => {
-                fs::create_dir_all(config_dir)?;
-            }
-            _ => Err(error)?,
+#[doc(hidden)]
+#[derive(Subcommand, Debug, PartialEq)]
+enum ThemeCommands {
+    /// Print the current theme as a bundle
+    Export(SubCmdThemeExportArgs),
+
+    /// Apply a bundle to the configuration file
+    Import(SubCmdThemeImportArgs),
+
+    /// List the themes that can be previewed or set, the one in use is marked with a *
+    List,
+
+    /// Draw a sample prompt with a theme, from made up values
+    Preview(SubCmdThemeNameArgs),
+
+    /// Make a theme the one the configuration file uses
+    Set(SubCmdThemeNameArgs),
+}
+
+#[doc(hidden)]
+#[derive(Subcommand, Debug, PartialEq)]
+enum PluginCommands {
+    /// Print each configured plugin and its limits
+    List,
+
+    /// Run a plugin and print the segments it produced, or why they were rejected
+    Test(SubCmdPluginTestArgs),
+}
+
+#[doc(hidden)]
+#[derive(Args, Debug, PartialEq)]
+struct SubCmdPluginTestArgs {
+    /// Name of the plugin in the configuration
+    name: String,
+}
+
+#[doc(hidden)]
+#[derive(Args, Debug, PartialEq)]
+struct SubCmdThemeNameArgs {
+    /// Name of the theme, default is the base theme
+    name: String,
+}
+
+#[doc(hidden)]
+#[derive(Args, Debug, PartialEq)]
+struct SubCmdThemeExportArgs {
+    /// Name to suggest when the bundle is imported as a named theme
+    #[clap(long)]
+    name: Option<String>,
+}
+
+#[doc(hidden)]
+#[derive(Args, Debug, PartialEq)]
+struct SubCmdThemeImportArgs {
+    /// Bundle to import, or - for STDIN
+    path: PathBuf,
+
+    /// Add the theme to `themes` under this name instead of replacing the base theme.  Without a
+    /// value the name from the bundle is used.
+    #[clap(long = "as")]
+    as_name: Option<Option<String>>,
+}
+
+#[doc(hidden)]
+#[derive(Args, Debug, PartialEq)]
+struct SubCmdInstallHookArgs {
+    /// Startup file to change instead of the usual one for the current shell
+    #[clap(long, alias = "rc")]
+    bashrc: Option<PathBuf>,
+
+    /// Remove the line instead of adding it
+    #[clap(long)]
+    remove: bool,
+}
+
+#[doc(hidden)]
+#[derive(Args, Debug, PartialEq)]
+struct SubCmdVersionArgs {
+    /// Include build details
+    #[clap(long, short)]
+    verbose: bool,
+}
+
+#[doc(hidden)]
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Shell,
+    Json,
+}
+
+/// Cargo features that were enabled at build time
+#[doc(hidden)]
+fn enabled_features() -> Vec<&'static str> {
+    registry::BUILTINS
+        .iter()
+        .filter(|builtin| builtin.enabled())
+        .filter_map(|builtin| builtin.feature)
+        .collect()
+}
+
+/// Prints the version and, if asked, how this binary was built
+#[doc(hidden)]
+fn print_version(verbose: bool) {
+    println!("promptr {}", env!("CARGO_PKG_VERSION"));
+
+    if !verbose {
+        return;
+    }
+
+    let features = enabled_features();
+
+    println!(
+        "commit:   {}",
+        option_env!("PROMPTR_GIT_COMMIT").unwrap_or("unknown")
+    );
+    println!(
+        "target:   {}",
+        option_env!("PROMPTR_TARGET").unwrap_or("unknown")
+    );
+    println!(
+        "features: {}",
+        match features.is_empty() {
+            true => String::from("none"),
+            false => features.join(" "),
         }
-    };
+    );
+
+    #[cfg(feature = "segment-git")]
+    {
+        let (major, minor, patch) = git2::Version::get().libgit2_version();
+        println!("libgit2:  {}.{}.{}", major, minor, patch);
+    }
+}
+
+/// Loads the configuration file without falling back to the defaults and prints any warnings.
+/// Nothing is rendered, see [`lint`].
+#[doc(hidden)]
+fn validate() -> Result<()> {
+    let path = config_dir()?.join("promptr.json");
+
+    let mut raw: serde_json::Value = json_from_reader(File::open(&path)?)?;
+    prompt::load_theme_file(&mut raw, &config_dir()?)?;
+    // Missing colors are dropped so the rest of the configuration can still be checked
+    let palette = prompt::resolve_palette(&mut raw).err();
+
+    let mut warnings = lint::lint_raw(&raw);
+    warnings.extend(palette.map(|err| format!("palette: {}", err)));
+    warnings
+        .iter()
+        .for_each(|warning| eprintln!("warning: {}", warning));
+
+    let mut config: PromptrConfig = serde_json::from_value(raw)?;
+
+    if config.promptr_config != 12 {
+        return Err(anyhow!(
+            "promptr_config is {}, expected 12",
+            config.promptr_config
+        ));
+    }
+
+    let mut later = lint::lint_config(&config);
+    later.extend(lint::lint_segment_args(&config));
+
+    later.extend(
+        config
+            .redact
+            .iter()
+            .filter_map(|pattern| Redactor::new(&[pattern]).err())
+            .map(|err| format!("redact: {}", err)),
+    );
+
+    apply_schedule(&mut config);
+    apply_glyph_set(&mut config, &env::vars().collect());
+    let glyphs = config.glyph_set.resolve(&env::vars().collect());
+    later.extend(lint::lint_theme(&config.theme, glyphs));
+    later.extend(lint::lint_theme_colors(&config.theme));
+
+    later
+        .iter()
+        .for_each(|warning| eprintln!("warning: {}", warning));
+    warnings.extend(later);
+
+    match warnings.len() {
+        0 => {
+            println!("{}: looks good", path.display());
+            Ok(())
+        }
+        count => Err(anyhow!("{}: {} warning(s)", path.display(), count)),
+    }
+}
 
-    Ok(config_dir.into())
+/// Reads the configuration file at `path` as plain JSON so it can be edited without losing
+/// anything, a missing file is treated as an empty configuration
+#[doc(hidden)]
+fn read_raw_config(path: &Path) -> Result<serde_json::Value> {
+    match File::open(path) {
+        Ok(file) => Ok(json_from_reader(file)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            Ok(serde_json::json!({ "promptr_config": 12 }))
+        }
+        Err(err) => Err(err.into()),
+    }
 }
 
-/// Loads the configuration from disk
-///
-/// ## Arguments
-///
-/// * `quiet` – Whether or not to print parsing errors to STDERR
-pub fn load_config(quiet: bool) -> PromptrConfig {
-    let config_file_path: String = match config_dir() {
-        Ok(config_dir) => {
-            let path = config_dir.join("promptr.json");
-            path.into_os_string().to_string_lossy().into()
+/// Replaces the configuration file at `path` with `raw`, as long as it still loads
+#[doc(hidden)]
+fn write_raw_config(path: &Path, raw: &serde_json::Value) -> Result<()> {
+    // Theme names and palette references are only valid once they've been resolved
+    let mut check = raw.clone();
+    if let Some(dir) = path.parent() {
+        prompt::load_theme_file(&mut check, dir)?;
+    }
+    // Missing colors fall back to the defaults like they do when the prompt is drawn
+    if let Err(err) = prompt::resolve_palette(&mut check) {
+        eprintln!("warning: {}", err);
+    }
+    serde_json::from_value::<PromptrConfig>(check)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Write somewhere else first so a failed write doesn't take the configuration with it
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    fs::write(&tmp, serde_json::to_string_pretty(raw)? + "\n")?;
+    fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+/// Shows the prompt in each glyph set and saves the one the user says draws properly
+#[doc(hidden)]
+fn doctor() -> Result<()> {
+    if let Ok(log) = crash::log_path(&env::vars().collect()) {
+        if log.exists() {
+            println!(
+                "promptr has crashed before, the details are in {}.",
+                log.display()
+            );
+            println!("Please include that file when reporting the problem.\n");
+        }
+    }
+
+    println!("Which of these looks right?  Look out for boxes, question marks, and gaps between");
+    println!("the colors.\n");
+
+    for (idx, glyphs) in glyphs::CHOICES.iter().enumerate() {
+        println!("  {}) {:<9} {}", idx + 1, glyphs, glyphs.sample());
+    }
+
+    print!(
+        "\nPick one (1-{}), or press enter to leave things as they are: ",
+        glyphs::CHOICES.len()
+    );
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    let glyph_set = match GlyphSet::choose(&answer)? {
+        Some(glyph_set) => glyph_set,
+        None => {
+            println!("Leaving the glyph set alone.");
+            return Ok(());
         }
-        Err(_) => "".into(),
     };
 
-    File::open(config_file_path)
-        .map_err(|e| e.into()) // Into anyhow
-        .and_then(|file| {
-            json_from_reader(file).map_err(|e| {
-                if !quiet {
-                    eprintln!("JSON parsing error, using default config.");
-                    eprintln!("{:?}", e);
-                }
-                anyhow!("{}", e)
-            })
-        })
-        .unwrap_or_default()
-}
-
-/// Runs through the current configuration and renders each segment.
-///
-/// ## Arguments
-///
-/// * `config` – the configuration instance to iterate over
-///
-/// ## Returns
-///
-/// An iterator over [`Segment`].
-pub fn load_segments(config: PromptrConfig) -> Result<impl Iterator<Item = Segment>> {
-    let state = ApplicationState {
-        theme: &config.theme,
-        env: env::vars().fold(HashMap::new(), |mut acc, (key, value)| {
-            acc.insert(key, value);
-            acc
-        }),
+    let path = config_dir()?.join("promptr.json");
+
+    let mut raw = read_raw_config(&path)?;
+    raw.as_object_mut()
+        .ok_or_else(|| anyhow!("the configuration isn't a JSON object"))?
+        .insert("glyph_set".into(), serde_json::to_value(glyph_set)?);
+    write_raw_config(&path, &raw)?;
+
+    println!("{}: the glyph set is now {}", path.display(), glyph_set);
+
+    Ok(())
+}
+
+/// Applies the theme bundle at `path` to the configuration file
+#[doc(hidden)]
+fn import_theme(args: SubCmdThemeImportArgs) -> Result<()> {
+    let json = match args.path.to_str() {
+        Some("-") => std::io::read_to_string(std::io::stdin())?,
+        _ => fs::read_to_string(&args.path)?,
+    };
+
+    let bundle = Bundle::parse(&json)?;
+
+    for feature in bundle.missing_features() {
+        eprintln!(
+            "warning: this theme is meant for segments that need the {} feature, which wasn't compiled in",
+            feature
+        );
+    }
+
+    for key in bundle.unsupported_keys() {
+        eprintln!(
+            "warning: leaving out theme.{}, the segment it's for wasn't compiled in",
+            key
+        );
+    }
+
+    let name = match args.as_name {
+        Some(Some(name)) => Some(name),
+        Some(None) => Some(
+            bundle
+                .name
+                .clone()
+                .ok_or_else(|| anyhow!("the bundle doesn't have a name, pass one to --as"))?,
+        ),
+        None => None,
     };
 
-    assert_eq!(config.promptr_config, 12);
-
-    let segments = config
-        .segments
-        .into_iter()
-        .map(|SegmentConfig { name, args }| match name.as_str() {
-            "command_status" => segment::CommandStatus::to_segment_generic(args, &state),
-            "hostname" => segment::Hostname::to_segment_generic(args, &state),
-            "path" => segment::Path::to_segment_generic(args, &state),
-            "rvm" => segment::Rvm::to_segment_generic(args, &state),
-            "screen" => segment::Screen::to_segment_generic(args, &state),
-            "username" => segment::Username::to_segment_generic(args, &state),
-
-            #[cfg(feature = "segment-battery")]
-            "battery" => segment::BatteryStatus::to_segment_generic(args, &state),
-
-            #[cfg(feature = "segment-git")]
-            "git" => segment::Git::to_segment_generic(args, &state),
-
-            seg => Err(anyhow!("Unknown segment: {}", seg)),
-        })
-        .filter_map(|segment_result| match segment_result {
-            Ok(unflat_segments) => Some(unflat_segments),
-            Err(err) => {
-                eprintln!("Error in promptr: {:?}", err);
-                None
+    let path = config_dir()?.join("promptr.json");
+
+    let mut raw = read_raw_config(&path)?;
+    bundle.apply(&mut raw, name.as_deref())?;
+    write_raw_config(&path, &raw)?;
+
+    match name {
+        Some(name) => println!("{}: added theme {}", path.display(), name),
+        None => println!("{}: replaced the theme", path.display()),
+    }
+
+    Ok(())
+}
+
+/// Lists the themes, draws a sample of one, or switches the configuration file over to one
+#[doc(hidden)]
+fn themes(command: ThemeCommands) -> Result<()> {
+    let path = || -> Result<PathBuf> { Ok(config_dir()?.join("promptr.json")) };
+
+    match command {
+        ThemeCommands::Export(args) => {
+            let config = load_config(false);
+            let bundle = Bundle::export(&config, args.name)?;
+
+            println!("{}", serde_json::to_string_pretty(&bundle)?);
+        }
+        ThemeCommands::Import(args) => import_theme(args)?,
+        ThemeCommands::List => {
+            let config = load_config(false);
+            let raw = read_raw_config(&path()?)?;
+            let current = match (&raw["theme"], &raw["theme_file"]) {
+                (serde_json::Value::String(name), _) => Some(name.as_str()),
+                (_, serde_json::Value::Null) => Some(schedule::DEFAULT_THEME),
+                _ => None,
+            };
+
+            let named = config
+                .themes
+                .keys()
+                .map(|name| (name.clone(), "promptr.json"));
+            let files = prompt::theme_files(&config_dir()?)
+                .into_iter()
+                .map(|name| (name, "themes/"));
+
+            std::iter::once((schedule::DEFAULT_THEME.to_string(), "base theme"))
+                .chain(named)
+                .chain(files)
+                .unique_by(|(name, _)| name.clone())
+                .for_each(|(name, source)| {
+                    let marker = match current == Some(name.as_str()) {
+                        true => '*',
+                        false => ' ',
+                    };
+                    println!("{} {:<16} {}", marker, name, source);
+                });
+        }
+        ThemeCommands::Preview(args) => {
+            let config = load_config(false);
+            let mut theme = prompt::named_theme(&config, &args.name)?;
+            let env = env::vars().collect();
+            theme.apply_glyph_set(config.glyph_set.resolve(&env));
+            theme.downgrade_colors(config.color_depth.resolve(&env));
+
+            println!("{}", prompt::preview(&theme));
+        }
+        ThemeCommands::Set(args) => {
+            // Make sure there's such a theme before touching anything
+            let config = load_config(false);
+            prompt::named_theme(&config, &args.name)?;
+
+            let path = path()?;
+
+            let mut raw = read_raw_config(&path)?;
+            let object = raw
+                .as_object_mut()
+                .ok_or_else(|| anyhow!("the configuration isn't a JSON object"))?;
+
+            // The base theme is whatever's written out in the file, if anything
+            match (args.name == schedule::DEFAULT_THEME, object.get("theme")) {
+                (true, Some(serde_json::Value::String(_))) => {
+                    object.remove("theme");
+                }
+                (true, _) => {}
+                (false, Some(serde_json::Value::Object(theme))) if !theme.is_empty() => {
+                    return Err(anyhow!(
+                        "{}: the theme in there would be lost, move it into themes or a file first",
+                        path.display()
+                    ))
+                }
+                (false, _) => {
+                    object.insert("theme".into(), args.name.clone().into());
+                }
+            }
+            object.remove("theme_file");
+            write_raw_config(&path, &raw)?;
+
+            println!("{}: the theme is now {}", path.display(), args.name);
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints each configured plugin, or runs one and prints what it produced
+#[doc(hidden)]
+fn plugins(command: PluginCommands, escape_style: EscapeStyle) -> Result<()> {
+    let config = load_config(false);
+
+    match command {
+        PluginCommands::List => {
+            for (name, plugin) in &config.plugins {
+                println!("{}: {}", name, plugin.command.join(" "));
+                println!(
+                    "  timeout {}ms, at most {} bytes, env: {}",
+                    plugin.timeout_ms,
+                    plugin.max_output_bytes,
+                    plugin.env.join(" ")
+                );
+                if !matches!(registry::lookup(name), Lookup::Unknown) {
+                    println!("  warning: hidden by the built in segment of the same name");
+                }
             }
-        })
-        .collect_vec()
-        .into_iter()
-        .flatten();
+        }
+        PluginCommands::Test(args) => {
+            let plugin = config
+                .plugins
+                .get(&args.name)
+                .ok_or_else(|| anyhow!("no plugin named {}", args.name))?;
+
+            let segments = plugin.run(&args.name, &env::vars().collect(), escape_style)?;
+
+            println!("{}", serde_json::to_string_pretty(&segments)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds or removes the line that loads `promptr` from the shell's startup file
+#[doc(hidden)]
+fn install_hook(shell: Shell, args: SubCmdInstallHookArgs) -> Result<()> {
+    let env = env::vars().collect();
+
+    let path = match args.bashrc {
+        Some(path) => path,
+        None => shell
+            .rc_file(&env)
+            .ok_or_else(|| anyhow!("couldn't guess the startup file, pass one with --bashrc"))?,
+    };
+
+    let rc = match fs::read_to_string(&path) {
+        Ok(rc) => rc,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+
+    let changed = match args.remove {
+        true => shell::remove_hook(&rc),
+        false => shell::install_hook(&rc, shell.hook_line()),
+    };
+
+    match (changed, args.remove) {
+        (Some(changed), true) => {
+            fs::write(&path, changed)?;
+            println!("{}: removed the promptr hook", path.display());
+        }
+        (Some(changed), false) => {
+            fs::write(&path, changed)?;
+            println!("{}: added the promptr hook", path.display());
+            println!("If the prompt shows boxes or question marks run `promptr doctor`.");
+        }
+        (None, true) => println!("{}: the promptr hook isn't there", path.display()),
+        (None, false) => println!("{}: the promptr hook is already there", path.display()),
+    }
 
-    Ok(segments)
+    Ok(())
 }
 
 #[doc(hidden)]
@@ -163,47 +635,47 @@ fn main() -> Result<()> {
 
     match args.command {
         Commands::Load => shell.generate_loader(&self_exe),
-        Commands::Prompt => {
-            let config = load_config(false);
-            let thin_separator_fg = config.theme.thin_separator_fg;
-            let segments = load_segments(config)?;
+        Commands::InstallHook(args) => install_hook(shell, args)?,
+        Commands::Doctor => doctor()?,
+        Commands::Prompt(args) => {
+            let escape_style = match args.escape {
+                Some(style) => style,
+                None => match env::var("PROMPTR_ESCAPE") {
+                    Ok(style) => style.parse()?,
+                    Err(_) => shell.escape_style(),
+                },
+            };
+
+            // A JSON consumer is better off with the error than a prompt it can't parse
+            if args.format == OutputFormat::Shell {
+                crash::install(env::vars().collect());
+            }
 
-            let mut it = segments.into_iter().peekable();
+            env::set_var(ACTIVE_VAR, active_marker());
 
-            while let Some(seg) = it.next() {
-                let mut separator = seg.separator;
-                if let Some(next_seg) = it.peek() {
-                    if seg.bg == next_seg.bg {
-                        separator = Separator::Thin;
-                    }
-                }
+            if args.plain {
+                env::set_var("NO_COLOR", "1");
+            }
 
-                let separator_fg = match separator {
-                    Separator::Thick => seg.bg.set_fg(),
-                    Separator::Thin => thin_separator_fg.set_fg(),
-                };
-
-                let separator_bg = if let Some(next_seg) = it.peek() {
-                    next_seg.bg.set_bg()
-                } else {
-                    Color::reset_colors()
-                };
-
-                print!(
-                    "{}{} {} {}{}{}",
-                    seg.fg.set_fg(),
-                    seg.bg.set_bg(),
-                    seg.text,
-                    separator_bg,
-                    separator_fg,
-                    separator
-                );
+            let rendered = prompt::render(shell, escape_style, env::vars().collect())?;
+
+            if args.format == OutputFormat::Json {
+                println!("{}", serde_json::to_string(&rendered.lines)?);
+                return Ok(());
             }
 
-            print!("{} ", Color::reset_colors());
+            print!("{}", rendered.text);
+
+            // The shell is waiting on us, make sure it has the prompt before anything else starts
+            std::io::stdout().flush()?;
+            crash::printed();
+
+            rendered.finish();
         }
         Commands::Segment(args) => {
-            let config = load_config(false);
+            let mut config = load_config(false);
+            select_theme(&mut config, &env::vars().collect());
+            apply_glyph_set(&mut config, &env::vars().collect());
 
             // Mock the variables needed to render the segments
             // It's worth thinking about moving this back into a bash alias
@@ -211,7 +683,10 @@ fn main() -> Result<()> {
             env::set_var("code", "123");
             env::set_var("hostname", "dummy-hostname.dummy-domain");
 
-            let segments = load_segments(config)?.collect_vec();
+            let segments = prompt::load_lines(config, shell.escape_style(), env::vars().collect())?
+                .into_iter()
+                .flatten()
+                .collect_vec();
 
             match segments.get(args.idx) {
                 Some(seg) => eprintln!("{:#?}", seg),
@@ -227,6 +702,27 @@ fn main() -> Result<()> {
                     .expect("Error turning configuration into JSON")
             );
         }
+        Commands::Validate => validate()?,
+        Commands::Stats => {
+            let log = stats::Log::from_env(&env::vars().collect())?;
+
+            // The history file is only trimmed every so often
+            let samples = match load_config(false).latency_history {
+                Some(keep) => log.recent(keep),
+                None => log.read(),
+            };
+
+            if samples.is_empty() {
+                eprintln!(
+                    "No render times have been recorded, set latency_history in the configuration."
+                );
+            }
+
+            print!("{}", stats::to_prometheus(&stats::summarize(&samples)));
+        }
+        Commands::Plugins(command) => plugins(command, shell.escape_style())?,
+        Commands::Theme(command) => themes(command)?,
+        Commands::Version(args) => print_version(args.verbose),
         Commands::Location => match config_dir() {
             Ok(dir) => println!(
                 "{}",
@@ -0,0 +1,187 @@
+//! Theme bundles package up a theme with the glyph set it was drawn for and the features it needs
+//! so it can be shared and applied with one command.
+//!
+//! `promptr theme export` writes one out and `promptr theme import` applies one to the
+//! configuration file:
+//! ```json
+//! {
+//!     "promptr_theme_bundle": 1,
+//!     "name": "dusk",
+//!     "glyph_set": "nerdfont",
+//!     "features": ["segment-battery"],
+//!     "theme": { "path": { "bg": 236 }, "battery": { "normal_bg": 52 } }
+//! }
+//! ```
+//!
+//! Like the configuration file the theme only lists what differs from the defaults.  The version
+//! only goes up when an older `promptr` would get a newer bundle wrong, bundles it doesn't
+//! understand are turned away instead.  Settings for segments that weren't compiled in are
+//! dropped with a warning rather than refusing the whole bundle, see [`Bundle::unsupported_keys`].
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::glyphs::GlyphSet;
+use crate::lint::FEATURE_THEME_KEYS;
+use crate::registry::{self, Lookup};
+use crate::{PromptrConfig, Theme};
+
+/// The newest bundle format this build understands
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A theme along with what it takes to show it as intended
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bundle {
+    /// Format version, see [`BUNDLE_VERSION`]
+    pub promptr_theme_bundle: u32,
+
+    /// What to call the theme when it's imported as one of the named `themes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Glyph set the theme was designed with
+    #[serde(default)]
+    pub glyph_set: GlyphSet,
+
+    /// Cargo features needed by the segments the theme sets colors for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+
+    /// The theme itself, kept as JSON so settings for segments that weren't compiled in don't
+    /// keep the rest from loading
+    #[serde(default = "empty_theme")]
+    pub theme: serde_json::Value,
+}
+
+fn empty_theme() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+impl Bundle {
+    /// Bundles up the base theme and glyph set from `config`
+    pub fn export(config: &PromptrConfig, name: Option<String>) -> crate::Result<Self> {
+        let theme = serde_json::to_value(&config.theme)?;
+
+        let features = FEATURE_THEME_KEYS
+            .iter()
+            .filter(|(key, _)| theme.get(key).is_some())
+            .filter_map(|(_, segment)| {
+                registry::BUILTINS
+                    .iter()
+                    .find(|builtin| builtin.name == *segment)
+                    .and_then(|builtin| builtin.feature)
+            })
+            .map(String::from)
+            .collect();
+
+        Ok(Self {
+            promptr_theme_bundle: BUNDLE_VERSION,
+            name,
+            glyph_set: config.glyph_set,
+            features,
+            theme,
+        })
+    }
+
+    /// Reads a bundle, refusing any from a newer version of the format
+    pub fn parse(json: &str) -> crate::Result<Self> {
+        let raw: serde_json::Value = serde_json::from_str(json)?;
+
+        match raw.get("promptr_theme_bundle").and_then(|v| v.as_u64()) {
+            Some(version) if version >= 1 && version <= BUNDLE_VERSION as u64 => {}
+            Some(version) => {
+                return Err(anyhow!(
+                    "theme bundle version {} isn't supported, expected {} or older",
+                    version,
+                    BUNDLE_VERSION
+                ))
+            }
+            None => {
+                return Err(anyhow!(
+                    "not a theme bundle, promptr_theme_bundle is missing"
+                ))
+            }
+        }
+
+        let bundle: Self = serde_json::from_value(raw)?;
+
+        // Make sure what's left is a theme this build can load
+        bundle.to_theme()?;
+
+        Ok(bundle)
+    }
+
+    /// Keys in the theme for segments that weren't compiled in, these are left out when the
+    /// bundle is applied
+    pub fn unsupported_keys(&self) -> Vec<&'static str> {
+        FEATURE_THEME_KEYS
+            .iter()
+            .filter(|(key, _)| self.theme.get(key).is_some())
+            .filter(|(_, segment)| matches!(registry::lookup(segment), Lookup::Disabled(_)))
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// The theme without any [unsupported keys](`Self::unsupported_keys`)
+    fn supported_theme(&self) -> serde_json::Value {
+        let mut theme = self.theme.clone();
+
+        if let Some(theme) = theme.as_object_mut() {
+            for key in self.unsupported_keys() {
+                theme.remove(key);
+            }
+        }
+
+        theme
+    }
+
+    /// Loads the theme, leaving out anything for segments that weren't compiled in
+    pub fn to_theme(&self) -> crate::Result<Theme> {
+        Ok(serde_json::from_value(self.supported_theme())?)
+    }
+
+    /// Features the bundle asks for that weren't compiled in
+    pub fn missing_features(&self) -> Vec<&str> {
+        self.features
+            .iter()
+            .map(String::as_str)
+            .filter(|feature| {
+                registry::BUILTINS.iter().any(|builtin| {
+                    builtin.feature == Some(*feature)
+                        && matches!(registry::lookup(builtin.name), Lookup::Disabled(_))
+                })
+            })
+            .collect()
+    }
+
+    /// Applies the bundle to the raw JSON of a configuration file, leaving everything else alone.
+    ///
+    /// With `name` set the theme is added to the named `themes` instead of replacing the base
+    /// theme, and the glyph set is left as is since that's shared by every theme.
+    pub fn apply(&self, config: &mut serde_json::Value, name: Option<&str>) -> crate::Result<()> {
+        let config = config
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("the configuration isn't a JSON object"))?;
+
+        let theme = self.supported_theme();
+
+        match name {
+            Some(name) => {
+                let themes = config
+                    .entry("themes")
+                    .or_insert_with(|| serde_json::json!({}))
+                    .as_object_mut()
+                    .ok_or_else(|| anyhow!("themes isn't a JSON object"))?;
+
+                themes.insert(name.to_string(), theme);
+            }
+            None => {
+                config.insert("theme".into(), theme);
+                config.insert("glyph_set".into(), serde_json::to_value(self.glyph_set)?);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! A small on-disk cache for segments that are too slow to compute on every prompt.
+//!
+//! Each entry is a JSON file in the cache directory that records when it was written.  The cache
+//! lives in the platform specific cache directory (e.g. `~/.cache/promptr` on Linux) unless
+//! `PROMPTR_CACHE_DIR` is set.  Anything that goes wrong reading the cache is treated as a miss.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use directories::ProjectDirs;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Handle to the cache directory
+#[derive(Clone, Debug)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// What's written out to disk for each entry
+#[derive(Deserialize, Serialize)]
+struct Entry<T> {
+    /// Seconds since the epoch
+    created: u64,
+    value: T,
+}
+
+impl Cache {
+    /// Opens the cache in `dir`
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Opens the cache in `$PROMPTR_CACHE_DIR` or the platform's cache directory
+    pub fn from_env(env: &HashMap<String, String>) -> crate::Result<Self> {
+        if let Some(dir) = env.get("PROMPTR_CACHE_DIR") {
+            return Ok(Self::new(dir));
+        }
+
+        let project_dirs = ProjectDirs::from("com", "inferiorhumanorgans", "promptr")
+            .ok_or_else(|| anyhow!("couldn't create ProjectDirs"))?;
+
+        Ok(Self::new(project_dirs.cache_dir()))
+    }
+
+    /// Returns the directory the cache lives in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Turns anything hashable into a key that's safe to use as a file name
+    pub fn key<H: Hash>(prefix: &str, value: &H) -> String {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+
+        format!("{}-{:016x}", prefix, hasher.finish())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+
+    /// Returns the value stored under `key` if it's younger than `ttl`
+    pub fn get<T: DeserializeOwned>(&self, key: &str, ttl: Duration) -> Option<T> {
+        let contents = fs::read(self.path(key)).ok()?;
+        let entry: Entry<T> = serde_json::from_slice(&contents).ok()?;
+
+        let age = now().checked_sub(entry.created)?;
+
+        match age < ttl.as_secs() {
+            true => Some(entry.value),
+            false => None,
+        }
+    }
+
+    /// Stores `value` under `key`
+    pub fn set<T: Serialize>(&self, key: &str, value: &T) -> crate::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let entry = Entry {
+            created: now(),
+            value,
+        };
+
+        // Write somewhere else first so a concurrent prompt never sees half a file
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp, serde_json::to_vec(&entry)?)?;
+        fs::rename(tmp, path)?;
+
+        Ok(())
+    }
+
+    /// Returns the value stored under `key`, computing and storing it with `f` if it's missing or
+    /// older than `ttl`.  Failures are remembered for `ttl` as well so a broken producer doesn't
+    /// slow down every prompt.
+    pub fn get_or_insert_with<T, F>(&self, key: &str, ttl: Duration, f: F) -> crate::Result<T>
+    where
+        T: DeserializeOwned + Serialize,
+        F: FnOnce() -> crate::Result<T>,
+    {
+        if let Some(value) = self.get(key, ttl) {
+            return Ok(value);
+        }
+
+        let error_key = format!("{}-error", key);
+
+        if let Some(error) = self.get::<String>(&error_key, ttl) {
+            return Err(anyhow!(error));
+        }
+
+        // A cache we can't write to is only a performance problem
+        match f() {
+            Ok(value) => {
+                let _ = self.set(key, &value);
+                Ok(value)
+            }
+            Err(error) => {
+                let _ = self.set(&error_key, &format!("{:#}", error));
+                Err(error)
+            }
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,115 @@
+//! What happens when rendering the prompt panics.  The shell is waiting on a prompt, so instead of
+//! a backtrace on the command line it gets [`FALLBACK_PROMPT`] and the details go to the file at
+//! [`log_path`].
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use anyhow::anyhow;
+use directories::ProjectDirs;
+
+/// What the shell shows if we couldn't come up with anything better
+pub const FALLBACK_PROMPT: &str = "$ ";
+
+/// Set once the real prompt is out, after that there's no taking it back
+static PRINTED: AtomicBool = AtomicBool::new(false);
+
+/// Returns where the details of the last crash are written: `$PROMPTR_STATE_DIR` or the
+/// platform's state directory.  macOS and Windows don't have one of those so the local data
+/// directory stands in.
+pub fn log_path(env: &HashMap<String, String>) -> crate::Result<PathBuf> {
+    let dir = match env.get("PROMPTR_STATE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let project_dirs = ProjectDirs::from("com", "inferiorhumanorgans", "promptr")
+                .ok_or_else(|| anyhow!("couldn't create ProjectDirs"))?;
+
+            project_dirs
+                .state_dir()
+                .unwrap_or_else(|| project_dirs.data_local_dir())
+                .to_path_buf()
+        }
+    };
+
+    Ok(dir.join("crash.log"))
+}
+
+/// Returns the message a panic was started with, `panic!` only ever hands over strings
+pub fn message(payload: &(dyn Any + Send)) -> &str {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .unwrap_or("Box<dyn Any>"),
+    }
+}
+
+/// Formats a crash for the log
+pub fn report(message: &str, location: Option<String>, backtrace: &str) -> String {
+    format!(
+        "promptr {} panicked at {}:\n{}\n\nstack backtrace:\n{}",
+        env!("CARGO_PKG_VERSION"),
+        location.as_deref().unwrap_or("an unknown location"),
+        message,
+        backtrace,
+    )
+}
+
+/// Call once the prompt has been handed to the shell.  A panic after that point can't replace it
+/// with [`FALLBACK_PROMPT`] anymore, so only the log is written.
+pub fn printed() {
+    PRINTED.store(true, Ordering::SeqCst);
+}
+
+/// Replaces the default panic hook with one that prints [`FALLBACK_PROMPT`], keeps the backtrace
+/// in [`log_path`], and exits instead of unwinding.  That's only for the thread that calls this,
+/// the one rendering the prompt.  A panic on any other thread (e.g. a git status scan) is only
+/// logged, the thread goes down and the prompt carries on without whatever it was doing.
+pub fn install(env: HashMap<String, String>) {
+    let render_thread = thread::current().id();
+
+    panic::set_hook(Box::new(move |info| {
+        let backtrace = Backtrace::force_capture();
+        let report = report(
+            message(info.payload()),
+            info.location().map(ToString::to_string),
+            &backtrace.to_string(),
+        );
+
+        let logged = log_path(&env).and_then(|path| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&path, report)?;
+            Ok(path)
+        });
+
+        if thread::current().id() != render_thread {
+            return;
+        }
+
+        if !PRINTED.swap(true, Ordering::SeqCst) {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(FALLBACK_PROMPT.as_bytes());
+            let _ = stdout.flush();
+        }
+
+        match logged {
+            Ok(path) => eprintln!(
+                "promptr crashed, the details are in {}.  Try `promptr doctor`.",
+                path.display()
+            ),
+            Err(_) => eprintln!("promptr crashed.  Try `promptr doctor`."),
+        }
+
+        std::process::exit(1);
+    }));
+}
@@ -0,0 +1,79 @@
+//! Runs external programs without letting them hold up the prompt.
+//!
+//! Everything `promptr` runs on the way to a prompt (hooks, the [`command`](`crate::segment::command`)
+//! segment) gets a deadline and is killed once it's passed.  Output is drained as it comes in so a
+//! chatty program can't fill the pipe and stall, and can be capped so it can't balloon either.
+
+use std::io::Read;
+use std::process::{Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+/// How often to check on a running program
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// A program that finished before its deadline
+#[derive(Debug)]
+pub struct Finished {
+    pub status: ExitStatus,
+
+    /// Everything written to stdout, empty unless stdout was piped
+    pub stdout: Vec<u8>,
+}
+
+/// Starts `command` and waits up to `timeout` for it to finish, returning `None` if it had to be
+/// killed.  Reading stops after `max_output` bytes of stdout, which is an error.
+pub fn run(
+    command: &mut Command,
+    timeout: Duration,
+    max_output: Option<u64>,
+) -> crate::Result<Option<Finished>> {
+    let mut child = command.spawn()?;
+
+    // Read one byte past the limit to tell a full buffer from too much output
+    let reader = child.stdout.take().map(|stdout| {
+        thread::spawn(move || {
+            let mut stdout: Box<dyn Read + Send> = match max_output {
+                Some(max) => Box::new(stdout.take(max + 1)),
+                None => Box::new(stdout),
+            };
+
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).map(|_| output)
+        })
+    });
+
+    let deadline = Instant::now() + timeout;
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+
+            return Ok(None);
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let stdout = match reader {
+        Some(reader) => reader
+            .join()
+            .map_err(|_| anyhow!("couldn't read the output"))??,
+        None => vec![],
+    };
+
+    if let Some(max) = max_output {
+        if stdout.len() as u64 > max {
+            return Err(anyhow!("printed more than {} bytes", max));
+        }
+    }
+
+    Ok(Some(Finished { status, stdout }))
+}
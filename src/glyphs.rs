@@ -0,0 +1,152 @@
+//! Glyph sets pick which flavor of symbols the prompt is drawn with.
+//!
+//! The defaults assume a font patched with the powerline/Nerd Font glyphs and working emoji.  Not
+//! every terminal has those so the glyph set can be pinned in the configuration file:
+//! ```json
+//! { "glyph_set": "ascii" }
+//! ```
+//!
+//! When left at `auto` we try to guess based on the terminal emulator.  Symbols that have been
+//! overridden in the theme are never replaced.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::ansi::{Color, EscapeStyle};
+use crate::render;
+use crate::segment::terminal::Emulator;
+use crate::segment::Segment;
+use crate::shell::Shell;
+use crate::Theme;
+
+/// Glyph sets to pick from when there's no guessing, most capable first
+pub const CHOICES: [GlyphSet; 3] = [GlyphSet::NerdFont, GlyphSet::Emoji, GlyphSet::Ascii];
+
+/// Flavors of symbols that can be rendered
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GlyphSet {
+    /// Guess based on the terminal emulator
+    #[default]
+    Auto,
+    /// Powerline / Nerd Font glyphs, this is what the default theme is built around
+    NerdFont,
+    /// Emoji and widely available unicode, no private use area glyphs
+    Emoji,
+    /// Plain ASCII for the Linux console and friends, every default symbol is swapped for text
+    Ascii,
+}
+
+impl GlyphSet {
+    /// Returns a concrete glyph set, probing the terminal if we were asked to.
+    ///
+    /// The environment is checked first as it's cheap, then we fall back to walking the process
+    /// tree.  If nothing rings a bell we assume the terminal can handle the default theme.
+    pub fn resolve(self, env: &HashMap<String, String>) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+
+        Self::from_env(env)
+            .or_else(|| Emulator::find().and_then(Self::from_emulator))
+            .unwrap_or(Self::NerdFont)
+    }
+
+    /// Draws a few segments of the default theme with this glyph set, both thick and thin
+    /// separators included, so someone can check by eye whether their font is up to it
+    pub fn sample(self) -> String {
+        let mut theme = Theme::default();
+        theme.apply_glyph_set(self);
+
+        let segments = [("~", 31), ("src", 31), ("promptr", 236)]
+            .into_iter()
+            .map(|(text, bg)| {
+                Segment::new("Sample", Color::Numbered(15), Color::Numbered(bg), text)
+            })
+            .collect::<Vec<_>>();
+
+        render::render_prompt(&segments, &theme, Shell::PosixSh, EscapeStyle::Raw)
+    }
+
+    /// Reads the answer to "which of the [`CHOICES`] looks right?", either its number starting
+    /// from one or its name.  Nothing at all means none of them.
+    pub fn choose(answer: &str) -> crate::Result<Option<Self>> {
+        let answer = answer.trim();
+
+        if answer.is_empty() {
+            return Ok(None);
+        }
+
+        let by_number = answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| CHOICES.get(idx.checked_sub(1)?));
+        let by_name = CHOICES
+            .iter()
+            .find(|glyphs| glyphs.to_string().eq_ignore_ascii_case(answer));
+
+        match by_number.or(by_name) {
+            Some(glyphs) => Ok(Some(*glyphs)),
+            None => Err(anyhow!(
+                "{:?} isn't one of the choices, pick 1 to {}",
+                answer,
+                CHOICES.len()
+            )),
+        }
+    }
+
+    fn from_env(env: &HashMap<String, String>) -> Option<Self> {
+        if let Some(term) = env.get("TERM") {
+            match term.as_str() {
+                "linux" | "vt100" | "vt220" | "dumb" => return Some(Self::Ascii),
+                "xterm-kitty" => return Some(Self::NerdFont),
+                _ => {}
+            }
+        }
+
+        if env.contains_key("KITTY_WINDOW_ID") || env.contains_key("WEZTERM_EXECUTABLE") {
+            return Some(Self::NerdFont);
+        }
+
+        match env.get("TERM_PROGRAM").map(String::as_str) {
+            Some("iTerm.app") | Some("WezTerm") | Some("vscode") => Some(Self::NerdFont),
+            Some("Apple_Terminal") => Some(Self::Emoji),
+            _ => None,
+        }
+    }
+
+    fn from_emulator(emulator: Emulator) -> Option<Self> {
+        match emulator {
+            // These bundle or draw the powerline glyphs themselves
+            Emulator::ITerm2 | Emulator::Kitty | Emulator::WezTerm => Some(Self::NerdFont),
+            Emulator::AppleTerminal => Some(Self::Emoji),
+            Emulator::Xterm => Some(Self::Ascii),
+            // Everything else depends on the font
+            _ => None,
+        }
+    }
+}
+
+impl Display for GlyphSet {
+    /// The name used in the configuration file
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Auto => "auto",
+            Self::NerdFont => "nerdfont",
+            Self::Emoji => "emoji",
+            Self::Ascii => "ascii",
+        };
+
+        f.pad(name)
+    }
+}
+
+/// Replaces `symbol` with `replacement` unless it's been changed from `default`.
+pub(crate) fn swap_default(symbol: &mut String, default: &str, replacement: &str) {
+    if symbol == default {
+        *symbol = replacement.to_string();
+    }
+}
@@ -0,0 +1,189 @@
+//! Commands to run before and after the prompt is rendered.
+//!
+//! Hooks are handy for keeping a directory history, poking a terminal integration, or feeding a
+//! dashboard without touching the shell init script.  Each hook is a program and its arguments:
+//! ```json
+//! {
+//!     "hooks": {
+//!         "post_render": [
+//!             { "command": ["sh", "-c", "echo \"$PROMPTR_PWD\" >> ~/.dir_history"] }
+//!         ]
+//!     }
+//! }
+//! ```
+//!
+//! Along with the usual environment each hook gets `PROMPTR_HOOK` (`pre_render` or
+//! `post_render`), `PROMPTR_EXIT_CODE`, and `PROMPTR_PWD`.  Output is thrown away.
+//!
+//! The shell waits on `promptr` so a `pre_render` hook that's still running after its timeout is
+//! killed.  `post_render` hooks are started in the background and left to finish on their own,
+//! the prompt is drawn without waiting on them.  On unix they get `SIGALRM` once their timeout,
+//! rounded up to the second, has passed.
+
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::exec;
+use crate::shell;
+
+/// The `hooks` stanza in the config file
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Run before any segments are rendered
+    pub pre_render: Vec<Hook>,
+
+    /// Run after the prompt has been printed
+    pub post_render: Vec<Hook>,
+}
+
+/// A single command to run
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hook {
+    /// Program and arguments
+    pub command: Vec<String>,
+
+    /// Kill the command if it's still running after this many milliseconds
+    pub timeout_ms: u64,
+}
+
+/// When a hook runs
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Stage {
+    PreRender,
+    PostRender,
+}
+
+impl Default for Hook {
+    fn default() -> Self {
+        Self {
+            command: vec![],
+            timeout_ms: 500,
+        }
+    }
+}
+
+impl Stage {
+    fn name(self) -> &'static str {
+        match self {
+            Self::PreRender => "pre_render",
+            Self::PostRender => "post_render",
+        }
+    }
+}
+
+impl Config {
+    /// Runs every hook for `stage` in order, returning the first error after they've all run
+    pub fn run(&self, stage: Stage, env: &HashMap<String, String>) -> crate::Result<()> {
+        let hooks = match stage {
+            Stage::PreRender => &self.pre_render,
+            Stage::PostRender => &self.post_render,
+        };
+
+        let mut result = Ok(());
+
+        // One broken hook shouldn't keep the others from running
+        for hook in hooks {
+            let hook_result = match stage {
+                Stage::PreRender => hook.run(stage, env),
+                Stage::PostRender => hook.spawn(stage, env),
+            };
+
+            if result.is_ok() {
+                result = hook_result;
+            }
+        }
+
+        result
+    }
+}
+
+impl Hook {
+    /// Runs the command, waiting up to [`Hook::timeout_ms`] for it to finish
+    pub fn run(&self, stage: Stage, env: &HashMap<String, String>) -> crate::Result<()> {
+        let (program, mut command) = self.command(stage, env)?;
+
+        match exec::run(&mut command, Duration::from_millis(self.timeout_ms), None)? {
+            Some(finished) if finished.status.success() => Ok(()),
+            Some(finished) => Err(anyhow!(
+                "{} hook {} failed: {}",
+                stage.name(),
+                program,
+                finished.status
+            )),
+            None => Err(anyhow!(
+                "{} hook {} timed out after {}ms",
+                stage.name(),
+                program,
+                self.timeout_ms
+            )),
+        }
+    }
+
+    /// Starts the command in its own session and returns without waiting for it.  Only failing
+    /// to start is an error, how the command fares is anyone's guess.
+    pub fn spawn(&self, stage: Stage, env: &HashMap<String, String>) -> crate::Result<()> {
+        let (_, mut command) = self.command(stage, env)?;
+
+        #[cfg(unix)]
+        {
+            let seconds = self
+                .timeout_ms
+                .div_ceil(1000)
+                .clamp(1, libc::c_uint::MAX as u64);
+
+            // SAFETY: setsid and alarm are async-signal-safe.  The alarm outlives the exec, so a
+            // hook that overstays its welcome is ended by the default SIGALRM handler.
+            unsafe {
+                command.pre_exec(move || {
+                    libc::setsid();
+                    libc::alarm(seconds as libc::c_uint);
+                    Ok(())
+                });
+            }
+        }
+
+        // Whoever ends up reaping it, it isn't us
+        command.spawn()?;
+
+        Ok(())
+    }
+
+    /// Builds the command for `stage` with the hook environment and no stdio, returning the
+    /// program name for error messages
+    fn command(
+        &self,
+        stage: Stage,
+        env: &HashMap<String, String>,
+    ) -> crate::Result<(&str, Command)> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or_else(|| anyhow!("{} hook has an empty command", stage.name()))?;
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .env("PROMPTR_HOOK", stage.name())
+            .env(
+                "PROMPTR_EXIT_CODE",
+                shell::last_exit_code(env).unwrap_or_default(),
+            )
+            .env(
+                "PROMPTR_PWD",
+                env.get("PWD").map(String::as_str).unwrap_or_default(),
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        Ok((program, command))
+    }
+}
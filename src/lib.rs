@@ -6,17 +6,252 @@
 
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Display};
 
 use promptr_macros::SerializeNonDefault;
 
-#[cfg(any(target_os = "macos", target_os = "freebsd"))]
+#[cfg(all(
+    feature = "ffi",
+    any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    )
+))]
 #[cxx::bridge]
 mod ffi {
     unsafe extern "C++" {
         include!("promptr/ffi/ffi.h");
         fn get_process_name(pid: i64) -> String;
+        fn get_parent_pid(pid: i64) -> i64;
+        fn get_wifi_ssid(interface: &str) -> String;
+    }
+}
+
+/// procfs gets us everything we need on Linux so there's no need to drag a C++ toolchain along.
+/// This mirrors the C++ bridge, including returning an empty string on failure.
+#[cfg(target_os = "linux")]
+mod ffi {
+    use std::fs::read_to_string;
+
+    /// Returns the name of the process `pid` or an empty string if it can't be determined.
+    ///
+    /// Note that the kernel truncates `comm` to 15 bytes.
+    pub fn get_process_name(pid: i64) -> String {
+        read_to_string(format!("/proc/{}/comm", pid))
+            .map(|comm| comm.trim_end_matches('\n').to_string())
+            .unwrap_or_default()
+    }
+
+    /// Returns the parent of process `pid` or -1 if it can't be determined.
+    pub fn get_parent_pid(pid: i64) -> i64 {
+        // The second field is the command name in parens, which may itself contain spaces or
+        // parens, so start looking after the last closing paren.
+        read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| {
+                let (_, fields) = stat.rsplit_once(')')?;
+                fields.split_whitespace().nth(1)?.parse().ok()
+            })
+            .unwrap_or(-1)
+    }
+
+    #[cfg(feature = "segment-wifi")]
+    pub use wifi::get_wifi_ssid;
+
+    #[cfg(feature = "segment-wifi")]
+    mod wifi {
+        /// Returns the SSID of the network `interface` is connected to, or an empty string if it
+        /// isn't connected or can't be determined.  An empty `interface` means the first wireless
+        /// interface that's connected to anything.
+        ///
+        /// The wireless extensions `ioctl` is cheap but optional, the kernel may have been built
+        /// without it.  Failing that, `iw` asks over netlink.
+        pub fn get_wifi_ssid(interface: &str) -> String {
+            let interfaces = match interface {
+                "" => std::fs::read_dir("/sys/class/net")
+                    .map(|entries| {
+                        entries
+                            .filter_map(|entry| entry.ok())
+                            .filter(|entry| {
+                                // wireless is only there with the wireless extensions
+                                let path = entry.path();
+                                path.join("phy80211").exists() || path.join("wireless").exists()
+                            })
+                            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                interface => vec![interface.to_string()],
+            };
+
+            interfaces
+                .iter()
+                .find_map(|interface| essid(interface).or_else(|| iw_link(interface)))
+                .unwrap_or_default()
+        }
+
+        /// `struct iw_point` from `linux/wireless.h`
+        #[repr(C)]
+        #[derive(Copy, Clone)]
+        struct IwPoint {
+            pointer: *mut libc::c_void,
+            length: u16,
+            flags: u16,
+        }
+
+        /// `union iwreq_data` from `linux/wireless.h`, only the parts we need
+        #[repr(C)]
+        union IwReqData {
+            essid: IwPoint,
+            _size: [u8; 16],
+        }
+
+        /// `struct iwreq` from `linux/wireless.h`
+        #[repr(C)]
+        struct IwReq {
+            name: [libc::c_char; libc::IFNAMSIZ],
+            data: IwReqData,
+        }
+
+        const SIOCGIWESSID: libc::c_ulong = 0x8b1b;
+        const IW_ESSID_MAX_SIZE: usize = 32;
+
+        fn essid(interface: &str) -> Option<String> {
+            if interface.len() >= libc::IFNAMSIZ {
+                return None;
+            }
+
+            let mut essid = [0u8; IW_ESSID_MAX_SIZE + 1];
+            let mut request = IwReq {
+                name: [0; libc::IFNAMSIZ],
+                data: IwReqData {
+                    essid: IwPoint {
+                        pointer: essid.as_mut_ptr().cast(),
+                        length: essid.len() as u16,
+                        flags: 0,
+                    },
+                },
+            };
+            for (dst, src) in request.name.iter_mut().zip(interface.bytes()) {
+                *dst = src as libc::c_char;
+            }
+
+            // SAFETY: the request points at a buffer that outlives the call, the kernel writes at
+            // most `length` bytes to it
+            let length = unsafe {
+                let sock = libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0);
+                if sock < 0 {
+                    return None;
+                }
+                let result = libc::ioctl(sock, SIOCGIWESSID as _, &mut request);
+                libc::close(sock);
+                if result < 0 {
+                    return None;
+                }
+                request.data.essid.length as usize
+            };
+
+            let essid = &essid[..length.min(IW_ESSID_MAX_SIZE)];
+            match essid.is_empty() {
+                true => None,
+                false => Some(String::from_utf8_lossy(essid).into_owned()),
+            }
+        }
+
+        fn iw_link(interface: &str) -> Option<String> {
+            let mut command = std::process::Command::new("iw");
+            command
+                .args(["dev", interface, "link"])
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::null());
+
+            let timeout = std::time::Duration::from_millis(250);
+            let finished = crate::exec::run(&mut command, timeout, None).ok()??;
+
+            match finished.status.success() {
+                true => {
+                    let link = String::from_utf8_lossy(&finished.stdout);
+                    crate::platform::iw_link_ssid(&link)
+                }
+                false => None,
+            }
+        }
+    }
+}
+
+/// `/proc` on illumos and Solaris hands out C structs rather than text, but what we need is at a
+/// fixed spot so there's still no need for a C++ toolchain.  This mirrors the C++ bridge,
+/// including returning an empty string on failure.
+#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+mod ffi {
+    use std::fs::read;
+
+    /// Where `pr_ppid` is in a 64-bit `psinfo_t`
+    const PR_PPID: usize = 12;
+
+    /// Where `pr_fname` is in a 64-bit `psinfo_t`, and how long it is
+    const PR_FNAME: usize = 136;
+    const PRFNSZ: usize = 16;
+
+    /// Returns the name of the process `pid` or an empty string if it can't be determined.
+    ///
+    /// Note that the kernel truncates `pr_fname` to 15 bytes.
+    pub fn get_process_name(pid: i64) -> String {
+        read(format!("/proc/{}/psinfo", pid))
+            .ok()
+            .and_then(|psinfo| {
+                let fname = psinfo.get(PR_FNAME..PR_FNAME + PRFNSZ)?;
+                let len = fname.iter().position(|b| *b == 0).unwrap_or(PRFNSZ);
+                Some(String::from_utf8_lossy(&fname[..len]).into_owned())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the parent of process `pid` or -1 if it can't be determined.
+    pub fn get_parent_pid(pid: i64) -> i64 {
+        read(format!("/proc/{}/psinfo", pid))
+            .ok()
+            .and_then(|psinfo| {
+                let ppid = psinfo.get(PR_PPID..PR_PPID + 4)?;
+                Some(i32::from_ne_bytes(ppid.try_into().ok()?) as i64)
+            })
+            .unwrap_or(-1)
+    }
+
+    /// There's no wireless lookup here yet, so act like nothing is connected
+    #[cfg(feature = "segment-wifi")]
+    pub fn get_wifi_ssid(_interface: &str) -> String {
+        String::new()
+    }
+}
+
+/// Without the C++ bridge there's no way to walk the process tree or find the Wi-Fi network, so
+/// act like every lookup failed.  The shell then has to come from `PROMPTR_SHELL` and the
+/// [`terminal`](`segment::terminal`) and `wifi` segments stay hidden.
+#[cfg(all(
+    not(feature = "ffi"),
+    any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    )
+))]
+mod ffi {
+    pub fn get_process_name(_pid: i64) -> String {
+        String::new()
+    }
+
+    pub fn get_parent_pid(_pid: i64) -> i64 {
+        -1
+    }
+
+    #[cfg(feature = "segment-wifi")]
+    pub fn get_wifi_ssid(_interface: &str) -> String {
+        String::new()
     }
 }
 
@@ -24,18 +259,143 @@ mod ffi {
 mod test;
 
 pub mod ansi;
+pub mod bundle;
+pub mod cache;
+pub mod crash;
+pub mod exec;
+pub mod glyphs;
+pub mod hooks;
+pub mod lint;
+pub mod notify;
+pub mod platform;
+pub mod plugin;
+pub mod prompt;
+pub mod redact;
+pub mod registry;
+pub mod render;
+pub mod schedule;
 pub mod segment;
 pub mod shell;
+pub mod stats;
+pub mod template;
 
 /// Result that takes an [`anyhow::Error`]
 pub use anyhow::Result;
 
+/// Serializes every field, including the ones that `SerializeNonDefault` would normally leave
+/// off for matching the default.  Derived along with `SerializeNonDefault`, everything else in a
+/// theme is written out as is.
+pub trait SerializeAll {
+    fn serialize_all<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer;
+}
+
+/// Serializes the value it wraps with [`SerializeAll`]
+pub struct All<'a, T: ?Sized>(pub &'a T);
+
+impl<T: SerializeAll + ?Sized> Serialize for All<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize_all(serializer)
+    }
+}
+
+macro_rules! serialize_all_as_is {
+    ($($type:ty),*) => {
+        $(
+            impl SerializeAll for $type {
+                fn serialize_all<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    self.serialize(serializer)
+                }
+            }
+        )*
+    };
+}
+
+serialize_all_as_is!(
+    bool,
+    usize,
+    String,
+    ansi::Color,
+    ansi::TextStyle,
+    BTreeMap<String, String>,
+    BTreeMap<String, ansi::Color>
+);
+
+impl<T: SerializeAll> SerializeAll for Option<T> {
+    fn serialize_all<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Some(value) => serializer.serialize_some(&All(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<T: SerializeAll> SerializeAll for Vec<T> {
+    fn serialize_all<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.iter().map(All))
+    }
+}
+
+/// Converts `value` to JSON with every field written out, see [`SerializeAll`]
+pub fn to_json_with_defaults<T: SerializeAll>(value: &T) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(All(value))?)
+}
+
 /// Global application state.  Includes information that we've captured from the shell and theme
 /// information.
 #[derive(Clone, Debug)]
 pub struct ApplicationState<'a> {
     pub theme: &'a Theme,
     pub env: HashMap<String, String>,
+    /// Width of the terminal, if the shell told us
+    pub columns: Option<usize>,
+    /// How the rendered prompt will be consumed
+    pub escape_style: ansi::EscapeStyle,
+    /// Set by `PROMPTR_DEMO=1`, names are swapped for placeholders once the segments have been
+    /// rendered, see [`Redactor::demo`](`redact::Redactor::demo`)
+    pub demo: bool,
+}
+
+impl<'a> ApplicationState<'a> {
+    /// Username shown in demo mode
+    pub const DEMO_USER: &'static str = "user";
+
+    /// Hostname shown in demo mode
+    pub const DEMO_HOST: &'static str = "host";
+
+    /// Builds the state from the variables captured by the shell.
+    ///
+    /// When `PROMPTR_DEMO=1` is set the username, hostname, and home directory are to be replaced
+    /// with placeholders so recordings and screenshots don't give anything away.
+    pub fn new(
+        theme: &'a Theme,
+        env: HashMap<String, String>,
+        escape_style: ansi::EscapeStyle,
+    ) -> Self {
+        let columns = env.get("COLUMNS").and_then(|columns| columns.parse().ok());
+        let demo = env.get("PROMPTR_DEMO").map(String::as_str) == Some("1");
+
+        Self {
+            theme,
+            env,
+            columns,
+            escape_style,
+            demo,
+        }
+    }
 }
 
 /// Represents the contents of a JSON config file.
@@ -48,9 +408,28 @@ pub struct PromptrConfig {
     /// Magic number, currently needs to be 12.
     pub promptr_config: u32,
 
-    /// List of segments to render for the left prompt.
+    /// List of segments to render for the left prompt.  Ignored if `lines` is set.
+    ///
+    /// The built-in `newline` segment starts a new line, e.g.:
+    /// ```json
+    /// [{ "name": "path" }, { "name": "newline" }, { "name": "command_status" }]
+    /// ```
+    #[serde(default)]
     pub segments: Vec<SegmentConfig>,
 
+    /// Splits the prompt across multiple lines, each with its own list of segments.  For example
+    /// to put the path on the first line and the prompt indicator on the second:
+    /// ```json
+    /// {
+    ///     "lines": [
+    ///         { "segments": [{ "name": "path" }, { "name": "git" }] },
+    ///         { "segments": [{ "name": "command_status" }] }
+    ///     ]
+    /// }
+    /// ```
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Vec<LineConfig>>,
+
     /// Theme options.  Each module under [`segment`] defines a Theme object with the configurable
     /// colors specific to each segment.  The only parts that need to be specified are those that
     /// you wish to override.  For instance to override only the background color for the [`Hostname`](`segment::hostname`)
@@ -62,8 +441,123 @@ pub struct PromptrConfig {
     /// ```
     ///
     /// In this case `bg` is a [`Color`](`ansi::Color`) object which can be represented by an integer.
+    ///
+    /// A name instead (e.g. `"theme": "gruvbox"`) loads `themes/gruvbox.json` from the
+    /// configuration directory, see [`prompt::load_theme_file`].
+    ///
+    /// `PROMPTR_THEME=gruvbox` in the environment picks a theme by name when the prompt is drawn,
+    /// e.g. for one tmux pane or one host, see [`prompt::select_theme`].
     #[serde(default, skip_serializing_if = "is_default")]
     pub theme: Theme,
+
+    /// Loads the theme from this file instead, with anything in `theme` laid over it.  Relative
+    /// paths start from the configuration directory and `~` is the home directory, e.g.
+    /// `~/.config/promptr/themes/work.json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme_file: Option<String>,
+
+    /// Named themes that can be switched to by the `schedule`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub themes: BTreeMap<String, Theme>,
+
+    /// Switches to one of the `themes` based on the time of day, see [`schedule`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<schedule::Entry>,
+
+    /// Name of one of the `themes` to switch to when the terminal has a light background, or
+    /// `default` for the base theme.  The terminal is only asked if this or `theme_dark` names
+    /// another theme, once per session, see [`prompt::detect_background`].  The `schedule`
+    /// takes precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme_light: Option<String>,
+
+    /// Name of one of the `themes` to switch to when the terminal has a dark background, see
+    /// `theme_light`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme_dark: Option<String>,
+
+    /// Which [`GlyphSet`](`glyphs::GlyphSet`) to draw the prompt with.  Defaults to `auto`
+    /// which guesses based on the terminal emulator.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub glyph_set: glyphs::GlyphSet,
+
+    /// How many colors the terminal can draw, see [`ColorDepth`](`ansi::ColorDepth`).  Defaults
+    /// to `auto` which guesses based on `COLORTERM` and `TERM`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub color_depth: ansi::ColorDepth,
+
+    /// Segments that are cheap enough to render when the system is under pressure.  If this isn't
+    /// set only segments that don't touch the filesystem or spawn processes are rendered:
+    /// `command_status`, `exit_code`, `hostname`, `path`, `screen`, and `username`.
+    ///
+    /// The fast profile is used when `PROMPTR_FAST=1` is set or the one minute load average is
+    /// above `fast_load_threshold`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fast_profile: Option<Vec<String>>,
+
+    /// Segments that are left out when `PROMPTR_DEMO=1` is set because they'd give away who you
+    /// are or what you're working on.  Defaults to `bookmark` and `screen`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub demo_hidden: Option<Vec<String>>,
+
+    /// Switch to the fast profile when the one minute load average goes above this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fast_load_threshold: Option<f64>,
+
+    /// Keep the prompt narrower than this fraction of the terminal (`$COLUMNS`) by dropping or
+    /// truncating segments based on their `priority`, e.g. `0.5` for half the width.  Each line
+    /// of a multiline prompt is fit separately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<f64>,
+
+    /// Last resort for narrow terminals: once everything else has been laid out the prompt is cut
+    /// down from the left so it leaves at least this many columns of `$COLUMNS` free for typing.
+    /// Off if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reserve_columns: Option<usize>,
+
+    /// Remember how long this many of the most recent prompts took to render so `promptr stats`
+    /// can report on them, see [`stats`].  Off if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_history: Option<usize>,
+
+    /// Desktop notifications for long-running commands, see [`notify`].  Disabled if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<notify::Config>,
+    /// Regular expressions for text to mask in every segment, see [`redact`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<String>,
+
+    /// Apply `redact` all the time instead of only when `PROMPTR_REDACT=1` is set.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub redact_always: bool,
+
+    /// Render the prompt as labeled plain text for screen readers instead of colored segments, see
+    /// [`render::render_accessible`].  This can also be turned on with `PROMPTR_A11Y=1`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub accessible: bool,
+
+    /// Write the username, hostname, and working directory into `PS1` as `bash` prompt escapes
+    /// instead of their current values, see [`template`].
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub template: bool,
+
+    /// Commands to run before and after the prompt is rendered, see [`hooks`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<hooks::Config>,
+
+    /// Programs that print segments of their own, used by name like built in segments, see
+    /// [`plugin`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, plugin::Plugin>,
+
+    /// Other names for segments, resolved before anything else looks at the segment list:
+    /// ```json
+    /// { "segment_aliases": { "cwd": "path", "st": "command_status" } }
+    /// ```
+    /// A few old segment names are always recognized, see [`registry::ALIASES`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub segment_aliases: BTreeMap<String, String>,
 }
 
 /// This represents a stanza in the config file that describes a segment. The `args` field is typed
@@ -76,15 +570,46 @@ pub struct SegmentConfig {
 
     #[serde(skip_serializing_if = "SegmentConfig::serialize_optional_json")]
     pub args: Option<serde_json::Value>,
+
+    /// When the prompt is wider than [`PromptrConfig::max_width`] segments with the lowest
+    /// priority are dropped first.  Ties are broken right to left.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub priority: i32,
+
+    /// Shorten the text instead of dropping the segment when there's not enough room
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub truncate: bool,
+
+    /// Always use this separator after the segment(s) this stanza renders instead of picking
+    /// between thick and thin automatically
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub separator: Option<Separator>,
+}
+
+/// A single line of a multiline prompt, see [`PromptrConfig::lines`]
+#[derive(Deserialize, Debug, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LineConfig {
+    pub segments: Vec<SegmentConfig>,
 }
 
 /// Separator shown between segments
 ///
 /// Typically the thick separator is used unless the background of two adjacent segments is the same.
-#[derive(Debug, PartialEq)]
+/// A segment stanza in the config file can pick one instead:
+/// ```json
+/// { "name": "path", "separator": "none" }
+/// { "name": "git", "separator": { "custom": ">" } }
+/// ```
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Separator {
     Thin,
     Thick,
+    /// Nothing but a change in background color
+    None,
+    /// Any string, colored like the thick separator
+    Custom(String),
 }
 
 /// Contains colors for the active theme.
@@ -93,18 +618,67 @@ pub enum Separator {
 #[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
 #[serde(default, deny_unknown_fields)]
 pub struct Theme {
+    /// Theme for the [`aws`](`segment::aws`) segment.
+    pub aws: segment::aws::Theme,
+
     /// Theme for the [`battery_status`](`segment::battery_status`) segment.
     #[cfg(feature = "segment-battery")]
     pub battery: segment::battery_status::Theme,
 
+    /// Theme for the [`bookmark`](`segment::bookmark`) segment.
+    pub bookmark: segment::bookmark::Theme,
+
+    /// Theme for the [`clock`](`segment::clock`) segment.
+    pub clock: segment::clock::Theme,
+
+    /// Theme for the [`command`](`segment::command`) segment.
+    pub command: segment::command::Theme,
+
     /// Theme for the [`command_status`](`segment::command_status`) segment.
     pub command_status: segment::command_status::Theme,
 
+    /// Theme for the [`container`](`segment::container`) segment.
+    pub container: segment::container::Theme,
+
+    /// Theme for the [`cpu_time`](`segment::cpu_time`) segment.
+    pub cpu_time: segment::cpu_time::Theme,
+
+    /// Theme for the [`disk`](`segment::disk`) segment.
+    pub disk: segment::disk::Theme,
+
+    /// Theme for the [`exit_code`](`segment::exit_code`) segment.
+    pub exit_code: segment::exit_code::Theme,
+
+    /// Theme for the [`fill`](`segment::fill`) segment.
+    pub fill: segment::fill::Theme,
+
+    /// Theme for the [`frecency`](`segment::frecency`) segment.
+    pub frecency: segment::frecency::Theme,
+
     /// Theme for the [`hostname`](`segment::hostname`) segment.
     pub hostname: segment::hostname::Theme,
 
-    /// Theme for the version control segments including the [`git`](`segment::git`) segment.
-    #[cfg(feature = "segment-git")]
+    /// Theme for the [`identity`](`segment::identity`) segment.
+    pub identity: segment::identity::Theme,
+
+    /// Theme for the [`instance`](`segment::instance`) segment.
+    pub instance: segment::instance::Theme,
+
+    /// Theme for the [`load`](`segment::load`) segment.
+    pub load: segment::load::Theme,
+
+    /// Theme for the [`memory`](`segment::memory`) segment.
+    pub memory: segment::memory::Theme,
+
+    /// Theme for the [`nix`](`segment::nix`) segment.
+    pub nix: segment::nix::Theme,
+
+    /// Theme for the [`os`](`segment::os`) segment.
+    pub os: segment::os::Theme,
+
+    /// Theme for the version control segments, the [`git`](`segment::git`) and
+    /// [`sapling`](`segment::sapling`) segments.
+    #[serde(deserialize_with = "segment::vcs::deserialize_theme")]
     pub vcs: segment::vcs::Theme,
 
     /// Theme for the [`username`](`segment::username`) segment.
@@ -113,6 +687,9 @@ pub struct Theme {
     /// Theme for the [`path`](`segment::path`) segment.
     pub path: segment::path::Theme,
 
+    /// Theme for the [`rbenv`](`segment::rbenv`) segment.
+    pub rbenv: segment::rbenv::Theme,
+
     /// Theme for the [`rvm`](`segment::rvm`) segment.
     #[cfg(feature = "segment-rvm")]
     pub rvm: segment::rvm::Theme,
@@ -120,7 +697,63 @@ pub struct Theme {
     /// Theme for the [`screen`](`segment::screen`) segment.
     pub screen: segment::screen::Theme,
 
+    /// Theme for the [`tasks`](`segment::tasks`) segment.
+    #[cfg(feature = "segment-tasks")]
+    pub tasks: segment::tasks::Theme,
+
+    /// Theme for the [`terminal`](`segment::terminal`) segment.
+    pub terminal: segment::terminal::Theme,
+
+    /// Theme for the [`thermal`](`segment::thermal`) segment.
+    pub thermal: segment::thermal::Theme,
+
+    /// Theme for the [`wifi`](`segment::wifi`) segment.
+    #[cfg(feature = "segment-wifi")]
+    pub wifi: segment::wifi::Theme,
+
+    /// Theme for the [`wsl`](`segment::wsl`) segment.
+    pub wsl: segment::wsl::Theme,
+
     pub thin_separator_fg: ansi::Color,
+
+    /// Drawn between segments with different backgrounds, should be a single column wide.
+    pub separator_thick: String,
+
+    /// Drawn between segments with the same background, should be a single column wide.
+    pub separator_thin: String,
+
+    /// Drawn after the last segment on a line, defaults to [`Theme::separator_thick`].
+    pub separator_end: Option<String>,
+
+    /// Drawn before the first segment on a line, e.g. `\u{e0b6}` for a rounded cap.  Nothing if
+    /// not set.
+    pub separator_start: Option<String>,
+
+    /// Background behind the caps at either end of a line, for prompts drawn on a colored bar.
+    /// Defaults to the terminal background.
+    pub bar_bg: Option<ansi::Color>,
+
+    /// Shown before the first segment on each line
+    pub prefix: String,
+
+    /// Shown after the last separator on each line
+    pub suffix: String,
+
+    /// Columns of space on either side of the text in each segment
+    pub padding: usize,
+
+    /// Colors with names of their own, any `fg` or `bg` in the theme can refer to one with a `$`
+    /// so changing it here changes it everywhere:
+    /// ```json
+    /// {
+    ///     "palette": { "accent": 208 },
+    ///     "path": { "home_bg": "$accent" },
+    ///     "vcs": { "repo_clean_bg": "$accent" }
+    /// }
+    /// ```
+    /// Named themes can use the base theme's palette as well as their own, see
+    /// [`prompt::resolve_palette`].
+    pub palette: BTreeMap<String, ansi::Color>,
 }
 
 /// For use with `serde`'s `skip_serializing_if` field attribute
@@ -143,18 +776,203 @@ impl Default for PromptrConfig {
                 SegmentConfig {
                     name: "username".into(),
                     args: None,
+                    priority: 0,
+                    truncate: false,
+                    separator: None,
                 },
                 SegmentConfig {
                     name: "path".into(),
                     args: None,
+                    priority: 0,
+                    truncate: false,
+                    separator: None,
                 },
                 SegmentConfig {
                     name: "command_status".into(),
                     args: None,
+                    priority: 0,
+                    truncate: false,
+                    separator: None,
                 },
             ],
 
+            lines: None,
             theme: Theme::default(),
+            theme_file: None,
+            themes: BTreeMap::new(),
+            schedule: vec![],
+            theme_light: None,
+            theme_dark: None,
+            glyph_set: Default::default(),
+            color_depth: Default::default(),
+            fast_profile: None,
+            demo_hidden: None,
+            fast_load_threshold: None,
+            max_width: None,
+            reserve_columns: None,
+            latency_history: None,
+            notifications: None,
+            redact: vec![],
+            redact_always: false,
+            accessible: false,
+            template: false,
+            hooks: None,
+            segment_aliases: BTreeMap::new(),
+            plugins: BTreeMap::new(),
+        }
+    }
+}
+
+impl PromptrConfig {
+    /// Segments that are rendered when no `fast_profile` has been configured
+    const DEFAULT_FAST_PROFILE: [&'static str; 6] = [
+        "command_status",
+        "exit_code",
+        "hostname",
+        "path",
+        "screen",
+        "username",
+    ];
+
+    /// Returns true if we should only render the segments in the fast profile
+    pub fn use_fast_profile(&self, env: &HashMap<String, String>) -> bool {
+        if env.get("PROMPTR_FAST").map(String::as_str) == Some("1") {
+            return true;
+        }
+
+        match (self.fast_load_threshold, platform::load_average()) {
+            (Some(threshold), Some([one_minute, _, _])) => one_minute > threshold,
+            _ => false,
+        }
+    }
+
+    /// Returns the [`Redactor`](`redact::Redactor`) to run segments through, if redaction is on.
+    /// Patterns that don't compile are left out, there's an error for each of them.
+    pub fn redactor(
+        &self,
+        env: &HashMap<String, String>,
+    ) -> (Option<redact::Redactor>, Vec<anyhow::Error>) {
+        let enabled =
+            self.redact_always || env.get("PROMPTR_REDACT").map(String::as_str) == Some("1");
+
+        match enabled && !self.redact.is_empty() {
+            true => {
+                let (redactor, errors) = redact::Redactor::skipping_invalid(&self.redact);
+                (Some(redactor), errors)
+            }
+            false => (None, vec![]),
+        }
+    }
+
+    /// Returns true if the prompt should be rendered for a screen reader
+    pub fn accessible(&self, env: &HashMap<String, String>) -> bool {
+        self.accessible || env.get("PROMPTR_A11Y").map(String::as_str) == Some("1")
+    }
+
+    /// Swaps in the theme the `schedule` calls for at `now`
+    pub fn apply_schedule(&mut self, now: schedule::TimeOfDay) -> Result<()> {
+        let name = match schedule::active(&self.schedule, now) {
+            Some(entry) if entry.theme != schedule::DEFAULT_THEME => &entry.theme,
+            _ => return Ok(()),
+        };
+
+        self.theme = self
+            .themes
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("The schedule refers to an unknown theme: {}", name))?
+            .clone();
+
+        Ok(())
+    }
+
+    /// Returns true if `theme_light` or `theme_dark` would swap in another theme, otherwise
+    /// there's no point asking the terminal about its background
+    pub fn has_background_themes(&self) -> bool {
+        [&self.theme_light, &self.theme_dark]
+            .into_iter()
+            .any(|name| matches!(name, Some(name) if name != schedule::DEFAULT_THEME))
+    }
+
+    /// Swaps in the theme named by `theme_light` or `theme_dark` to suit `background`
+    pub fn apply_background(&mut self, background: ansi::Background) -> Result<()> {
+        let (key, name) = match background {
+            ansi::Background::Light => ("theme_light", &self.theme_light),
+            ansi::Background::Dark => ("theme_dark", &self.theme_dark),
+        };
+
+        let name = match name {
+            Some(name) if name != schedule::DEFAULT_THEME => name,
+            _ => return Ok(()),
+        };
+
+        self.theme = self
+            .themes
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!("{} refers to an unknown theme: {}", key, name))?
+            .clone();
+
+        Ok(())
+    }
+
+    /// Name of the pseudo-segment that starts a new line
+    pub const NEWLINE: &'static str = "newline";
+
+    /// Returns every segment stanza that will be rendered, in order
+    pub fn stanzas(&self) -> Vec<&SegmentConfig> {
+        match &self.lines {
+            Some(lines) if !lines.is_empty() => {
+                lines.iter().flat_map(|line| &line.segments).collect()
+            }
+            _ => self.segments.iter().collect(),
+        }
+    }
+
+    /// Takes the segments to render out of the config, one list per line.  Lines are further
+    /// split wherever there's a [`newline`](`Self::NEWLINE`) segment.  Any aliased segment names
+    /// are replaced with the real thing.
+    pub fn take_lines(&mut self) -> Vec<Vec<SegmentConfig>> {
+        let lines = match self.lines.take() {
+            Some(lines) if !lines.is_empty() => {
+                lines.into_iter().map(|line| line.segments).collect()
+            }
+            _ => vec![std::mem::take(&mut self.segments)],
+        };
+
+        lines
+            .into_iter()
+            .flat_map(|line| {
+                let mut split = vec![vec![]];
+
+                for mut segment in line {
+                    segment.name = registry::resolve(&segment.name, &self.segment_aliases).into();
+
+                    match segment.name == Self::NEWLINE {
+                        true => split.push(vec![]),
+                        false => split.last_mut().expect("never empty").push(segment),
+                    }
+                }
+
+                split
+            })
+            .collect()
+    }
+
+    /// Segments that are hidden in demo mode when no `demo_hidden` has been configured
+    const DEFAULT_DEMO_HIDDEN: [&'static str; 3] = ["bookmark", "command", "screen"];
+
+    /// Returns true if the named segment should be left out in demo mode
+    pub fn hidden_in_demo(&self, name: &str) -> bool {
+        match &self.demo_hidden {
+            Some(hidden) => hidden.iter().any(|hide| hide == name),
+            None => Self::DEFAULT_DEMO_HIDDEN.contains(&name),
+        }
+    }
+
+    /// Returns true if the named segment is part of the fast profile
+    pub fn in_fast_profile(&self, name: &str) -> bool {
+        match &self.fast_profile {
+            Some(profile) => profile.iter().any(|fast| fast == name),
+            None => Self::DEFAULT_FAST_PROFILE.contains(&name),
         }
     }
 }
@@ -170,28 +988,153 @@ impl SegmentConfig {
     }
 }
 
+impl Separator {
+    /// Returns the separator as drawn with `theme`.  The [`Display`] impl always uses the
+    /// powerline glyphs.
+    pub fn glyph<'a>(&'a self, theme: &'a Theme) -> &'a str {
+        match self {
+            Self::Thin => &theme.separator_thin,
+            Self::Thick => &theme.separator_thick,
+            Self::None => "",
+            Self::Custom(custom) => custom,
+        }
+    }
+}
+
 impl Display for Separator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Thin => write!(f, "\u{e0b1}"),
             Self::Thick => write!(f, "\u{e0b0}"),
+            Self::None => Ok(()),
+            Self::Custom(custom) => write!(f, "{}", custom),
         }
     }
 }
 
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: glyphs::GlyphSet) {
+        let default = Self::default();
+
+        let (thick, thin) = match glyphs {
+            glyphs::GlyphSet::Auto | glyphs::GlyphSet::NerdFont => (
+                default.separator_thick.as_str(),
+                default.separator_thin.as_str(),
+            ),
+            // ▶ – black right-pointing triangle, ❯ – heavy right-pointing angle quotation mark
+            glyphs::GlyphSet::Emoji => ("\u{25b6}", "\u{276f}"),
+            glyphs::GlyphSet::Ascii => (">", "|"),
+        };
+        glyphs::swap_default(&mut self.separator_thick, &default.separator_thick, thick);
+        glyphs::swap_default(&mut self.separator_thin, &default.separator_thin, thin);
+
+        if glyphs == glyphs::GlyphSet::Ascii {
+            for (symbol, default_symbol, replacement) in [
+                (&mut self.aws.symbol, &default.aws.symbol, "aws"),
+                (&mut self.bookmark.symbol, &default.bookmark.symbol, "*"),
+                (&mut self.clock.symbol, &default.clock.symbol, ""),
+                (&mut self.cpu_time.symbol, &default.cpu_time.symbol, "cpu"),
+                (&mut self.disk.symbol, &default.disk.symbol, "disk"),
+                (&mut self.exit_code.symbol, &default.exit_code.symbol, "x"),
+                (&mut self.frecency.symbol, &default.frecency.symbol, "#"),
+                (&mut self.instance.symbol, &default.instance.symbol, "vm"),
+                (&mut self.load.symbol, &default.load.symbol, "load"),
+                (&mut self.memory.symbol, &default.memory.symbol, "mem"),
+                (&mut self.nix.symbol, &default.nix.symbol, "nix"),
+                (
+                    &mut self.path.dir_stack_indicator,
+                    &default.path.dir_stack_indicator,
+                    "dirs",
+                ),
+                (&mut self.rbenv.symbol, &default.rbenv.symbol, "rb"),
+                (&mut self.screen.screen_symbol, &default.screen.screen_symbol, "screen"),
+                (&mut self.terminal.symbol, &default.terminal.symbol, "term"),
+                (&mut self.thermal.symbol, &default.thermal.symbol, "temp"),
+                #[cfg(feature = "segment-rvm")]
+                (&mut self.rvm.mismatch_symbol, &default.rvm.mismatch_symbol, " !="),
+            ] {
+                glyphs::swap_default(symbol, default_symbol, replacement);
+            }
+        }
+
+        #[cfg(feature = "segment-battery")]
+        self.battery.apply_glyph_set(glyphs);
+        self.command_status.apply_glyph_set(glyphs);
+        self.container.apply_glyph_set(glyphs);
+        self.hostname.apply_glyph_set(glyphs);
+        #[cfg(feature = "segment-tasks")]
+        self.tasks.apply_glyph_set(glyphs);
+        self.username.apply_glyph_set(glyphs);
+        self.vcs.symbols.apply_glyph_set(glyphs);
+        self.os.apply_glyph_set(glyphs);
+        #[cfg(feature = "segment-wifi")]
+        self.wifi.apply_glyph_set(glyphs);
+        self.wsl.apply_glyph_set(glyphs);
+    }
+
+    /// Swaps out the colors drawn between segments if the terminal can't handle them.  The
+    /// segments take care of their own, see
+    /// [`Segment::downgrade_colors`](`segment::Segment::downgrade_colors`).
+    pub fn downgrade_colors(&mut self, depth: ansi::ColorDepth) {
+        self.thin_separator_fg = self.thin_separator_fg.downgrade(depth);
+        self.bar_bg = self.bar_bg.map(|bg| bg.downgrade(depth));
+    }
+}
+
 impl Default for Theme {
     fn default() -> Self {
         Self {
+            aws: Default::default(),
+            #[cfg(feature = "segment-battery")]
             battery: Default::default(),
+            bookmark: Default::default(),
+            clock: Default::default(),
+            command: Default::default(),
             command_status: Default::default(),
+            container: Default::default(),
+            cpu_time: Default::default(),
+            disk: Default::default(),
+            exit_code: Default::default(),
+            fill: Default::default(),
+            frecency: Default::default(),
             hostname: Default::default(),
+            identity: Default::default(),
+            instance: Default::default(),
+            load: Default::default(),
+            memory: Default::default(),
+            nix: Default::default(),
+            os: Default::default(),
             vcs: Default::default(),
             username: Default::default(),
             path: Default::default(),
+            rbenv: Default::default(),
+            #[cfg(feature = "segment-rvm")]
             rvm: Default::default(),
             screen: Default::default(),
+            #[cfg(feature = "segment-tasks")]
+            tasks: Default::default(),
+            terminal: Default::default(),
+            thermal: Default::default(),
+            #[cfg(feature = "segment-wifi")]
+            wifi: Default::default(),
+            wsl: Default::default(),
 
             thin_separator_fg: ansi::Color::Numbered(244),
+
+            //  – powerline right arrow
+            separator_thick: "\u{e0b0}".into(),
+            //  – powerline right arrow outline
+            separator_thin: "\u{e0b1}".into(),
+            separator_end: None,
+            separator_start: None,
+            bar_bg: None,
+
+            prefix: String::new(),
+            suffix: " ".into(),
+            padding: 1,
+
+            palette: BTreeMap::new(),
         }
     }
 }
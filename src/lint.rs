@@ -0,0 +1,212 @@
+//! Checks for configurations that parse fine but won't look right, used by `promptr validate`.
+//!
+//! Nothing is rendered, so no commands, hooks, or plugins are run.  Colors are checked in the
+//! theme and arguments are checked against what each segment accepts.  Each check returns a list
+//! of human readable warnings, an empty list means all is well.
+
+use crate::ansi::Color;
+use crate::glyphs::GlyphSet;
+use crate::registry::{self, Lookup};
+use crate::{PromptrConfig, SegmentConfig, Theme};
+
+/// Anything below this contrast ratio between the foreground and background is flagged.  This is
+/// well below what's recommended for body text, prompts are short and the default theme is dim.
+pub const MIN_CONTRAST: f64 = 2.0;
+
+/// Theme keys that belong to segments that can be compiled out
+pub(crate) const FEATURE_THEME_KEYS: &[(&str, &str)] =
+    &[("battery", "battery"), ("rvm", "rvm"), ("tasks", "tasks")];
+
+/// WCAG contrast ratio between two colors, from 1 (identical) to 21 (black on white).  Returns
+/// `None` if either color isn't known ahead of time.
+pub fn contrast_ratio(a: Color, b: Color) -> Option<f64> {
+    let luminance = |color: Color| {
+        let (r, g, b) = color.to_rgb()?;
+
+        let linear = |channel: u8| {
+            let channel = channel as f64 / 255.0;
+            match channel <= 0.03928 {
+                true => channel / 12.92,
+                false => ((channel + 0.055) / 1.055).powf(2.4),
+            }
+        };
+
+        Some(0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b))
+    };
+
+    let (a, b) = (luminance(a)?, luminance(b)?);
+    let (light, dark) = match a > b {
+        true => (a, b),
+        false => (b, a),
+    };
+
+    Some((light + 0.05) / (dark + 0.05))
+}
+
+/// Returns why `text` won't render with `glyphs`, if it won't
+fn glyph_problem(text: &str, glyphs: GlyphSet) -> Option<&'static str> {
+    match glyphs {
+        GlyphSet::Ascii if !text.is_ascii() => Some("isn't plain ASCII"),
+        GlyphSet::Emoji if text.chars().any(|c| ('\u{e000}'..='\u{f8ff}').contains(&c)) => {
+            Some("needs a Nerd Font")
+        }
+        _ => None,
+    }
+}
+
+/// Returns a warning if `fg` on `bg` is hard to read, `path` says where the colors came from
+fn color_problem(path: &str, fg: Color, bg: Color) -> Option<String> {
+    if fg == bg && fg != Color::Auto {
+        return Some(format!(
+            "{}: the foreground and background are both {:?}",
+            path, fg
+        ));
+    }
+
+    match contrast_ratio(fg, bg) {
+        Some(ratio) if ratio < MIN_CONTRAST => Some(format!(
+            "{}: {:?} on {:?} is hard to read (contrast {:.1}:1)",
+            path, fg, bg, ratio
+        )),
+        _ => None,
+    }
+}
+
+/// Checks every foreground and background pair in `theme`, i.e. `fg` next to `bg` or `ahead_fg`
+/// next to `ahead_bg`
+pub fn lint_theme_colors(theme: &Theme) -> Vec<String> {
+    fn walk(path: &str, value: &serde_json::Value, warnings: &mut Vec<String>) {
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return,
+        };
+
+        for (key, value) in object {
+            let path = format!("{}.{}", path, key);
+
+            let bg = key
+                .strip_suffix("fg")
+                .and_then(|prefix| object.get(&format!("{}bg", prefix)));
+
+            let colors = bg.and_then(|bg| {
+                Some((
+                    serde_json::from_value::<Color>(value.clone()).ok()?,
+                    serde_json::from_value::<Color>(bg.clone()).ok()?,
+                ))
+            });
+
+            match colors {
+                Some((fg, bg)) => warnings.extend(color_problem(&path, fg, bg)),
+                None => walk(&path, value, warnings),
+            }
+        }
+    }
+
+    let mut warnings = vec![];
+
+    if let Ok(json) = crate::to_json_with_defaults(theme) {
+        walk("theme", &json, &mut warnings);
+    }
+
+    warnings
+}
+
+/// Checks the separators in `theme` against the glyph set
+pub fn lint_theme(theme: &Theme, glyphs: GlyphSet) -> Vec<String> {
+    [
+        ("separator_thick", Some(&theme.separator_thick)),
+        ("separator_thin", Some(&theme.separator_thin)),
+        ("separator_start", theme.separator_start.as_ref()),
+        ("separator_end", theme.separator_end.as_ref()),
+    ]
+    .into_iter()
+    .filter_map(|(key, text)| Some((key, text?)))
+    .filter_map(|(key, text)| {
+        glyph_problem(text, glyphs).map(|problem| {
+            format!(
+                "theme.{}: {:?} {} but the glyph set is {:?}",
+                key, text, problem, glyphs
+            )
+        })
+    })
+    .collect()
+}
+
+/// Every line of segments in `config`
+fn config_lines(config: &PromptrConfig) -> Vec<&[SegmentConfig]> {
+    match &config.lines {
+        Some(lines) if !lines.is_empty() => {
+            lines.iter().map(|line| line.segments.as_slice()).collect()
+        }
+        _ => vec![config.segments.as_slice()],
+    }
+}
+
+/// Checks that every segment exists and that its arguments make sense.  Plugins are left alone,
+/// there's no telling what they accept without running them.
+pub fn lint_segment_args(config: &PromptrConfig) -> Vec<String> {
+    config_lines(config)
+        .into_iter()
+        .flatten()
+        .filter_map(|segment| {
+            let name = registry::resolve(&segment.name, &config.segment_aliases);
+
+            match registry::lookup(name) {
+                Lookup::Unknown if config.plugins.contains_key(name) => None,
+                _ => registry::check(name, segment.args.clone())
+                    .err()
+                    .map(|err| format!("{}: {}", segment.name, err)),
+            }
+        })
+        .collect()
+}
+
+/// Checks for the same segment twice in a row with the same arguments
+pub fn lint_config(config: &PromptrConfig) -> Vec<String> {
+    config_lines(config)
+        .into_iter()
+        .flat_map(|line| line.windows(2))
+        .filter(|pair| {
+            registry::resolve(&pair[0].name, &config.segment_aliases)
+                == registry::resolve(&pair[1].name, &config.segment_aliases)
+                && pair[0].args == pair[1].args
+        })
+        .map(|pair| {
+            format!(
+                "{}: the same segment is listed twice in a row",
+                pair[1].name
+            )
+        })
+        .collect()
+}
+
+/// Checks the raw configuration file for theme settings that belong to segments that weren't
+/// compiled in.  Those keep the whole file from loading so they're checked before parsing.
+pub fn lint_raw(raw: &serde_json::Value) -> Vec<String> {
+    let mut themes = vec![(String::from("theme"), &raw["theme"])];
+
+    if let Some(named) = raw["themes"].as_object() {
+        themes.extend(
+            named
+                .iter()
+                .map(|(name, theme)| (format!("themes.{}", name), theme)),
+        );
+    }
+
+    themes
+        .into_iter()
+        .filter_map(|(path, theme)| Some((path, theme.as_object()?)))
+        .flat_map(|(path, theme)| {
+            FEATURE_THEME_KEYS
+                .iter()
+                .filter(|(key, _)| theme.contains_key(*key))
+                .filter_map(move |(key, segment)| match registry::lookup(segment) {
+                    Lookup::Disabled(feature) => Some(format!(
+                        "{}.{}: the {} segment needs the {} feature, which wasn't compiled in",
+                        path, key, segment, feature
+                    )),
+                    _ => None,
+                })
+        })
+        .collect()
+}
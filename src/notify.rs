@@ -0,0 +1,177 @@
+//! Desktop notifications for long-running commands.
+//!
+//! The shell hook captures how long the previous command took (in microseconds) as `duration_us`.
+//! When that crosses [`Config::min_seconds`] we ask the terminal to pop up a notification, or run
+//! a command of your choosing.  Notifications are off unless there's a `notifications` block in the
+//! config file:
+//! ```json
+//! { "notifications": { "min_seconds": 30, "method": "osc777" } }
+//! ```
+//!
+//! The sequence is written straight to STDERR rather than embedded in the prompt so it isn't
+//! replayed whenever the shell redraws the prompt.
+//!
+//! There's no portable way to ask if the terminal has focus.  On X11 we compare `$WINDOWID` against
+//! the active window and skip the notification if they match.  Everywhere else we leave it up to
+//! the terminal, most of which only show notifications while they're in the background anyway.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ansi::{self, OscCommand};
+use crate::shell;
+
+/// The `notifications` stanza in the config file
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Only notify when the previous command ran for at least this long
+    pub min_seconds: f64,
+
+    /// How to deliver the notification
+    pub method: Method,
+
+    /// Program and arguments to run when `method` is `command`.  The message is appended as the
+    /// last argument.
+    pub command: Vec<String>,
+
+    /// Notify even when the terminal appears to be focused
+    pub when_focused: bool,
+}
+
+/// Ways to deliver a notification
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Method {
+    /// iTerm2 style, also supported by kitty, WezTerm, and Windows Terminal
+    Osc9,
+    /// rxvt-unicode style, also supported by a number of VTE based terminals
+    Osc777,
+    /// Run [`Config::command`]
+    Command,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            min_seconds: 10.0,
+            method: Method::Osc9,
+            command: vec![],
+            when_focused: false,
+        }
+    }
+}
+
+impl Config {
+    /// Returns the notification text if the previous command ran long enough to warrant one
+    pub fn message(&self, env: &HashMap<String, String>) -> Option<String> {
+        let micros = env.get("duration_us")?.parse::<u64>().ok()?;
+        let seconds = micros as f64 / 1_000_000.0;
+
+        if seconds < self.min_seconds {
+            return None;
+        }
+
+        let outcome = match shell::last_exit_code(env) {
+            None | Some("0") => "finished".to_string(),
+            Some(code) => format!("failed with status {}", code),
+        };
+
+        Some(format!(
+            "Command {} after {}",
+            outcome,
+            format_duration(seconds)
+        ))
+    }
+
+    /// Returns the escape sequence for `message`, or `None` if the method doesn't use one
+    pub fn sequence(&self, env: &HashMap<String, String>, message: &str) -> Option<String> {
+        match self.method {
+            Method::Osc9 => Some(ansi::osc_sequence(env, OscCommand::Notify, message)),
+            Method::Osc777 => Some(ansi::osc_sequence(
+                env,
+                OscCommand::NotifyExtended,
+                &format!("notify;promptr;{}", message),
+            )),
+            Method::Command => None,
+        }
+    }
+
+    /// Sends a notification if the previous command was slow and the terminal isn't focused
+    pub fn notify(&self, env: &HashMap<String, String>) -> crate::Result<()> {
+        let message = match self.message(env) {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+
+        if !self.when_focused && is_focused(env) {
+            return Ok(());
+        }
+
+        match self.sequence(env, &message) {
+            Some(sequence) => {
+                let mut stderr = std::io::stderr();
+                stderr.write_all(sequence.as_bytes())?;
+                stderr.flush()?;
+            }
+            None => {
+                let (program, args) = match self.command.split_first() {
+                    Some(split) => split,
+                    None => return Err(anyhow::anyhow!("notifications.command is empty")),
+                };
+
+                // Don't wait around, the prompt has places to be
+                Command::new(program)
+                    .args(args)
+                    .arg(&message)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats `seconds` like `1h2m3s`, `4m5s`, or `6.7s`
+pub fn format_duration(seconds: f64) -> String {
+    let whole = seconds as u64;
+
+    match (whole / 3600, (whole % 3600) / 60, whole % 60) {
+        (0, 0, _) => format!("{:.1}s", seconds),
+        (0, m, s) => format!("{}m{}s", m, s),
+        (h, m, s) => format!("{}h{}m{}s", h, m, s),
+    }
+}
+
+/// Best effort check to see if our terminal window has focus, currently only works on X11
+fn is_focused(env: &HashMap<String, String>) -> bool {
+    let window_id = match (env.get("DISPLAY"), env.get("WINDOWID")) {
+        (Some(_), Some(window_id)) => match window_id.parse::<u64>() {
+            Ok(window_id) => window_id,
+            Err(_) => return false,
+        },
+        _ => return false,
+    };
+
+    // _NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+    let output = match Command::new("xprop")
+        .args(["-root", "_NET_ACTIVE_WINDOW"])
+        .stderr(Stdio::null())
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => return false,
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .rsplit(' ')
+        .next()
+        .and_then(|active| u64::from_str_radix(active.trim().trim_start_matches("0x"), 16).ok())
+        == Some(window_id)
+}
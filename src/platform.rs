@@ -0,0 +1,508 @@
+//! Platform specific odds and ends that aren't tied to any one segment.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Where Linux lists its batteries and chargers
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Where Linux keeps track of how much memory is in use
+pub const MEMINFO: &str = "/proc/meminfo";
+
+/// Where Linux says which kernel is running, and on WSL whose
+pub const PROC_VERSION: &str = "/proc/version";
+
+/// Where Linux lists its temperature sensors
+pub const THERMAL_DIR: &str = "/sys/class/thermal";
+
+/// Where Linux shows what the firmware says about the machine
+pub const DMI_DIR: &str = "/sys/class/dmi/id";
+
+/// Where Linux distributions describe themselves, the second one is only there if the first isn't
+pub const OS_RELEASE: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+
+/// Returns the one, five, and fifteen minute load averages, if the platform has such a thing.
+/// On illumos and Solaris `getloadavg` reads them from kstat.
+#[cfg(unix)]
+pub fn load_average() -> Option<[f64; 3]> {
+    let mut loads = [0f64; 3];
+
+    // SAFETY: we hand getloadavg a buffer with room for exactly as many samples as we ask for
+    let count = unsafe { libc::getloadavg(loads.as_mut_ptr(), 3) };
+
+    match count {
+        3 => Some(loads),
+        _ => None,
+    }
+}
+
+/// Returns the one, five, and fifteen minute load averages, if the platform has such a thing.
+#[cfg(not(unix))]
+pub fn load_average() -> Option<[f64; 3]> {
+    None
+}
+
+/// Returns the name of the operating system `uname` reports, e.g. `Darwin` or `FreeBSD`
+#[cfg(unix)]
+pub fn sysname() -> Option<String> {
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::uname(&mut uts) } < 0 {
+        return None;
+    }
+
+    let sysname = unsafe { std::ffi::CStr::from_ptr(uts.sysname.as_ptr()) };
+    Some(sysname.to_string_lossy().into_owned())
+}
+
+/// Returns the name of the operating system `uname` reports, e.g. `Darwin` or `FreeBSD`
+#[cfg(not(unix))]
+pub fn sysname() -> Option<String> {
+    None
+}
+
+/// Returns the local time of day in minutes since midnight.
+#[cfg(unix)]
+pub fn local_minutes() -> Option<u16> {
+    // SAFETY: time accepts a null pointer and localtime_r only writes to the tm we hand it
+    let tm = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+
+        match libc::localtime_r(&now, &mut tm).is_null() {
+            true => return None,
+            false => tm,
+        }
+    };
+
+    Some((tm.tm_hour * 60 + tm.tm_min) as u16)
+}
+
+/// Returns the local time of day in minutes since midnight.
+#[cfg(not(unix))]
+pub fn local_minutes() -> Option<u16> {
+    None
+}
+
+/// Writes `query` to the terminal and returns its answer, everything up to the reply to the
+/// device attributes request `query` ends with, see [`Background::query`].  Returns `None` if
+/// there's no terminal or it hasn't answered within `timeout`.
+///
+/// An answer that turns up late would otherwise land in the shell's input, so if we've given up
+/// waiting the terminal gets another `timeout` to finish and whatever it says is thrown away.
+///
+/// [`Background::query`]: `crate::ansi::Background::query`
+#[cfg(unix)]
+pub fn query_terminal(query: &str, timeout: Duration) -> Option<String> {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+
+    // SAFETY: tcgetattr only writes to the termios we hand it
+    let original = unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        match libc::tcgetattr(fd, &mut termios) {
+            0 => termios,
+            _ => return None,
+        }
+    };
+
+    // The answer shouldn't be echoed and shouldn't wait for a newline that's never coming
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+
+    // SAFETY: raw is a copy of what the terminal just handed us with a few flags changed
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+
+    let mut ask = || {
+        tty.write_all(query.as_bytes()).ok()?;
+        tty.flush().ok()?;
+
+        let start = Instant::now();
+        let mut reply = vec![];
+
+        let end = loop {
+            if let Some(end) = device_attributes_reply(&reply) {
+                break end;
+            }
+
+            let left = (start + timeout * 2).checked_duration_since(Instant::now())?;
+            let mut poll = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+
+            // SAFETY: poll is handed the one pollfd above, which outlives the call
+            if unsafe { libc::poll(&mut poll, 1, left.as_millis() as libc::c_int) } <= 0 {
+                return None;
+            }
+
+            let mut buf = [0; 64];
+            match tty.read(&mut buf).ok()? {
+                0 => return None,
+                read => reply.extend_from_slice(&buf[..read]),
+            }
+        };
+
+        match start.elapsed() <= timeout {
+            true => String::from_utf8(reply[..end].to_vec()).ok(),
+            false => None,
+        }
+    };
+    let reply = ask();
+
+    // SAFETY: puts back the settings from before
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+
+    reply
+}
+
+/// Writes `query` to the terminal and returns its answer.  Not implemented here yet.
+#[cfg(not(unix))]
+pub fn query_terminal(_query: &str, _timeout: Duration) -> Option<String> {
+    None
+}
+
+/// Returns where the reply to a device attributes request (e.g. `\x1b[?62;22c`) starts in
+/// `reply`, once all of it is there
+pub fn device_attributes_reply(reply: &[u8]) -> Option<usize> {
+    let start = reply.windows(3).rposition(|window| window == b"\x1b[?")?;
+
+    match reply[start + 3..].split_last() {
+        Some((b'c', params)) if params.iter().all(|b| b.is_ascii_digit() || *b == b';') => {
+            Some(start)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the session the process belongs to, which is the same for every prompt drawn in a
+/// terminal and different in the next one
+#[cfg(unix)]
+pub fn session_id() -> Option<u32> {
+    // SAFETY: getsid has no side effects
+    match unsafe { libc::getsid(0) } {
+        -1 => None,
+        session => Some(session as u32),
+    }
+}
+
+/// Returns the session the process belongs to.  Not implemented here yet.
+#[cfg(not(unix))]
+pub fn session_id() -> Option<u32> {
+    None
+}
+
+/// Returns the local time of day as hours, minutes, and seconds.
+#[cfg(unix)]
+pub fn local_time() -> Option<(u8, u8, u8)> {
+    // SAFETY: time accepts a null pointer and localtime_r only writes to the tm we hand it
+    let tm = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+
+        match libc::localtime_r(&now, &mut tm).is_null() {
+            true => return None,
+            false => tm,
+        }
+    };
+
+    Some((tm.tm_hour as u8, tm.tm_min as u8, tm.tm_sec as u8))
+}
+
+/// Returns the local time of day as hours, minutes, and seconds.
+#[cfg(not(unix))]
+pub fn local_time() -> Option<(u8, u8, u8)> {
+    None
+}
+
+/// Returns true if any charger listed under `dir` is plugged in, or None if there are no chargers
+/// to ask.  Outside of Linux there's nothing at [`POWER_SUPPLY_DIR`] so this is always None.
+pub fn on_ac_power(dir: &Path) -> Option<bool> {
+    let read = |path: &Path| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+
+    let online = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| read(&entry.path().join("type")).as_deref() == Some("Mains"))
+        .map(|entry| read(&entry.path().join("online")).as_deref() == Some("1"))
+        .collect::<Vec<_>>();
+
+    match online.is_empty() {
+        true => None,
+        false => Some(online.into_iter().any(|online| online)),
+    }
+}
+
+/// What `apm -b` says about the battery on OpenBSD and NetBSD
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApmBattery {
+    High,
+    Low,
+    Critical,
+    Charging,
+    Absent,
+    Unknown,
+}
+
+/// Parses what `apm -l` (percent left) and `apm -b` (battery status) print on OpenBSD and
+/// NetBSD.  A missing battery comes back as empty whatever the percentage says.
+pub fn parse_apm(life: &str, status: &str) -> Option<(f32, ApmBattery)> {
+    let status = match status.trim().parse::<u8>().ok()? {
+        0 => ApmBattery::High,
+        1 => ApmBattery::Low,
+        2 => ApmBattery::Critical,
+        3 => ApmBattery::Charging,
+        4 => ApmBattery::Absent,
+        _ => ApmBattery::Unknown,
+    };
+
+    let life = life
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|life| (0.0..=100.0).contains(life));
+
+    match (status, life) {
+        (ApmBattery::Absent, _) => Some((0.0, ApmBattery::Absent)),
+        (status, Some(life)) => Some((life, status)),
+        _ => None,
+    }
+}
+
+/// Asks `apm` how the battery is doing, see [`parse_apm`]
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+pub fn apm_battery() -> Option<(f32, ApmBattery)> {
+    parse_apm(&apm("-l")?, &apm("-b")?)
+}
+
+/// Asks `apm` whether the machine is plugged in, None if it doesn't know
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+pub fn apm_on_ac() -> Option<bool> {
+    match apm("-a")?.trim() {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+/// Asks `apm` how many minutes of battery are left, None if it can't estimate that
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+pub fn apm_minutes() -> Option<u64> {
+    apm("-m")?.trim().parse().ok()
+}
+
+#[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+fn apm(flag: &str) -> Option<String> {
+    let output = std::process::Command::new("apm").arg(flag).output().ok()?;
+
+    match output.status.success() {
+        true => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+        false => None,
+    }
+}
+
+/// Picks the SSID out of what `iw dev <interface> link` prints on Linux, which is just
+/// `Not connected.` when there isn't one
+pub fn iw_link_ssid(link: &str) -> Option<String> {
+    link.lines()
+        .find_map(|line| line.trim_start().strip_prefix("SSID: "))
+        .map(|ssid| ssid.trim_end().to_string())
+        .filter(|ssid| !ssid.is_empty())
+}
+
+/// Returns the total and available memory in bytes, as read from `path` in the format of
+/// [`MEMINFO`].  Outside of Linux there's no such file so this is always None.
+pub fn memory(path: &Path) -> Option<(u64, u64)> {
+    let meminfo = fs::read_to_string(path).ok()?;
+
+    // Each line looks like `MemTotal:       16314504 kB`
+    let field = |name: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(name)?.strip_prefix(':')?;
+            let kb = value
+                .trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<u64>()
+                .ok()?;
+            Some(kb * 1024)
+        })
+    };
+
+    Some((field("MemTotal")?, field("MemAvailable")?))
+}
+
+/// Returns the total and available bytes on the filesystem holding `path`, available being what
+/// an unprivileged user could still write
+#[cfg(unix)]
+pub fn disk_space(path: &Path) -> Option<(u64, u64)> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: path is NUL terminated and statvfs only writes to stat when it succeeds
+    let stat = match unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } {
+        0 => unsafe { stat.assume_init() },
+        _ => return None,
+    };
+
+    // The field types vary from platform to platform
+    #[allow(clippy::unnecessary_cast)]
+    let (blocks, available, block_size) = (
+        stat.f_blocks as u64,
+        stat.f_bavail as u64,
+        stat.f_frsize as u64,
+    );
+
+    Some((blocks * block_size, available * block_size))
+}
+
+/// Returns the total and available bytes on the filesystem holding `path`.  Not implemented
+/// here yet.
+#[cfg(not(unix))]
+pub fn disk_space(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+/// Returns each temperature sensor listed under `dir`, in the layout of [`THERMAL_DIR`], along
+/// with its reading in degrees Celsius.  Outside of Linux there's nothing to list.
+pub fn temperatures(dir: &Path) -> Vec<(String, f64)> {
+    let read = |path: &Path| fs::read_to_string(path).ok().map(|s| s.trim().to_string());
+
+    let mut zones = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("thermal_zone")
+            })
+            .filter_map(|entry| {
+                let kind = read(&entry.path().join("type"))?;
+                // Millidegrees
+                let temp = read(&entry.path().join("temp"))?.parse::<i64>().ok()?;
+                Some((kind, temp as f64 / 1000.0))
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => vec![],
+    };
+
+    zones.sort_by(|(a, _), (b, _)| a.cmp(b));
+    zones
+}
+
+/// Returns the DMI field `name` (e.g. `sys_vendor`) from `dir`, in the layout of [`DMI_DIR`].
+/// Outside of Linux there's nothing to read.
+pub fn dmi(dir: &Path, name: &str) -> Option<String> {
+    let value = fs::read_to_string(dir.join(name)).ok()?;
+    let value = value.trim();
+
+    match value.is_empty() {
+        true => None,
+        false => Some(value.to_string()),
+    }
+}
+
+/// Returns true if process `pid` is still running
+#[cfg(unix)]
+pub fn process_alive(pid: i32) -> bool {
+    // SAFETY: signal zero only checks that the process exists and that we could signal it
+    match unsafe { libc::kill(pid, 0) } {
+        0 => true,
+        // It's there, it just isn't ours
+        _ => std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM),
+    }
+}
+
+/// Returns true if process `pid` is still running.  There's no cheap way to ask here so it's
+/// assumed not to be.
+#[cfg(not(unix))]
+pub fn process_alive(_pid: i32) -> bool {
+    false
+}
+
+/// Returns when process `pid` started, in clock ticks since boot.  Along with the pid this tells
+/// a process apart from a later one that was handed the same pid.
+#[cfg(target_os = "linux")]
+pub fn process_start_time(pid: i32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+
+    // The command name in parentheses can have spaces in it, the fields after it can't.
+    // starttime is the 22nd field, the 20th after the command name.
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Returns when process `pid` started.  Not implemented here yet.
+#[cfg(not(target_os = "linux"))]
+pub fn process_start_time(_pid: i32) -> Option<u64> {
+    None
+}
+
+/// Returns true if our root directory isn't the same as that of `init`, which means we're in a
+/// chroot.  Looking at the root of `init` usually takes root, otherwise this is None.
+#[cfg(target_os = "linux")]
+pub fn in_chroot() -> Option<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let ours = fs::metadata("/").ok()?;
+    let init = fs::metadata("/proc/1/root").ok()?;
+
+    Some((ours.dev(), ours.ino()) != (init.dev(), init.ino()))
+}
+
+/// Returns true if we're in a chroot.  Not implemented here yet.
+#[cfg(not(target_os = "linux"))]
+pub fn in_chroot() -> Option<bool> {
+    None
+}
+
+/// Returns true if we're running in a non-global zone, the illumos and Solaris take on a jail
+#[cfg(any(target_os = "illumos", target_os = "solaris"))]
+pub fn in_zone() -> bool {
+    extern "C" {
+        fn getzoneid() -> libc::c_int;
+    }
+
+    /// `GLOBAL_ZONEID` from `<zone.h>`
+    const GLOBAL_ZONEID: libc::c_int = 0;
+
+    // SAFETY: getzoneid takes no arguments and can't fail
+    unsafe { getzoneid() != GLOBAL_ZONEID }
+}
+
+/// Returns true if we've been reached over `ssh`
+pub fn is_ssh(env: &HashMap<String, String>) -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|key| env.contains_key(*key))
+}
+
+/// Returns true if we're running inside a container.
+///
+/// systemd-nspawn, podman, and friends set `$container`.  Docker and podman also drop a marker
+/// file in the root of the container.
+pub fn in_container(env: &HashMap<String, String>) -> bool {
+    env.contains_key("container")
+        || ["/.dockerenv", "/run/.containerenv"]
+            .iter()
+            .any(|marker| Path::new(marker).exists())
+}
@@ -0,0 +1,145 @@
+//! Segments that live outside of `promptr`.
+//!
+//! A plugin is any program that prints segments as JSON, either one object or a list of them:
+//! ```json
+//! { "text": "☀ 21°C", "fg": 0, "bg": 220, "metadata": { "celsius": 21 } }
+//! ```
+//! Plugins are declared in the `plugins` stanza and used like any other segment by name.  They
+//! aren't trusted: each one is killed after its timeout, can only print so much, and only sees
+//! the environment variables it's been allowed:
+//! ```json
+//! {
+//!     "plugins": {
+//!         "weather": { "command": ["/home/me/bin/weather", "--short"], "env": ["HOME", "PATH"] }
+//!     },
+//!     "segments": [{ "name": "path" }, { "name": "weather" }]
+//! }
+//! ```
+//!
+//! The plugin runs in the current directory.  Anything it prints that isn't valid JSON matching
+//! the schema above (unknown keys included) is rejected, `promptr plugins test <name>` shows what
+//! a plugin produces without putting it in the prompt.
+
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::ansi::{Color, EscapeStyle};
+use crate::exec;
+use crate::segment::Segment;
+
+/// An entry in the `plugins` stanza
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Plugin {
+    /// Program and arguments
+    pub command: Vec<String>,
+
+    /// Kill the plugin if it's still running after this many milliseconds
+    pub timeout_ms: u64,
+
+    /// Reject the plugin's output if it's longer than this
+    pub max_output_bytes: u64,
+
+    /// Environment variables passed along to the plugin, nothing else is
+    pub env: Vec<String>,
+}
+
+/// A segment as printed by a plugin
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Printed {
+    text: String,
+    fg: Color,
+    bg: Color,
+    #[serde(default)]
+    metadata: Option<serde_json::Value>,
+}
+
+impl Default for Plugin {
+    fn default() -> Self {
+        Self {
+            command: vec![],
+            timeout_ms: 250,
+            max_output_bytes: 4096,
+            env: vec!["HOME".into(), "LANG".into(), "PATH".into()],
+        }
+    }
+}
+
+impl Plugin {
+    /// Runs the plugin and checks what it printed, see [`Plugin::parse`]
+    pub fn run(
+        &self,
+        name: &str,
+        env: &HashMap<String, String>,
+        style: EscapeStyle,
+    ) -> crate::Result<Vec<Segment>> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or_else(|| anyhow!("plugin {} has an empty command", name))?;
+
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .env_clear()
+            .envs(self.env.iter().filter_map(|key| Some((key, env.get(key)?))))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        if let Some(pwd) = env.get("PWD") {
+            command.current_dir(pwd);
+        }
+
+        let finished = exec::run(
+            &mut command,
+            Duration::from_millis(self.timeout_ms),
+            Some(self.max_output_bytes),
+        )
+        .with_context(|| format!("plugin {}", name))?
+        .ok_or_else(|| anyhow!("plugin {} timed out after {}ms", name, self.timeout_ms))?;
+
+        if !finished.status.success() {
+            return Err(anyhow!("plugin {} failed: {}", name, finished.status));
+        }
+
+        Self::parse(&finished.stdout, style).with_context(|| format!("plugin {}", name))
+    }
+
+    /// Turns what a plugin printed into segments.  The text is shown as is, see
+    /// [`EscapeStyle::escape_literal`].  Printing nothing at all hides the segment.
+    pub fn parse(output: &[u8], style: EscapeStyle) -> crate::Result<Vec<Segment>> {
+        if output.iter().all(u8::is_ascii_whitespace) {
+            return Ok(vec![]);
+        }
+
+        // Plugins can print a single segment or a list.  Checking which up front gives better
+        // errors than an untagged enum would.
+        let printed: Vec<Printed> = match serde_json::from_slice(output)? {
+            list @ serde_json::Value::Array(_) => serde_json::from_value(list)?,
+            one => vec![serde_json::from_value(one)?],
+        };
+
+        Ok(printed
+            .into_iter()
+            .map(|printed| {
+                let segment = Segment::new(
+                    "Plugin",
+                    printed.fg,
+                    printed.bg,
+                    style.escape_literal(&printed.text),
+                );
+
+                match printed.metadata {
+                    Some(metadata) => segment.with_metadata(metadata),
+                    None => segment,
+                }
+            })
+            .collect())
+    }
+}
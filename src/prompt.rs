@@ -0,0 +1,656 @@
+//! Renders a whole prompt, from reading the configuration file to the escaped text the shell
+//! shows.
+//!
+//! Everything here takes the environment as a map instead of reading it from the process.  The
+//! `promptr` binary hands over its own, the bash builtin hands over the shell's exported
+//! variables since those are the ones a child process would have seen.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use directories::{BaseDirs, ProjectDirs};
+use itertools::Itertools;
+
+use crate::ansi::{Background, EscapeStyle};
+use crate::cache::Cache;
+use crate::hooks::{self, Hook, Stage};
+use crate::notify;
+use crate::platform;
+use crate::plugin::Plugin;
+use crate::redact::Redactor;
+use crate::registry::{self, Lookup};
+use crate::render::{self, Stanza};
+use crate::schedule::{self, TimeOfDay};
+use crate::segment::{Frecency, Segment};
+use crate::shell::{nested_render, Shell};
+use crate::stats::{self, Sample};
+use crate::template;
+use crate::{ApplicationState, PromptrConfig, SegmentConfig, Theme};
+
+/// How long the terminal has to say what color its background is, see [`apply_background`]
+pub const BACKGROUND_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How long the terminal's background is remembered for, see [`detect_background`]
+pub const BACKGROUND_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A prompt that's ready to be shown, see [`render`]
+pub struct Rendered {
+    /// Segments on each line of the prompt
+    pub lines: Vec<Vec<Segment>>,
+    /// Text to hand to the shell
+    pub text: String,
+    hooks: hooks::Config,
+    notifications: Option<notify::Config>,
+    env: HashMap<String, String>,
+}
+
+/// Returns the directory the configuration file lives in, creating it if need be
+pub fn config_dir() -> crate::Result<PathBuf> {
+    let project_dirs = ProjectDirs::from("com", "inferiorhumanorgans", "promptr")
+        .ok_or_else(|| anyhow!("couldn't create ProjectDirs"))?;
+    let config_dir = project_dirs.config_dir();
+
+    if let Err(error) = fs::metadata(config_dir) {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => {
+                fs::create_dir_all(config_dir)?;
+            }
+            _ => Err(error)?,
+        }
+    };
+
+    Ok(config_dir.into())
+}
+
+/// Loads the configuration from disk
+///
+/// ## Arguments
+///
+/// * `quiet` – Whether or not to print parsing errors to STDERR
+pub fn load_config(quiet: bool) -> PromptrConfig {
+    let config_dir = match config_dir() {
+        Ok(config_dir) => config_dir,
+        Err(_) => return PromptrConfig::default(),
+    };
+
+    let parse_error = |e: serde_json::Error| {
+        if !quiet {
+            eprintln!("JSON parsing error, using default config.");
+            eprintln!("{:?}", e);
+        }
+        anyhow!("{}", e)
+    };
+
+    File::open(config_dir.join("promptr.json"))
+        .map_err(|e| e.into()) // Into anyhow
+        .and_then(|file| {
+            let mut raw: serde_json::Value = serde_json::from_reader(file).map_err(parse_error)?;
+
+            // A theme that can't be found shouldn't cost the whole layout
+            if let Err(err) = load_theme_file(&mut raw, &config_dir) {
+                if !quiet {
+                    eprintln!("Error in promptr: {:?}", err);
+                }
+                if raw["theme"].is_string() {
+                    raw["theme"] = serde_json::json!({});
+                }
+            }
+
+            if let Err(err) = resolve_palette(&mut raw) {
+                if !quiet {
+                    eprintln!("Error in promptr: {:?}", err);
+                }
+            }
+
+            serde_json::from_value(raw).map_err(parse_error)
+        })
+        .unwrap_or_default()
+}
+
+/// Swaps a theme kept in a file of its own into the raw JSON of a configuration.
+///
+/// `"theme": "gruvbox"` uses the named theme `gruvbox` from `themes` if there is one, otherwise
+/// it reads `themes/gruvbox.json` under `config_dir`.  `"theme_file"` reads
+/// the file it names, with `~` for the home directory and relative paths starting from
+/// `config_dir`, and a `theme` object alongside it is laid over the top.
+pub fn load_theme_file(raw: &mut serde_json::Value, config_dir: &Path) -> crate::Result<()> {
+    let config = match raw.as_object_mut() {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+
+    let path = match (config.get("theme"), config.get("theme_file")) {
+        (Some(serde_json::Value::String(_)), Some(_)) => {
+            return Err(anyhow!("theme can't be a name when theme_file is set"))
+        }
+        (Some(serde_json::Value::String(name)), None) => {
+            if let Some(theme) = config.get("themes").and_then(|themes| themes.get(name)) {
+                let theme = theme.clone();
+                config.insert("theme".into(), theme);
+                return Ok(());
+            }
+
+            config_dir.join("themes").join(format!("{}.json", name))
+        }
+        (_, Some(serde_json::Value::String(file))) => match file.strip_prefix("~/") {
+            Some(rest) => BaseDirs::new()
+                .ok_or_else(|| anyhow!("couldn't find the home directory"))?
+                .home_dir()
+                .join(rest),
+            None => config_dir.join(file),
+        },
+        (_, Some(_)) => return Err(anyhow!("theme_file should be a path")),
+        (_, None) => return Ok(()),
+    };
+
+    let file = File::open(&path).with_context(|| format!("couldn't open {}", path.display()))?;
+    let mut theme: serde_json::Value = serde_json::from_reader(file)
+        .with_context(|| format!("couldn't read the theme in {}", path.display()))?;
+
+    if let Some(inline @ serde_json::Value::Object(_)) = config.remove("theme") {
+        overlay(&mut theme, inline);
+    }
+    config.insert("theme".into(), theme);
+
+    Ok(())
+}
+
+/// Swaps references to the theme's `palette` (e.g. `"$accent"`) in the raw JSON of a
+/// configuration for the colors they name, anywhere in the theme including lists like the
+/// hostname's `hash_palette`.  Named themes in `themes` can use the base theme's palette as well
+/// as their own.
+///
+/// References to colors that aren't in the palette are dropped so the defaults are used instead,
+/// they're all listed in the error once everything else has been resolved.  That's only done for
+/// `fg` and `bg` keys and lists, elsewhere text that happens to start with a `$` is left alone.
+pub fn resolve_palette(raw: &mut serde_json::Value) -> crate::Result<()> {
+    let base = raw["theme"]["palette"]
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+
+    let mut missing = vec![];
+
+    if let Some(theme) = raw.get_mut("theme") {
+        resolve_references("theme", theme, &base, &mut missing, false);
+    }
+
+    if let Some(serde_json::Value::Object(themes)) = raw.get_mut("themes") {
+        for (name, theme) in themes.iter_mut() {
+            let mut palette = base.clone();
+            if let Some(own) = theme["palette"].as_object() {
+                palette.extend(own.clone());
+            }
+
+            let path = format!("themes.{}", name);
+            resolve_references(&path, theme, &palette, &mut missing, false);
+        }
+    }
+
+    match missing.is_empty() {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "no such colors in the palette: {}",
+            missing.join(", ")
+        )),
+    }
+}
+
+/// Resolves the palette references under `value` for [`resolve_palette`], `path` says where
+/// `value` is for the error.  Returns false if `value` is a reference to a missing color where
+/// one is expected, i.e. `color` is set, and should be dropped.
+fn resolve_references(
+    path: &str,
+    value: &mut serde_json::Value,
+    palette: &serde_json::Map<String, serde_json::Value>,
+    missing: &mut Vec<String>,
+    color: bool,
+) -> bool {
+    match value {
+        serde_json::Value::Object(object) => {
+            object.retain(|key, value| {
+                let color = key.ends_with("fg") || key.ends_with("bg");
+                let path = format!("{}.{}", path, key);
+                key == "palette" || resolve_references(&path, value, palette, missing, color)
+            });
+            return true;
+        }
+        // Lists in a theme are colors, e.g. the hostname's `hash_palette`
+        serde_json::Value::Array(array) => {
+            let mut index = 0;
+            array.retain_mut(|value| {
+                let path = format!("{}[{}]", path, index);
+                index += 1;
+                resolve_references(&path, value, palette, missing, true)
+            });
+            return true;
+        }
+        _ => {}
+    }
+
+    let name = match value.as_str().and_then(|text| text.strip_prefix('$')) {
+        Some(name) => name.to_string(),
+        None => return true,
+    };
+
+    match palette.get(&name) {
+        Some(found) => {
+            *value = found.clone();
+            true
+        }
+        None if color => {
+            missing.push(format!("{} (${})", path, name));
+            false
+        }
+        None => true,
+    }
+}
+
+/// Returns the names of the theme files under `config_dir`, see [`load_theme_file`]
+pub fn theme_files(config_dir: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(config_dir.join("themes")) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            match path.extension()?.to_str()? {
+                "json" => Some(path.file_stem()?.to_str()?.to_string()),
+                _ => None,
+            }
+        })
+        .sorted()
+        .collect()
+}
+
+/// Segments drawn by [`preview`]
+const PREVIEW_SEGMENTS: [&str; 4] = ["username", "hostname", "path", "command_status"];
+
+/// Draws a sample prompt with `theme`.  The segments are fed made up values so nothing about
+/// this machine or the current directory shows up, or is needed.
+pub fn preview(theme: &Theme) -> String {
+    let env = [
+        ("USER", "user"),
+        ("hostname", "host.example.com"),
+        ("HOME", "/home/user"),
+        ("PWD", "/home/user/src/promptr"),
+        ("code", "0"),
+        ("uid", "1000"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+
+    let state = ApplicationState {
+        theme,
+        env,
+        columns: None,
+        escape_style: EscapeStyle::Raw,
+        demo: false,
+    };
+
+    let segments = PREVIEW_SEGMENTS
+        .iter()
+        .filter_map(|name| registry::render(name, None, &state).ok())
+        .flatten()
+        .collect_vec();
+
+    render::render_prompt(&segments, theme, Shell::PosixSh, EscapeStyle::Raw)
+}
+
+/// Lays `top` over `base`, objects are merged key by key and anything else replaces what was
+/// there
+fn overlay(base: &mut serde_json::Value, top: serde_json::Value) {
+    match (base, top) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(top)) => {
+            for (key, value) in top {
+                match base.get_mut(&key) {
+                    Some(existing) => overlay(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, top) => *base = top,
+    }
+}
+
+/// Switches to the theme the `schedule` calls for right now, this has to happen before anything
+/// else touches the theme
+pub fn apply_schedule(config: &mut PromptrConfig) {
+    if let Some(now) = platform::local_minutes() {
+        if let Err(err) = config.apply_schedule(TimeOfDay(now)) {
+            eprintln!("Error in promptr: {:?}", err);
+        }
+    }
+}
+
+/// Picks the theme for this prompt.  `PROMPTR_THEME` names one to use no matter what, otherwise
+/// `theme_light` or `theme_dark` and then the `schedule` have their say.  A `PROMPTR_THEME` that
+/// can't be found is reported and ignored.
+pub fn select_theme(config: &mut PromptrConfig, env: &HashMap<String, String>) {
+    if let Some(name) = env.get("PROMPTR_THEME").filter(|name| !name.is_empty()) {
+        match named_theme(config, name) {
+            Ok(theme) => {
+                config.theme = theme;
+                return;
+            }
+            Err(err) => eprintln!("Error in promptr: {:?}", err),
+        }
+    }
+
+    apply_background(config, env);
+    apply_schedule(config);
+}
+
+/// Finds the theme called `name`.  `default` is the base theme, after that come the named
+/// `themes` in the configuration (where imported bundles end up) and then the theme files in the
+/// configuration directory, see [`load_theme_file`].
+pub fn named_theme(config: &PromptrConfig, name: &str) -> crate::Result<Theme> {
+    if name == schedule::DEFAULT_THEME {
+        return Ok(config.theme.clone());
+    }
+
+    if let Some(theme) = config.themes.get(name) {
+        return Ok(theme.clone());
+    }
+
+    let mut raw = serde_json::json!({ "theme": name });
+    load_theme_file(&mut raw, &config_dir()?)
+        .with_context(|| format!("there's no theme called {}", name))?;
+    resolve_palette(&mut raw)?;
+
+    serde_json::from_value(raw["theme"].take())
+        .with_context(|| format!("couldn't read the theme called {}", name))
+}
+
+/// Swaps in `theme_light` or `theme_dark` to suit the terminal, if either names a theme.  The
+/// terminal isn't asked otherwise, see [`detect_background`].
+pub fn apply_background(config: &mut PromptrConfig, env: &HashMap<String, String>) {
+    if !config.has_background_themes() {
+        return;
+    }
+
+    if let Some(background) = detect_background(env) {
+        if let Err(err) = config.apply_background(background) {
+            eprintln!("Error in promptr: {:?}", err);
+        }
+    }
+}
+
+/// Returns the terminal's background, see [`Background::detect`].  Terminals that don't set
+/// `COLORFGBG` are asked once per session and get [`BACKGROUND_TIMEOUT`] to answer, the answer
+/// (or lack of one) is cached for [`BACKGROUND_TTL`] so later prompts don't wait on it.
+pub fn detect_background(env: &HashMap<String, String>) -> Option<Background> {
+    let session = match env.contains_key("COLORFGBG") {
+        true => None,
+        false => platform::session_id(),
+    };
+
+    // Without a cache there's no remembering, so it's asked every time
+    match (session, Cache::from_env(env)) {
+        (Some(session), Ok(cache)) => cache
+            .get_or_insert_with(&Cache::key("background", &session), BACKGROUND_TTL, || {
+                Ok(Background::detect(env, BACKGROUND_TIMEOUT))
+            })
+            .ok()
+            .flatten(),
+        _ => Background::detect(env, BACKGROUND_TIMEOUT),
+    }
+}
+
+/// Resolves the configured [`GlyphSet`](`crate::glyphs::GlyphSet`) and swaps it into the theme,
+/// along with any colors the terminal can't draw
+pub fn apply_glyph_set(config: &mut PromptrConfig, env: &HashMap<String, String>) {
+    let glyph_set = config.glyph_set.resolve(env);
+    config.theme.apply_glyph_set(glyph_set);
+
+    let color_depth = config.color_depth.resolve(env);
+    config.theme.downgrade_colors(color_depth);
+}
+
+/// Hooks that segments in the configuration ask for, e.g. [`Frecency`] recording visits
+pub fn implied_hooks(config: &PromptrConfig, env: &HashMap<String, String>) -> Vec<Hook> {
+    let pwd = env.get("PWD").map(String::as_str).unwrap_or_default();
+
+    config
+        .stanzas()
+        .into_iter()
+        .filter(|stanza| registry::resolve(&stanza.name, &config.segment_aliases) == "frecency")
+        .filter_map(
+            |stanza| match Frecency::visit_hook(stanza.args.clone(), pwd) {
+                Ok(hook) => hook,
+                Err(err) => {
+                    eprintln!("Error in promptr: {:?}", err);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Renders the segments for a single stanza in the configuration.  Built in segments take
+/// precedence over plugins of the same name.
+pub fn render_stanza(
+    SegmentConfig {
+        name,
+        args,
+        priority,
+        truncate,
+        separator,
+    }: SegmentConfig,
+    plugins: &BTreeMap<String, Plugin>,
+    state: &ApplicationState,
+) -> crate::Result<Stanza> {
+    let segments = match (registry::lookup(&name), plugins.get(&name)) {
+        (Lookup::Unknown, Some(plugin)) => plugin.run(&name, &state.env, state.escape_style)?,
+        _ => registry::render(&name, args, state)?,
+    };
+
+    let segments = match separator {
+        Some(separator) => segments
+            .into_iter()
+            .map(|mut segment| {
+                segment.separator_override = Some(separator.clone());
+                segment
+            })
+            .collect(),
+        None => segments,
+    };
+
+    Ok(Stanza {
+        priority,
+        truncate,
+        segments,
+    })
+}
+
+/// Runs through the current configuration and renders each segment, line by line.
+///
+/// ## Arguments
+///
+/// * `config` – the configuration instance to iterate over
+/// * `escape_style` – how the rendered prompt will be consumed
+/// * `env` – the environment the shell would hand a child process
+///
+/// ## Returns
+///
+/// A [`Vec`] of [`Segment`]s for each line of the prompt.
+pub fn load_lines(
+    mut config: PromptrConfig,
+    escape_style: EscapeStyle,
+    env: HashMap<String, String>,
+) -> crate::Result<Vec<Vec<Segment>>> {
+    let started = Instant::now();
+    let mut sample = Sample::default();
+
+    let lines = config.take_lines();
+
+    let state = ApplicationState::new(&config.theme, env, escape_style);
+
+    assert_eq!(config.promptr_config, 12);
+
+    let fast = config.use_fast_profile(&state.env);
+    let color_depth = config.color_depth.resolve(&state.env);
+    let (redactor, errors) = config.redactor(&state.env);
+    errors
+        .iter()
+        .for_each(|err| eprintln!("Error in promptr: {:?}", err));
+    let demo = state.demo.then(|| Redactor::demo(&state.env));
+    // Demo mode has to hide the real values, which is exactly what bash would put back
+    let template = config.template && escape_style == EscapeStyle::Bash && !state.demo;
+
+    let max_width = match (config.max_width, state.columns) {
+        (Some(fraction), Some(columns)) => Some((fraction * columns as f64) as usize),
+        _ => None,
+    };
+
+    let lines = lines
+        .into_iter()
+        .map(|segment_configs| {
+            segment_configs
+                .into_iter()
+                .filter(|SegmentConfig { name, .. }| !fast || config.in_fast_profile(name))
+                .filter(|SegmentConfig { name, .. }| !state.demo || !config.hidden_in_demo(name))
+                .map(|segment_config| {
+                    let name = segment_config.name.clone();
+                    let started = Instant::now();
+                    let stanza = render_stanza(segment_config, &config.plugins, &state);
+                    sample.add(&name, started.elapsed());
+                    stanza
+                })
+                .filter_map(|stanza_result| match stanza_result {
+                    Ok(mut stanza) => {
+                        for redactor in redactor.iter().chain(demo.iter()) {
+                            stanza
+                                .segments
+                                .iter_mut()
+                                .for_each(|segment| redactor.redact_segment(segment));
+                        }
+                        if template {
+                            template::apply(&mut stanza.segments, &state.env);
+                        }
+                        Some(stanza)
+                    }
+                    Err(err) => {
+                        eprintln!("Error in promptr: {:?}", err);
+                        None
+                    }
+                })
+                .collect_vec()
+        })
+        .map(|stanzas| {
+            let mut segments = match max_width {
+                Some(max_width) => render::fit(stanzas, max_width, state.theme),
+                None => stanzas
+                    .into_iter()
+                    .flat_map(|stanza| stanza.segments)
+                    .collect_vec(),
+            };
+
+            if let (Some(reserve), Some(columns)) = (config.reserve_columns, state.columns) {
+                render::clamp(&mut segments, columns.saturating_sub(reserve), state.theme);
+            }
+
+            render::place_right(&mut segments, state.columns, state.theme);
+
+            if let Some(columns) = state.columns {
+                render::expand_fills(&mut segments, columns, state.theme);
+            }
+
+            segments
+                .iter_mut()
+                .for_each(|segment| segment.downgrade_colors(color_depth));
+
+            segments
+        })
+        .collect_vec();
+
+    if let Some(keep) = config.latency_history {
+        sample.total_us = started.elapsed().as_micros() as u64;
+
+        // Losing a sample isn't worth interrupting the prompt over
+        if let Ok(log) = stats::Log::from_env(&state.env) {
+            let _ = log.record(&sample, keep);
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Returns true if `NO_COLOR` is set to anything but an empty string, in which case the prompt is
+/// rendered without escape sequences, see <https://no-color.org>
+pub fn no_color(env: &HashMap<String, String>) -> bool {
+    env.get("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Renders the prompt for `shell` the way the configuration file asks, running the pre-render
+/// hooks along the way.  Call [`Rendered::finish`] once the shell has the text.
+pub fn render(
+    shell: Shell,
+    escape_style: EscapeStyle,
+    env: HashMap<String, String>,
+) -> crate::Result<Rendered> {
+    // A shell started by a hook or segment that loads promptr again shouldn't kick off another
+    // full render, that way lies a pile of nested prompts
+    if nested_render(&env) {
+        return Ok(Rendered {
+            lines: vec![],
+            text: render::render_lines(&[], &Theme::default(), shell, escape_style),
+            hooks: Default::default(),
+            notifications: None,
+            env,
+        });
+    }
+
+    let mut config = load_config(false);
+    select_theme(&mut config, &env);
+    apply_glyph_set(&mut config, &env);
+    let theme = config.theme.clone();
+    let accessible = config.accessible(&env);
+    let plain = no_color(&env);
+    let notifications = config.notifications.take();
+    let mut hooks = config.hooks.take().unwrap_or_default();
+    hooks.post_render.extend(implied_hooks(&config, &env));
+
+    if let Err(err) = hooks.run(Stage::PreRender, &env) {
+        eprintln!("Error in promptr: {:?}", err);
+    }
+
+    let lines = load_lines(config, escape_style, env.clone())?;
+
+    let text = match (accessible, plain) {
+        (true, _) => render::render_accessible(&lines, &theme, shell, escape_style),
+        (false, true) => render::render_plain(&lines, &theme, shell, escape_style),
+        (false, false) => render::render_lines(&lines, &theme, shell, escape_style),
+    };
+
+    Ok(Rendered {
+        lines,
+        text,
+        hooks,
+        notifications,
+        env,
+    })
+}
+
+impl Rendered {
+    /// Sends any notification and runs the post-render hooks, for after the shell has the prompt
+    pub fn finish(self) {
+        if let Some(notifications) = self.notifications {
+            if let Err(err) = notifications.notify(&self.env) {
+                eprintln!("Error in promptr: {:?}", err);
+            }
+        }
+
+        if let Err(err) = self.hooks.run(Stage::PostRender, &self.env) {
+            eprintln!("Error in promptr: {:?}", err);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Masks sensitive text, like account numbers, before it makes it into the prompt.
+//!
+//! The patterns are regular expressions matched against the text and metadata of every segment.
+//! Each match is replaced with one `*` per character so the layout doesn't shift around.  By
+//! default this only happens when `PROMPTR_REDACT=1` is set, which is handy for screen shares:
+//! ```json
+//! { "redact": ["\\d{12}", "prod-[a-z]+"] }
+//! ```
+//!
+//! Demo mode works the same way, with the user and host names swapped for placeholders instead,
+//! see [`Redactor::demo`].
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::segment::Segment;
+use crate::ApplicationState;
+
+/// Compiled `redact` patterns from the config file
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    patterns: Vec<(Regex, Replacement)>,
+}
+
+/// What a match is replaced with
+#[derive(Clone, Debug)]
+enum Replacement {
+    /// One `*` per character
+    Mask,
+    /// A fixed string
+    Placeholder(String),
+}
+
+impl Redactor {
+    /// Compiles `patterns`, failing on the first one that isn't a valid regular expression
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> crate::Result<Self> {
+        let (redactor, mut errors) = Self::skipping_invalid(patterns);
+
+        match errors.is_empty() {
+            true => Ok(redactor),
+            false => Err(errors.remove(0)),
+        }
+    }
+
+    /// Compiles `patterns`, leaving out any that aren't valid regular expressions.  An error is
+    /// returned for each one left out.
+    pub fn skipping_invalid<S: AsRef<str>>(patterns: &[S]) -> (Self, Vec<anyhow::Error>) {
+        let mut errors = vec![];
+
+        let patterns = patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern.as_ref()) {
+                Ok(regex) => Some((regex, Replacement::Mask)),
+                Err(err) => {
+                    errors.push(
+                        anyhow::Error::new(err)
+                            .context(format!("bad redact pattern: {}", pattern.as_ref())),
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        (Self { patterns }, errors)
+    }
+
+    /// Swaps the names demo mode hides for placeholders: the user's name, the host name (short or
+    /// not), and the home directory.  `env` is left as is so segments still see the real values.
+    pub fn demo(env: &HashMap<String, String>) -> Self {
+        let user = ApplicationState::DEMO_USER;
+        let host = ApplicationState::DEMO_HOST;
+
+        let mut names = vec![];
+
+        if let Some(home) = env.get("HOME") {
+            names.push((home.clone(), format!("/home/{}", user)));
+        }
+
+        for key in ["USER", "LOGNAME", "SUDO_USER"] {
+            if let Some(name) = env.get(key) {
+                names.push((name.clone(), user.to_string()));
+            }
+        }
+
+        for key in ["hostname", "HOSTNAME"] {
+            if let Some(name) = env.get(key) {
+                names.push((name.clone(), host.to_string()));
+                if let Some((short, _)) = name.split_once('.') {
+                    names.push((short.to_string(), host.to_string()));
+                }
+            }
+        }
+
+        // Longest first so the home directory goes before the user name inside it
+        names.retain(|(name, _)| !name.is_empty() && name != "/");
+        names.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+        names.dedup_by(|a, b| a.0 == b.0);
+
+        let patterns = names
+            .into_iter()
+            .map(|(name, placeholder)| {
+                // Only whole names, a user called `al` shouldn't turn `alias` into `userias`
+                let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                let pattern = format!(
+                    "{}{}{}",
+                    if word(name.chars().next()) { r"\b" } else { "" },
+                    regex::escape(&name),
+                    if word(name.chars().last()) { r"\b" } else { "" },
+                );
+                let regex = Regex::new(&pattern).expect("escaped names are valid patterns");
+
+                (regex, Replacement::Placeholder(placeholder))
+            })
+            .collect();
+
+        Self { patterns }
+    }
+
+    /// Masks anything in `text` that matches one of the patterns
+    pub fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.patterns
+            .iter()
+            .fold(Cow::Borrowed(text), |text, (pattern, replacement)| {
+                let replaced = pattern.replace_all(&text, |captures: &regex::Captures| {
+                    replacement.apply(&captures[0])
+                });
+
+                match replaced {
+                    Cow::Borrowed(_) => text,
+                    Cow::Owned(replaced) => Cow::Owned(replaced),
+                }
+            })
+    }
+
+    /// Masks the text and any string metadata of `segment`
+    pub fn redact_segment(&self, segment: &mut Segment) {
+        if let Cow::Owned(text) = self.redact(&segment.text) {
+            segment.text = text;
+        }
+
+        for span in segment.spans.iter_mut() {
+            if let Cow::Owned(text) = self.redact(&span.text) {
+                span.text = text;
+            }
+        }
+
+        // A match that straddles two spans only shows up in the whole text, drop the colors
+        // rather than let any of it through
+        let joined: String = segment
+            .spans
+            .iter()
+            .map(|span| span.text.as_str())
+            .collect();
+        if joined != segment.text {
+            segment.spans.clear();
+        }
+
+        if let Some(metadata) = segment.metadata.as_mut() {
+            self.redact_value(metadata);
+        }
+    }
+
+    fn redact_value(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(string) => {
+                if let Cow::Owned(redacted) = self.redact(string) {
+                    *string = redacted;
+                }
+            }
+            serde_json::Value::Array(values) => {
+                values.iter_mut().for_each(|value| self.redact_value(value))
+            }
+            serde_json::Value::Object(map) => {
+                map.values_mut().for_each(|value| self.redact_value(value))
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Replacement {
+    /// Returns what `matched` is replaced with
+    fn apply(&self, matched: &str) -> String {
+        match self {
+            Self::Mask => "*".repeat(matched.chars().count()),
+            Self::Placeholder(placeholder) => placeholder.clone(),
+        }
+    }
+}
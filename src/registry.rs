@@ -0,0 +1,264 @@
+//! Maps the segment names used in the configuration file to the code that renders them.
+//!
+//! Segments that sit behind a cargo feature are listed even when that feature is turned off so
+//! that a configuration asking for one gets a useful error instead of a shrug.
+
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::segment::{self, Segment, ToSegment};
+use crate::{ApplicationState, SerializeAll};
+
+/// Renders a segment from its untyped arguments, see [`ToSegment::to_segment_generic`]
+pub type RenderFn = fn(Option<serde_json::Value>, &ApplicationState) -> crate::Result<Vec<Segment>>;
+
+/// Checks untyped arguments without rendering anything, see [`check`]
+pub type CheckFn = fn(Option<serde_json::Value>) -> crate::Result<()>;
+
+/// Returns the default arguments and theme of a segment as JSON, see [`defaults`]
+pub type DefaultsFn = fn() -> crate::Result<(serde_json::Value, serde_json::Value)>;
+
+/// A segment that ships with `promptr`
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    /// Name used in the configuration file
+    pub name: &'static str,
+    /// Cargo feature the segment is gated behind, if any
+    pub feature: Option<&'static str>,
+    /// One line summary of what the segment shows
+    pub doc: &'static str,
+    /// `None` if the segment was compiled out
+    render: Option<RenderFn>,
+    /// `None` if the segment was compiled out
+    check: Option<CheckFn>,
+    /// `None` if the segment was compiled out
+    defaults: Option<DefaultsFn>,
+}
+
+/// Everything there is to know about a built in segment without reading the source, for tools
+/// that help write configuration files
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SegmentDescriptor {
+    /// Name used in the configuration file
+    pub name: &'static str,
+    /// Other names the segment answers to, see [`ALIASES`]
+    pub aliases: Vec<&'static str>,
+    /// Cargo feature the segment is gated behind, if any
+    pub feature: Option<&'static str>,
+    /// Whether the segment was compiled in
+    pub enabled: bool,
+    /// One line summary of what the segment shows
+    pub doc: &'static str,
+    /// Arguments used when the configuration doesn't set any, `None` if the segment was compiled
+    /// out
+    pub default_args: Option<serde_json::Value>,
+    /// Theme used when the configuration doesn't set one, `None` if the segment was compiled out
+    pub default_theme: Option<serde_json::Value>,
+}
+
+/// What [`lookup`] found for a name
+pub enum Lookup {
+    Found(RenderFn),
+    /// The segment exists but needs the named feature
+    Disabled(&'static str),
+    Unknown,
+}
+
+/// One line summaries of each segment, taken from their module docs by `build.rs`
+mod docs {
+    include!(concat!(env!("OUT_DIR"), "/segment_docs.rs"));
+}
+
+macro_rules! builtin {
+    ($name:literal, $module:ident :: $segment:ident) => {
+        Builtin {
+            name: $name,
+            feature: None,
+            doc: docs::$module,
+            render: Some(segment::$segment::to_segment_generic),
+            check: Some(check_args::<segment::$segment>),
+            defaults: Some(defaults::<segment::$segment>),
+        }
+    };
+
+    ($name:literal, $module:ident :: $segment:ident, $feature:literal) => {
+        Builtin {
+            name: $name,
+            feature: Some($feature),
+            doc: docs::$module,
+            #[cfg(feature = $feature)]
+            render: Some(segment::$segment::to_segment_generic),
+            #[cfg(not(feature = $feature))]
+            render: None,
+            #[cfg(feature = $feature)]
+            check: Some(check_args::<segment::$segment>),
+            #[cfg(not(feature = $feature))]
+            check: None,
+            #[cfg(feature = $feature)]
+            defaults: Some(defaults::<segment::$segment>),
+            #[cfg(not(feature = $feature))]
+            defaults: None,
+        }
+    };
+}
+
+/// Every segment that ships with `promptr`, enabled or not
+pub const BUILTINS: &[Builtin] = &[
+    builtin!("aws", aws::Aws),
+    builtin!("battery", battery_status::BatteryStatus, "segment-battery"),
+    builtin!("bookmark", bookmark::Bookmark),
+    builtin!("clock", clock::Clock),
+    builtin!("command", command::Command),
+    builtin!("command_status", command_status::CommandStatus),
+    builtin!("container", container::Container),
+    builtin!("cpu_time", cpu_time::CpuTime),
+    builtin!("disk", disk::Disk),
+    builtin!("exit_code", exit_code::ExitCode),
+    builtin!("fill", fill::Fill),
+    builtin!("frecency", frecency::Frecency),
+    builtin!("git", git::Git, "segment-git"),
+    builtin!("hostname", hostname::Hostname),
+    builtin!("identity", identity::Identity),
+    builtin!("instance", instance::Instance),
+    builtin!("load", load::Load),
+    builtin!("memory", memory::Memory),
+    builtin!("nix", nix::Nix),
+    builtin!("os", os::Os),
+    builtin!("path", path::Path),
+    builtin!("rbenv", rbenv::Rbenv),
+    builtin!("rvm", rvm::Rvm, "segment-rvm"),
+    builtin!("sapling", sapling::Sapling),
+    builtin!("screen", screen::Screen),
+    builtin!("tasks", tasks::Tasks, "segment-tasks"),
+    builtin!("terminal", terminal::Terminal),
+    builtin!("thermal", thermal::Thermal),
+    builtin!("username", username::Username),
+    builtin!("vcs", vcs::Vcs),
+    builtin!("wifi", wifi::Wifi, "segment-wifi"),
+    builtin!("wsl", wsl::Wsl),
+];
+
+/// Old or alternate names for built in segments
+pub const ALIASES: &[(&str, &str)] = &[("battery_status", "battery"), ("paths", "path")];
+
+impl Builtin {
+    /// Returns true if the segment was compiled in
+    pub fn enabled(&self) -> bool {
+        self.render.is_some()
+    }
+
+    /// Describes the segment, see [`describe_all`]
+    pub fn describe(&self) -> SegmentDescriptor {
+        let (default_args, default_theme) = match self.defaults.map(|defaults| defaults()) {
+            Some(Ok((args, theme))) => (Some(args), Some(theme)),
+            _ => (None, None),
+        };
+
+        SegmentDescriptor {
+            name: self.name,
+            aliases: ALIASES
+                .iter()
+                .filter(|(_, name)| *name == self.name)
+                .map(|(alias, _)| *alias)
+                .collect(),
+            feature: self.feature,
+            enabled: self.enabled(),
+            doc: self.doc.trim(),
+            default_args,
+            default_theme,
+        }
+    }
+}
+
+/// Returns the default arguments and theme of `T` with every field written out
+fn defaults<T: ToSegment>() -> crate::Result<(serde_json::Value, serde_json::Value)>
+where
+    T::Args: Default + Serialize,
+    T::Theme: Default + SerializeAll,
+{
+    Ok((
+        serde_json::to_value(T::Args::default())?,
+        crate::to_json_with_defaults(&T::Theme::default())?,
+    ))
+}
+
+/// Makes sure `args` deserialize into the arguments for `T`
+fn check_args<T: ToSegment>(args: Option<serde_json::Value>) -> crate::Result<()>
+where
+    for<'de> T::Args: Deserialize<'de>,
+{
+    if let Some(args) = args {
+        serde_json::from_value::<T::Args>(args)?;
+    }
+
+    Ok(())
+}
+
+/// Describes every segment that ships with `promptr`, enabled or not, in [`BUILTINS`] order
+pub fn describe_all() -> Vec<SegmentDescriptor> {
+    BUILTINS.iter().map(Builtin::describe).collect()
+}
+
+/// Returns the segment `name` refers to, checking the user's `aliases` and then [`ALIASES`]
+pub fn resolve<'a>(name: &'a str, aliases: &'a BTreeMap<String, String>) -> &'a str {
+    let name = aliases.get(name).map(String::as_str).unwrap_or(name);
+
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, real)| *real)
+        .unwrap_or(name)
+}
+
+/// Finds the segment called `name`
+pub fn lookup(name: &str) -> Lookup {
+    match BUILTINS.iter().find(|builtin| builtin.name == name) {
+        Some(Builtin {
+            render: Some(render),
+            ..
+        }) => Lookup::Found(*render),
+        Some(Builtin {
+            feature: Some(feature),
+            ..
+        }) => Lookup::Disabled(feature),
+        _ => Lookup::Unknown,
+    }
+}
+
+/// Renders the segment called `name` with `args`
+pub fn render(
+    name: &str,
+    args: Option<serde_json::Value>,
+    state: &ApplicationState,
+) -> crate::Result<Vec<Segment>> {
+    match lookup(name) {
+        Lookup::Found(render) => render(args, state),
+        Lookup::Disabled(feature) => Err(anyhow!(
+            "Segment {} is unavailable: compiled without feature {}",
+            name,
+            feature
+        )),
+        Lookup::Unknown => Err(anyhow!("Unknown segment: {}", name)),
+    }
+}
+
+/// Checks that `name` is a segment that was compiled in and that `args` suit it, without
+/// rendering anything
+pub fn check(name: &str, args: Option<serde_json::Value>) -> crate::Result<()> {
+    match BUILTINS.iter().find(|builtin| builtin.name == name) {
+        Some(Builtin {
+            check: Some(check), ..
+        }) => check(args),
+        Some(Builtin {
+            feature: Some(feature),
+            ..
+        }) => Err(anyhow!(
+            "Segment {} is unavailable: compiled without feature {}",
+            name,
+            feature
+        )),
+        _ => Err(anyhow!("Unknown segment: {}", name)),
+    }
+}
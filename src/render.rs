@@ -0,0 +1,692 @@
+//! Turns rendered [`Segment`]s into something the shell can display.
+//!
+//! Each segment is drawn as its text padded by [`Theme::padding`] spaces on either side, followed
+//! by a separator.  The final separator is followed by a color reset and [`Theme::suffix`], which
+//! defaults to a single space.  Multiline prompts repeat this for every line.
+//!
+//! Before rendering, [`fit`] can be used to drop or shorten segments so a line stays within a
+//! given width and [`expand_fills`] pads out any [fill](`crate::segment::fill`) segments.  If
+//! that's still too wide for the terminal [`clamp`] chops the line down from the left.
+//!
+//! For screen readers [`render_accessible`] skips all of that and writes each segment out as
+//! labeled plain text instead, e.g. `[dir ~/src/promptr] [git main, 2 changed]`.
+//! [`render_plain`] keeps the text as is but also leaves out every escape sequence, for
+//! `NO_COLOR` and for prompts that end up in logs.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::ansi::{Color, EscapeStyle, TextStyle};
+use crate::segment::{Kind, Segment, Span};
+use crate::shell::Shell;
+use crate::{Separator, Theme};
+
+/// Shown when there are no segments at all, `bash` turns this into `#` for root
+pub const FALLBACK: &str = r"\$";
+
+/// Goes between segments in place of the separators when rendering with [`render_plain`]
+pub const PLAIN_SEPARATOR: &str = " | ";
+
+/// Labels read out by [`render_accessible`] and how to join the text of consecutive segments,
+/// keyed by the start of [`Segment::source`].  Anything else is labeled with the lowercased source.
+const ACCESSIBLE_LABELS: &[(&str, &str, &str)] = &[
+    ("BatteryStatus", "battery", ", "),
+    ("CommandStatus", "status", ", "),
+    ("CpuTime", "cpu", ", "),
+    ("ExitCode", "exit", ", "),
+    ("Path", "dir", "/"),
+];
+
+/// Marks text that has been shortened by [`fit`]
+const ELLIPSIS: char = '\u{2026}';
+
+/// The segments rendered from one stanza in the config, along with how to lay them out
+#[derive(Clone, Debug)]
+pub struct Stanza {
+    /// Lower priorities are dropped first
+    pub priority: i32,
+    /// Try shortening the text before dropping the stanza
+    pub truncate: bool,
+    pub segments: Vec<Segment>,
+}
+
+/// Renders `segments` into a string ready to be handed to `shell`, with escape sequences written
+/// out in `style`.
+pub fn render_prompt(
+    segments: &[Segment],
+    theme: &Theme,
+    shell: Shell,
+    style: EscapeStyle,
+) -> String {
+    let (right, segments): (Vec<&Segment>, Vec<&Segment>) =
+        segments.iter().partition(|seg| seg.kind == Kind::Right);
+
+    let mut it = segments.iter().copied().peekable();
+
+    // Shell specific prompt escapes are only meaningful if the shell is the one reading them
+    let escape_text = |text| {
+        if style == shell.escape_style() {
+            shell.escape_text(text)
+        } else {
+            style.escape_text(text)
+        }
+    };
+
+    let padding = " ".repeat(theme.padding);
+
+    let mut prompt = escape_text(&theme.prefix).into_owned();
+
+    if !right.is_empty() {
+        prompt.push_str(&render_right(&right, theme, style));
+    }
+
+    if let (Some(cap), Some(first)) = (&theme.separator_start, segments.first()) {
+        prompt.push_str(&format!(
+            "{}{}{}",
+            bar_bg(theme, style),
+            first.bg.set_fg(style),
+            escape_text(cap)
+        ));
+    }
+
+    while let Some(seg) = it.next() {
+        let separator = separator_for(seg, it.peek().copied());
+        let glyph = separator_glyph(seg, it.peek().copied(), theme);
+
+        let separator_fg = match separator {
+            Separator::Thin => theme.thin_separator_fg.set_fg(style),
+            _ => seg.bg.set_fg(style),
+        };
+
+        let separator_bg = if let Some(next_seg) = it.peek() {
+            next_seg.bg.set_bg(style)
+        } else {
+            bar_bg(theme, style)
+        };
+
+        let text = match seg.styled_spans() {
+            Some(spans) => spans
+                .iter()
+                .map(|span| render_span(seg, span, escape_text(&span.text).as_ref(), style))
+                .collect(),
+            None => escape_text(&seg.text).into_owned(),
+        };
+        let text = with_text_style(&seg.style, &text, style);
+
+        prompt.push_str(&format!(
+            "{}{}{}{}{}{}{}{}",
+            seg.fg.set_fg(style),
+            seg.bg.set_bg(style),
+            padding,
+            text,
+            padding,
+            separator_bg,
+            separator_fg,
+            escape_text(glyph)
+        ));
+    }
+
+    prompt.push_str(&format!(
+        "{}{}",
+        Color::reset_colors(style),
+        escape_text(&theme.suffix)
+    ));
+
+    prompt
+}
+
+/// Draws `segments` against the right edge of the terminal and puts the cursor back where it was.
+/// The whole thing is wrapped up as non-printing so the shell doesn't count it towards the width
+/// of the prompt.
+///
+/// The cursor is pushed as far right as it goes and then backed up, so this doesn't need to know
+/// how wide the terminal is.  Drawing into the last column leaves the terminal waiting to wrap,
+/// restoring the cursor cancels that before the next character can spill onto a new line.
+fn render_right(segments: &[&Segment], theme: &Theme, style: EscapeStyle) -> String {
+    let padding = " ".repeat(theme.padding);
+
+    let width = right_width(segments.iter().copied(), theme);
+
+    let body = segments
+        .iter()
+        .map(|seg| {
+            format!(
+                "{}{}{}{}{}",
+                seg.fg.set_fg(EscapeStyle::Raw),
+                seg.bg.set_bg(EscapeStyle::Raw),
+                padding,
+                with_text_style(
+                    &seg.style,
+                    &EscapeStyle::Raw.escape_text(&seg.text),
+                    EscapeStyle::Raw
+                ),
+                padding
+            )
+        })
+        .collect::<String>();
+
+    style.non_printing(&format!(
+        "\x1b7\x1b[999C\x1b[{}D{}{}\x1b8",
+        width.saturating_sub(1),
+        body,
+        Color::reset_colors(EscapeStyle::Raw)
+    ))
+}
+
+/// Returns the number of columns taken up by segments drawn with [`render_right`]
+fn right_width<'a>(segments: impl Iterator<Item = &'a Segment>, theme: &Theme) -> usize {
+    segments
+        .map(|seg| text_width(&seg.text) + 2 * theme.padding)
+        .sum()
+}
+
+/// Draws one piece of `seg`, putting the segment's background back afterwards if the span
+/// changed it
+fn render_span(seg: &Segment, span: &Span, text: &str, style: EscapeStyle) -> String {
+    let mut out = span.fg.unwrap_or(seg.fg).set_fg(style);
+
+    if let Some(bg) = span.bg {
+        out.push_str(&bg.set_bg(style));
+    }
+
+    match span.style {
+        Some(text_style) => {
+            out.push_str(&with_text_style(&[text_style], text, style));
+            // Turning the span's style off can turn off the segment's too, bold and dim go together
+            for seg_style in &seg.style {
+                out.push_str(&seg_style.on(style));
+            }
+        }
+        None => out.push_str(text),
+    }
+
+    if span.bg.is_some() {
+        out.push_str(&seg.bg.set_bg(style));
+    }
+
+    out
+}
+
+/// Wraps `text` in the escape sequences that turn `styles` on and back off
+fn with_text_style(styles: &[TextStyle], text: &str, style: EscapeStyle) -> String {
+    let on: String = styles
+        .iter()
+        .map(|text_style| text_style.on(style))
+        .collect();
+    let off: String = styles
+        .iter()
+        .map(|text_style| text_style.off(style))
+        .collect();
+
+    format!("{}{}{}", on, text, off)
+}
+
+/// Returns the escape sequence for what's behind the caps at either end of a line
+fn bar_bg(theme: &Theme, style: EscapeStyle) -> String {
+    match theme.bar_bg {
+        Some(color) => format!("{}{}", Color::reset_colors(style), color.set_bg(style)),
+        None => Color::reset_colors(style),
+    }
+}
+
+/// Renders each line of a multiline prompt with [`render_prompt`] and joins them together.
+///
+/// If every segment was skipped a bare [`FALLBACK`] is rendered instead so there's always
+/// something to type after.
+pub fn render_lines(
+    lines: &[Vec<Segment>],
+    theme: &Theme,
+    shell: Shell,
+    style: EscapeStyle,
+) -> String {
+    if lines.iter().all(Vec::is_empty) {
+        let escape_text = |text| match style == shell.escape_style() {
+            true => shell.escape_text(text),
+            false => style.escape_text(text),
+        };
+
+        return format!(
+            "{}{}{}{}",
+            Color::reset_colors(style),
+            escape_text(&theme.prefix),
+            escape_text(FALLBACK),
+            escape_text(&theme.suffix)
+        );
+    }
+
+    lines
+        .iter()
+        .map(|segments| render_prompt(segments, theme, shell, style))
+        .collect::<Vec<_>>()
+        .join(newline(shell, style))
+}
+
+/// The shell's own line break unless the escape style was overridden
+fn newline(shell: Shell, style: EscapeStyle) -> &'static str {
+    match style == shell.escape_style() {
+        true => shell.newline(),
+        false => style.newline(),
+    }
+}
+
+/// Renders `lines` without any escape sequences at all, the text of each segment is joined by
+/// [`PLAIN_SEPARATOR`].  Fill segments are left out and right aligned segments go at the end of
+/// the line.
+pub fn render_plain(
+    lines: &[Vec<Segment>],
+    theme: &Theme,
+    shell: Shell,
+    style: EscapeStyle,
+) -> String {
+    let escape_text = |text: &str| -> String {
+        match style == shell.escape_style() {
+            true => shell.escape_text(text).into_owned(),
+            false => style.escape_text(text).into_owned(),
+        }
+    };
+
+    let rendered = lines
+        .iter()
+        .map(|segments| {
+            let (right, left): (Vec<_>, Vec<_>) = segments
+                .iter()
+                .filter(|seg| seg.kind != Kind::Fill && !seg.text.is_empty())
+                .partition(|seg| seg.kind == Kind::Right);
+
+            left.into_iter()
+                .chain(right)
+                .map(|seg| escape_text(&seg.text))
+                .collect::<Vec<_>>()
+                .join(PLAIN_SEPARATOR)
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+
+    let body = match rendered.is_empty() {
+        true => escape_text(FALLBACK),
+        false => rendered.join(newline(shell, style)),
+    };
+
+    format!(
+        "{}{}{}",
+        escape_text(&theme.prefix),
+        body,
+        escape_text(&theme.suffix)
+    )
+}
+
+/// Renders `lines` as labeled plain text without any colors, separators, or glyphs.  Consecutive
+/// segments from the same source are grouped under one label, counts are followed by what they're
+/// counting (e.g. `Git::Changed` becomes `2 changed`).
+pub fn render_accessible(
+    lines: &[Vec<Segment>],
+    theme: &Theme,
+    shell: Shell,
+    style: EscapeStyle,
+) -> String {
+    let escape_text = |text: &str| -> String {
+        match style == shell.escape_style() {
+            true => shell.escape_text(text).into_owned(),
+            false => style.escape_text(text).into_owned(),
+        }
+    };
+
+    let rendered = lines
+        .iter()
+        .map(|segments| accessible_line(segments))
+        .filter(|line| !line.is_empty())
+        .map(|line| escape_text(&line))
+        .collect::<Vec<_>>();
+
+    let body = match rendered.is_empty() {
+        true => escape_text(FALLBACK),
+        false => rendered.join(newline(shell, style)),
+    };
+
+    format!(
+        "{}{}{}",
+        escape_text(&theme.prefix),
+        body,
+        escape_text(&theme.suffix)
+    )
+}
+
+/// Groups and labels the segments on a single line for [`render_accessible`]
+fn accessible_line(segments: &[Segment]) -> String {
+    let mut groups: Vec<(&str, Vec<String>)> = vec![];
+
+    for seg in segments.iter().filter(|seg| seg.kind != Kind::Fill) {
+        let (source, detail) = match seg.source.split_once("::") {
+            Some((source, detail)) => (source, Some(detail)),
+            None => (seg.source, None),
+        };
+        let detail =
+            detail.map(|detail| detail.split("::").last().unwrap_or(detail).to_lowercase());
+
+        let text = plain_text(&seg.text);
+        let text = match (text.chars().all(|c| c.is_ascii_digit()), detail) {
+            (true, Some(detail)) if text.is_empty() => detail,
+            (true, Some(detail)) => format!("{} {}", text, detail),
+            _ => text,
+        };
+
+        if text.is_empty() {
+            continue;
+        }
+
+        match groups.last_mut() {
+            Some((group, texts)) if *group == source => texts.push(text),
+            _ => groups.push((source, vec![text])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(source, texts)| {
+            let (label, joiner) = match ACCESSIBLE_LABELS
+                .iter()
+                .find(|(name, _, _)| *name == source)
+            {
+                Some((_, label, joiner)) => (label.to_string(), *joiner),
+                None => (source.to_lowercase(), ", "),
+            };
+
+            // The root path component already ends in a slash
+            let text = match source {
+                "Path" => texts.join(joiner).replace("//", "/"),
+                _ => texts.join(joiner),
+            };
+
+            format!("[{} {}]", label, text)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Drops anything that's only there to look nice (Nerd Font glyphs, emoji, dingbats) from `text`
+/// and tidies up the whitespace left behind
+fn plain_text(text: &str) -> String {
+    let is_glyph = |c: char| {
+        matches!(c as u32,
+            0x2190..=0x2bff | 0xe000..=0xf8ff | 0xfe00..=0xfe0f | 0x1f000..=0x1faff | 0xf0000..
+        )
+    };
+
+    text.replace(|c: char| is_glyph(c), " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the number of columns `segments` will take up on screen once rendered.
+///
+/// This includes padding and separators but not escape sequences, which take up no room.
+/// [`Kind::Right`] segments are drawn over the end of the line and aren't counted.
+pub fn prompt_width(segments: &[Segment], theme: &Theme) -> usize {
+    segments
+        .iter()
+        .enumerate()
+        .filter(|(_, seg)| seg.kind != Kind::Right)
+        .map(|(idx, seg)| {
+            text_width(&seg.text)
+                + 2 * theme.padding
+                + separator_width(seg, next_left(segments, idx), theme)
+        })
+        .sum::<usize>()
+        + trailer_width(theme)
+}
+
+/// Returns the segment drawn after `segments[idx]` on the left side of the line.  [`Kind::Right`]
+/// segments are drawn on their own so they're skipped over.
+fn next_left(segments: &[Segment], idx: usize) -> Option<&Segment> {
+    segments[idx + 1..]
+        .iter()
+        .find(|seg| seg.kind != Kind::Right)
+}
+
+/// Returns the number of columns taken up by the prefix, suffix, and starting cap on each line
+fn trailer_width(theme: &Theme) -> usize {
+    text_width(&theme.prefix)
+        + theme.separator_start.as_deref().map_or(0, text_width)
+        + text_width(&theme.suffix)
+}
+
+/// Returns the separator drawn after `seg` when it's followed by `next`.  Segments that share a
+/// background are split with a thin separator.
+fn separator_for<'a>(seg: &'a Segment, next: Option<&Segment>) -> &'a Separator {
+    match (&seg.separator_override, next) {
+        (Some(separator), _) => separator,
+        (None, Some(next)) if seg.bg == next.bg => &Separator::Thin,
+        (None, _) => &seg.separator,
+    }
+}
+
+/// Returns what's drawn for the separator after `seg`, the theme's end cap replaces the last
+/// thick separator on the line
+fn separator_glyph<'a>(seg: &'a Segment, next: Option<&Segment>, theme: &'a Theme) -> &'a str {
+    match (&theme.separator_end, next, separator_for(seg, next)) {
+        (Some(end), None, Separator::Thick) if seg.separator_override.is_none() => end,
+        (_, _, separator) => separator.glyph(theme),
+    }
+}
+
+/// Returns the number of columns taken up by the separator after `seg`
+fn separator_width(seg: &Segment, next: Option<&Segment>, theme: &Theme) -> usize {
+    text_width(separator_glyph(seg, next, theme))
+}
+
+/// Returns the number of columns `text` will take up on a terminal.
+///
+/// Wide characters (most emoji, CJK) take up two columns.  The `bash` prompt escape `\$` is
+/// counted as the single character it expands to.
+pub fn text_width(text: &str) -> usize {
+    text.replace("\\$", "$").width()
+}
+
+/// Drops or truncates the lowest priority stanzas until the line fits in `max_width` columns.
+///
+/// The highest priority stanza is always kept, even if it doesn't fit.
+pub fn fit(stanzas: Vec<Stanza>, max_width: usize, theme: &Theme) -> Vec<Segment> {
+    let mut stanzas = stanzas
+        .into_iter()
+        .filter(|stanza| !stanza.segments.is_empty())
+        .collect::<Vec<_>>();
+
+    loop {
+        // Measured as one line, the separator between stanzas depends on both of them
+        let line = stanzas
+            .iter()
+            .flat_map(|stanza| stanza.segments.iter().cloned())
+            .collect::<Vec<_>>();
+        let width = prompt_width(&line, theme);
+
+        if width <= max_width || stanzas.len() <= 1 {
+            break;
+        }
+
+        // min_by_key picks the first of equals, so walk backwards to drop from the right
+        let (idx, _) = stanzas
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by_key(|(_, stanza)| stanza.priority)
+            .expect("there are at least two stanzas");
+
+        let stanza = &mut stanzas[idx];
+        if stanza.truncate && shorten(&mut stanza.segments, width - max_width) {
+            // If that wasn't enough it'll be dropped next time around
+            stanza.truncate = false;
+        } else {
+            stanzas.remove(idx);
+        }
+    }
+
+    stanzas
+        .into_iter()
+        .flat_map(|stanza| stanza.segments)
+        .collect()
+}
+
+/// Trims up to `excess` columns from the end of `segments`, keeping at least one character of
+/// each.  Returns false if nothing could be trimmed.
+fn shorten(segments: &mut [Segment], mut excess: usize) -> bool {
+    let mut shortened = false;
+
+    for seg in segments.iter_mut().rev() {
+        if excess == 0 {
+            break;
+        }
+
+        let width = text_width(&seg.text);
+        // Leave one column of text plus the ellipsis
+        let available = width.saturating_sub(2);
+        if available == 0 {
+            continue;
+        }
+
+        let target = width - excess.min(available) - 1;
+        seg.text = truncate_to(&seg.text, target);
+        // Cutting before a wide character can free up more than was asked for
+        excess = excess.saturating_sub(width - text_width(&seg.text));
+        shortened = true;
+    }
+
+    shortened
+}
+
+/// Cuts `text` down to `width` columns and adds an ellipsis
+fn truncate_to(text: &str, width: usize) -> String {
+    let mut used = 0;
+    let mut truncated = text
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or_default();
+            used <= width
+        })
+        .collect::<String>();
+
+    truncated.push(ELLIPSIS);
+    truncated
+}
+
+/// Cuts `segments` down from the left until the line fits in `max_width` columns, regardless of
+/// priority.  The end of the line, where the cursor ends up, is kept.
+///
+/// Whole segments are dropped until the first one can be shortened to fit, that one loses the
+/// start of its text.  The last segment is always kept.  [`Kind::Right`] segments don't take up
+/// room here and are left for [`place_right`] to deal with.
+pub fn clamp(segments: &mut Vec<Segment>, max_width: usize, theme: &Theme) {
+    loop {
+        let width = prompt_width(segments, theme);
+
+        // Right edge segments are placed separately, see place_right
+        let left = segments
+            .iter()
+            .enumerate()
+            .filter(|(_, seg)| seg.kind != Kind::Right)
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+
+        let first = match left.first() {
+            Some(first) if width > max_width => *first,
+            _ => break,
+        };
+
+        let excess = width - max_width;
+        let text_width = text_width(&segments[first].text);
+
+        // Leave one column of text plus the ellipsis
+        if segments[first].kind == Kind::Text && text_width >= excess + 2 {
+            segments[first].text =
+                truncate_start_to(&segments[first].text, text_width - excess - 1);
+            break;
+        }
+
+        if left.len() == 1 {
+            break;
+        }
+
+        segments.remove(first);
+    }
+}
+
+/// Cuts `text` down to its last `width` columns and adds an ellipsis to the front
+fn truncate_start_to(text: &str, width: usize) -> String {
+    let mut used = 0;
+    let mut tail = text
+        .chars()
+        .rev()
+        .take_while(|c| {
+            used += c.width().unwrap_or_default();
+            used <= width
+        })
+        .collect::<Vec<_>>();
+
+    tail.push(ELLIPSIS);
+    tail.into_iter().rev().collect()
+}
+
+/// Keeps the [`Kind::Right`] segments on a line only if they're sure to stay out of the way.
+///
+/// They're drawn over whatever is at the right edge of the first row of the line, so if the rest of
+/// the line would reach them, or wrap underneath them, they're dropped.  The same goes for when
+/// there's no telling how wide the terminal is.  At least one column is left between the two for
+/// the cursor.
+pub fn place_right(segments: &mut Vec<Segment>, columns: Option<usize>, theme: &Theme) {
+    if segments.iter().all(|seg| seg.kind != Kind::Right) {
+        return;
+    }
+
+    let fits = columns.is_some_and(|columns| {
+        let (right, left): (Vec<Segment>, Vec<Segment>) = segments
+            .iter()
+            .cloned()
+            .partition(|seg| seg.kind == Kind::Right);
+
+        prompt_width(&left, theme) + right_width(right.iter(), theme) < columns
+    });
+
+    if !fits {
+        segments.retain(|seg| seg.kind != Kind::Right);
+    }
+}
+
+/// Pads out [`Kind::Fill`] segments so the line is `columns` wide.  The text of each fill
+/// segment is the character to pad with.
+///
+/// When there's more than one fill on the line the room is split between them, leftmost first.
+pub fn expand_fills(segments: &mut [Segment], columns: usize, theme: &Theme) {
+    let fills = segments.iter().filter(|seg| seg.kind == Kind::Fill).count();
+
+    if fills == 0 {
+        return;
+    }
+
+    let used = segments
+        .iter()
+        .enumerate()
+        .map(|(idx, seg)| match seg.kind {
+            Kind::Fill => 2 * theme.padding + separator_width(seg, next_left(segments, idx), theme),
+            Kind::Text => {
+                text_width(&seg.text)
+                    + 2 * theme.padding
+                    + separator_width(seg, next_left(segments, idx), theme)
+            }
+            // Leave room for them at the end of the line
+            Kind::Right => text_width(&seg.text) + 2 * theme.padding,
+        })
+        .sum::<usize>()
+        + trailer_width(theme);
+
+    let room = columns.saturating_sub(used);
+
+    for (idx, seg) in segments
+        .iter_mut()
+        .filter(|seg| seg.kind == Kind::Fill)
+        .enumerate()
+    {
+        let pad = seg.text.chars().next().unwrap_or(' ').to_string();
+        let count = room / fills + usize::from(idx < room % fills);
+
+        seg.text = pad.repeat(count);
+    }
+}
@@ -0,0 +1,113 @@
+//! Switches between named themes based on the time of day.
+//!
+//! Each entry in the `schedule` names a theme from `themes` and the time it takes over.  An entry
+//! lasts until the next one starts, going past midnight if need be, so a lone entry never ends.
+//! Give it a `to` and it ends then instead, after which the base `theme` is used.  The name
+//! `default` also refers to the base theme:
+//! ```json
+//! {
+//!     "themes": { "dim": { "path": { "bg": 236 } } },
+//!     "schedule": [
+//!         { "from": "20:00", "to": "07:30", "theme": "dim" }
+//!     ]
+//! }
+//! ```
+//!
+//! When entries overlap the one that started last wins.  Named themes start from the defaults,
+//! not from the base theme.
+
+use std::fmt::{self, Display};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+/// Name that always refers to the base theme
+pub const DEFAULT_THEME: &str = "default";
+
+/// Minutes in a day
+const DAY: u16 = 24 * 60;
+
+/// Minutes since midnight, written as `HH:MM`
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(pub u16);
+
+/// An entry in the `schedule` list
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    /// When the theme takes over
+    pub from: TimeOfDay,
+
+    /// When the theme stops, if that's before the next entry starts.  Earlier than `from` means
+    /// the next day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<TimeOfDay>,
+
+    /// Name of a theme in `themes`, or `default`
+    pub theme: String,
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> crate::Result<Self> {
+        let (hours, minutes) = value
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected HH:MM, got {}", value))?;
+
+        let hours = hours.parse::<u16>()?;
+        let minutes = minutes.parse::<u16>()?;
+
+        if hours > 23 || minutes > 59 {
+            return Err(anyhow!("{} isn't a time of day", value));
+        }
+
+        Ok(Self(hours * 60 + minutes))
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> Self {
+        time.to_string()
+    }
+}
+
+impl Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+impl TimeOfDay {
+    /// Minutes from `self` until `later`, going past midnight if `later` is earlier in the day
+    fn until(self, later: Self) -> u16 {
+        (later.0 + DAY - self.0) % DAY
+    }
+}
+
+/// Returns the entry in effect at `now`, if any
+pub fn active(schedule: &[Entry], now: TimeOfDay) -> Option<&Entry> {
+    // How long each entry lasts, a whole day if it's the only one or runs from `to` to `to`
+    let length = |entry: &Entry| {
+        let length = match entry.to {
+            Some(to) => entry.from.until(to),
+            None => schedule
+                .iter()
+                .map(|next| entry.from.until(next.from))
+                .filter(|&minutes| minutes > 0)
+                .min()
+                .unwrap_or_default(),
+        };
+
+        match length {
+            0 => DAY,
+            length => length,
+        }
+    };
+
+    schedule
+        .iter()
+        .filter(|entry| entry.from.until(now) < length(entry))
+        .min_by_key(|entry| entry.from.until(now))
+}
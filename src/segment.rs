@@ -12,14 +12,17 @@
 //! * A struct named `Theme` that defines the themeable knobs.  The fields *should* be either
 //! [`String`]s or [`Color`]s.  This struct *must* implement the [`Default`] trait.
 
+// The list above predates this lint, leave it be
+#![allow(clippy::doc_lazy_continuation)]
+
 use anyhow::Context;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::ansi::Color;
+use crate::ansi::{Color, ColorDepth, TextStyle};
 use crate::{ApplicationState, Separator};
 
 /// Represents a rendered segment
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct Segment {
     /// Background color
     pub bg: Color,
@@ -31,6 +34,161 @@ pub struct Segment {
     pub separator: Separator,
     /// Debugging info visible from the `segment` subcommand
     pub source: &'static str,
+    /// Structured values behind the text (e.g. a branch name or a count) for consumers of the
+    /// JSON output who'd rather not parse the display text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    /// How the segment takes part in the layout
+    #[serde(skip_serializing_if = "Kind::is_text")]
+    pub kind: Kind,
+    /// Separator from the config that's used as is, regardless of the neighboring segments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separator_override: Option<Separator>,
+    /// Pieces of [`Segment::text`] drawn in their own colors, see [`Segment::with_spans`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<Span>,
+    /// Text attributes for the whole segment, see [`Segment::with_style`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub style: Vec<TextStyle>,
+}
+
+/// A piece of a segment's text with its own colors and style.  Plain text converts straight into
+/// a span that looks like the rest of the segment.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct Span {
+    pub text: String,
+    /// Uses the segment's foreground color if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fg: Option<Color>,
+    /// Uses the segment's background color if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bg: Option<Color>,
+    /// Drawn on top of the segment's own [`Segment::style`], plain if neither is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<TextStyle>,
+}
+
+impl From<String> for Span {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&str> for Span {
+    fn from(text: &str) -> Self {
+        Self::from(text.to_string())
+    }
+}
+
+/// How a [`Segment`] takes part in the layout
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    /// Regular text
+    #[default]
+    Text,
+    /// Grows to take up whatever room is left on the line, see [`render::expand_fills`](`crate::render::expand_fills`)
+    Fill,
+    /// Drawn against the right edge of the terminal without taking up any room in the prompt, see
+    /// [`render::place_right`](`crate::render::place_right`)
+    Right,
+}
+
+impl Kind {
+    fn is_text(&self) -> bool {
+        *self == Self::Text
+    }
+}
+
+impl Segment {
+    /// Creates a segment with a thick separator and no metadata.  An [`Auto`](`Color::Auto`)
+    /// foreground is worked out from the background.
+    pub fn new<S: Into<String>>(source: &'static str, fg: Color, bg: Color, text: S) -> Self {
+        Self {
+            bg,
+            fg: match fg {
+                Color::Auto => bg.contrasting(),
+                fg => fg,
+            },
+            text: text.into(),
+            separator: Separator::Thick,
+            source,
+            metadata: None,
+            kind: Kind::Text,
+            separator_override: None,
+            spans: vec![],
+            style: vec![],
+        }
+    }
+
+    /// Creates a segment that's padded out with `text` to fill the rest of the line
+    pub fn fill<S: Into<String>>(source: &'static str, fg: Color, bg: Color, text: S) -> Self {
+        Self {
+            kind: Kind::Fill,
+            ..Self::new(source, fg, bg, text)
+        }
+    }
+
+    /// Sets how the segment takes part in the layout
+    pub fn with_kind(mut self, kind: Kind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the type of trailing separator
+    pub fn with_separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Draws the text in pieces with their own colors.  The text is replaced with the pieces run
+    /// together, anything that rewrites the text afterwards (e.g. [`fit`](`crate::render::fit`)
+    /// shortening it) turns the pieces back into plain text.
+    pub fn with_spans<I, S>(mut self, spans: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Span>,
+    {
+        self.spans = spans.into_iter().map(Into::into).collect();
+        self.text = self.spans.iter().map(|span| span.text.as_str()).collect();
+        self
+    }
+
+    /// Returns the spans to draw, or `None` if the text should be drawn as is
+    pub fn styled_spans(&self) -> Option<&[Span]> {
+        let joined: String = self.spans.iter().map(|span| span.text.as_str()).collect();
+
+        match !self.spans.is_empty() && joined == self.text {
+            true => Some(&self.spans),
+            false => None,
+        }
+    }
+
+    /// Sets the text attributes (e.g. bold) the whole segment is drawn with
+    pub fn with_style<I: IntoIterator<Item = TextStyle>>(mut self, style: I) -> Self {
+        self.style = style.into_iter().collect();
+        self
+    }
+
+    /// Attaches structured values to the segment
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Swaps out any colors the terminal can't draw, see [`Color::downgrade`]
+    pub fn downgrade_colors(&mut self, depth: ColorDepth) {
+        self.fg = self.fg.downgrade(depth);
+        self.bg = self.bg.downgrade(depth);
+
+        for span in self.spans.iter_mut() {
+            span.fg = span.fg.map(|fg| fg.downgrade(depth));
+            span.bg = span.bg.map(|bg| bg.downgrade(depth));
+        }
+    }
 }
 
 /// Implement this trait for each potential segment.  It's generic over the `Deserialize` trait
@@ -66,27 +224,81 @@ pub trait ToSegment {
     }
 }
 
+pub mod aws;
 #[cfg(feature = "segment-battery")]
 pub mod battery_status;
+pub mod bookmark;
+pub mod clock;
+pub mod command;
 pub mod command_status;
+pub mod container;
+pub mod cpu_time;
+pub mod disk;
+pub mod exit_code;
+pub mod fill;
+pub mod frecency;
 #[cfg(feature = "segment-git")]
 pub mod git;
 pub mod hostname;
+pub mod identity;
+pub mod instance;
+pub mod load;
+pub mod memory;
+pub mod nix;
+pub mod os;
 pub mod path;
+pub mod rbenv;
+pub mod ruby;
 #[cfg(feature = "segment-rvm")]
 pub mod rvm;
+pub mod sapling;
 pub mod screen;
+#[cfg(feature = "segment-tasks")]
+pub mod tasks;
+pub mod terminal;
+pub mod thermal;
+pub mod threshold;
 pub mod username;
+pub mod util;
 pub mod vcs;
+#[cfg(feature = "segment-wifi")]
+pub mod wifi;
+pub mod wsl;
 
+pub use aws::Aws;
 #[cfg(feature = "segment-battery")]
 pub use battery_status::BatteryStatus;
+pub use bookmark::Bookmark;
+pub use clock::Clock;
+pub use command::Command;
 pub use command_status::CommandStatus;
+pub use container::Container;
+pub use cpu_time::CpuTime;
+pub use disk::Disk;
+pub use exit_code::ExitCode;
+pub use fill::Fill;
+pub use frecency::Frecency;
 #[cfg(feature = "segment-git")]
 pub use git::Git;
 pub use hostname::Hostname;
+pub use identity::Identity;
+pub use instance::Instance;
+pub use load::Load;
+pub use memory::Memory;
+pub use nix::Nix;
+pub use os::Os;
 pub use path::Path;
+pub use rbenv::Rbenv;
 #[cfg(feature = "segment-rvm")]
 pub use rvm::Rvm;
+pub use sapling::Sapling;
 pub use screen::Screen;
+#[cfg(feature = "segment-tasks")]
+pub use tasks::Tasks;
+pub use terminal::Terminal;
+pub use thermal::Thermal;
 pub use username::Username;
+pub use vcs::Vcs;
+#[cfg(feature = "segment-wifi")]
+pub use wifi::Wifi;
+pub use wsl::Wsl;
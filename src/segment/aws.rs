@@ -0,0 +1,201 @@
+//! The `Aws` segment shows the active AWS profile
+//!
+//! The profile comes from `AWS_VAULT` (set by `aws-vault exec`) or `AWS_PROFILE`, and the region
+//! from `AWS_REGION` or `AWS_DEFAULT_REGION` when [`Args::show_region`] is set.  Temporary
+//! credentials that expire within [`Args::warn_minutes`], going by `AWS_SESSION_EXPIRATION` or
+//! `AWS_CREDENTIAL_EXPIRATION`, switch the segment to the expiring colors.
+//!
+//! Nothing is shown unless a profile is set.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Aws {}
+
+/// Arguments for the [`Aws`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the region after the profile
+    pub show_region: bool,
+
+    /// Switch to the expiring colors when the credentials expire within this many minutes
+    pub warn_minutes: u64,
+}
+
+/// Theme for the [`Aws`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+    /// Background color
+    pub bg: Color,
+
+    /// Foreground color when the credentials are about to expire, or already have
+    pub expiring_fg: Color,
+    /// Background color when the credentials are about to expire, or already have
+    pub expiring_bg: Color,
+
+    /// Icon to display before the profile
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            show_region: false,
+            warn_minutes: 15,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(0),
+            bg: Color::Numbered(214),
+
+            expiring_fg: Color::Numbered(15),
+            expiring_bg: Color::Numbered(160),
+
+            // ☁ – cloud
+            symbol: "\u{2601}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Aws {
+    /// Parses an RFC 3339 timestamp like `2024-05-01T12:34:56Z` or `2024-05-01T05:34:56-07:00`
+    /// into seconds since the epoch.  Fractional seconds are ignored.
+    pub fn parse_timestamp(timestamp: &str) -> Option<i64> {
+        let (date, time) = timestamp.split_once(['T', 't', ' '])?;
+
+        let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+        let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+        let (time, offset) = match time.strip_suffix(['Z', 'z']) {
+            Some(time) => (time, 0),
+            None => {
+                let idx = time.rfind(['+', '-'])?;
+                let sign = match &time[idx..idx + 1] {
+                    "-" => -1,
+                    _ => 1,
+                };
+                let (hours, minutes) = time[idx + 1..].split_once(':')?;
+                let offset = hours
+                    .parse::<i64>()
+                    .ok()?
+                    .checked_mul(3600)?
+                    .checked_add(minutes.parse::<i64>().ok()?.checked_mul(60)?)?;
+
+                (&time[..idx], sign * offset)
+            }
+        };
+
+        let time = time.split('.').next()?;
+        let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+        let (hour, minute, second) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || !(0..=23).contains(&hour)
+            || !(0..=59).contains(&minute)
+            || !(0..=60).contains(&second)
+        {
+            return None;
+        }
+
+        // Days since the epoch for a proleptic Gregorian date, see
+        // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        // A year too big to represent is treated like any other garbage
+        let year = if month <= 2 {
+            year.checked_sub(1)?
+        } else {
+            year
+        };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era.checked_mul(146097)?.checked_add(day_of_era - 719468)?;
+
+        days.checked_mul(86400)?
+            .checked_add(hour * 3600 + minute * 60 + second)?
+            .checked_sub(offset)
+    }
+}
+
+impl ToSegment for Aws {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Aws"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.aws;
+
+        let profile = match ["AWS_VAULT", "AWS_PROFILE"]
+            .iter()
+            .find_map(|key| state.env.get(*key).filter(|value| !value.is_empty()))
+        {
+            Some(profile) => profile,
+            None => return Ok(vec![]),
+        };
+
+        let region = match args.show_region {
+            true => ["AWS_REGION", "AWS_DEFAULT_REGION"]
+                .iter()
+                .find_map(|key| state.env.get(*key).filter(|value| !value.is_empty())),
+            false => None,
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs() as i64)
+            .unwrap_or_default();
+
+        let expires_in = ["AWS_SESSION_EXPIRATION", "AWS_CREDENTIAL_EXPIRATION"]
+            .iter()
+            .find_map(|key| state.env.get(*key))
+            .and_then(|expiration| Self::parse_timestamp(expiration))
+            .map(|expiration| expiration - now);
+
+        let (fg, bg) = match expires_in {
+            Some(seconds) if seconds < args.warn_minutes as i64 * 60 => {
+                (theme.expiring_fg, theme.expiring_bg)
+            }
+            _ => (theme.fg, theme.bg),
+        };
+
+        let text = match region {
+            Some(region) => format!("{} {} {}", theme.symbol, profile, region),
+            None => format!("{} {}", theme.symbol, profile),
+        };
+
+        Ok(vec![Segment::new("Aws", fg, bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(
+                json!({ "profile": profile, "region": region, "expires_in": expires_in }),
+            )])
+    }
+}
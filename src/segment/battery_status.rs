@@ -1,21 +1,66 @@
 //! The `BatteryStatus` segment shows current state-of-charge and charging status, if applicable
+//!
+//! Desktops usually don't have a battery, in which case the segment quietly shows nothing unless
+//! [`Args::hide_when_missing`] is turned off.  With [`Args::show_on_ac`] set it'll instead show
+//! [`Theme::on_ac_symbol`] when the machine reports that it's plugged in.
+//!
+//! Laptops with more than one battery are shown as a single battery holding all of their energy,
+//! see [`Reading::combine`].  With [`Args::show_time`] set the segment also estimates how long
+//! until the batteries run out or finish charging:
+//! ```json
+//! { "name": "battery_status", "args": { "show_time": "always", "time_format": "%hh%Mm" } }
+//! ```
+//!
+//! OpenBSD and NetBSD aren't covered by the `battery` crate, there the battery is read with
+//! `apm` instead, which only estimates the time left while discharging.  On illumos and Solaris
+//! there's no battery to be found yet.
+#[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
+use std::path::Path;
+use std::time::Duration;
+
 use anyhow::anyhow;
-use battery::State as BatteryState;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
 
-use crate::ansi::Color;
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
+use crate::platform;
 use crate::segment::{Segment, ToSegment};
-use crate::{ApplicationState, Separator};
+use crate::ApplicationState;
 use promptr_macros::SerializeNonDefault;
 
 pub struct BatteryStatus {}
 
 /// Arguments for the [`BatteryStatus`] segment
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Args {
     /// If state of charge is less than this value, switch to the warning colors
     pub low_battery_threshold: f32,
+
+    /// Show nothing instead of an error when there's no battery
+    pub hide_when_missing: bool,
+
+    /// Show [`Theme::on_ac_symbol`] when there's no battery but a charger reports it's plugged in
+    pub show_on_ac: bool,
+
+    /// When to show how long until the batteries are empty or full
+    pub show_time: ShowTime,
+
+    /// How to write out the time left, see [`BatteryStatus::format_time`]
+    pub time_format: String,
+}
+
+/// When [`BatteryStatus`] shows the time left
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShowTime {
+    #[default]
+    Never,
+    /// Only the time until empty
+    Discharging,
+    /// The time until empty, or until full while charging
+    Always,
 }
 
 /// Theme for the [`BatteryStatus`] segment
@@ -47,12 +92,22 @@ pub struct Theme {
 
     /// Displayed when the battery is finished charging
     pub full_symbol: String,
+
+    /// Displayed when there's no battery but the computer is running on wall power
+    pub on_ac_symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
 }
 
 impl Default for Args {
     fn default() -> Self {
         Self {
             low_battery_threshold: 50.0,
+            hide_when_missing: true,
+            show_on_ac: false,
+            show_time: ShowTime::Never,
+            time_format: "%h:%M".into(),
         }
     }
 }
@@ -74,6 +129,294 @@ impl Default for Theme {
             empty_symbol: "\u{2757}".into(),
             // 🔋
             full_symbol: "\u{1f50b}".into(),
+            // 🔌
+            on_ac_symbol: "\u{1f50c} AC".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        if glyphs == GlyphSet::Ascii {
+            swap_default(&mut self.charging_symbol, &default.charging_symbol, "+");
+            swap_default(
+                &mut self.discharging_symbol,
+                &default.discharging_symbol,
+                "-",
+            );
+            swap_default(&mut self.empty_symbol, &default.empty_symbol, "!");
+            swap_default(&mut self.full_symbol, &default.full_symbol, "=");
+            swap_default(&mut self.on_ac_symbol, &default.on_ac_symbol, "AC");
+        }
+    }
+}
+
+/// What the battery is doing, however it was read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charge {
+    Charging,
+    Discharging,
+    Full,
+    Empty,
+    Unknown,
+}
+
+impl Charge {
+    /// Name used in the segment metadata
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Charging => "charging",
+            Self::Discharging => "discharging",
+            Self::Full => "full",
+            Self::Empty => "empty",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// One battery as reported by the system
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    /// Energy left in joules
+    pub energy: f32,
+    /// Energy when fully charged in joules
+    pub energy_full: f32,
+    /// How fast it's charging or discharging in watts
+    pub energy_rate: f32,
+    pub charge: Charge,
+}
+
+/// All of the batteries taken together
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reading {
+    /// State of charge in percent
+    pub percent: f32,
+    pub charge: Charge,
+    /// Until empty while discharging or until full while charging, None if there's no telling
+    pub time_left: Option<Duration>,
+    /// How many batteries went into this
+    pub batteries: usize,
+}
+
+impl Reading {
+    /// Adds up `cells` as if they were one big battery, None if there aren't any.  The state of
+    /// charge is weighted by capacity, any battery that's charging or discharging makes the whole
+    /// lot count as doing so, and they're only full or empty when all of them are.
+    pub fn combine(cells: &[Cell]) -> Option<Self> {
+        let first = cells.first()?;
+
+        let energy = cells.iter().map(|cell| cell.energy).sum::<f32>();
+        let energy_full = cells.iter().map(|cell| cell.energy_full).sum::<f32>();
+        let energy_rate = cells.iter().map(|cell| cell.energy_rate.abs()).sum::<f32>();
+
+        let any = |charge| cells.iter().any(|cell| cell.charge == charge);
+        let charge = if any(Charge::Charging) {
+            Charge::Charging
+        } else if any(Charge::Discharging) {
+            Charge::Discharging
+        } else if cells.iter().all(|cell| cell.charge == first.charge) {
+            first.charge
+        } else {
+            Charge::Unknown
+        };
+
+        let percent = match energy_full > 0.0 {
+            true => (energy / energy_full * 100.0).clamp(0.0, 100.0),
+            false => 0.0,
+        };
+
+        let to_go = match charge {
+            Charge::Charging => energy_full - energy,
+            Charge::Discharging => energy,
+            _ => 0.0,
+        };
+        let time_left = match energy_rate > 0.0 && to_go > 0.0 {
+            true => Some(Duration::from_secs_f32(to_go / energy_rate)),
+            false => None,
+        };
+
+        Some(Self {
+            percent,
+            charge,
+            time_left,
+            batteries: cells.len(),
+        })
+    }
+
+    /// Combines the batteries that could be read and passes over the rest, a laptop with a flaky
+    /// second battery still shows the first.  Only an error if none of them could be read.
+    pub fn combine_readable(cells: Vec<crate::Result<Cell>>) -> crate::Result<Option<Self>> {
+        let mut readable = vec![];
+        let mut error = None;
+        for cell in cells {
+            match cell {
+                Ok(cell) => readable.push(cell),
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+
+        match (readable.is_empty(), error) {
+            (true, Some(err)) => Err(err),
+            _ => Ok(Self::combine(&readable)),
+        }
+    }
+}
+
+impl BatteryStatus {
+    /// Reads every battery and adds them up, or None if there's no battery
+    #[cfg(not(any(
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "illumos",
+        target_os = "solaris"
+    )))]
+    fn read() -> crate::Result<Option<Reading>> {
+        use battery::State;
+
+        let manager = battery::Manager::new()?;
+        let cells = manager
+            .batteries()?
+            .map(|battery| {
+                let battery = battery?;
+
+                let charge = match battery.state() {
+                    State::Charging => Charge::Charging,
+                    State::Discharging => Charge::Discharging,
+                    State::Full => Charge::Full,
+                    State::Empty => Charge::Empty,
+                    State::Unknown => Charge::Unknown,
+                    cur_state => Err(anyhow!("unknown battery state:{:?}", cur_state))?,
+                };
+
+                Ok(Cell {
+                    energy: battery.energy().value,
+                    energy_full: battery.energy_full().value,
+                    energy_rate: battery.energy_rate().value,
+                    charge,
+                })
+            })
+            .collect::<Vec<crate::Result<_>>>();
+
+        Reading::combine_readable(cells)
+    }
+
+    /// Reads the battery, or None if there's no battery.  `apm` doesn't say when the battery is
+    /// full or empty so that's worked out from the percentage.
+    #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+    fn read() -> crate::Result<Option<Reading>> {
+        use platform::ApmBattery;
+
+        let (percent, charge) = match platform::apm_battery() {
+            None | Some((_, ApmBattery::Absent)) => return Ok(None),
+            Some((percent, ApmBattery::Charging)) => (percent, Charge::Charging),
+            Some((percent, _)) if percent >= 100.0 => (percent, Charge::Full),
+            Some((percent, _)) if percent <= 0.0 => (percent, Charge::Empty),
+            Some((percent, ApmBattery::Unknown)) => (percent, Charge::Unknown),
+            Some((percent, _)) => (percent, Charge::Discharging),
+        };
+
+        let time_left = match charge {
+            Charge::Discharging => platform::apm_minutes().map(|m| Duration::from_secs(m * 60)),
+            _ => None,
+        };
+
+        Ok(Some(Reading {
+            percent,
+            charge,
+            time_left,
+            batteries: 1,
+        }))
+    }
+
+    /// There's no way to ask about the battery here yet, so act like there isn't one
+    #[cfg(any(target_os = "illumos", target_os = "solaris"))]
+    fn read() -> crate::Result<Option<Reading>> {
+        Ok(None)
+    }
+
+    /// Writes out how long is left following `format`, which understands `%h` (hours), `%m`
+    /// (minutes past the hour), `%M` (the same, padded to two digits), `%t` (total minutes), and
+    /// `%%`.  Anything else is copied as is.
+    pub fn format_time(format: &str, time_left: Duration) -> String {
+        let minutes = time_left.as_secs() / 60;
+
+        let mut out = String::new();
+        let mut chars = format.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('h') => out.push_str(&(minutes / 60).to_string()),
+                Some('m') => out.push_str(&(minutes % 60).to_string()),
+                Some('M') => out.push_str(&format!("{:02}", minutes % 60)),
+                Some('t') => out.push_str(&minutes.to_string()),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+
+        out
+    }
+
+    /// Returns the time left written out, if `args` asks for it and there's an estimate
+    pub fn time_text(args: &Args, reading: &Reading) -> Option<String> {
+        let shown = matches!(
+            (args.show_time, reading.charge),
+            (ShowTime::Always, Charge::Charging | Charge::Discharging)
+                | (ShowTime::Discharging, Charge::Discharging)
+        );
+
+        match shown {
+            true => reading
+                .time_left
+                .map(|time_left| Self::format_time(&args.time_format, time_left)),
+            false => None,
+        }
+    }
+
+    /// Returns true if a charger is plugged in, None if there's no way to tell
+    #[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
+    fn on_ac() -> Option<bool> {
+        platform::on_ac_power(Path::new(platform::POWER_SUPPLY_DIR))
+    }
+
+    /// Returns true if a charger is plugged in, None if there's no way to tell
+    #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+    fn on_ac() -> Option<bool> {
+        platform::apm_on_ac()
+    }
+
+    /// What to show on a machine without a battery
+    fn without_battery(args: &Args, theme: &Theme) -> crate::Result<Vec<Segment>> {
+        if args.show_on_ac && Self::on_ac() == Some(true) {
+            return Ok(vec![Segment::new(
+                "BatteryStatus::OnAc",
+                theme.normal_fg,
+                theme.normal_bg,
+                &theme.on_ac_symbol,
+            )
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({ "state": "ac" }))]);
+        }
+
+        match args.hide_when_missing {
+            true => Ok(vec![]),
+            false => Err(anyhow!("no battery found")),
         }
     }
 }
@@ -94,55 +437,44 @@ impl ToSegment for BatteryStatus {
 
         let theme = &state.theme.battery;
 
-        let manager = battery::Manager::new()?;
-        let battery = manager
-            .batteries()?
-            .next()
-            .ok_or_else(|| anyhow!("battery status unwrapping nightmare"))??;
-        let state_of_charge = battery.state_of_charge().value * 100.0;
-
-        let seg = match battery.state() {
-            BatteryState::Charging => Segment {
-                fg: theme.normal_fg,
-                bg: theme.normal_bg,
-                separator: Separator::Thick,
-                text: format!("{:.0}% {}", state_of_charge, theme.charging_symbol),
-                source: "BatteryStatus::Charging",
-            },
-            BatteryState::Discharging | BatteryState::Unknown
-                if state_of_charge < args.low_battery_threshold =>
-            {
-                Segment {
-                    fg: theme.low_fg,
-                    bg: theme.low_bg,
-                    separator: Separator::Thick,
-                    text: format!("{:.0}% {}", state_of_charge, theme.discharging_symbol),
-                    source: "BatteryStatus::Discharging/Unknown",
-                }
-            }
-            BatteryState::Discharging | BatteryState::Unknown => Segment {
-                fg: theme.normal_fg,
-                bg: theme.normal_bg,
-                separator: Separator::Thick,
-                text: format!("{:.0}% {}", state_of_charge, theme.discharging_symbol),
-                source: "BatteryStatus::Discharging/Unknown",
-            },
-            BatteryState::Full => Segment {
-                fg: theme.normal_fg,
-                bg: theme.normal_bg,
-                separator: Separator::Thick,
-                text: format!("100% {}", theme.full_symbol),
-                source: "BatteryStatus::Full",
-            },
-            BatteryState::Empty => Segment {
-                fg: theme.low_fg,
-                bg: theme.low_bg,
-                separator: Separator::Thick,
-                text: format!("{:.0}% {}", state_of_charge, theme.empty_symbol),
-                source: "BatteryStatus::Empty",
-            },
-            cur_state => Err(anyhow!("unknown battery state:{:?}", cur_state))?,
+        let reading = match Self::read()? {
+            Some(reading) => reading,
+            None => return Self::without_battery(&args, theme),
+        };
+        let state_of_charge = reading.percent;
+        let metadata = json!({
+            "percentage": state_of_charge,
+            "state": reading.charge.name(),
+            "batteries": reading.batteries,
+            "time_left": reading.time_left.map(|time_left| time_left.as_secs()),
+        });
+
+        let (name, low, symbol) = match reading.charge {
+            Charge::Charging => ("BatteryStatus::Charging", false, &theme.charging_symbol),
+            Charge::Discharging | Charge::Unknown => (
+                "BatteryStatus::Discharging/Unknown",
+                state_of_charge < args.low_battery_threshold,
+                &theme.discharging_symbol,
+            ),
+            Charge::Full => ("BatteryStatus::Full", false, &theme.full_symbol),
+            Charge::Empty => ("BatteryStatus::Empty", true, &theme.empty_symbol),
+        };
+        let (fg, bg) = match low {
+            true => (theme.low_fg, theme.low_bg),
+            false => (theme.normal_fg, theme.normal_bg),
+        };
+
+        let mut text = match reading.charge {
+            Charge::Full => format!("100% {}", symbol),
+            _ => format!("{:.0}% {}", state_of_charge, symbol),
         };
+        if let Some(time_left) = Self::time_text(&args, &reading) {
+            text = format!("{} {}", text, time_left);
+        }
+
+        let seg = Segment::new(name, fg, bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(metadata);
 
         Ok(vec![seg])
     }
@@ -0,0 +1,189 @@
+//! The `Bookmark` segment labels directories you've configured
+//!
+//! Each key in `bookmarks` is either an exact path or a glob, a leading `~` is replaced with the
+//! home directory:
+//! ```json
+//! {
+//!     "name": "bookmark",
+//!     "args": {
+//!         "bookmarks": {
+//!             "~/src/billing": { "label": "billing", "icon": "🏦" },
+//!             "~/src/billing/**": { "label": "billing" },
+//!             "/etc/*": { "label": "careful" }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! In a glob `*` and `?` don't match `/` while `**` matches anything.  An exact match always wins,
+//! otherwise the longest matching glob is used.
+
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Bookmark {}
+
+/// Arguments for the [`Bookmark`] segment
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Directories (or globs) to label
+    pub bookmarks: BTreeMap<String, Label>,
+}
+
+/// What to show for a bookmarked directory
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Label {
+    /// Text to show, can be empty if you only want the icon
+    pub label: String,
+
+    /// Icon to show before the label, defaults to [`theme.bookmark.symbol`](`Theme`)
+    pub icon: Option<String>,
+}
+
+/// Theme for the [`Bookmark`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon for bookmarks that don't have their own
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(61),
+
+            // 🔖 – bookmark
+            symbol: "\u{1f516}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Bookmark {
+    /// Converts a glob into an anchored regular expression
+    fn glob_to_regex(glob: &str) -> crate::Result<Regex> {
+        let mut pattern = String::from("^");
+        let mut chars = glob.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    pattern.push_str(".*");
+                }
+                '*' => pattern.push_str("[^/]*"),
+                '?' => pattern.push_str("[^/]"),
+                c => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+
+        pattern.push('$');
+
+        Ok(Regex::new(&pattern)?)
+    }
+
+    /// Returns true if `pattern` should be treated as a glob rather than an exact path
+    fn is_glob(pattern: &str) -> bool {
+        pattern.contains(['*', '?'])
+    }
+
+    /// Finds the bookmark for `path`, if any
+    pub fn find<'a>(
+        bookmarks: &'a BTreeMap<String, Label>,
+        path: &str,
+        home: Option<&str>,
+    ) -> crate::Result<Option<&'a Label>> {
+        let expand = |pattern: &str| match (pattern.strip_prefix('~'), home) {
+            (Some(rest), Some(home)) => format!("{}{}", home, rest),
+            _ => pattern.to_string(),
+        };
+
+        let path = path.trim_end_matches('/');
+
+        if let Some((_, label)) = bookmarks.iter().find(|(pattern, _)| {
+            !Self::is_glob(pattern) && expand(pattern).trim_end_matches('/') == path
+        }) {
+            return Ok(Some(label));
+        }
+
+        let mut best: Option<(usize, &Label)> = None;
+
+        for (pattern, label) in bookmarks
+            .iter()
+            .filter(|(pattern, _)| Self::is_glob(pattern))
+        {
+            let better = best.is_none_or(|(len, _)| pattern.len() > len);
+
+            if better && Self::glob_to_regex(&expand(pattern))?.is_match(path) {
+                best = Some((pattern.len(), label));
+            }
+        }
+
+        Ok(best.map(|(_, label)| label))
+    }
+}
+
+impl ToSegment for Bookmark {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Bookmark"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.bookmark;
+
+        let path = match state.env.get("PWD") {
+            Some(path) => path,
+            None => return Ok(vec![]),
+        };
+
+        let label = match Self::find(
+            &args.bookmarks,
+            path,
+            state.env.get("HOME").map(String::as_str),
+        )? {
+            Some(label) => label,
+            None => return Ok(vec![]),
+        };
+
+        let icon = label.icon.as_ref().unwrap_or(&theme.symbol);
+
+        let text = match label.label.is_empty() {
+            true => icon.clone(),
+            false => format!("{} {}", icon, label.label),
+        };
+
+        Ok(vec![Segment::new("Bookmark", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({ "label": label.label }))])
+    }
+}
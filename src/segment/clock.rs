@@ -0,0 +1,141 @@
+//! The `Clock` segment shows the time the prompt was drawn
+//!
+//! `bash` has no right prompt so with [`Args::right_edge`] set the clock is drawn against the right
+//! edge of the terminal instead, by saving the cursor, jumping over, and restoring it again.  None
+//! of that counts towards the width of the prompt.  The terminal has to understand the save and
+//! restore escapes, and the clock is left out when `$COLUMNS` isn't known or the line is too long
+//! to leave room for it, see [`render::place_right`](`crate::render::place_right`).
+//! ```json
+//! { "name": "clock", "args": { "format": "%H:%M", "right_edge": true } }
+//! ```
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::ansi::{Color, TextStyle};
+use crate::platform;
+use crate::segment::{Kind, Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Clock {}
+
+/// Arguments for the [`Clock`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// How to write out the time, see [`Clock::format`]
+    pub format: String,
+
+    /// Draw the clock at the right edge of the terminal instead of in the prompt
+    pub right_edge: bool,
+}
+
+/// Theme for the [`Clock`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the time, left out if empty
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            format: "%H:%M:%S".into(),
+            right_edge: false,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(250),
+            bg: Color::Numbered(238),
+
+            // 🕒 – clock face three oclock
+            symbol: "\u{1f552}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Clock {
+    /// Writes out a time of day following `format`, which understands a few `strftime(3)`
+    /// directives: `%H` (00-23), `%I` (01-12), `%M`, `%S`, `%p` (AM/PM), and `%%`.  Anything else
+    /// is copied as is.
+    pub fn format(format: &str, hour: u8, minute: u8, second: u8) -> String {
+        let mut out = String::new();
+        let mut chars = format.chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                out.push(c);
+                continue;
+            }
+
+            match chars.next() {
+                Some('H') => out.push_str(&format!("{:02}", hour)),
+                Some('I') => out.push_str(&format!("{:02}", (hour + 11) % 12 + 1)),
+                Some('M') => out.push_str(&format!("{:02}", minute)),
+                Some('S') => out.push_str(&format!("{:02}", second)),
+                Some('p') => out.push_str(if hour < 12 { "AM" } else { "PM" }),
+                Some('%') => out.push('%'),
+                Some(other) => {
+                    out.push('%');
+                    out.push(other);
+                }
+                None => out.push('%'),
+            }
+        }
+
+        out
+    }
+}
+
+impl ToSegment for Clock {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Clock"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.clock;
+
+        let time = match platform::local_time() {
+            Some((hour, minute, second)) => Self::format(&args.format, hour, minute, second),
+            None => return Ok(vec![]),
+        };
+
+        let text = match theme.symbol.is_empty() {
+            true => time,
+            false => format!("{} {}", theme.symbol, time),
+        };
+
+        let kind = match args.right_edge {
+            true => Kind::Right,
+            false => Kind::Text,
+        };
+
+        Ok(vec![Segment::new("Clock", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_kind(kind)])
+    }
+}
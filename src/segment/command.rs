@@ -0,0 +1,164 @@
+//! The `Command` segment runs a shell command and shows what it prints
+//!
+//! This is the escape hatch for anything promptr doesn't know about natively.  The command is run
+//! with `sh -c` in the current directory, only the first line of its output is shown, and nothing
+//! is shown if it fails or prints nothing.  A command that's still running after
+//! [`Args::timeout_ms`] is killed.  Set [`Args::ttl_seconds`] to [cache](`crate::cache`) the
+//! output, or the failure, for commands that are too slow to run on every prompt:
+//! ```json
+//! { "name": "command", "args": { "command": "kubectl config current-context", "ttl_seconds": 30 } }
+//! ```
+
+use std::process::{self, Stdio};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::cache::Cache;
+use crate::exec;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Command {}
+
+/// Arguments for the [`Command`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Shell command to run
+    pub command: String,
+
+    /// Kill the command if it's still running after this many milliseconds
+    pub timeout_ms: u64,
+
+    /// How long to hold on to the output before running the command again, zero runs it on every
+    /// prompt
+    pub ttl_seconds: u64,
+}
+
+/// Theme for the [`Command`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the output, nothing is shown when this is empty
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            timeout_ms: 250,
+            ttl_seconds: 0,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(250),
+            bg: Color::Numbered(238),
+
+            symbol: String::new(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Command {
+    /// Runs `args.command`, returning the first line it printed or `None` if it failed or printed
+    /// nothing
+    pub fn run(args: &Args, cwd: Option<&str>) -> crate::Result<Option<String>> {
+        let mut command = process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg(&args.command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+
+        let finished = exec::run(&mut command, Duration::from_millis(args.timeout_ms), None)?
+            .ok_or_else(|| anyhow!("{} timed out after {}ms", args.command, args.timeout_ms))?;
+
+        if !finished.status.success() {
+            return Ok(None);
+        }
+
+        Ok(String::from_utf8_lossy(&finished.stdout)
+            .lines()
+            .next()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from))
+    }
+}
+
+impl ToSegment for Command {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Command"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.command;
+
+        if args.command.is_empty() {
+            return Err(anyhow!("no command was given"));
+        }
+
+        let cwd = state.env.get("PWD").map(String::as_str);
+
+        let output = match args.ttl_seconds {
+            0 => Self::run(&args, cwd)?,
+            ttl => Cache::from_env(&state.env)?.get_or_insert_with(
+                &Cache::key("command", &(&args.command, cwd)),
+                Duration::from_secs(ttl),
+                || Self::run(&args, cwd),
+            )?,
+        };
+
+        let output = match output {
+            Some(output) => output,
+            None => return Ok(vec![]),
+        };
+
+        let shown = state.escape_style.escape_literal(&output);
+
+        let text = match theme.symbol.is_empty() {
+            true => shown.into_owned(),
+            false => format!("{} {}", theme.symbol, shown),
+        };
+
+        let metadata = json!({ "command": args.command, "output": output });
+
+        Ok(vec![Segment::new("Command", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(metadata)])
+    }
+}
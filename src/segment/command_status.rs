@@ -1,17 +1,26 @@
 //! The `CommandStatus` segment displays the root / non-privileged indicator and the last exit value
 
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
 
-use crate::ansi::Color;
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
 use crate::segment::{Segment, ToSegment};
-use crate::{ApplicationState, Separator};
+use crate::shell;
+use crate::ApplicationState;
 use promptr_macros::SerializeNonDefault;
 
 pub struct CommandStatus {}
 
-#[derive(Default, Deserialize)]
+/// Arguments for the [`CommandStatus`] segment
+#[derive(Default, Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
-pub struct Args {}
+pub struct Args {
+    /// Show a strip with the exit status of up to this many recent commands (oldest first) before
+    /// the indicator.  Zero turns it off.  The shell remembers the last
+    /// [`EXIT_CODE_HISTORY`](`crate::shell::Shell::EXIT_CODE_HISTORY`) commands.
+    pub history: usize,
+}
 
 /// Theme for the [`CommandStatus`] segment.
 ///
@@ -34,6 +43,15 @@ pub struct Theme {
 
     /// Indicator for non-privileged users.  On bash this is typically `$`.
     pub user_indicator: String,
+
+    /// Marks a command that succeeded in the history strip
+    pub history_success: String,
+
+    /// Marks a command that failed in the history strip
+    pub history_failure: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
 }
 
 impl Default for Theme {
@@ -47,6 +65,25 @@ impl Default for Theme {
 
             root_indicator: "#".into(),
             user_indicator: "\\$".into(),
+
+            // ● – black circle
+            history_success: "\u{25cf}".into(),
+            // ○ – white circle
+            history_failure: "\u{25cb}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        if glyphs == GlyphSet::Ascii {
+            swap_default(&mut self.history_success, &default.history_success, "o");
+            swap_default(&mut self.history_failure, &default.history_failure, "x");
         }
     }
 }
@@ -60,12 +97,17 @@ impl ToSegment for CommandStatus {
     }
 
     fn to_segment(
-        _args: Option<Self::Args>,
+        args: Option<Self::Args>,
         state: &ApplicationState,
     ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
         let theme = &state.theme.command_status;
 
-        let exit_code = state.env.get("code").map_or("0", String::as_str);
+        let mut segments = vec![];
+
+        let exit_code = shell::last_exit_code(&state.env).unwrap_or("0");
+        let metadata = json!({ "exit_code": exit_code.parse::<u8>().ok() });
         let (fg, bg) = match exit_code.parse::<u8>() {
             Ok(0) => (theme.success_fg, theme.success_bg),
             Ok(_) => (theme.failure_fg, theme.failure_bg),
@@ -79,12 +121,42 @@ impl ToSegment for CommandStatus {
             _ => theme.user_indicator.clone(),
         };
 
-        Ok(vec![Segment {
-            bg,
-            fg,
-            separator: Separator::Thick,
-            text,
-            source: "CommandStatus",
-        }])
+        if args.history > 0 {
+            let codes = state
+                .env
+                .get("codes")
+                .map(|codes| codes.split_whitespace().collect::<Vec<_>>())
+                .unwrap_or_default();
+            let codes = &codes[codes.len().saturating_sub(args.history)..];
+
+            if !codes.is_empty() {
+                let strip = codes
+                    .iter()
+                    .map(|code| match *code {
+                        "0" => theme.history_success.as_str(),
+                        _ => theme.history_failure.as_str(),
+                    })
+                    .collect::<String>();
+
+                segments.push(
+                    Segment::new("CommandStatus", theme.success_fg, theme.success_bg, strip)
+                        .with_style(theme.style.iter().copied())
+                        .with_metadata(json!({
+                            "exit_codes": codes
+                                .iter()
+                                .map(|code| code.parse::<u8>().ok())
+                                .collect::<Vec<_>>()
+                        })),
+                );
+            }
+        }
+
+        segments.push(
+            Segment::new("CommandStatus", fg, bg, text)
+                .with_style(theme.style.iter().copied())
+                .with_metadata(metadata),
+        );
+
+        Ok(segments)
     }
 }
@@ -0,0 +1,285 @@
+//! The `Container` segment shows the container or chroot the shell is running in
+//!
+//! Docker and Podman leave `/.dockerenv` and `/run/.containerenv` behind, and the cgroup of the
+//! shell usually has the container ID or LXC container name in it.  Failing that, container
+//! managers like `systemd-nspawn` set `$container`.  A chroot is recognized by Debian's
+//! `/etc/debian_chroot` or, when we're allowed to look, by our root being different from that of
+//! `init` (see [`platform::in_chroot`]).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
+use crate::platform;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Container {}
+
+/// Arguments for the [`Container`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the name or ID of the container after the symbol, otherwise just the runtime
+    pub show_name: bool,
+
+    /// Container IDs are cut down to this many characters, the same as `docker ps` does
+    pub id_length: usize,
+}
+
+/// Theme for the [`Container`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the container
+    pub symbol: String,
+
+    /// Icon to display before the chroot
+    pub chroot_symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+/// What the shell is running inside of
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Runtime {
+    Docker,
+    Podman,
+    Lxc,
+    /// `systemd-nspawn`
+    Nspawn,
+    Chroot,
+}
+
+/// What [`Container::detect`] found
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Detected {
+    pub runtime: Runtime,
+    /// Name of the container, or the full ID if it doesn't have a name we can find
+    pub name: Option<String>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            show_name: true,
+            id_length: 12,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(24),
+
+            // 📦 – package
+            symbol: "\u{1f4e6}".into(),
+            // 🔒 – lock
+            chroot_symbol: "\u{1f512}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        if glyphs == GlyphSet::Ascii {
+            swap_default(&mut self.symbol, &default.symbol, "ctr");
+            swap_default(&mut self.chroot_symbol, &default.chroot_symbol, "chroot");
+        }
+    }
+}
+
+impl Runtime {
+    /// Name to show when there's nothing more specific
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Docker => "docker",
+            Self::Podman => "podman",
+            Self::Lxc => "lxc",
+            Self::Nspawn => "nspawn",
+            Self::Chroot => "chroot",
+        }
+    }
+}
+
+impl Container {
+    /// Looks for signs of a container under `root`, which is `/` outside of the tests.  Doesn't
+    /// look for a chroot that didn't leave a name behind, see [`platform::in_chroot`].
+    pub fn detect(root: &Path, env: &HashMap<String, String>) -> Option<Detected> {
+        let read = |path: &str| fs::read_to_string(root.join(path)).ok();
+        let cgroup = read("proc/self/cgroup").unwrap_or_default();
+
+        if let Some(containerenv) = read("run/.containerenv") {
+            // Lines like name="web", some are left out for rootless containers
+            let field = |key: &str| {
+                containerenv.lines().find_map(|line| {
+                    let value = line.strip_prefix(key)?.strip_prefix('=')?;
+                    Some(value.trim_matches('"').to_string()).filter(|value| !value.is_empty())
+                })
+            };
+
+            return Some(Detected {
+                runtime: Runtime::Podman,
+                name: field("name")
+                    .or_else(|| field("id"))
+                    .or_else(|| Self::from_cgroup(&cgroup).and_then(|found| found.name)),
+            });
+        }
+
+        if root.join(".dockerenv").exists() {
+            return Some(Detected {
+                runtime: Runtime::Docker,
+                name: Self::from_cgroup(&cgroup).and_then(|found| found.name),
+            });
+        }
+
+        if let Some(found) = Self::from_cgroup(&cgroup) {
+            return Some(found);
+        }
+
+        let runtime = match env.get("container").map(String::as_str) {
+            Some("docker") => Some(Runtime::Docker),
+            Some("podman") => Some(Runtime::Podman),
+            Some("lxc") | Some("lxc-libvirt") => Some(Runtime::Lxc),
+            Some("systemd-nspawn") => Some(Runtime::Nspawn),
+            _ => None,
+        };
+
+        if let Some(runtime) = runtime {
+            return Some(Detected {
+                runtime,
+                name: None,
+            });
+        }
+
+        let debian_chroot = env
+            .get("debian_chroot")
+            .cloned()
+            .or_else(|| read("etc/debian_chroot"))
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+
+        debian_chroot.map(|name| Detected {
+            runtime: Runtime::Chroot,
+            name: Some(name),
+        })
+    }
+
+    /// Picks a container out of `/proc/self/cgroup`.  With cgroup v2 and a private cgroup
+    /// namespace this is just `0::/` and there's nothing to find.
+    pub fn from_cgroup(cgroup: &str) -> Option<Detected> {
+        cgroup.lines().find_map(|line| {
+            // hierarchy-ID:controllers:path
+            let path = line.splitn(3, ':').nth(2)?;
+
+            path.split('/')
+                .find_map(|part| {
+                    let scope = |prefix: &str| {
+                        part.strip_prefix(prefix)
+                            .map(|id| id.trim_end_matches(".scope").to_string())
+                    };
+
+                    if let Some(id) = scope("docker-") {
+                        return Some((Runtime::Docker, id));
+                    }
+                    if let Some(id) = scope("libpod-") {
+                        return Some((Runtime::Podman, id));
+                    }
+                    if let Some(name) = part.strip_prefix("lxc.payload.") {
+                        return Some((Runtime::Lxc, name.to_string()));
+                    }
+
+                    None
+                })
+                .or_else(|| {
+                    // cgroup v1 puts the runtime and the container in separate parts of the path
+                    let mut parts = path.split('/').skip_while(|part| part.is_empty());
+                    let runtime = match parts.next()? {
+                        "docker" => Runtime::Docker,
+                        "lxc" | "lxc.payload" => Runtime::Lxc,
+                        _ => return None,
+                    };
+                    Some((runtime, parts.next()?.to_string()))
+                })
+                .filter(|(_, name)| !name.is_empty())
+                .map(|(runtime, name)| Detected {
+                    runtime,
+                    name: Some(name),
+                })
+        })
+    }
+}
+
+impl ToSegment for Container {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Container"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.container;
+
+        let detected = match Self::detect(Path::new("/"), &state.env) {
+            Some(detected) => detected,
+            None if platform::in_chroot() == Some(true) => Detected {
+                runtime: Runtime::Chroot,
+                name: None,
+            },
+            None => return Ok(vec![]),
+        };
+
+        let symbol = match detected.runtime {
+            Runtime::Chroot => &theme.chroot_symbol,
+            _ => &theme.symbol,
+        };
+
+        // LXC and chroot names are names, everything else is a long hex ID
+        let name = match (&detected.name, detected.runtime) {
+            (Some(name), Runtime::Docker | Runtime::Podman) if name.len() == 64 => {
+                name.chars().take(args.id_length).collect()
+            }
+            (Some(name), _) => name.clone(),
+            (None, runtime) => runtime.name().to_string(),
+        };
+
+        let text = match args.show_name {
+            true => format!("{} {}", symbol, name),
+            false => format!("{} {}", symbol, detected.runtime.name()),
+        };
+
+        Ok(vec![Segment::new("Container", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({
+                "runtime": detected.runtime,
+                "name": detected.name,
+            }))])
+    }
+}
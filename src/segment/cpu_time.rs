@@ -0,0 +1,125 @@
+//! The `CpuTime` segment displays the user and system CPU time used by the previous command
+//!
+//! The shell hook records the accumulated CPU time of the shell's children (as reported by the
+//! `times` builtin) before each prompt and passes along the values from the previous prompt.  The
+//! difference between the two is what the last command used.  This includes the previous
+//! invocation of `promptr` itself, which is typically a few milliseconds, so the segment is hidden
+//! until the total crosses [`Args::min_seconds`].
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct CpuTime {}
+
+/// Arguments for the [`CpuTime`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Hide the segment when user + system time is below this many seconds
+    pub min_seconds: f64,
+
+    /// Show system time alongside user time
+    pub show_sys: bool,
+}
+
+/// Theme for the [`CpuTime`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the times
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            min_seconds: 1.0,
+            show_sys: true,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(250),
+            bg: Color::Numbered(238),
+
+            // ⚙ – gear
+            symbol: "\u{2699}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+/// Parses the `XmY.ZZZs` format used by the bash `times` builtin into seconds
+pub fn parse_times(value: &str) -> Option<f64> {
+    let (minutes, seconds) = value.strip_suffix('s')?.split_once('m')?;
+
+    Some(minutes.parse::<f64>().ok()? * 60.0 + seconds.parse::<f64>().ok()?)
+}
+
+impl CpuTime {
+    /// Returns the seconds elapsed between the `{name}_prev` and `{name}` captured variables
+    fn delta(state: &ApplicationState, name: &str) -> Option<f64> {
+        let current = parse_times(state.env.get(name)?)?;
+        let previous = parse_times(state.env.get(&format!("{}_prev", name))?)?;
+
+        Some((current - previous).max(0.0))
+    }
+}
+
+impl ToSegment for CpuTime {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::CpuTime"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.cpu_time;
+
+        let (user, sys) = match (
+            Self::delta(state, "cpu_user"),
+            Self::delta(state, "cpu_sys"),
+        ) {
+            (Some(user), Some(sys)) => (user, sys),
+            _ => return Ok(vec![]),
+        };
+
+        if user + sys < args.min_seconds {
+            return Ok(vec![]);
+        }
+
+        let text = if args.show_sys {
+            format!("{} {:.2}u {:.2}s", theme.symbol, user, sys)
+        } else {
+            format!("{} {:.2}u", theme.symbol, user)
+        };
+
+        Ok(vec![Segment::new("CpuTime", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({ "user": user, "sys": sys }))])
+    }
+}
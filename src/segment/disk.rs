@@ -0,0 +1,210 @@
+//! The `Disk` segment shows how full the filesystem holding the current directory is
+//!
+//! The percentage in use is checked against [`Args::thresholds`], see
+//! [`threshold`](`crate::segment::threshold`).  Space reserved for root counts as used, the same
+//! as `df` does it.  Set [`Args::path`] to keep an eye on one filesystem no matter where you are.
+//!
+//! With `"show": "free"` the space that's left is shown instead, in [`Args::units`].  Whichever is
+//! shown, [`Args::warn_below_gib`] and [`Args::crit_below_gib`] change colors once there isn't
+//! much room left.  A percentage isn't much of a warning on a big disk:
+//! ```json
+//! { "name": "disk", "args": { "show": "free", "warn_below_gib": 10, "crit_below_gib": 2 } }
+//! ```
+
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::TextStyle;
+use crate::platform;
+use crate::segment::threshold::{Level, ThresholdColors, ThresholdDisplay};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Disk {}
+
+const GIB: f64 = (1u64 << 30) as f64;
+
+/// Arguments for the [`Disk`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Check the filesystem holding this path instead of the current directory
+    pub path: Option<String>,
+
+    /// When to show the segment and when to change colors, based on the percentage in use
+    pub thresholds: ThresholdDisplay,
+
+    /// Show the percentage in use or the space that's free
+    pub show: Show,
+
+    /// Units for the free space
+    pub units: Units,
+
+    /// Switch to the warning colors when less than this many GiB are free
+    pub warn_below_gib: Option<f64>,
+
+    /// Switch to the critical colors when less than this many GiB are free
+    pub crit_below_gib: Option<f64>,
+}
+
+/// What to show about the filesystem, see [`Args::show`]
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Show {
+    Used,
+    Free,
+}
+
+/// How to show the free space, see [`Args::units`].  These are all powers of 1024, like `df -h`.
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum Units {
+    /// Whichever unit keeps the number under 1024
+    #[serde(rename = "auto")]
+    Auto,
+    KiB,
+    MiB,
+    GiB,
+    TiB,
+}
+
+/// Theme for the [`Disk`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Colors for each threshold
+    pub colors: ThresholdColors,
+
+    /// Icon to display before the percentage
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            path: None,
+            thresholds: ThresholdDisplay {
+                show_above: None,
+                warn_at: Some(90.0),
+                crit_at: Some(95.0),
+            },
+            show: Show::Used,
+            units: Units::Auto,
+            warn_below_gib: None,
+            crit_below_gib: None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            colors: Default::default(),
+
+            // 💾 – floppy disk
+            symbol: "\u{1f4be}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Units {
+    const SUFFIXES: [&'static str; 6] = ["B", "K", "M", "G", "T", "P"];
+
+    /// Formats `bytes` the way `df -h` would, one decimal place for anything under ten
+    pub fn format(self, bytes: u64) -> String {
+        let bytes = bytes as f64;
+
+        let power = match self {
+            Self::Auto => (1..Self::SUFFIXES.len())
+                .take_while(|power| bytes >= 1024f64.powi(*power as i32))
+                .count(),
+            Self::KiB => 1,
+            Self::MiB => 2,
+            Self::GiB => 3,
+            Self::TiB => 4,
+        };
+
+        let value = bytes / 1024f64.powi(power as i32);
+
+        match value < 10.0 && power > 0 {
+            true => format!("{:.1}{}", value, Self::SUFFIXES[power]),
+            false => format!("{:.0}{}", value, Self::SUFFIXES[power]),
+        }
+    }
+}
+
+impl Args {
+    /// Returns how alarming the disk looks, whichever of the percentage in use and the space that's
+    /// free is worse
+    pub fn level(&self, used: f64, available: u64) -> Level {
+        let gib = available as f64 / GIB;
+        let below = |threshold: Option<f64>| threshold.is_some_and(|below| gib < below);
+
+        let free = match (below(self.crit_below_gib), below(self.warn_below_gib)) {
+            (true, _) => Level::Crit,
+            (false, true) => Level::Warn,
+            (false, false) => Level::Normal,
+        };
+
+        self.thresholds.level(used).max(free)
+    }
+}
+
+impl ToSegment for Disk {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Disk"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.disk;
+
+        let path = match args.path.as_ref().or_else(|| state.env.get("PWD")) {
+            Some(path) => path,
+            None => return Ok(vec![]),
+        };
+
+        let (total, available) = match platform::disk_space(Path::new(path)) {
+            Some((total, available)) if total > 0 => (total, available),
+            _ => return Ok(vec![]),
+        };
+
+        let used = 100.0 * total.saturating_sub(available) as f64 / total as f64;
+
+        if !args.thresholds.visible(used) {
+            return Ok(vec![]);
+        }
+
+        let (fg, bg) = theme.colors.colors(args.level(used, available));
+
+        let metadata = json!({
+            "path": path,
+            "used_percent": used,
+            "total_bytes": total,
+            "available_bytes": available,
+        });
+
+        let text = match args.show {
+            Show::Used => format!("{} {:.0}%", theme.symbol, used),
+            Show::Free => format!("{} {}", theme.symbol, args.units.format(available)),
+        };
+
+        Ok(vec![Segment::new("Disk", fg, bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(metadata)])
+    }
+}
@@ -0,0 +1,146 @@
+//! The `ExitCode` segment displays the exit status of the last command when it failed
+//!
+//! Shells report a command killed by a signal as 128 plus the signal number, those are shown by
+//! name (e.g. `SIGINT` after hitting Ctrl-C) unless [`Args::signal_names`] is turned off.
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{Segment, ToSegment};
+use crate::shell;
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct ExitCode {}
+
+/// Arguments for the [`ExitCode`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the signal name instead of the number when a command was killed by a signal
+    pub signal_names: bool,
+}
+
+/// Theme for the [`ExitCode`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+    /// Background color
+    pub bg: Color,
+
+    /// Foreground color when the command was killed by a signal
+    pub signal_fg: Color,
+    /// Background color when the command was killed by a signal
+    pub signal_bg: Color,
+
+    /// Icon to display before the exit status
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self { signal_names: true }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(52),
+
+            signal_fg: Color::Numbered(15),
+            signal_bg: Color::Numbered(90),
+
+            // ✘ – heavy ballot x
+            symbol: "\u{2718}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl ExitCode {
+    /// Returns the name of signal number `signal`, if it's one of the usual suspects
+    pub fn signal_name(signal: i32) -> Option<&'static str> {
+        let name = match signal {
+            libc::SIGHUP => "SIGHUP",
+            libc::SIGINT => "SIGINT",
+            libc::SIGQUIT => "SIGQUIT",
+            libc::SIGILL => "SIGILL",
+            libc::SIGTRAP => "SIGTRAP",
+            libc::SIGABRT => "SIGABRT",
+            libc::SIGBUS => "SIGBUS",
+            libc::SIGFPE => "SIGFPE",
+            libc::SIGKILL => "SIGKILL",
+            libc::SIGUSR1 => "SIGUSR1",
+            libc::SIGSEGV => "SIGSEGV",
+            libc::SIGUSR2 => "SIGUSR2",
+            libc::SIGPIPE => "SIGPIPE",
+            libc::SIGALRM => "SIGALRM",
+            libc::SIGTERM => "SIGTERM",
+            libc::SIGCHLD => "SIGCHLD",
+            libc::SIGCONT => "SIGCONT",
+            libc::SIGSTOP => "SIGSTOP",
+            libc::SIGTSTP => "SIGTSTP",
+            libc::SIGTTIN => "SIGTTIN",
+            libc::SIGTTOU => "SIGTTOU",
+            libc::SIGXCPU => "SIGXCPU",
+            libc::SIGXFSZ => "SIGXFSZ",
+            libc::SIGWINCH => "SIGWINCH",
+            libc::SIGSYS => "SIGSYS",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+}
+
+impl ToSegment for ExitCode {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::ExitCode"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.exit_code;
+
+        let code = match shell::last_exit_code(&state.env).and_then(|code| code.parse::<i32>().ok())
+        {
+            Some(0) | None => return Ok(vec![]),
+            Some(code) => code,
+        };
+
+        let signal = match (args.signal_names, code) {
+            (true, 129..=255) => Self::signal_name(code - 128),
+            _ => None,
+        };
+
+        let (fg, bg, status) = match signal {
+            Some(name) => (theme.signal_fg, theme.signal_bg, name.to_string()),
+            None => (theme.fg, theme.bg, code.to_string()),
+        };
+
+        Ok(vec![Segment::new(
+            "ExitCode",
+            fg,
+            bg,
+            format!("{} {}", theme.symbol, status),
+        )
+        .with_style(theme.style.iter().copied())
+        .with_metadata(json!({ "exit_code": code, "signal": signal }))])
+    }
+}
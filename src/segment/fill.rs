@@ -0,0 +1,76 @@
+//! The `Fill` segment pads out the line so everything after it is pushed to the right edge
+//!
+//! This needs to know how wide the terminal is, which comes from `$COLUMNS`.  If that isn't set
+//! the fill collapses to nothing.  More than one fill on a line splits the room between them.
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::ansi::Color;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Fill {}
+
+/// Arguments for the [`Fill`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Character to pad with, should be a single column wide
+    pub character: char,
+}
+
+/// Theme for the [`Fill`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color, only visible if the padding isn't a space
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self { character: ' ' }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(240),
+            bg: Color::Numbered(235),
+        }
+    }
+}
+
+impl ToSegment for Fill {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Fill"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.fill;
+
+        if state.columns.is_none() {
+            return Ok(vec![]);
+        }
+
+        Ok(vec![Segment::fill(
+            "Fill",
+            theme.fg,
+            theme.bg,
+            args.character,
+        )])
+    }
+}
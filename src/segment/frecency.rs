@@ -0,0 +1,244 @@
+//! The `Frecency` segment shows where the current directory ranks in `zoxide` or `autojump`
+//!
+//! Only directories in the top [`Args::top`] are marked.  Asking the tool for its database on
+//! every prompt would be slow so the ranking is [cached](`crate::cache`) for
+//! [`Args::ttl_seconds`].
+//!
+//! With [`Args::feed`] set a [`post_render`](`crate::hooks`) hook records each visit so the tool's
+//! own shell integration can be left out:
+//! ```json
+//! { "name": "frecency", "args": { "tool": "autojump", "feed": true } }
+//! ```
+
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::cache::Cache;
+use crate::hooks::Hook;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Frecency {}
+
+/// Which directory jumper to ask
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tool {
+    Zoxide,
+    Autojump,
+}
+
+/// Arguments for the [`Frecency`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Which directory jumper to ask
+    pub tool: Tool,
+
+    /// Only show the rank for directories this high up the list
+    pub top: usize,
+
+    /// How long to hold on to the ranking before asking again
+    pub ttl_seconds: u64,
+
+    /// Record a visit to the current directory after each prompt
+    pub feed: bool,
+}
+
+/// Theme for the [`Frecency`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the rank
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Zoxide,
+            top: 10,
+            ttl_seconds: 60,
+            feed: false,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(0),
+            bg: Color::Numbered(179),
+
+            // ★ – black star
+            symbol: "\u{2605}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Tool {
+    fn program(self) -> &'static str {
+        match self {
+            Self::Zoxide => "zoxide",
+            Self::Autojump => "autojump",
+        }
+    }
+
+    /// Arguments that dump the database with scores
+    fn list_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Zoxide => &["query", "--list", "--score"],
+            Self::Autojump => &["--stat"],
+        }
+    }
+
+    /// Arguments that record a visit to `dir`
+    fn add_args(self, dir: &str) -> Vec<String> {
+        match self {
+            Self::Zoxide => vec!["add".into(), "--".into(), dir.into()],
+            Self::Autojump => vec!["--add".into(), dir.into()],
+        }
+    }
+}
+
+impl Frecency {
+    /// Parses `zoxide query --list --score`, e.g. `  12.5 /home/me/src`
+    pub fn parse_zoxide(output: &str) -> Vec<(f64, String)> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let (score, path) = line.trim_start().split_once(' ')?;
+                Some((score.parse().ok()?, path.trim_start().to_string()))
+            })
+            .collect()
+    }
+
+    /// Parses `autojump --stat`, e.g. `12.5:\t/home/me/src`.  The totals at the end don't look
+    /// like paths and are skipped.
+    pub fn parse_autojump(output: &str) -> Vec<(f64, String)> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let (score, path) = line.split_once(":\t")?;
+                match path.starts_with('/') {
+                    true => Some((score.trim().parse().ok()?, path.to_string())),
+                    false => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the `top` highest scoring directories, best first
+    pub fn ranking(mut scores: Vec<(f64, String)>, top: usize) -> Vec<String> {
+        scores.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        scores.into_iter().take(top).map(|(_, path)| path).collect()
+    }
+
+    fn query(args: &Args) -> crate::Result<Vec<String>> {
+        let program = args.tool.program();
+
+        let output = Command::new(program)
+            .args(args.tool.list_args())
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!("{} failed: {}", program, output.status));
+        }
+
+        let output = String::from_utf8_lossy(&output.stdout);
+
+        let scores = match args.tool {
+            Tool::Zoxide => Self::parse_zoxide(&output),
+            Tool::Autojump => Self::parse_autojump(&output),
+        };
+
+        Ok(Self::ranking(scores, args.top))
+    }
+
+    /// Returns the hook that records a visit to `dir`, if the segment was asked to feed the
+    /// tool's database
+    pub fn visit_hook(args: Option<serde_json::Value>, dir: &str) -> crate::Result<Option<Hook>> {
+        let args: Args = match args {
+            Some(json) => serde_json::from_value(json)?,
+            None => Args::default(),
+        };
+
+        if !args.feed {
+            return Ok(None);
+        }
+
+        let mut command = vec![args.tool.program().to_string()];
+        command.extend(args.tool.add_args(dir));
+
+        Ok(Some(Hook {
+            command,
+            ..Default::default()
+        }))
+    }
+}
+
+impl ToSegment for Frecency {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Frecency"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.frecency;
+
+        let cwd = match state.env.get("PWD") {
+            Some(cwd) => cwd,
+            None => return Ok(vec![]),
+        };
+
+        // The ranking is cut down to `top` before it's cached
+        let key = format!("frecency-{}-{}", args.tool.program(), args.top);
+
+        let ranking = Cache::from_env(&state.env)?.get_or_insert_with(
+            &key,
+            Duration::from_secs(args.ttl_seconds),
+            || Self::query(&args),
+        )?;
+
+        let rank = match ranking.iter().take(args.top).position(|path| path == cwd) {
+            Some(idx) => idx + 1,
+            None => return Ok(vec![]),
+        };
+
+        Ok(vec![Segment::new(
+            "Frecency",
+            theme.fg,
+            theme.bg,
+            format!("{} {}", theme.symbol, rank),
+        )
+        .with_style(theme.style.iter().copied())
+        .with_metadata(json!({ "rank": rank }))])
+    }
+}
@@ -2,42 +2,124 @@
 //!
 //! This module provides the following segments that can be configured from the [`Args`] struct:
 //! * branch
+//! * upstream branch
 //! * ahead / behind remote
+//! * ahead / behind another ref
 //! * staged items count
 //! * modified items count
 //! * untracked items count
-//! * in-progress action (e.g. rebase, merge, cherry pick)
+//! * conflicted items count
+//! * in-progress action (e.g. rebase, merge, cherry pick, am)
 //! * stash count
+//! * time since the last commit
+//! * sparse checkout
+//! * committer email
+//! * nearest tag
+//!
+//! Counting changes means looking at every file in the working tree, which is slow in big
+//! repositories.  If `core.fsmonitor` is configured the counting is handed to `git status`, which
+//! can ask the file system monitor (e.g. watchman) what changed instead of looking.  With
+//! [`Args::status_cache_seconds`] set the counts are also reused between prompts as long as the
+//! index and `HEAD` haven't moved, with a badge showing how old they are.  Add
+//! [`Args::status_cache_background`] and once they've expired the old counts are shown one more
+//! time while `git status` works out new ones in the background for the next prompt:
+//! ```json
+//! { "name": "git", "args": { "status_cache_seconds": 30, "status_cache_background": true } }
+//! ```
+//!
+//! Turning off [`Args::show_counts`] skips the counting altogether.  Short of that,
+//! [`Args::skip_untracked`] and [`Args::untracked_limit`] cut down on the untracked files, and
+//! [`Args::status_timeout_ms`] gives up on counting and draws only the branch when it's too slow:
+//! ```json
+//! { "name": "git", "args": { "untracked_limit": 50, "status_timeout_ms": 200 } }
+//! ```
+//!
+//! With [`Args::show_email`] set the email commits will be made with is shown, and flagged when
+//! it's not the one expected for where `origin` points:
+//! ```json
+//! {
+//!     "name": "git",
+//!     "args": {
+//!         "show_email": true,
+//!         "email_aliases": { "me@example.com": "home", "me@acme.com": "work" },
+//!         "expected_emails": { "github\\.com[:/]acme/": "@acme\\.com$" }
+//!     }
+//! }
+//! ```
+//!
+//! Bare repositories have no working tree, so there only the branch, upstream, tag, ahead /
+//! behind, and commit age segments are shown.
+//!
+//! The repository is found from `$PWD`, or `$GIT_DIR` and `$GIT_WORK_TREE` for setups that keep
+//! the two apart, see [`Git::open`].
 //!
 //! For information about the tests see the README in git-tests/
 
-use std::fs::read_to_string;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, metadata, read_to_string};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, Context, Result};
-use git2::{BranchType, ErrorCode, Repository, RepositoryState, StatusOptions};
-use serde::Deserialize;
+use git2::{
+    BranchType, DescribeFormatOptions, DescribeOptions, ErrorCode, ObjectType, Repository,
+    RepositoryState, StatusOptions,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 
+use crate::ansi::EscapeStyle;
+use crate::cache::Cache;
+use crate::exec;
+use crate::segment::threshold::ThresholdDisplay;
 use crate::segment::vcs::Theme as VcsTheme;
-use crate::segment::{Segment, ToSegment};
+use crate::segment::{Segment, Span, ToSegment};
 use crate::{ApplicationState, Separator};
 
 pub struct Git {}
 
+/// Repositories a [`Git::scan_within`] thread is still counting changes in
+static SCANNING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
 /// Arguments for the `Git` segment
 ///
 /// **TODO** make a variety of things configurable here including which segments to display.
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Args {
     /// Show the git badge before the branch.  The badge itself can be configured via the
-    /// [`vcs::Symbols`](`crate::segment::vcs::Symbols`) config object.
-    ///
-    /// **TODO** implement badges for well known remotes (e.g. GitHub, Bitbucket)
+    /// [`vcs::Symbols`](`crate::segment::vcs::Symbols`) config object, repositories with their
+    /// `origin` on a well known host (e.g. GitHub, Bitbucket) get that host's logo instead.
     pub show_vcs_badge: bool,
 
     /// Show count of stashed objects after the untracked badge.
     pub show_stash: bool,
 
+    /// Show [`vcs::Symbols::sparse`](`crate::segment::vcs::Symbols::sparse`) when only part of
+    /// the repository is checked out, the counts don't include what's been left out.
+    pub show_sparse: bool,
+
+    /// Count the staged, changed, untracked, and conflicted files.  Turning this off skips
+    /// looking at the working tree, which is the slow part in big repositories, but the branch is
+    /// then always drawn in the clean colors.
+    pub show_counts: bool,
+
+    /// Don't look for untracked files, they're neither counted nor make the branch dirty
+    pub skip_untracked: bool,
+
+    /// Stop counting untracked files at this many and show e.g. `50+?` instead.  No limit if not
+    /// set.
+    pub untracked_limit: Option<usize>,
+
+    /// Give up on counting if it takes longer than this many milliseconds and show only the
+    /// branch.  No limit if not set.
+    pub status_timeout_ms: Option<u64>,
+
     /// Whether to show a segment when there's an in-progress operation.  More granular options are below.
     pub show_in_progress: bool,
 
@@ -52,9 +134,122 @@ pub struct Args {
 
     /// Show a segment if we're in the middle of a rebase.
     pub show_rebase: bool,
+
+    /// Show a segment if we're applying patches with `git am`.
+    pub show_am: bool,
+
+    /// Draw the staged, changed, untracked, and stash counts as a single segment (e.g.
+    /// `+2 ✎1 ?3`) colored with [`vcs::Compact`](`crate::segment::vcs::Compact`) instead of one
+    /// segment each.
+    pub compact_counts: bool,
+
+    /// Reuse the staged, changed, and untracked counts from an earlier prompt for up to this many
+    /// seconds, as long as `.git/index` and `HEAD` haven't changed since.  Edits to tracked files
+    /// won't show up until the counts expire.  Off if not set.
+    pub status_cache_seconds: Option<u64>,
+
+    /// Instead of counting again when the cached counts have expired or the index has changed,
+    /// show the old ones and run `git status` in the background to have new ones ready for the
+    /// next prompt.  Needs [`Args::status_cache_seconds`], and `sh` so it only works on Unix.
+    pub status_cache_background: bool,
+
+    /// Show the nearest tag after the branch, see [`ShowTag`]
+    pub show_tag: ShowTag,
+
+    /// Show the branch the current one tracks (e.g. `origin/main`), see [`ShowUpstream`]
+    pub show_upstream: ShowUpstream,
+
+    /// Also count how far ahead and behind this ref `HEAD` is, e.g. `origin/main` for how far a
+    /// feature branch has strayed from the branch it'll be merged into.  Anything `git
+    /// rev-parse` understands will do, nothing is shown if it doesn't exist or is the upstream.
+    pub compare_ref: Option<String>,
+
+    /// Show how long ago `HEAD` was committed, e.g. `3h`
+    pub show_commit_age: bool,
+
+    /// When to show the commit age and when to change its colors, in hours
+    pub commit_age_thresholds: ThresholdDisplay,
+
+    /// Show the email commits will be made with, `$GIT_AUTHOR_EMAIL` or `user.email`
+    pub show_email: bool,
+
+    /// Shorter names to show instead of some emails, keyed by email
+    pub email_aliases: BTreeMap<String, String>,
+
+    /// Which email to expect where, a regular expression for the `origin` URL mapped to one the
+    /// email should match.  The first URL that matches counts, when the email doesn't match it's
+    /// drawn in the mismatch colors.
+    pub expected_emails: BTreeMap<String, String>,
+}
+
+/// Where [`Args::show_upstream`] shows the upstream branch
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShowUpstream {
+    /// Not at all
+    #[default]
+    Never,
+    /// In a segment of its own after the branch
+    Beside,
+    /// In place of the local branch, if there is an upstream
+    Instead,
+}
+
+/// Which tag [`Args::show_tag`] shows
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShowTag {
+    /// No tag
+    #[default]
+    Never,
+    /// Only a tag that points at `HEAD`
+    Exact,
+    /// The closest tag reachable from `HEAD` and how many commits since, like `git describe
+    /// --tags`, e.g. `v1.0+2`
+    Describe,
+}
+
+/// Staged, changed, untracked, and conflicted counts
+type Counts = (usize, usize, usize, usize);
+
+/// Counts from an earlier prompt, see [`Args::status_cache_seconds`]
+#[derive(Deserialize, Serialize)]
+struct CachedStatus {
+    /// Modification time of `.git/index` in nanoseconds since the epoch
+    index_mtime: u64,
+    /// Commit `HEAD` pointed at
+    head: Option<String>,
+    /// When the counts were taken, in seconds since the epoch
+    taken: u64,
+    staged: usize,
+    changed: usize,
+    untracked: usize,
+    #[serde(default)]
+    conflicted: usize,
+}
+
+impl CachedStatus {
+    fn new(index_mtime: u64, head: Option<String>, taken: u64, counts: Counts) -> Self {
+        let (staged, changed, untracked, conflicted) = counts;
+
+        Self {
+            index_mtime,
+            head,
+            taken,
+            staged,
+            changed,
+            untracked,
+            conflicted,
+        }
+    }
+
+    fn counts(&self) -> Counts {
+        (self.staged, self.changed, self.untracked, self.conflicted)
+    }
 }
 
 /// High level statistics for the current git repo
+#[derive(Default)]
 struct Stats {
     /// Number of files with unstaged changes
     pub changed: usize,
@@ -72,64 +267,102 @@ struct Stats {
     pub stashed: usize,
 }
 
+/// Reads how many steps of an operation are done and how many there are from the files `done`
+/// and `total` under `.git`, None if either is missing or garbled
+fn progress(repo: &Repository, done: &str, total: &str) -> Option<(usize, usize)> {
+    let read = |name| {
+        read_to_string(repo.path().join(name))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+
+    Some((read(done)?, read(total)?))
+}
+
+/// Adds `progress` to `label` if there is any, e.g. `rebase 2/5`
+fn with_progress(label: &str, progress: Option<(usize, usize)>) -> String {
+    match progress {
+        Some((done, total)) => format!("{} {}/{}", label, done, total),
+        None => label.into(),
+    }
+}
+
 fn seg_in_progress(repo: &Repository, args: &Args, theme: &VcsTheme, segments: &mut Vec<Segment>) {
     if !args.show_in_progress {
         return;
     }
 
     match repo.state() {
-        RepositoryState::Bisect if args.show_bisect => segments.push(Segment {
-            fg: theme.git_in_progress_fg,
-            bg: theme.git_in_progress_bg,
-            separator: Separator::Thick,
-            text: "bisect".to_string(),
-            source: "Git::Bisect",
-        }),
+        RepositoryState::Bisect if args.show_bisect => segments.push(Segment::new(
+            "Git::Bisect",
+            theme.git.in_progress_fg,
+            theme.git.in_progress_bg,
+            "bisect",
+        )),
         RepositoryState::CherryPick | RepositoryState::CherryPickSequence
             if args.show_cherry_pick =>
         {
-            segments.push(Segment {
-                fg: theme.git_in_progress_fg,
-                bg: theme.git_in_progress_bg,
-                separator: Separator::Thick,
-                text: theme.symbols.cherry_pick.clone(),
-                source: "Git::CherryPick",
-            })
+            segments.push(Segment::new(
+                "Git::CherryPick",
+                theme.git.in_progress_fg,
+                theme.git.in_progress_bg,
+                theme.symbols.cherry_pick.clone(),
+            ))
         }
-        RepositoryState::Merge if args.show_merge => segments.push(Segment {
-            fg: theme.git_in_progress_fg,
-            bg: theme.git_in_progress_bg,
-            separator: Separator::Thick,
-            text: "merge".to_string(),
-            source: "Git::Merge",
-        }),
+        RepositoryState::Merge if args.show_merge => segments.push(Segment::new(
+            "Git::Merge",
+            theme.git.in_progress_fg,
+            theme.git.in_progress_bg,
+            "merge",
+        )),
         state @ RepositoryState::Rebase
         | state @ RepositoryState::RebaseInteractive
         | state @ RepositoryState::RebaseMerge
             if args.show_rebase =>
         {
-            let text = match state {
-                RepositoryState::RebaseInteractive => {
-                    // Well this is annoying
-                    // https://github.com/libgit2/libgit2/issues/6127
-                    let total_cmds = read_to_string(repo.path().join("rebase-merge/end")).unwrap();
-                    let completed_cmds =
-                        read_to_string(repo.path().join("rebase-merge/msgnum")).unwrap();
-                    let total_cmds: usize = total_cmds.trim_end_matches('\n').parse().unwrap();
-                    let completed_cmds: usize =
-                        completed_cmds.trim_end_matches('\n').parse().unwrap();
-                    format!("int rebase {}/{}", completed_cmds, total_cmds)
-                }
-                _ => "rebase".into(),
+            // Well this is annoying, libgit2 doesn't say how far along we are
+            // https://github.com/libgit2/libgit2/issues/6127
+            let (label, progress) = match state {
+                RepositoryState::RebaseInteractive => (
+                    "int rebase",
+                    progress(repo, "rebase-merge/msgnum", "rebase-merge/end"),
+                ),
+                RepositoryState::RebaseMerge => (
+                    "rebase",
+                    progress(repo, "rebase-merge/msgnum", "rebase-merge/end"),
+                ),
+                _ => (
+                    "rebase",
+                    progress(repo, "rebase-apply/next", "rebase-apply/last"),
+                ),
             };
 
-            segments.push(Segment {
-                fg: theme.git_in_progress_fg,
-                bg: theme.git_in_progress_bg,
-                separator: Separator::Thick,
-                text,
-                source: "Git::Rebase",
-            })
+            segments.push(Segment::new(
+                "Git::Rebase",
+                theme.git.in_progress_fg,
+                theme.git.in_progress_bg,
+                with_progress(label, progress),
+            ))
+        }
+        state @ RepositoryState::ApplyMailbox | state @ RepositoryState::ApplyMailboxOrRebase
+            if args.show_am =>
+        {
+            let label = match state {
+                RepositoryState::ApplyMailbox => "am",
+                _ => "am/rebase",
+            };
+
+            segments.push(Segment::new(
+                "Git::Am",
+                theme.git.in_progress_fg,
+                theme.git.in_progress_bg,
+                with_progress(
+                    label,
+                    progress(repo, "rebase-apply/next", "rebase-apply/last"),
+                ),
+            ))
         }
         _ => {}
     }
@@ -181,44 +414,121 @@ fn seg_ahead_behind(
         };
 
         if ahead > 0 {
-            segments.push(Segment {
-                bg: theme.git_ahead_bg,
-                fg: theme.git_ahead_fg,
-                separator: first_separator,
-                text: format!("{}{}", ahead, theme.symbols.ahead),
-                source: "Git::Ahead",
-            });
+            segments.push(
+                Segment::new(
+                    "Git::Ahead",
+                    theme.git.ahead_fg,
+                    theme.git.ahead_bg,
+                    format!("{}{}", ahead, theme.symbols.ahead),
+                )
+                .with_separator(first_separator)
+                .with_metadata(json!({ "ahead": ahead })),
+            );
         }
 
         if behind > 0 {
-            segments.push(Segment {
-                bg: theme.git_behind_bg,
-                fg: theme.git_behind_fg,
-                separator: Separator::Thick,
-                text: format!("{}{}", behind, theme.symbols.behind),
-                source: "Git::Behind",
-            });
+            segments.push(
+                Segment::new(
+                    "Git::Behind",
+                    theme.git.behind_fg,
+                    theme.git.behind_bg,
+                    format!("{}{}", behind, theme.symbols.behind),
+                )
+                .with_metadata(json!({ "behind": behind })),
+            );
         }
     }
 
     Ok(())
 }
 
+/// Writes out the untracked count, with a `+` once it's reached [`Args::untracked_limit`]
+fn untracked_count(stats: &Stats, args: &Args) -> String {
+    match args.untracked_limit {
+        Some(limit) if stats.untracked >= limit => format!("{}+", limit),
+        _ => stats.untracked.to_string(),
+    }
+}
+
+fn seg_compare(
+    repo: &Repository,
+    args: &Args,
+    theme: &VcsTheme,
+    segments: &mut Vec<Segment>,
+) -> Result<()> {
+    let name = match &args.compare_ref {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    let head_oid = match repo.head().ok().and_then(|head| head.target()) {
+        Some(oid) => oid,
+        None => return Ok(()),
+    };
+
+    let compare_oid = match repo.revparse_single(name) {
+        Ok(object) => object.peel_to_commit()?.id(),
+        Err(ref e) if e.code() == ErrorCode::NotFound => return Ok(()),
+        Err(e) => Err(e)?,
+    };
+
+    // Already covered by the usual ahead and behind
+    let upstream_oid = repo
+        .head()
+        .ok()
+        .and_then(|head| head.shorthand().map(str::to_owned))
+        .and_then(|branch| repo.find_branch(&branch, BranchType::Local).ok())
+        .and_then(|branch| branch.upstream().ok())
+        .and_then(|upstream| upstream.get().target());
+    if upstream_oid == Some(compare_oid) {
+        return Ok(());
+    }
+
+    let (ahead, behind) = repo.graph_ahead_behind(head_oid, compare_oid)?;
+
+    let counts = [
+        (ahead, &theme.symbols.ahead),
+        (behind, &theme.symbols.behind),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, symbol)| format!("{}{}", count, symbol))
+    .collect::<Vec<_>>();
+
+    if counts.is_empty() {
+        return Ok(());
+    }
+
+    segments.push(
+        Segment::new(
+            "Git::Compare",
+            theme.git.compare_fg,
+            theme.git.compare_bg,
+            format!("{} {}", name, counts.join(" ")),
+        )
+        .with_metadata(json!({ "ref": name, "ahead": ahead, "behind": behind })),
+    );
+
+    Ok(())
+}
+
 fn seg_untracked(
     _repo: &Repository,
     stats: &Stats,
-    _args: &Args,
+    args: &Args,
     theme: &VcsTheme,
     segments: &mut Vec<Segment>,
 ) {
     if stats.untracked > 0 {
-        segments.push(Segment {
-            fg: theme.git_untracked_fg,
-            bg: theme.git_untracked_bg,
-            separator: Separator::Thick,
-            text: format!("{}{}", stats.untracked, theme.symbols.new),
-            source: "Git::Untracked",
-        });
+        segments.push(
+            Segment::new(
+                "Git::Untracked",
+                theme.git.untracked_fg,
+                theme.git.untracked_bg,
+                format!("{}{}", untracked_count(stats, args), theme.symbols.new),
+            )
+            .with_metadata(json!({ "untracked": stats.untracked })),
+        );
     }
 }
 
@@ -230,13 +540,35 @@ fn seg_changed(
     segments: &mut Vec<Segment>,
 ) {
     if stats.changed > 0 {
-        segments.push(Segment {
-            fg: theme.git_changed_fg,
-            bg: theme.git_changed_bg,
-            separator: Separator::Thick,
-            text: format!("{}{}", stats.changed, theme.symbols.changed),
-            source: "Git::Changed",
-        });
+        segments.push(
+            Segment::new(
+                "Git::Changed",
+                theme.git.changed_fg,
+                theme.git.changed_bg,
+                format!("{}{}", stats.changed, theme.symbols.changed),
+            )
+            .with_metadata(json!({ "changed": stats.changed })),
+        );
+    }
+}
+
+fn seg_conflicted(
+    _repo: &Repository,
+    stats: &Stats,
+    _args: &Args,
+    theme: &VcsTheme,
+    segments: &mut Vec<Segment>,
+) {
+    if stats.conflicted > 0 {
+        segments.push(
+            Segment::new(
+                "Git::Conflicted",
+                theme.git.conflict_fg,
+                theme.git.conflict_bg,
+                format!("{}{}", stats.conflicted, theme.symbols.conflicted),
+            )
+            .with_metadata(json!({ "conflicted": stats.conflicted })),
+        );
     }
 }
 
@@ -248,13 +580,15 @@ fn seg_staged(
     segments: &mut Vec<Segment>,
 ) {
     if stats.staged > 0 {
-        segments.push(Segment {
-            fg: theme.git_staged_fg,
-            bg: theme.git_staged_bg,
-            separator: Separator::Thick,
-            text: format!("{}+", stats.staged),
-            source: "Git::Staged",
-        });
+        segments.push(
+            Segment::new(
+                "Git::Staged",
+                theme.git.staged_fg,
+                theme.git.staged_bg,
+                format!("{}+", stats.staged),
+            )
+            .with_metadata(json!({ "staged": stats.staged })),
+        );
     }
 }
 
@@ -266,27 +600,356 @@ fn seg_stashed(
     segments: &mut Vec<Segment>,
 ) {
     if stats.stashed > 0 && args.show_stash {
-        segments.push(Segment {
-            fg: theme.git_stashed_fg,
-            bg: theme.git_stashed_bg,
-            separator: Separator::Thick,
-            text: format!("{}{}", stats.stashed, theme.symbols.stash),
-            source: "Git::Stashed",
+        segments.push(
+            Segment::new(
+                "Git::Stashed",
+                theme.git.stashed_fg,
+                theme.git.stashed_bg,
+                format!("{}{}", stats.stashed, theme.symbols.stash),
+            )
+            .with_metadata(json!({ "stashed": stats.stashed })),
+        )
+    }
+}
+
+/// Returns the short name of the branch `HEAD` tracks, and whether it's gone i.e. the config
+/// still points at it but the ref has been deleted (usually by `git fetch --prune`)
+fn upstream(repo: &Repository) -> Option<(String, bool)> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+
+    let upstream = repo.branch_upstream_name(head.name()?).ok()?;
+    let upstream = upstream.as_str()?;
+
+    let gone = repo.find_reference(upstream).is_err();
+    let name = upstream
+        .strip_prefix("refs/remotes/")
+        .or_else(|| upstream.strip_prefix("refs/heads/"))
+        .unwrap_or(upstream);
+
+    Some((name.to_owned(), gone))
+}
+
+fn seg_upstream(
+    repo: &Repository,
+    args: &Args,
+    theme: &VcsTheme,
+    escape_style: EscapeStyle,
+    segments: &mut Vec<Segment>,
+) {
+    if args.show_upstream != ShowUpstream::Beside {
+        return;
+    }
+
+    let (name, gone) = match upstream(repo) {
+        Some(upstream) => upstream,
+        None => return,
+    };
+
+    let (fg, bg) = match gone {
+        false => (theme.git.upstream_fg, theme.git.upstream_bg),
+        true => (theme.git.upstream_gone_fg, theme.git.upstream_gone_bg),
+    };
+
+    segments.push(
+        Segment::new("Git::Upstream", fg, bg, escape_style.escape_literal(&name))
+            .with_metadata(json!({ "upstream": name, "gone": gone })),
+    )
+}
+
+/// Splits what `git describe` says (e.g. `v1.0-2-g639b2ca`) into the tag and how many commits
+/// `HEAD` is past it.  An exact match is just the tag.
+pub fn parse_describe(described: &str) -> (&str, usize) {
+    let mut parts = described.rsplitn(3, '-');
+
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(hash), Some(distance), Some(tag)) if hash.starts_with('g') => {
+            match distance.parse() {
+                Ok(distance) => (tag, distance),
+                Err(_) => (described, 0),
+            }
+        }
+        _ => (described, 0),
+    }
+}
+
+fn seg_tag(
+    repo: &Repository,
+    args: &Args,
+    theme: &VcsTheme,
+    escape_style: EscapeStyle,
+    segments: &mut Vec<Segment>,
+) {
+    let mut options = DescribeOptions::new();
+    options.describe_tags();
+
+    match args.show_tag {
+        ShowTag::Never => return,
+        ShowTag::Exact => {
+            options.max_candidates_tags(0);
+        }
+        ShowTag::Describe => {}
+    }
+
+    // No commits, no tags, or none close enough.  Describe the commit rather than the working
+    // tree so this works in bare repositories too.
+    let described = match repo.head().and_then(|head| head.peel(ObjectType::Commit)) {
+        Ok(commit) => commit
+            .describe(&options)
+            .and_then(|describe| describe.format(Some(&DescribeFormatOptions::new()))),
+        Err(err) => Err(err),
+    };
+    let described = match described {
+        Ok(described) => described,
+        Err(_) => return,
+    };
+
+    // Tags come from whoever we fetched from
+    let (tag, distance) = parse_describe(&described);
+    let shown = escape_style.escape_literal(tag);
+    let text = match distance {
+        0 => format!("{} {}", theme.symbols.tag, shown),
+        distance => format!("{} {}+{}", theme.symbols.tag, shown, distance),
+    };
+
+    segments.push(
+        Segment::new("Git::Tag", theme.git.tag_fg, theme.git.tag_bg, text)
+            .with_metadata(json!({ "tag": tag, "distance": distance })),
+    )
+}
+
+fn seg_compact_counts(stats: &Stats, args: &Args, theme: &VcsTheme, segments: &mut Vec<Segment>) {
+    let colors = &theme.git.compact;
+
+    let counts = [
+        (stats.staged, stats.staged.to_string(), "+", colors.staged),
+        (
+            stats.changed,
+            stats.changed.to_string(),
+            theme.symbols.changed.as_str(),
+            colors.changed,
+        ),
+        (
+            stats.untracked,
+            untracked_count(stats, args),
+            theme.symbols.new.as_str(),
+            colors.untracked,
+        ),
+        (
+            match args.show_stash {
+                true => stats.stashed,
+                false => 0,
+            },
+            stats.stashed.to_string(),
+            theme.symbols.stash.as_str(),
+            colors.stashed,
+        ),
+    ];
+
+    let spans = counts
+        .into_iter()
+        .filter(|(count, _, _, _)| *count > 0)
+        .map(|(_, count, symbol, fg)| Span {
+            text: format!("{}{}", symbol, count),
+            fg: Some(fg),
+            ..Default::default()
         })
+        .fold(vec![], |mut spans, span| {
+            if !spans.is_empty() {
+                spans.push(Span::from(" "));
+            }
+            spans.push(span);
+            spans
+        });
+
+    if spans.is_empty() {
+        return;
+    }
+
+    segments.push(
+        Segment::new("Git::Counts", colors.fg, colors.bg, "")
+            .with_spans(spans)
+            .with_metadata(json!({
+                "staged": stats.staged,
+                "changed": stats.changed,
+                "untracked": stats.untracked,
+                "stashed": stats.stashed,
+            })),
+    );
+}
+
+/// Writes out `age` in seconds in its largest whole unit, e.g. `42s`, `3h`, or `12d`
+pub fn format_age(age: u64) -> String {
+    match age {
+        0..=59 => format!("{}s", age),
+        60..=3599 => format!("{}m", age / 60),
+        3600..=86399 => format!("{}h", age / 3600),
+        _ => format!("{}d", age / 86400),
+    }
+}
+
+fn seg_cached(age: u64, theme: &VcsTheme, segments: &mut Vec<Segment>) {
+    segments.push(
+        Segment::new(
+            "Git::Cached",
+            theme.git.cached_fg,
+            theme.git.cached_bg,
+            format!("{}{}", theme.symbols.cached, format_age(age)),
+        )
+        .with_metadata(json!({ "cache_age_seconds": age })),
+    );
+}
+
+fn seg_sparse(repo: &Repository, args: &Args, theme: &VcsTheme, segments: &mut Vec<Segment>) {
+    if !args.show_sparse {
+        return;
+    }
+
+    let config = match repo.config() {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+
+    if !config.get_bool("core.sparseCheckout").unwrap_or(false) {
+        return;
+    }
+
+    let mode = match config.get_bool("core.sparseCheckoutCone").unwrap_or(false) {
+        true => "cone",
+        false => "legacy",
+    };
+
+    segments.push(
+        Segment::new(
+            "Git::Sparse",
+            theme.git.sparse_fg,
+            theme.git.sparse_bg,
+            &theme.symbols.sparse,
+        )
+        .with_metadata(json!({ "sparse": mode })),
+    )
+}
+
+/// Returns false if `origin` matches one of `expected` and `email` doesn't match what it's mapped
+/// to
+pub fn email_expected(
+    expected: &BTreeMap<String, String>,
+    origin: Option<&str>,
+    email: &str,
+) -> Result<bool> {
+    let origin = match origin {
+        Some(origin) => origin,
+        None => return Ok(true),
+    };
+
+    for (remote, pattern) in expected {
+        if Regex::new(remote)?.is_match(origin) {
+            return Ok(Regex::new(pattern)?.is_match(email));
+        }
+    }
+
+    Ok(true)
+}
+
+fn seg_email(
+    repo: &Repository,
+    args: &Args,
+    state: &ApplicationState,
+    segments: &mut Vec<Segment>,
+) -> Result<()> {
+    if !args.show_email {
+        return Ok(());
+    }
+
+    let theme = &state.theme.vcs;
+
+    let email = match state.env.get("GIT_AUTHOR_EMAIL") {
+        Some(email) => email.clone(),
+        None => match repo.config()?.get_string("user.email") {
+            Ok(email) => email,
+            // Not set up, git will complain about that on its own
+            Err(_) => return Ok(()),
+        },
+    };
+
+    let origin = repo.find_remote("origin").ok();
+    let expected = email_expected(
+        &args.expected_emails,
+        origin.as_ref().and_then(|origin| origin.url()),
+        &email,
+    )?;
+
+    let (fg, bg) = match expected {
+        true => (theme.git.email_fg, theme.git.email_bg),
+        false => (theme.git.email_mismatch_fg, theme.git.email_mismatch_bg),
+    };
+    let shown = args.email_aliases.get(&email).unwrap_or(&email);
+
+    segments.push(
+        Segment::new(
+            "Git::Email",
+            fg,
+            bg,
+            format!("{} {}", theme.symbols.email, shown),
+        )
+        .with_metadata(json!({ "email": email, "expected": expected })),
+    );
+
+    Ok(())
+}
+
+fn seg_commit_age(
+    repo: &Repository,
+    args: &Args,
+    theme: &VcsTheme,
+    segments: &mut Vec<Segment>,
+) -> Result<()> {
+    if !args.show_commit_age {
+        return Ok(());
+    }
+
+    let commit = match repo.head() {
+        Ok(head) => head.peel_to_commit()?,
+        // Nothing's been committed yet
+        Err(_) => return Ok(()),
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() as i64)
+        .unwrap_or_default();
+    // A commit from the future means someone's clock is off, call it brand new
+    let age = now.saturating_sub(commit.time().seconds()).max(0) as u64;
+
+    let hours = age as f64 / 3600.0;
+    if !args.commit_age_thresholds.visible(hours) {
+        return Ok(());
     }
+    let (fg, bg) = theme
+        .git
+        .commit_age
+        .colors(args.commit_age_thresholds.level(hours));
+
+    segments.push(
+        Segment::new("Git::CommitAge", fg, bg, format_age(age))
+            .with_metadata(json!({ "commit_age_seconds": age })),
+    );
+
+    Ok(())
 }
 
 fn seg_current_branch(
     repo: &Repository,
     stats: &Stats,
-    _args: &Args,
+    args: &Args,
     theme: &VcsTheme,
     segments: &mut Vec<Segment>,
 ) -> Result<()> {
-    let (fg, bg) = match stats.dirty() {
-        false => (theme.repo_clean_fg, theme.repo_clean_bg),
-        true => (theme.repo_dirty_fg, theme.repo_dirty_bg),
-    };
+    let (fg, bg) = theme.git_colors(stats.dirty());
+
+    let mut unborn = false;
 
     let head = match repo.state() {
         RepositoryState::RebaseInteractive => {
@@ -296,32 +959,66 @@ fn seg_current_branch(
             let head_ref = repo.find_reference(head_ref)?;
             Some(head_ref.shorthand().unwrap().to_owned())
         }
-        _ => {
-            let head = match repo.head() {
-                Ok(head) => Some(head),
-                Err(ref e)
-                    if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound =>
-                {
-                    None
-                }
-                Err(e) => Err(e)?,
-            };
+        _ => match repo.head() {
+            Ok(head) => head.shorthand().map(str::to_owned),
+            Err(ref e) if e.code() == ErrorCode::UnbornBranch => {
+                // HEAD points at a branch that doesn't exist yet, it's still got a name though
+                unborn = true;
+                repo.find_reference("HEAD")?
+                    .symbolic_target()
+                    .map(|target| target.trim_start_matches("refs/heads/").to_owned())
+            }
+            Err(ref e) if e.code() == ErrorCode::NotFound => None,
+            Err(e) => Err(e)?,
+        },
+    };
+
+    let metadata = json!({
+        "branch": head,
+        "unborn": unborn,
+        "bare": repo.is_bare(),
+        "dirty": stats.dirty(),
+        "changed": stats.changed,
+        "conflicted": stats.conflicted,
+        "staged": stats.staged,
+        "untracked": stats.untracked,
+        "stashed": stats.stashed,
+    });
+
+    let upstream = match args.show_upstream {
+        ShowUpstream::Instead => upstream(repo),
+        _ => None,
+    };
+
+    let (fg, bg) = match upstream {
+        Some((_, true)) => (theme.git.upstream_gone_fg, theme.git.upstream_gone_bg),
+        _ => (fg, bg),
+    };
 
-            head.as_ref().and_then(|h| h.shorthand()).map(str::to_owned)
+    let branch = match (upstream, head, unborn, repo.is_bare()) {
+        (Some((upstream, _)), _, _, _) => upstream,
+        (None, Some(head), true, _) => format!("{} (unborn)", head),
+        (None, Some(head), false, true) => format!("{} (bare)", head),
+        (None, Some(head), false, false) => head,
+        (None, None, _, _) => String::from("HEAD (no branch)"),
+    };
+
+    let text = match args.show_vcs_badge {
+        true => {
+            let origin = repo.find_remote("origin").ok();
+            let badge = theme
+                .symbols
+                .badge(origin.as_ref().and_then(|origin| origin.url()));
+            format!("{} {}", badge, branch)
         }
+        false => branch,
     };
 
-    segments.push(Segment {
-        bg,
-        fg,
-        separator: Separator::Thick,
-        text: format!(
-            "{} {}",
-            theme.symbols.git,
-            head.unwrap_or(String::from("HEAD (no branch)"))
-        ),
-        source: "Git::Branch",
-    });
+    segments.push(
+        Segment::new("Git::Branch", fg, bg, text)
+            .with_style(theme.repo_style(stats.dirty()).iter().copied())
+            .with_metadata(metadata),
+    );
 
     Ok(())
 }
@@ -331,11 +1028,31 @@ impl Default for Args {
         Self {
             show_vcs_badge: true,
             show_stash: true,
+            show_sparse: true,
+            show_counts: true,
+            skip_untracked: false,
+            untracked_limit: None,
+            status_timeout_ms: None,
             show_in_progress: true,
             show_bisect: true,
             show_cherry_pick: true,
             show_merge: true,
             show_rebase: true,
+            show_am: true,
+            compact_counts: false,
+            status_cache_seconds: None,
+            status_cache_background: false,
+            show_tag: ShowTag::Never,
+            show_upstream: ShowUpstream::Never,
+            compare_ref: None,
+            show_commit_age: false,
+            commit_age_thresholds: ThresholdDisplay {
+                warn_at: Some(24.0 * 7.0),
+                ..Default::default()
+            },
+            show_email: false,
+            email_aliases: BTreeMap::new(),
+            expected_emails: BTreeMap::new(),
         }
     }
 }
@@ -349,51 +1066,91 @@ impl Stats {
     }
 }
 
-impl ToSegment for Git {
-    type Args = Args;
-    type Theme = super::vcs::Theme;
-
-    fn error_context() -> &'static str {
-        "segment::Git"
+impl Git {
+    /// Counts the staged, changed, untracked, and conflicted entries in `git status
+    /// --porcelain=v1` output
+    pub fn parse_porcelain(output: &str) -> Counts {
+        output
+            .lines()
+            .filter_map(|line| {
+                let mut codes = line.chars();
+                Some((codes.next()?, codes.next()?))
+            })
+            .fold(
+                (0, 0, 0, 0),
+                |(staged, changed, untracked, conflicted), codes| match codes {
+                    ('?', '?') => (staged, changed, untracked + 1, conflicted),
+                    // Ignored files only show up when asked for
+                    ('!', '!') => (staged, changed, untracked, conflicted),
+                    // Unmerged, either side is a U or both added or both deleted
+                    ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => {
+                        (staged, changed, untracked, conflicted + 1)
+                    }
+                    (index, worktree) => (
+                        staged + usize::from(matches!(index, 'M' | 'A' | 'D' | 'R' | 'C' | 'T')),
+                        changed + usize::from(matches!(worktree, 'M' | 'D' | 'R' | 'T')),
+                        untracked,
+                        conflicted,
+                    ),
+                },
+            )
     }
 
-    fn to_segment(
-        args: Option<Self::Args>,
-        state: &ApplicationState,
-    ) -> crate::Result<Vec<Segment>> {
-        let args = args.unwrap_or_default();
-
-        // Unfortunately we have to stub things out like this as rust runs all tests in a module
-        // from a single process.
-        #[cfg(test)]
-        let repo_path = state
-            .env
-            .get("__PROMPTR_GIT_REPO")
-            .expect("gotta set __PROMPTR_GIT_REPO to run tests");
+    /// Returns true if the repository has a file system monitor set up
+    fn fsmonitor_enabled(repo: &Repository) -> bool {
+        match repo
+            .config()
+            .and_then(|config| config.get_string("core.fsmonitor"))
+        {
+            Ok(value) => !matches!(value.as_str(), "" | "false" | "0" | "no" | "off"),
+            Err(_) => false,
+        }
+    }
 
-        #[cfg(not(test))]
-        let repo_path = ".";
+    /// The `--untracked-files` mode for `git status`
+    fn untracked_files(skip_untracked: bool) -> &'static str {
+        match skip_untracked {
+            true => "no",
+            false => "normal",
+        }
+    }
 
-        let mut repo = match Repository::discover(repo_path) {
-            Ok(repo) => repo,
-            Err(_) => return Ok(vec![]),
-        };
+    /// The `git status` that counts changes for [`Self::scan_with_git`] and
+    /// [`Self::scan_within`]
+    fn git_status(repo: &Repository, skip_untracked: bool) -> Option<Command> {
+        let mut command = Command::new("git");
+        // Don't refresh the index, that would throw out the cache on every prompt
+        command
+            .args(["--no-optional-locks", "status", "--porcelain=v1"])
+            .arg(format!(
+                "--untracked-files={}",
+                Self::untracked_files(skip_untracked)
+            ))
+            .current_dir(repo.workdir()?)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
 
-        // Meh
-        let mut stashed = 0;
-        repo.stash_foreach(|_, _, _| {
-            stashed += 1;
-            true
-        })?;
+        Some(command)
+    }
 
-        let mut segments = vec![];
+    /// Counts changes with `git status`, which can use the file system monitor where libgit2
+    /// can't.  Returns `None` if `git` isn't up to it.
+    fn scan_with_git(repo: &Repository, skip_untracked: bool) -> Option<Counts> {
+        let output = Self::git_status(repo, skip_untracked)?.output().ok()?;
 
-        if repo.is_bare() {
-            return Err(anyhow!("Git segment doesn't work on bare repos"));
+        match output.status.success() {
+            true => Some(Self::parse_porcelain(&String::from_utf8_lossy(
+                &output.stdout,
+            ))),
+            false => None,
         }
+    }
 
+    /// Counts changes by walking the working tree
+    fn scan(repo: &Repository, skip_untracked: bool) -> Result<Counts> {
         let mut opts = StatusOptions::new();
-        opts.include_untracked(true)
+        opts.include_untracked(!skip_untracked)
             .recurse_untracked_dirs(false);
         let statuses = repo.statuses(Some(&mut opts))?;
 
@@ -427,7 +1184,387 @@ impl ToSegment for Git {
             })
             .count();
 
-        let conflicted = 0;
+        let conflicted = statuses
+            .iter()
+            .filter(|e| e.status().contains(git2::Status::CONFLICTED))
+            .count();
+
+        Ok((staged, changed, untracked, conflicted))
+    }
+
+    /// Counts changes whichever way suits the repository
+    fn scan_any(repo: &Repository, skip_untracked: bool) -> Result<Counts> {
+        match Self::fsmonitor_enabled(repo) {
+            true => Self::scan_with_git(repo, skip_untracked)
+                .map_or_else(|| Self::scan(repo, skip_untracked), Ok),
+            false => Self::scan(repo, skip_untracked),
+        }
+    }
+
+    /// Counts changes, giving up after `timeout`.  A `git status` that runs out of time is killed,
+    /// but libgit2 can't be interrupted part way through so its scan runs on another thread that's
+    /// left behind.  Only one of those runs per repository at a time, while it's still going later
+    /// prompts give up straight away rather than pile more on.  Returns `None` if it took too
+    /// long.
+    fn scan_within(
+        repo: &Repository,
+        skip_untracked: bool,
+        timeout: Duration,
+    ) -> Result<Option<Counts>> {
+        let started = Instant::now();
+
+        if Self::fsmonitor_enabled(repo) {
+            if let Some(mut command) = Self::git_status(repo, skip_untracked) {
+                match exec::run(&mut command, timeout, None) {
+                    Ok(None) => return Ok(None),
+                    Ok(Some(finished)) if finished.status.success() => {
+                        return Ok(Some(Self::parse_porcelain(&String::from_utf8_lossy(
+                            &finished.stdout,
+                        ))))
+                    }
+                    // Fall back on libgit2 with whatever time is left
+                    _ => (),
+                }
+            }
+        }
+
+        // A Repository can't be shared between threads, the scan opens its own
+        let git_dir = repo.path().to_owned();
+        let workdir = repo.workdir().map(Path::to_owned);
+
+        {
+            let mut scanning = SCANNING
+                .lock()
+                .map_err(|_| anyhow!("the status scan died"))?;
+            if scanning.contains(&git_dir) {
+                return Ok(None);
+            }
+            scanning.push(git_dir.clone());
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let counts = Repository::open(&git_dir)
+                .and_then(|repo| {
+                    if let Some(workdir) = workdir {
+                        repo.set_workdir(&workdir, false)?;
+                    }
+                    Ok(repo)
+                })
+                .map_err(anyhow::Error::from)
+                .and_then(|repo| Self::scan(&repo, skip_untracked));
+
+            if let Ok(mut scanning) = SCANNING.lock() {
+                scanning.retain(|dir| dir != &git_dir);
+            }
+            let _ = sender.send(counts);
+        });
+
+        match receiver.recv_timeout(timeout.saturating_sub(started.elapsed())) {
+            Ok(counts) => counts.map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(anyhow!("the status scan died")),
+        }
+    }
+
+    /// Caps the untracked count at [`Args::untracked_limit`], and leaves it out altogether with
+    /// [`Args::skip_untracked`] in case it came from before that was set
+    fn limit_untracked(counts: Counts, args: &Args) -> Counts {
+        let (staged, changed, untracked, conflicted) = counts;
+
+        let untracked = match (args.skip_untracked, args.untracked_limit) {
+            (true, _) => 0,
+            (false, Some(limit)) => untracked.min(limit),
+            (false, None) => untracked,
+        };
+
+        (staged, changed, untracked, conflicted)
+    }
+
+    /// Finds the repository the way `git` would from the shell's environment rather than the
+    /// process's: `$GIT_DIR` (with `$GIT_WORK_TREE` if that's set too) or wherever `$PWD` is.
+    /// `$__PROMPTR_GIT_REPO` trumps both, the tests point it at their repositories.
+    pub fn open(env: &HashMap<String, String>) -> Result<Repository> {
+        let pwd = Path::new(env.get("PWD").map_or(".", String::as_str));
+
+        if let Some(path) = env.get("__PROMPTR_GIT_REPO") {
+            return Ok(Repository::discover(pwd.join(path))?);
+        }
+
+        let git_dir = match env.get("GIT_DIR") {
+            Some(git_dir) => pwd.join(git_dir),
+            None => return Ok(Repository::discover(pwd)?),
+        };
+
+        let repo = Repository::open(git_dir)?;
+        if let Some(work_tree) = env.get("GIT_WORK_TREE") {
+            repo.set_workdir(&pwd.join(work_tree), false)?;
+        }
+
+        Ok(repo)
+    }
+
+    /// Returns how many stashes there are.  Each one is an entry in the reflog of `refs/stash`,
+    /// reading that directly doesn't need the repository to be mutable like
+    /// [`Repository::stash_foreach`] does.
+    fn stash_count(repo: &Repository) -> Result<usize> {
+        Ok(repo.reflog("refs/stash")?.len())
+    }
+
+    /// Segments for a repository without a working tree, so nothing to count and nothing in
+    /// progress
+    fn bare(
+        repo: &Repository,
+        args: &Args,
+        theme: &VcsTheme,
+        escape_style: EscapeStyle,
+    ) -> Vec<Segment> {
+        let stats = Stats::default();
+
+        let mut segments = vec![];
+
+        seg_current_branch(repo, &stats, args, theme, &mut segments)
+            .context("seg_current_branch")
+            .map_err(|err| eprintln!("Error in promptr: {:?}", err))
+            .ok();
+        seg_upstream(repo, args, theme, escape_style, &mut segments);
+        seg_tag(repo, args, theme, escape_style, &mut segments);
+        seg_ahead_behind(repo, args, theme, &mut segments)
+            .context("seg_ahead_behind")
+            .map_err(|err| eprintln!("Error in promptr: {:?}", err))
+            .ok();
+        seg_compare(repo, args, theme, &mut segments)
+            .context("seg_compare")
+            .map_err(|err| eprintln!("Error in promptr: {:?}", err))
+            .ok();
+        seg_commit_age(repo, args, theme, &mut segments)
+            .context("seg_commit_age")
+            .map_err(|err| eprintln!("Error in promptr: {:?}", err))
+            .ok();
+
+        segments
+    }
+
+    /// Returns the staged, changed, untracked, and conflicted counts, and how many seconds old
+    /// they are if they came from the cache.  Returns `None` if counting took longer than
+    /// [`Args::status_timeout_ms`].
+    fn counts(
+        repo: &Repository,
+        args: &Args,
+        state: &ApplicationState,
+    ) -> Result<Option<(Counts, Option<u64>)>> {
+        let index_mtime = metadata(repo.path().join("index"))
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos() as u64)
+            .unwrap_or_default();
+        let head = repo
+            .head()
+            .ok()
+            .and_then(|head| head.target())
+            .map(|oid| oid.to_string());
+
+        let cache = match args.status_cache_seconds {
+            Some(ttl) => Cache::from_env(&state.env)
+                .ok()
+                .map(|cache| (cache, Duration::from_secs(ttl))),
+            None => None,
+        };
+        let key = Cache::key("git-status", &repo.path());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+
+        if let Some((cache, ttl)) = &cache {
+            // A refresh started by an earlier prompt has finished
+            if let Some((counts, taken)) = Self::finished_refresh(cache, &key) {
+                let _ = cache.set(
+                    &key,
+                    &CachedStatus::new(index_mtime, head.clone(), taken, counts),
+                );
+            }
+
+            if let Some(cached) = cache.get::<CachedStatus>(&key, *ttl) {
+                if cached.index_mtime == index_mtime && cached.head == head {
+                    let counts = Self::limit_untracked(cached.counts(), args);
+                    return Ok(Some((counts, Some(now.saturating_sub(cached.taken)))));
+                }
+            }
+
+            if args.status_cache_background {
+                if let Some(cached) = cache.get::<CachedStatus>(&key, Duration::MAX) {
+                    if Self::refresh_in_background(repo, args, cache, &key).is_ok() {
+                        let counts = Self::limit_untracked(cached.counts(), args);
+                        return Ok(Some((counts, Some(now.saturating_sub(cached.taken)))));
+                    }
+                }
+            }
+        }
+
+        let counts = match args.status_timeout_ms {
+            Some(timeout) => {
+                match Self::scan_within(repo, args.skip_untracked, Duration::from_millis(timeout))?
+                {
+                    Some(counts) => counts,
+                    None => return Ok(None),
+                }
+            }
+            None => Self::scan_any(repo, args.skip_untracked)?,
+        };
+
+        if let Some((cache, _)) = &cache {
+            // A cache we can't write to is only a performance problem
+            let _ = cache.set(&key, &CachedStatus::new(index_mtime, head, now, counts));
+        }
+
+        Ok(Some((Self::limit_untracked(counts, args), None)))
+    }
+
+    /// Where [`Self::refresh_in_background`] leaves the `git status` output for entry `key`
+    fn refresh_path(cache: &Cache, key: &str) -> PathBuf {
+        cache.dir().join(format!("{}.porcelain", key))
+    }
+
+    /// Starts `git status` in the background to count for the next prompt.  The output is only
+    /// moved into place once it's complete, until then a refresh is already running and another
+    /// isn't started.  One that's been running for over a minute is taken to have failed, in
+    /// which case this gives up and the caller counts the usual way.
+    #[cfg(unix)]
+    fn refresh_in_background(
+        repo: &Repository,
+        args: &Args,
+        cache: &Cache,
+        key: &str,
+    ) -> Result<()> {
+        use std::os::unix::process::CommandExt;
+
+        let workdir = repo.workdir().ok_or_else(|| anyhow!("no working tree"))?;
+        let output = Self::refresh_path(cache, key);
+        let partial = output.with_extension("porcelain.tmp");
+
+        if let Ok(started) = metadata(&partial).and_then(|meta| meta.modified()) {
+            match started.elapsed().unwrap_or_default() < Duration::from_secs(60) {
+                true => return Ok(()),
+                false => {
+                    let _ = fs::remove_file(&partial);
+                    return Err(anyhow!("the last background refresh never finished"));
+                }
+            }
+        }
+
+        fs::create_dir_all(cache.dir())?;
+
+        let mut command = Command::new("sh");
+        command
+            .args([
+                "-c",
+                r#"git --no-optional-locks status --porcelain=v1 --untracked-files="$3" \
+                    > "$1" && mv "$1" "$2""#,
+                "sh",
+            ])
+            .arg(&partial)
+            .arg(&output)
+            .arg(Self::untracked_files(args.skip_untracked))
+            .current_dir(workdir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        // SAFETY: setsid is async-signal-safe
+        unsafe {
+            command.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+
+        // Whoever ends up reaping it, it isn't us
+        command.spawn()?;
+
+        Ok(())
+    }
+
+    /// Not without `sh`
+    #[cfg(not(unix))]
+    fn refresh_in_background(
+        _repo: &Repository,
+        _args: &Args,
+        _cache: &Cache,
+        _key: &str,
+    ) -> Result<()> {
+        Err(anyhow!("background refreshes need sh"))
+    }
+
+    /// Returns the counts from a finished background refresh and when they were taken, the
+    /// output is removed so it's only used once
+    fn finished_refresh(cache: &Cache, key: &str) -> Option<(Counts, u64)> {
+        let output = Self::refresh_path(cache, key);
+
+        let taken = metadata(&output)
+            .and_then(|meta| meta.modified())
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+        let porcelain = read_to_string(&output).ok()?;
+        let _ = fs::remove_file(&output);
+
+        Some((Self::parse_porcelain(&porcelain), taken))
+    }
+}
+
+impl ToSegment for Git {
+    type Args = Args;
+    type Theme = super::vcs::Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Git"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let repo = match Self::open(&state.env) {
+            Ok(repo) => repo,
+            Err(_) => return Ok(vec![]),
+        };
+
+        if repo.is_bare() {
+            return Ok(Self::bare(
+                &repo,
+                &args,
+                &state.theme.vcs,
+                state.escape_style,
+            ));
+        }
+
+        let stashed = match args.show_stash {
+            true => Self::stash_count(&repo)?,
+            false => 0,
+        };
+
+        let mut segments = vec![];
+
+        let ((staged, changed, untracked, conflicted), cache_age) = match args.show_counts {
+            true => match Self::counts(&repo, &args, state)? {
+                Some(counts) => counts,
+                // Counting took too long, rather than a prompt that's wrong only show the branch
+                None => {
+                    let stats = Stats {
+                        stashed,
+                        ..Default::default()
+                    };
+                    seg_current_branch(&repo, &stats, &args, &state.theme.vcs, &mut segments)?;
+                    return Ok(segments);
+                }
+            },
+            false => ((0, 0, 0, 0), None),
+        };
 
         let stats = Stats {
             changed,
@@ -441,15 +1578,50 @@ impl ToSegment for Git {
             .context("seg_current_branch")
             .map_err(|err| eprintln!("Error in promptr: {:?}", err))
             .ok();
+        seg_upstream(
+            &repo,
+            &args,
+            &state.theme.vcs,
+            state.escape_style,
+            &mut segments,
+        );
+        seg_tag(
+            &repo,
+            &args,
+            &state.theme.vcs,
+            state.escape_style,
+            &mut segments,
+        );
         seg_ahead_behind(&repo, &args, &state.theme.vcs, &mut segments)
             .context("seg_ahead_behind")
             .map_err(|err| eprintln!("Error in promptr: {:?}", err))
             .ok();
+        seg_compare(&repo, &args, &state.theme.vcs, &mut segments)
+            .context("seg_compare")
+            .map_err(|err| eprintln!("Error in promptr: {:?}", err))
+            .ok();
         seg_in_progress(&repo, &args, &state.theme.vcs, &mut segments);
-        seg_staged(&repo, &stats, &args, &state.theme.vcs, &mut segments);
-        seg_changed(&repo, &stats, &args, &state.theme.vcs, &mut segments);
-        seg_untracked(&repo, &stats, &args, &state.theme.vcs, &mut segments);
-        seg_stashed(&repo, &stats, &args, &state.theme.vcs, &mut segments);
+        seg_conflicted(&repo, &stats, &args, &state.theme.vcs, &mut segments);
+        if args.compact_counts {
+            seg_compact_counts(&stats, &args, &state.theme.vcs, &mut segments);
+        } else {
+            seg_staged(&repo, &stats, &args, &state.theme.vcs, &mut segments);
+            seg_changed(&repo, &stats, &args, &state.theme.vcs, &mut segments);
+            seg_untracked(&repo, &stats, &args, &state.theme.vcs, &mut segments);
+            seg_stashed(&repo, &stats, &args, &state.theme.vcs, &mut segments);
+        }
+        seg_sparse(&repo, &args, &state.theme.vcs, &mut segments);
+        seg_email(&repo, &args, state, &mut segments)
+            .context("seg_email")
+            .map_err(|err| eprintln!("Error in promptr: {:?}", err))
+            .ok();
+        seg_commit_age(&repo, &args, &state.theme.vcs, &mut segments)
+            .context("seg_commit_age")
+            .map_err(|err| eprintln!("Error in promptr: {:?}", err))
+            .ok();
+        if let Some(age) = cache_age {
+            seg_cached(age, &state.theme.vcs, &mut segments);
+        }
 
         Ok(segments)
     }
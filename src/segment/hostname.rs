@@ -1,4 +1,11 @@
 //! The `Hostname` segment displays the system hostname
+//!
+//! With [`Theme::hash_bg`] each host gets a background of its own, picked from
+//! [`Theme::hash_palette`] by hashing the hostname.  The same host always gets the same color so
+//! it's easy to tell at a glance which machine a shell is on:
+//! ```json
+//! { "hostname": { "hash_bg": true } }
+//! ```
 
 use anyhow::anyhow;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
@@ -6,14 +13,15 @@ use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 #[cfg(target_os = "freebsd")]
 use sysctl::{Ctl, Sysctl};
 
-use crate::ansi::Color;
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
 use crate::segment::{Segment, ToSegment};
-use crate::{ApplicationState, Separator};
+use crate::ApplicationState;
 use promptr_macros::SerializeNonDefault;
 
 pub struct Hostname {}
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Args {
     pub show_domain: bool,
@@ -27,10 +35,18 @@ pub struct Theme {
     /// Foreground color
     pub fg: Color,
 
-    /// Background color
+    /// Background color, unless [`Self::hash_bg`] is set
     pub bg: Color,
 
-    /// Indicator to append if we're in a FreeBSD jail
+    /// Pick the background from [`Self::hash_palette`] based on the hostname
+    pub hash_bg: bool,
+
+    /// Backgrounds for [`Self::hash_bg`] to pick from.  Changing the list moves most hosts to a
+    /// different color.
+    pub hash_palette: Vec<Color>,
+
+    /// Indicator to append if we're in a FreeBSD jail or an illumos or Solaris non-global zone.
+    /// OpenBSD and NetBSD have nothing like jails so this never shows up there.
     pub jail_indicator: String,
 
     /// Indicator to append if we're running on macOS
@@ -42,8 +58,17 @@ pub struct Theme {
     /// Indicator to append if we're running on OpenBSD
     pub os_openbsd: String,
 
+    /// Indicator to append if we're running on NetBSD
+    pub os_netbsd: String,
+
+    /// Indicator to append if we're running on illumos or Solaris
+    pub os_illumos: String,
+
     /// Indicator to append if we're running on Linux
     pub os_linux: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
 }
 
 impl Default for Args {
@@ -62,6 +87,15 @@ impl Default for Theme {
             fg: Color::Numbered(250),
             bg: Color::Numbered(238),
 
+            hash_bg: false,
+            // Dark enough for the default foreground
+            hash_palette: [
+                24, 25, 29, 30, 53, 54, 58, 60, 66, 88, 89, 94, 95, 96, 130, 131,
+            ]
+            .into_iter()
+            .map(Color::Numbered)
+            .collect(),
+
             // 🔐 – lock and key
             jail_indicator: "\u{1f510}".into(),
 
@@ -71,12 +105,50 @@ impl Default for Theme {
             os_freebsd: "\u{1f47a}".into(),
             // 🐡 - puffy
             os_openbsd: "\u{1f421}".into(),
+            // 🚩 – the flag from the NetBSD logo
+            os_netbsd: "\u{1f6a9}".into(),
+            // ☀ – the sun that Solaris and then illumos rose from
+            os_illumos: "\u{2600}".into(),
             // 🐧 – tux
             os_linux: "\u{1f427}".into(),
+
+            style: vec![],
         }
     }
 }
 
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        if glyphs == GlyphSet::Ascii {
+            swap_default(&mut self.jail_indicator, &default.jail_indicator, "[jail]");
+            // Without logos the hostname is all there is
+            swap_default(&mut self.os_macos, &default.os_macos, "");
+            swap_default(&mut self.os_freebsd, &default.os_freebsd, "");
+            swap_default(&mut self.os_openbsd, &default.os_openbsd, "");
+            swap_default(&mut self.os_netbsd, &default.os_netbsd, "");
+            swap_default(&mut self.os_illumos, &default.os_illumos, "");
+            swap_default(&mut self.os_linux, &default.os_linux, "");
+        }
+    }
+
+    /// Returns the background for `hostname`, see [`Self::hash_bg`]
+    pub fn bg_for(&self, hostname: &str) -> Color {
+        if !self.hash_bg || self.hash_palette.is_empty() {
+            return self.bg;
+        }
+
+        // FNV-1a, unlike the standard library's hasher it's the same from one build to the next
+        let hash = hostname.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+        self.hash_palette[(hash % self.hash_palette.len() as u64) as usize]
+    }
+}
+
 impl ToSegment for Hostname {
     type Args = Args;
     type Theme = Theme;
@@ -91,7 +163,6 @@ impl ToSegment for Hostname {
     ) -> crate::Result<Vec<Segment>> {
         let args = args.unwrap_or_default();
 
-        let Theme { fg, bg, .. } = state.theme.hostname;
         let theme = &state.theme.hostname;
 
         let hostname = state
@@ -100,6 +171,8 @@ impl ToSegment for Hostname {
             .ok_or_else(|| anyhow!("Hostname not set, check init"))?
             .to_string();
 
+        let (fg, bg) = (theme.fg, theme.bg_for(&hostname));
+
         let hostname = match args.show_domain {
             true => hostname,
             false => hostname
@@ -118,6 +191,10 @@ impl ToSegment for Hostname {
                 hostname.push(theme.os_freebsd.to_string());
             } else if cfg!(target_os = "openbsd") {
                 hostname.push(theme.os_openbsd.to_string());
+            } else if cfg!(target_os = "netbsd") {
+                hostname.push(theme.os_netbsd.to_string());
+            } else if cfg!(any(target_os = "illumos", target_os = "solaris")) {
+                hostname.push(theme.os_illumos.to_string());
             } else if cfg!(target_os = "linux") {
                 hostname.push(theme.os_linux.to_string());
             }
@@ -134,12 +211,12 @@ impl ToSegment for Hostname {
             }
         }
 
-        Ok(vec![Segment {
-            bg,
-            fg,
-            separator: Separator::Thick,
-            text: hostname.join(""),
-            source: "Hostname",
-        }])
+        #[cfg(any(target_os = "illumos", target_os = "solaris"))]
+        if args.show_jail_indicator && crate::platform::in_zone() {
+            hostname.push(theme.jail_indicator.to_string());
+        }
+
+        Ok(vec![Segment::new("Hostname", fg, bg, hostname.join(""))
+            .with_style(theme.style.iter().copied())])
     }
 }
@@ -0,0 +1,184 @@
+//! The `Identity` segment shows who and where you are, but only when it's interesting
+//!
+//! This covers the common case of the [`username`](`crate::segment::username`) and
+//! [`hostname`](`crate::segment::hostname`) segments with a set of rules instead of needing to
+//! configure both.  By default:
+//! * the username is shown unless it's [`Args::default_user`]
+//! * the hostname is shown if we're connected over `ssh` or running in a container
+//! * the whole thing is drawn in [`theme.identity.root_bg`](`Theme`) when running as root
+//!
+//! If neither the username nor the hostname are shown the segment is left out entirely.
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::platform;
+use crate::segment::{Segment, Span, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Identity {}
+
+/// When to show the username
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRule {
+    Always,
+    Never,
+    /// Only if it isn't [`Args::default_user`]
+    NotDefault,
+}
+
+/// When to show the hostname
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostRule {
+    Always,
+    Never,
+    /// Only over `ssh` or inside a container
+    Remote,
+}
+
+/// Arguments for the [`Identity`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// When to show the username
+    pub user: UserRule,
+
+    /// When to show the hostname
+    pub host: HostRule,
+
+    /// The username you normally log in as, used by [`UserRule::NotDefault`]
+    pub default_user: Option<String>,
+
+    /// Use the root colors when running as root
+    pub highlight_root: bool,
+
+    /// Show the domain as part of the hostname
+    pub show_domain: bool,
+}
+
+/// Theme for the [`Identity`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+    /// Background color
+    pub bg: Color,
+
+    /// Foreground color when running as root
+    pub root_fg: Color,
+    /// Background color when running as root
+    pub root_bg: Color,
+
+    /// Goes between the username and hostname
+    pub separator: String,
+
+    /// How the separator is drawn, e.g. `"dim"` or `"bold"`.  Plain text if not set.
+    pub separator_style: Option<TextStyle>,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            user: UserRule::NotDefault,
+            host: HostRule::Remote,
+            default_user: None,
+            highlight_root: true,
+            show_domain: false,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(250),
+            bg: Color::Numbered(240),
+
+            root_fg: Color::Numbered(15),
+            root_bg: Color::Numbered(124),
+
+            separator: "@".into(),
+            separator_style: Some(TextStyle::Dim),
+
+            style: vec![],
+        }
+    }
+}
+
+impl ToSegment for Identity {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Identity"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.identity;
+
+        let user = state.env.get("USER");
+        let is_root = state.env.get("uid").map(String::as_str) == Some("0")
+            || user.map(String::as_str) == Some("root");
+
+        let user = user.filter(|user| match args.user {
+            UserRule::Always => true,
+            UserRule::Never => false,
+            UserRule::NotDefault => args.default_user.as_ref() != Some(user),
+        });
+
+        let host = state
+            .env
+            .get("hostname")
+            .filter(|_| match args.host {
+                HostRule::Always => true,
+                HostRule::Never => false,
+                HostRule::Remote => {
+                    platform::is_ssh(&state.env) || platform::in_container(&state.env)
+                }
+            })
+            .map(|host| match args.show_domain {
+                true => host.as_str(),
+                false => host.split('.').next().unwrap_or(host),
+            });
+
+        let spans = match (user, host) {
+            (Some(user), Some(host)) => vec![
+                Span::from(user.as_str()),
+                Span {
+                    text: theme.separator.clone(),
+                    style: theme.separator_style,
+                    ..Default::default()
+                },
+                Span::from(host),
+            ],
+            (Some(user), None) => vec![Span::from(user.as_str())],
+            (None, Some(host)) => vec![Span::from(host)],
+            (None, None) => return Ok(vec![]),
+        };
+
+        let (fg, bg) = match args.highlight_root && is_root {
+            true => (theme.root_fg, theme.root_bg),
+            false => (theme.fg, theme.bg),
+        };
+
+        Ok(vec![Segment::new("Identity", fg, bg, "")
+            .with_style(theme.style.iter().copied())
+            .with_spans(spans)
+            .with_metadata(
+                json!({ "user": user, "host": host, "root": is_root }),
+            )])
+    }
+}
@@ -0,0 +1,312 @@
+//! The `Instance` segment shows which cloud instance the shell is running on
+//!
+//! EC2, Google Compute Engine, and Azure are recognized from what the firmware reports (see
+//! [`platform::DMI_DIR`]), so outside of Linux nothing is shown.  On its own that's enough for the
+//! provider and, on newer EC2 instances, the instance type.  Anything more means asking the
+//! instance metadata service over the network, which only happens with
+//! [`Args::metadata_service`] set.  The answer, or the failure to get one, is kept in the
+//! [cache](`crate::cache`) for [`Args::cache_seconds`].  FreeBSD jails are flagged by the
+//! [`hostname`](`crate::segment::hostname`) segment instead.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::cache::Cache;
+use crate::platform;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Instance {}
+
+/// Where every supported provider runs its instance metadata service
+pub const METADATA_ADDR: &str = "169.254.169.254:80";
+
+/// Azure tags its virtual machines with this so they can be told apart from other Hyper-V guests
+const AZURE_ASSET_TAG: &str = "7783-7084-3265-9085-8269-3286-77";
+
+/// Arguments for the [`Instance`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// What to show about the instance
+    pub show: Show,
+
+    /// Ask the instance metadata service when the firmware doesn't have the answer
+    pub metadata_service: bool,
+
+    /// How long to wait for the metadata service, in milliseconds
+    pub timeout_ms: u64,
+
+    /// How long to reuse an answer from the metadata service, in seconds
+    pub cache_seconds: u64,
+}
+
+/// Theme for the [`Instance`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the instance
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+/// What to show about the instance, see [`Args::show`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Show {
+    /// Instance type, e.g. `t3.micro`
+    Type,
+    /// Name of the instance, on EC2 this needs tags to be exposed in the metadata
+    Name,
+}
+
+/// Cloud the instance is running in
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// Amazon EC2
+    Ec2,
+    /// Google Compute Engine
+    Gce,
+    /// Microsoft Azure
+    Azure,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            show: Show::Type,
+            metadata_service: false,
+            timeout_ms: 250,
+            cache_seconds: 24 * 60 * 60,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(25),
+
+            // ☁ – cloud
+            symbol: "\u{2601}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Provider {
+    /// Works out the provider from the DMI fields in `dir`, see [`platform::dmi`]
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let field = |name| platform::dmi(dir, name).unwrap_or_default();
+
+        let vendor = field("sys_vendor");
+
+        if vendor == "Amazon EC2" || field("bios_version").contains("amazon") {
+            Some(Self::Ec2)
+        } else if vendor == "Google" || field("product_name") == "Google Compute Engine" {
+            Some(Self::Gce)
+        } else if field("chassis_asset_tag") == AZURE_ASSET_TAG {
+            Some(Self::Azure)
+        } else {
+            None
+        }
+    }
+
+    /// Short name to show when there's nothing more specific
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ec2 => "ec2",
+            Self::Gce => "gce",
+            Self::Azure => "azure",
+        }
+    }
+}
+
+impl Instance {
+    /// Returns what the firmware in `dir` says about the instance.  Only newer EC2 instances put
+    /// their type there.
+    pub fn from_dmi(dir: &Path, provider: Provider, show: Show) -> Option<String> {
+        match (provider, show) {
+            (Provider::Ec2, Show::Type) => {
+                platform::dmi(dir, "product_name").filter(|product| product.contains('.'))
+            }
+            _ => None,
+        }
+    }
+
+    /// Asks the metadata service at `addr` about the instance
+    pub fn lookup(
+        addr: SocketAddr,
+        provider: Provider,
+        show: Show,
+        timeout: Duration,
+    ) -> crate::Result<String> {
+        let value = match provider {
+            Provider::Ec2 => {
+                let token = request(
+                    addr,
+                    "PUT",
+                    "/latest/api/token",
+                    &["X-aws-ec2-metadata-token-ttl-seconds: 60"],
+                    timeout,
+                )?;
+                let path = match show {
+                    Show::Type => "/latest/meta-data/instance-type",
+                    Show::Name => "/latest/meta-data/tags/instance/Name",
+                };
+                request(
+                    addr,
+                    "GET",
+                    path,
+                    &[&format!("X-aws-ec2-metadata-token: {}", token)],
+                    timeout,
+                )?
+            }
+            Provider::Gce => {
+                let path = match show {
+                    Show::Type => "/computeMetadata/v1/instance/machine-type",
+                    Show::Name => "/computeMetadata/v1/instance/name",
+                };
+                // The machine type comes back as projects/<id>/machineTypes/<type>
+                let value = request(addr, "GET", path, &["Metadata-Flavor: Google"], timeout)?;
+                value.rsplit('/').next().unwrap_or_default().to_string()
+            }
+            Provider::Azure => {
+                let path = match show {
+                    Show::Type => "/metadata/instance/compute/vmSize",
+                    Show::Name => "/metadata/instance/compute/name",
+                };
+                let path = format!("{}?api-version=2021-02-01&format=text", path);
+                request(addr, "GET", &path, &["Metadata: true"], timeout)?
+            }
+        };
+
+        match value.trim() {
+            "" => Err(anyhow!("metadata service had nothing to say")),
+            value => Ok(value.to_string()),
+        }
+    }
+
+    /// Returns the segment for an instance with `provider`, `detail` is escaped as it comes from
+    /// the metadata service
+    pub fn segment(
+        provider: Provider,
+        detail: Option<String>,
+        state: &ApplicationState,
+    ) -> Segment {
+        let theme = &state.theme.instance;
+
+        let text = match &detail {
+            Some(detail) => state.escape_style.escape_literal(detail),
+            None => provider.name().into(),
+        };
+
+        Segment::new(
+            "Instance",
+            theme.fg,
+            theme.bg,
+            format!("{} {}", theme.symbol, text),
+        )
+        .with_style(theme.style.iter().copied())
+        .with_metadata(json!({ "provider": provider, "detail": detail }))
+    }
+}
+
+/// Makes a bare bones HTTP/1.0 request and returns the body if the answer was a 200
+fn request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    headers: &[&str],
+    timeout: Duration,
+) -> crate::Result<String> {
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut request = format!("{} {} HTTP/1.0\r\nHost: {}\r\n", method, path, addr.ip());
+    for header in headers {
+        request.push_str(header);
+        request.push_str("\r\n");
+    }
+    request.push_str("Content-Length: 0\r\n\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow!("malformed response from the metadata service"))?;
+
+    match head.split(' ').nth(1) {
+        Some("200") => Ok(body.to_string()),
+        _ => Err(anyhow!(
+            "metadata service answered {} with {}",
+            path,
+            head.lines().next().unwrap_or_default()
+        )),
+    }
+}
+
+impl ToSegment for Instance {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Instance"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let dir = Path::new(platform::DMI_DIR);
+
+        let provider = match Provider::detect(dir) {
+            Some(provider) => provider,
+            None => return Ok(vec![]),
+        };
+
+        let mut detail = Self::from_dmi(dir, provider, args.show);
+
+        if detail.is_none() && args.metadata_service {
+            let key = Cache::key("instance", &(provider, args.show));
+            let addr = METADATA_ADDR.parse()?;
+            let timeout = Duration::from_millis(args.timeout_ms);
+            let lookup = || Self::lookup(addr, provider, args.show, timeout);
+
+            // A metadata service that doesn't answer shouldn't stop the provider from showing, and
+            // nor should a cache we can't find
+            detail = match Cache::from_env(&state.env) {
+                Ok(cache) => cache
+                    .get_or_insert_with(&key, Duration::from_secs(args.cache_seconds), lookup)
+                    .ok(),
+                Err(_) => lookup().ok(),
+            };
+        }
+
+        Ok(vec![Self::segment(provider, detail, state)])
+    }
+}
@@ -0,0 +1,109 @@
+//! The `Load` segment shows the system load average
+//!
+//! The one minute average is checked against [`Args::thresholds`], see
+//! [`threshold`](`crate::segment::threshold`).  Nothing is shown on platforms without load
+//! averages.
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::TextStyle;
+use crate::platform;
+use crate::segment::threshold::{ThresholdColors, ThresholdDisplay};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Load {}
+
+/// Arguments for the [`Load`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the five and fifteen minute averages too
+    pub show_all: bool,
+
+    /// When to show the segment and when to change colors, based on the one minute average
+    pub thresholds: ThresholdDisplay,
+}
+
+/// Theme for the [`Load`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Colors for each threshold
+    pub colors: ThresholdColors,
+
+    /// Icon to display before the load average
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            show_all: false,
+            thresholds: ThresholdDisplay {
+                show_above: None,
+                warn_at: Some(4.0),
+                crit_at: Some(8.0),
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            colors: Default::default(),
+
+            // ⏲ – timer clock
+            symbol: "\u{23f2}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl ToSegment for Load {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Load"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.load;
+
+        let loads = match platform::load_average() {
+            Some(loads) => loads,
+            None => return Ok(vec![]),
+        };
+
+        if !args.thresholds.visible(loads[0]) {
+            return Ok(vec![]);
+        }
+
+        let (fg, bg) = theme.colors.colors(args.thresholds.level(loads[0]));
+
+        let text = match args.show_all {
+            true => format!(
+                "{} {:.2} {:.2} {:.2}",
+                theme.symbol, loads[0], loads[1], loads[2]
+            ),
+            false => format!("{} {:.2}", theme.symbol, loads[0]),
+        };
+
+        Ok(vec![Segment::new("Load", fg, bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({ "load": loads }))])
+    }
+}
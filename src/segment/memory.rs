@@ -0,0 +1,112 @@
+//! The `Memory` segment shows how much memory is in use
+//!
+//! The percentage in use is checked against [`Args::thresholds`], see
+//! [`threshold`](`crate::segment::threshold`).  Memory the kernel could hand back if asked, like
+//! the page cache, doesn't count as used.  Nothing is shown outside of Linux.
+
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::TextStyle;
+use crate::platform;
+use crate::segment::threshold::{ThresholdColors, ThresholdDisplay};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Memory {}
+
+/// Arguments for the [`Memory`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// When to show the segment and when to change colors, based on the percentage in use
+    pub thresholds: ThresholdDisplay,
+}
+
+/// Theme for the [`Memory`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Colors for each threshold
+    pub colors: ThresholdColors,
+
+    /// Icon to display before the percentage
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            thresholds: ThresholdDisplay {
+                show_above: None,
+                warn_at: Some(80.0),
+                crit_at: Some(95.0),
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            colors: Default::default(),
+
+            // 🧠 – brain
+            symbol: "\u{1f9e0}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl ToSegment for Memory {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Memory"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.memory;
+
+        let (total, available) = match platform::memory(Path::new(platform::MEMINFO)) {
+            Some((total, available)) if total > 0 => (total, available),
+            _ => return Ok(vec![]),
+        };
+
+        let used = 100.0 * total.saturating_sub(available) as f64 / total as f64;
+
+        if !args.thresholds.visible(used) {
+            return Ok(vec![]);
+        }
+
+        let (fg, bg) = theme.colors.colors(args.thresholds.level(used));
+
+        let metadata = json!({
+            "used_percent": used,
+            "total_bytes": total,
+            "available_bytes": available,
+        });
+
+        Ok(vec![Segment::new(
+            "Memory",
+            fg,
+            bg,
+            format!("{} {:.0}%", theme.symbol, used),
+        )
+        .with_style(theme.style.iter().copied())
+        .with_metadata(metadata)])
+    }
+}
@@ -0,0 +1,152 @@
+//! The `Nix` segment shows a snowflake when running inside a Nix environment
+//!
+//! Any of these count:
+//! * `nix-shell` or `nix develop`, which set `IN_NIX_SHELL` to `pure` or `impure`
+//! * a `direnv` environment loaded from a flake, i.e. the `.envrc` named by `DIRENV_FILE` or
+//!   `DIRENV_DIR` sits next to a `flake.nix`
+//! * a build shell, which sets `NIX_BUILD_TOP`
+//!
+//! The name shown is the derivation's `$name` (e.g. the `pname` of a `mkShell`), falling back to
+//! how the environment was entered.
+
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Nix {}
+
+/// Arguments for the [`Nix`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the name of the shell after the snowflake
+    pub show_name: bool,
+
+    /// Show whether a `nix-shell` is `pure` or `impure`
+    pub show_purity: bool,
+}
+
+/// Theme for the [`Nix`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the shell name
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            show_name: true,
+            show_purity: false,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(61),
+
+            // ❄ – snowflake
+            symbol: "\u{2744}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Nix {
+    /// Returns how we ended up in a Nix environment, if we did
+    pub fn environment(state: &ApplicationState) -> Option<&'static str> {
+        let env = &state.env;
+
+        if env.contains_key("IN_NIX_SHELL") {
+            return Some("nix-shell");
+        }
+
+        // direnv exports where the .envrc it loaded lives, DIRENV_DIR marks the directory with a
+        // leading dash
+        let envrc_dir = env
+            .get("DIRENV_FILE")
+            .and_then(|file| Path::new(file).parent())
+            .or_else(|| {
+                env.get("DIRENV_DIR")
+                    .map(|dir| Path::new(dir.trim_start_matches('-')))
+            });
+
+        if envrc_dir.is_some_and(|dir| dir.join("flake.nix").exists()) {
+            return Some("flake");
+        }
+
+        if env.contains_key("NIX_BUILD_TOP") {
+            return Some("build");
+        }
+
+        None
+    }
+}
+
+impl ToSegment for Nix {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Nix"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.nix;
+
+        let environment = match Self::environment(state) {
+            Some(environment) => environment,
+            None => return Ok(vec![]),
+        };
+
+        let name = state
+            .env
+            .get("name")
+            .map(String::as_str)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(environment);
+        let purity = state.env.get("IN_NIX_SHELL");
+
+        let mut text = theme.symbol.clone();
+
+        if args.show_name {
+            text.push(' ');
+            text.push_str(name);
+        }
+
+        if let (true, Some(purity)) = (args.show_purity, purity) {
+            text.push_str(&format!(" ({})", purity));
+        }
+
+        Ok(vec![Segment::new("Nix", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(
+                json!({ "environment": environment, "name": name, "purity": purity }),
+            )])
+    }
+}
@@ -0,0 +1,332 @@
+//! The `Os` segment shows a logo for the operating system, or on Linux for the distribution
+//!
+//! Linux distributions are recognized by the `ID` in [`platform::OS_RELEASE`], failing that each
+//! of the distributions in `ID_LIKE` is tried in turn so e.g. Pop!_OS gets the Ubuntu logo.
+//! Everything else goes by what `uname` says.  The logos are in `theme.os.symbols`, keyed by
+//! those IDs:
+//! ```json
+//! {
+//!     "theme": {
+//!         "os": {
+//!             "symbols": { "arch": "btw", "macos": "🍏" }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! IDs that aren't in the table keep their default logo.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
+use crate::platform;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Os {}
+
+/// Arguments for the [`Os`] segment
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the name of the operating system after the logo, e.g. `Debian GNU/Linux`
+    pub show_name: bool,
+
+    /// Show the version of the distribution too, if it has one
+    pub show_version: bool,
+}
+
+/// Theme for the [`Os`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Logos keyed by distribution or operating system ID
+    #[serde(deserialize_with = "merge_symbols")]
+    pub symbols: BTreeMap<String, String>,
+
+    /// Logo for anything that isn't in `symbols`
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+/// What [`Os::detect`] found
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Release {
+    /// e.g. `debian`, `macos`, or `freebsd`
+    pub id: String,
+    /// IDs to try when there's no logo for `id`, closest first.  On Linux this always ends with
+    /// `linux`.
+    pub id_like: Vec<String>,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(238),
+
+            symbols: Self::symbols(&[
+                //  – nf-linux-alpine
+                ("alpine", "\u{f300}"),
+                //  – nf-linux-archlinux
+                ("arch", "\u{f303}"),
+                //  – nf-linux-centos
+                ("centos", "\u{f304}"),
+                //  – nf-linux-debian
+                ("debian", "\u{f306}"),
+                //  – nf-linux-fedora
+                ("fedora", "\u{f30a}"),
+                //  – nf-linux-freebsd
+                ("freebsd", "\u{f30c}"),
+                //  – nf-linux-gentoo
+                ("gentoo", "\u{f30d}"),
+                //  – nf-linux-tux
+                ("linux", "\u{f31a}"),
+                //  – nf-linux-linuxmint
+                ("linuxmint", "\u{f30e}"),
+                //  – nf-fa-apple
+                ("macos", "\u{f179}"),
+                //  – nf-linux-manjaro
+                ("manjaro", "\u{f312}"),
+                //  – nf-linux-nixos
+                ("nixos", "\u{f313}"),
+                //  – nf-linux-opensuse
+                ("opensuse", "\u{f314}"),
+                //  – nf-linux-raspberry_pi
+                ("raspbian", "\u{f315}"),
+                //  – nf-linux-redhat
+                ("rhel", "\u{f316}"),
+                //  – nf-linux-ubuntu
+                ("ubuntu", "\u{f31b}"),
+                //  – nf-fa-windows
+                ("windows", "\u{f17a}"),
+            ]),
+            //  – nf-fa-desktop
+            symbol: "\u{f108}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    fn symbols(symbols: &[(&str, &str)]) -> BTreeMap<String, String> {
+        symbols
+            .iter()
+            .map(|(id, symbol)| (id.to_string(), symbol.to_string()))
+            .collect()
+    }
+
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        let (symbols, symbol) = match glyphs {
+            GlyphSet::Auto | GlyphSet::NerdFont => return,
+            GlyphSet::Emoji => (
+                Self::symbols(&[
+                    // 👺 – beastie
+                    ("freebsd", "\u{1f47a}"),
+                    // 🐧 – tux
+                    ("linux", "\u{1f427}"),
+                    // 🍎 – apple
+                    ("macos", "\u{1f34e}"),
+                    // 🚩 – the flag from the NetBSD logo
+                    ("netbsd", "\u{1f6a9}"),
+                    // 🐡 – puffy
+                    ("openbsd", "\u{1f421}"),
+                    // 🪟 – window
+                    ("windows", "\u{1fa9f}"),
+                ]),
+                // 💻 – personal computer
+                "\u{1f4bb}",
+            ),
+            // Without logos the name is all there is
+            GlyphSet::Ascii => (BTreeMap::new(), ""),
+        };
+
+        // Logos that have been changed stay, the rest fall back to the generic ones
+        for (id, default_symbol) in &default.symbols {
+            if self.symbols.get(id) == Some(default_symbol) {
+                match symbols.get(id) {
+                    Some(symbol) => self.symbols.insert(id.clone(), symbol.clone()),
+                    None => self.symbols.remove(id),
+                };
+            }
+        }
+        for (id, symbol) in symbols {
+            self.symbols.entry(id).or_insert(symbol);
+        }
+        swap_default(&mut self.symbol, &default.symbol, symbol);
+    }
+
+    /// Returns the logo for `release`, trying each of the IDs it's like before falling back to
+    /// [`Self::symbol`]
+    pub fn symbol_for(&self, release: &Release) -> &str {
+        std::iter::once(&release.id)
+            .chain(release.id_like.iter())
+            .find_map(|id| self.symbols.get(id))
+            .unwrap_or(&self.symbol)
+    }
+}
+
+/// Entries in the configuration are added to the default table rather than replacing it
+fn merge_symbols<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut symbols = Theme::default().symbols;
+    symbols.extend(BTreeMap::<String, String>::deserialize(deserializer)?);
+    Ok(symbols)
+}
+
+impl Release {
+    /// Reads a release in the format of [`platform::OS_RELEASE`], anything that's left out
+    /// defaults to plain Linux
+    pub fn from_os_release(text: &str) -> Self {
+        let fields = text
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.trim().split_once('=')?;
+                Some((key, Self::unquote(value)))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let id = fields.get("ID").cloned().unwrap_or_else(|| "linux".into());
+
+        let mut id_like = fields
+            .get("ID_LIKE")
+            .map(|like| like.split_whitespace().map(String::from).collect())
+            .unwrap_or_else(Vec::new);
+        if id != "linux" {
+            id_like.push("linux".into());
+        }
+
+        Self {
+            id,
+            id_like,
+            name: fields
+                .get("NAME")
+                .cloned()
+                .unwrap_or_else(|| "Linux".into()),
+            version: fields.get("VERSION_ID").cloned(),
+        }
+    }
+
+    /// Values may be quoted like they would be for `sh`, with the usual backslash escapes inside
+    /// double quotes
+    fn unquote(value: &str) -> String {
+        let value = value.trim();
+
+        if let Some(value) = value
+            .strip_prefix('\'')
+            .and_then(|value| value.strip_suffix('\''))
+        {
+            return value.into();
+        }
+
+        match value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+        {
+            Some(value) => {
+                let mut unquoted = String::with_capacity(value.len());
+                let mut chars = value.chars();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => unquoted.extend(chars.next()),
+                        c => unquoted.push(c),
+                    }
+                }
+                unquoted
+            }
+            None => value.into(),
+        }
+    }
+}
+
+impl Os {
+    /// Works out which operating system `sysname` (as reported by `uname`) is, on Linux the
+    /// first of `os_release` that exists says which distribution
+    pub fn detect<P: AsRef<Path>>(sysname: Option<&str>, os_release: &[P]) -> Release {
+        match sysname.unwrap_or(std::env::consts::OS) {
+            "Linux" => {
+                let text = os_release
+                    .iter()
+                    .find_map(|path| fs::read_to_string(path).ok())
+                    .unwrap_or_default();
+                Release::from_os_release(&text)
+            }
+            "Darwin" => Release {
+                id: "macos".into(),
+                name: "macOS".into(),
+                ..Default::default()
+            },
+            sysname => Release {
+                id: sysname.to_lowercase(),
+                name: sysname.into(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl ToSegment for Os {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Os"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.os;
+
+        let sysname = platform::sysname();
+        let release = Self::detect(sysname.as_deref(), &platform::OS_RELEASE);
+
+        let symbol = theme.symbol_for(&release);
+
+        let mut text = vec![];
+        if !symbol.is_empty() {
+            text.push(symbol);
+        }
+        if args.show_name || symbol.is_empty() {
+            text.push(&release.name);
+        }
+        if let (true, Some(version)) = (args.show_version, &release.version) {
+            text.push(version);
+        }
+
+        Ok(vec![Segment::new("Os", theme.fg, theme.bg, text.join(" "))
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({
+                "id": release.id,
+                "id_like": release.id_like,
+                "name": release.name,
+                "version": release.version,
+            }))])
+    }
+}
@@ -1,4 +1,13 @@
 //! The `Path` segment displays breadcrumbs to the current working directory
+//!
+//! Long paths can be trimmed down to their last few directories the same way `bash` does for `\w`
+//! with `PROMPT_DIRTRIM`, which is also where [`Args::dir_trim`] comes from if it isn't set.
+//!
+//! Set both [`Theme::gradient_start_bg`] and [`Theme::gradient_end_bg`] and the background fades
+//! from one to the other across the directories instead:
+//! ```json
+//! { "path": { "gradient_start_bg": 31, "gradient_end_bg": { "r": 40, "g": 40, "b": 40 } } }
+//! ```
 use std::path::Component;
 use std::str::FromStr;
 
@@ -7,7 +16,7 @@ use itertools::{Itertools, Position};
 use regex::Regex;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-use crate::ansi::Color;
+use crate::ansi::{Color, TextStyle};
 use crate::segment::{Segment, ToSegment};
 use crate::{ApplicationState, Separator};
 use promptr_macros::SerializeNonDefault;
@@ -15,7 +24,7 @@ use promptr_macros::SerializeNonDefault;
 pub struct Path {}
 
 /// Arguments for the `Path` segment.
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Args {
     /// Whether or not to show a path segment for the root directory
@@ -23,6 +32,10 @@ pub struct Args {
 
     /// Add a leading segment if there's more than one directory in the [stack](https://www.gnu.org/software/bash/manual/html_node/The-Directory-Stack.html)
     pub show_dir_stack: bool,
+
+    /// Only show this many trailing directories, the rest are replaced with
+    /// [`Theme::trim_indicator`].  Falls back to `$PROMPT_DIRTRIM`, zero turns trimming off.
+    pub dir_trim: Option<usize>,
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
@@ -37,10 +50,23 @@ pub struct Theme {
     pub last_fg: Color,
     pub last_bg: Color,
 
+    /// Background of the first directory when fading from one color to another, the other
+    /// backgrounds are ignored if this and [`Self::gradient_end_bg`] are set
+    pub gradient_start_bg: Option<Color>,
+
+    /// Background of the last directory, the ones in between are mixed from the two
+    pub gradient_end_bg: Option<Color>,
+
     pub dir_stack_indicator: String,
 
     /// String/icon to replace the home directory component.  Grey beards probably want a tilde.
     pub home_dir_replacement: String,
+
+    /// Stands in for the directories left out by [`Args::dir_trim`]
+    pub trim_indicator: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
 }
 
 impl Default for Args {
@@ -48,6 +74,7 @@ impl Default for Args {
         Self {
             show_root: false,
             show_dir_stack: true,
+            dir_trim: None,
         }
     }
 }
@@ -64,9 +91,15 @@ impl Default for Theme {
             last_fg: Color::Numbered(254),
             last_bg: Color::Numbered(237),
 
+            gradient_start_bg: None,
+            gradient_end_bg: None,
+
             // 📚 – *stack* of books
             dir_stack_indicator: "\u{1f4da}".into(),
             home_dir_replacement: Path::HOME_SHORTENED.into(),
+            trim_indicator: "...".into(),
+
+            style: vec![],
         }
     }
 }
@@ -112,89 +145,130 @@ impl ToSegment for Path {
             .filter_map(|component| match component {
                 Position::First(Component::RootDir) => match args.show_root {
                     false => None,
-                    true => Some(Segment {
-                        fg: theme.fg,
-                        bg: theme.bg,
-                        separator: Separator::Thin,
-                        text: "/".into(),
-                        source: "Path::First::Root",
-                    }),
+                    true => Some(
+                        Segment::new("Path::First::Root", theme.fg, theme.bg, "/")
+                            .with_separator(Separator::Thin),
+                    ),
                 },
                 Position::First(Component::Normal(p)) => {
                     if p == Self::HOME_SHORTENED {
-                        Some(Segment {
-                            fg: theme.home_fg,
-                            bg: theme.home_bg,
-                            separator: Separator::Thick,
-                            text: theme.home_dir_replacement.clone(),
-                            source: "Path::First::Home",
-                        })
+                        Some(Segment::new(
+                            "Path::First::Home",
+                            theme.home_fg,
+                            theme.home_bg,
+                            theme.home_dir_replacement.clone(),
+                        ))
                     } else {
-                        Some(Segment {
-                            fg: theme.fg,
-                            bg: theme.bg,
-                            separator: Separator::Thin,
-                            text: p.to_string_lossy().into(),
-                            source: "Path::First::Normal",
-                        })
+                        Some(
+                            Segment::new(
+                                "Path::First::Normal",
+                                theme.fg,
+                                theme.bg,
+                                p.to_string_lossy(),
+                            )
+                            .with_separator(Separator::Thin),
+                        )
                     }
                 }
                 Position::First(_) => None,
                 Position::Only(Component::Normal(p)) => {
                     if p == Self::HOME_SHORTENED {
-                        Some(Segment {
-                            fg: theme.home_fg,
-                            bg: theme.home_bg,
-                            separator: Separator::Thick,
-                            text: theme.home_dir_replacement.clone(),
-                            source: "Path::Only::Home",
-                        })
+                        Some(Segment::new(
+                            "Path::Only::Home",
+                            theme.home_fg,
+                            theme.home_bg,
+                            theme.home_dir_replacement.clone(),
+                        ))
                     } else {
-                        Some(Segment {
-                            fg: theme.fg,
-                            bg: theme.bg,
-                            separator: Separator::Thick,
-                            text: p.to_string_lossy().into(),
-                            source: "Path::Only::Normal",
-                        })
+                        Some(Segment::new(
+                            "Path::Only::Normal",
+                            theme.fg,
+                            theme.bg,
+                            p.to_string_lossy(),
+                        ))
                     }
                 }
-                Position::Middle(Component::Normal(p)) => Some(Segment {
-                    fg: theme.fg,
-                    bg: theme.bg,
-                    separator: Separator::Thin,
-                    text: p.to_string_lossy().into(),
-                    source: "Path::Middle::Normal",
-                }),
-                Position::Last(Component::Normal(p)) => Some(Segment {
-                    fg: theme.last_fg,
-                    bg: theme.last_bg,
-                    separator: Separator::Thick,
-                    text: p.to_string_lossy().into(),
-                    source: "Path::Last::Normal",
-                }),
+                Position::Middle(Component::Normal(p)) => Some(
+                    Segment::new(
+                        "Path::Middle::Normal",
+                        theme.fg,
+                        theme.bg,
+                        p.to_string_lossy(),
+                    )
+                    .with_separator(Separator::Thin),
+                ),
+                Position::Last(Component::Normal(p)) => Some(Segment::new(
+                    "Path::Last::Normal",
+                    theme.last_fg,
+                    theme.last_bg,
+                    p.to_string_lossy(),
+                )),
                 _ => None,
             })
             .collect();
 
+        let dir_trim = args.dir_trim.or_else(|| {
+            state
+                .env
+                .get("PROMPT_DIRTRIM")
+                .and_then(|trim| trim.parse().ok())
+        });
+
+        // Like bash the home directory (or root) stays put and only what comes after is trimmed
+        if let Some(keep) = dir_trim.filter(|keep| *keep > 0) {
+            let start = match segments.first() {
+                Some(first) if first.source.starts_with("Path::First::") => {
+                    usize::from(first.source != "Path::First::Normal")
+                }
+                _ => 0,
+            };
+
+            if segments.len() - start > keep {
+                segments.splice(
+                    start..segments.len() - keep,
+                    [Segment::new(
+                        "Path::Trimmed",
+                        theme.fg,
+                        theme.bg,
+                        theme.trim_indicator.clone(),
+                    )
+                    .with_separator(Separator::Thin)],
+                );
+            }
+        }
+
+        if let (Some(start), Some(end)) = (theme.gradient_start_bg, theme.gradient_end_bg) {
+            let steps = segments.len().saturating_sub(1).max(1) as f64;
+
+            for (idx, segment) in segments.iter_mut().enumerate() {
+                if let Some(bg) = start.interpolate(end, idx as f64 / steps) {
+                    segment.bg = bg;
+                    // Thin separators are for segments that share a background
+                    segment.separator = Separator::Thick;
+                }
+            }
+        }
+
         if args.show_dir_stack {
             if let Some(dirs) = state.env.get("dirs") {
                 let dir_stack_depth = dirs.split('\n').count();
                 if dir_stack_depth > 1 {
                     segments.insert(
                         0,
-                        Segment {
-                            fg: theme.fg,
-                            bg: theme.bg,
-                            separator: Separator::Thick,
-                            text: format!("{} {}", dir_stack_depth, theme.dir_stack_indicator),
-                            source: "Path::BashDirStack",
-                        },
+                        Segment::new(
+                            "Path::BashDirStack",
+                            theme.fg,
+                            theme.bg,
+                            format!("{} {}", dir_stack_depth, theme.dir_stack_indicator),
+                        ),
                     );
                 }
             }
         }
 
-        Ok(segments)
+        Ok(segments
+            .into_iter()
+            .map(|seg| seg.with_style(theme.style.iter().copied()))
+            .collect())
     }
 }
@@ -0,0 +1,135 @@
+//! The `Rbenv` segment shows which ruby `rbenv` has picked
+//!
+//! This follows `rbenv version-name` without running it: `$RBENV_VERSION` wins, then the nearest
+//! `.ruby-version` in the current or ancestor directories, then the global `version` file under
+//! `$RBENV_ROOT` (`~/.rbenv` by default).  None of the `rvm` plumbing is needed.
+//!
+//! Most of the time the global ruby isn't worth a segment so it's only shown with
+//! [`Args::show_global`] set.
+
+use std::path::PathBuf;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{ruby, util, Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Rbenv {}
+
+/// Arguments for the [`Rbenv`] segment
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the global ruby too, not just one picked by the environment or a `.ruby-version`
+    pub show_global: bool,
+}
+
+/// Theme for the [`Rbenv`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the version
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+/// Where `rbenv` got the version from
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    /// `$RBENV_VERSION`
+    Env,
+    /// A `.ruby-version` file
+    File,
+    /// The global `version` file
+    Global,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(124),
+
+            // 💎 – gem stone
+            symbol: "\u{1f48e}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Rbenv {
+    /// Returns the selected version and where it came from
+    pub fn version(state: &ApplicationState) -> Option<(String, Origin)> {
+        if let Some(version) = state.env.get("RBENV_VERSION").filter(|v| !v.is_empty()) {
+            return Some((version.clone(), Origin::Env));
+        }
+
+        let local = state
+            .env
+            .get("PWD")
+            .and_then(|pwd| util::find_ancestor(ruby::VERSION_FILE, pwd, &[]))
+            .and_then(|path| ruby::read_version(&path));
+
+        if let Some(version) = local {
+            return Some((version, Origin::File));
+        }
+
+        let root = match state.env.get("RBENV_ROOT") {
+            Some(root) => PathBuf::from(root),
+            None => PathBuf::from(state.env.get("HOME")?).join(".rbenv"),
+        };
+
+        ruby::read_version(&root.join("version")).map(|version| (version, Origin::Global))
+    }
+}
+
+impl ToSegment for Rbenv {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Rbenv"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.rbenv;
+
+        let (version, origin) = match Self::version(state) {
+            Some((_, Origin::Global)) if !args.show_global => return Ok(vec![]),
+            Some(found) => found,
+            None => return Ok(vec![]),
+        };
+
+        // rbenv itself drops the prefix from .ruby-version
+        let version = version.strip_prefix("ruby-").unwrap_or(&version);
+
+        Ok(vec![Segment::new(
+            "Rbenv",
+            theme.fg,
+            theme.bg,
+            format!("{} {}", theme.symbol, version),
+        )
+        .with_style(theme.style.iter().copied())
+        .with_metadata(
+            json!({ "version": version, "origin": origin }),
+        )])
+    }
+}
@@ -0,0 +1,19 @@
+//! There are no segments here, just the bits of ruby version hunting that [`rbenv`](`super::rbenv`)
+//! and [`rvm`](`super::rvm`) have in common.
+
+use std::fs::read_to_string;
+use std::path::Path;
+
+/// Name of the file that pins a project to a ruby
+pub const VERSION_FILE: &str = ".ruby-version";
+
+/// Reads a [`VERSION_FILE`], returning the first line if there is one
+pub fn read_version(path: &Path) -> Option<String> {
+    let contents = read_to_string(path).ok()?;
+    let version = contents.lines().next()?.trim();
+
+    match version.is_empty() {
+        true => None,
+        false => Some(version.to_string()),
+    }
+}
@@ -1,4 +1,4 @@
-//! The `Rvm` segment displays the the current gemset if RVM is loaded
+//! The `Rvm` segment displays the current gemset if RVM is loaded
 //!
 //! This is a a very rough reinterpretation of how `rvm` does things.  Unfortunately `rvm` uses
 //! a huge mess of shell scripts which are, by their nature, not performant.  As this can be a
@@ -29,7 +29,6 @@
 //!     + Print the current ruby version and append [`theme.rvm.mismatch_symbol`](`Theme`)
 //! * If the two match, print the current ruby version
 
-use std::fs::metadata;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -37,14 +36,14 @@ use anyhow::anyhow;
 use regex::Regex;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-use crate::ansi::Color;
-use crate::segment::{Segment, ToSegment};
-use crate::{ApplicationState, Separator};
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{ruby, util, Segment, ToSegment};
+use crate::ApplicationState;
 use promptr_macros::SerializeNonDefault;
 
 pub struct Rvm {}
 
-#[derive(Default, Deserialize)]
+#[derive(Default, Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Args {
     /// Show even if there's no `Gemset` file in the current or ancestor directories
@@ -87,27 +86,9 @@ pub struct Theme {
 
     /// Appended if we suspect [`Rvm`] can't find the desired rubie
     pub mismatch_symbol: String,
-}
-
-fn find_ancestors(target: &str, pwd: &str, home: &str, rvm_path: &PathBuf) -> Option<PathBuf> {
-    let mut has_target = None;
-    let mut path = Some(Path::new(pwd));
-
-    let home_path = Path::new(home);
 
-    // This is loosely based on scripts/functions/rvmrc_project
-    while path.is_some() {
-        let path_ref = path.as_ref().unwrap();
-        let file_ref = path_ref.join(target);
-
-        if path_ref != &home_path && path_ref != rvm_path && metadata(&file_ref).is_ok() {
-            has_target = Some(file_ref);
-            break;
-        }
-
-        path = path_ref.parent();
-    }
-    has_target
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
 }
 
 impl<SemType> FromStr for Gemset<SemType>
@@ -152,6 +133,8 @@ impl Default for Theme {
             bg: Color::Numbered(124),
             // ≠ - not equal
             mismatch_symbol: " \u{2260}".to_string(),
+
+            style: vec![],
         }
     }
 }
@@ -201,7 +184,9 @@ impl ToSegment for Rvm {
             .to_string();
         let rvm_path = Path::new(rvm_path.as_str()).join("gems/");
 
-        let has_gemfile = find_ancestors("Gemfile", &pwd, &home, &rvm_path).is_some();
+        let skip = [Path::new(&home), rvm_path.as_path()];
+
+        let has_gemfile = util::find_ancestor("Gemfile", &pwd, &skip).is_some();
 
         // Unless forced to, skip directories without a bundler Gemfile
         if !args.force_show && !has_gemfile {
@@ -209,7 +194,7 @@ impl ToSegment for Rvm {
         }
 
         let requested_ruby_version: Option<PathBuf> =
-            find_ancestors(".ruby-version", &pwd, &home, &rvm_path);
+            util::find_ancestor(ruby::VERSION_FILE, &pwd, &skip);
         let requested_ruby_version: Option<String> = match requested_ruby_version {
             None => None,
             Some(ruby_version_path) => {
@@ -259,12 +244,8 @@ impl ToSegment for Rvm {
             false => format!("{}{}", text, theme.mismatch_symbol),
         };
 
-        Ok(vec![Segment {
-            fg: theme.fg,
-            bg: theme.bg,
-            separator: Separator::Thick,
-            text,
-            source: "Rvm",
-        }])
+        Ok(vec![
+            Segment::new("Rvm", theme.fg, theme.bg, text).with_style(theme.style.iter().copied())
+        ])
     }
 }
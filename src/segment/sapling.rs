@@ -0,0 +1,183 @@
+//! The `Sapling` segment shows the bookmark or commit checked out in a Sapling (`sl`) repository
+//!
+//! The repository is the nearest `.sl` directory in the current or ancestor directories.  The
+//! bookmark and commit are read straight out of it, only the dirty check runs `sl status`, which
+//! can be turned off with [`Args::show_status`] if that's too slow.  Colors and symbols are
+//! shared with the git segment through the [`vcs`](`crate::segment::vcs`) theme, its `sapling`
+//! section has colors for Sapling alone.
+
+use std::fs::{read, read_to_string};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::segment::{util, Segment, ToSegment};
+use crate::ApplicationState;
+
+pub struct Sapling {}
+
+/// Arguments for the [`Sapling`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show [`vcs::Symbols::sapling`](`crate::segment::vcs::Symbols::sapling`) before the
+    /// bookmark or commit
+    pub show_vcs_badge: bool,
+
+    /// Run `sl status` to find out if there are local changes, the segment is drawn in the
+    /// dirty colors if there are
+    pub show_status: bool,
+
+    /// The Sapling command, some systems already have an unrelated `sl`
+    pub command: String,
+}
+
+/// Changes reported by `sl status`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    /// Files that have been modified, added, removed, or have gone missing
+    pub changed: usize,
+
+    /// Files Sapling doesn't know about
+    pub untracked: usize,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            show_vcs_badge: true,
+            show_status: true,
+            command: String::from("sl"),
+        }
+    }
+}
+
+impl Status {
+    /// Returns true if there are any changes at all
+    pub fn dirty(&self) -> bool {
+        self.changed + self.untracked > 0
+    }
+}
+
+impl Sapling {
+    /// Returns the `.sl` directory of the repository `pwd` is in
+    pub fn find(pwd: &str) -> Option<PathBuf> {
+        util::find_ancestor(".sl", pwd, &[]).filter(|dot_sl| dot_sl.is_dir())
+    }
+
+    /// Returns the active bookmark, if there is one
+    pub fn bookmark(dot_sl: &Path) -> Option<String> {
+        let bookmark = read_to_string(dot_sl.join("bookmarks.current")).ok()?;
+        let bookmark = bookmark.trim();
+
+        match bookmark.is_empty() {
+            true => None,
+            false => Some(bookmark.to_string()),
+        }
+    }
+
+    /// Returns the hash of the working copy's parent, the first 20 bytes of the dirstate.
+    /// `None` if nothing's been committed yet.
+    pub fn commit(dot_sl: &Path) -> Option<String> {
+        let dirstate = read(dot_sl.join("dirstate")).ok()?;
+        let node = dirstate.get(..20)?;
+
+        match node.iter().all(|byte| *byte == 0) {
+            true => None,
+            false => Some(node.iter().map(|byte| format!("{:02x}", byte)).collect()),
+        }
+    }
+
+    /// Counts the changes in `sl status` output
+    pub fn parse_status(output: &str) -> Status {
+        output.lines().fold(Status::default(), |status, line| {
+            match line.chars().next() {
+                Some('M' | 'A' | 'R' | '!') => Status {
+                    changed: status.changed + 1,
+                    ..status
+                },
+                Some('?') => Status {
+                    untracked: status.untracked + 1,
+                    ..status
+                },
+                _ => status,
+            }
+        })
+    }
+
+    /// Runs `sl status` in the repository.  Returns `None` if Sapling isn't up to it.
+    fn status(command: &str, root: &Path) -> Option<Status> {
+        let output = Command::new(command)
+            .arg("status")
+            .current_dir(root)
+            // Keep the output free of anything the user's configured
+            .env("HGPLAIN", "1")
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+
+        match output.status.success() {
+            true => Some(Self::parse_status(&String::from_utf8_lossy(&output.stdout))),
+            false => None,
+        }
+    }
+}
+
+impl ToSegment for Sapling {
+    type Args = Args;
+    type Theme = super::vcs::Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Sapling"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.vcs;
+
+        let dot_sl = match state.env.get("PWD").and_then(|pwd| Self::find(pwd)) {
+            Some(dot_sl) => dot_sl,
+            None => return Ok(vec![]),
+        };
+
+        let bookmark = Self::bookmark(&dot_sl);
+        let commit = Self::commit(&dot_sl);
+
+        let status = match (args.show_status, dot_sl.parent()) {
+            (true, Some(root)) => Self::status(&args.command, root),
+            _ => None,
+        };
+        let dirty = status.as_ref().is_some_and(Status::dirty);
+
+        let (fg, bg) = theme.backend_colors(&theme.sapling, dirty);
+
+        let name = match (&bookmark, &commit) {
+            (Some(bookmark), _) => bookmark.clone(),
+            (None, Some(commit)) => commit[..12].to_string(),
+            (None, None) => String::from("(empty)"),
+        };
+
+        let name = state.escape_style.escape_literal(&name);
+        let text = match args.show_vcs_badge {
+            true => format!("{} {}", theme.symbols.sapling, name),
+            false => name.into_owned(),
+        };
+
+        Ok(vec![Segment::new("Sapling", fg, bg, text)
+            .with_style(theme.repo_style(dirty).iter().copied())
+            .with_metadata(json!({
+                "bookmark": bookmark,
+                "commit": commit,
+                "dirty": dirty,
+                "changed": status.as_ref().map(|status| status.changed),
+                "untracked": status.as_ref().map(|status| status.untracked),
+            }))])
+    }
+}
@@ -9,14 +9,14 @@
 use anyhow::anyhow;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-use crate::ansi::Color;
+use crate::ansi::{Color, TextStyle};
 use crate::segment::{Segment, ToSegment};
-use crate::{ApplicationState, Separator};
+use crate::ApplicationState;
 use promptr_macros::SerializeNonDefault;
 
 pub struct Screen {}
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Args {
     pub show_screen_icon: bool,
@@ -38,6 +38,9 @@ pub struct Theme {
 
     /// Icon to display if we're inside a screen session
     pub screen_symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
 }
 
 impl Default for Args {
@@ -61,6 +64,8 @@ impl Default for Theme {
 
             // 📺
             screen_symbol: "\u{1f4fa}".into(),
+
+            style: vec![],
         }
     }
 }
@@ -129,12 +134,8 @@ impl ToSegment for Screen {
             },
         );
 
-        Ok(vec![Segment {
-            bg,
-            fg,
-            separator: Separator::Thick,
-            text,
-            source: "Screen",
-        }])
+        Ok(vec![
+            Segment::new("Screen", fg, bg, text).with_style(theme.style.iter().copied())
+        ])
     }
 }
@@ -0,0 +1,236 @@
+//! The `Tasks` segment displays how many tasks are due or overdue
+//!
+//! Counts come from [Taskwarrior](https://taskwarrior.org) by default.  Anything else can be
+//! plugged in with the `command` source, which runs a program and reads a single number from its
+//! output:
+//! ```json
+//! { "name": "tasks", "args": { "source": "command", "command": ["todo.sh", "-p", "count"] } }
+//! ```
+//!
+//! Running either is far too slow to do on every prompt so the counts are [cached](`crate::cache`)
+//! for [`Args::ttl_seconds`].  This is gated by the `segment-tasks` feature.
+
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::cache::Cache;
+use crate::glyphs::{swap_default, GlyphSet};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Tasks {}
+
+/// Where the counts come from
+#[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// `task +DUE -OVERDUE count` and `task +OVERDUE count`
+    Taskwarrior,
+    /// Run [`Args::command`] and parse its output as a number
+    Command,
+}
+
+/// Arguments for the [`Tasks`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Where to get the counts from
+    pub source: Source,
+
+    /// Program and arguments to run when `source` is `command`
+    pub command: Vec<String>,
+
+    /// How long to hold on to the counts before asking again
+    pub ttl_seconds: u64,
+
+    /// Show the segment even if nothing is due
+    pub show_zero: bool,
+}
+
+/// Theme for the [`Tasks`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color for tasks that are due
+    pub fg: Color,
+    /// Background color for tasks that are due
+    pub bg: Color,
+
+    /// Foreground color for overdue tasks
+    pub overdue_fg: Color,
+    /// Background color for overdue tasks
+    pub overdue_bg: Color,
+
+    /// Icon to display before the due count
+    pub symbol: String,
+
+    /// Icon to display before the overdue count
+    pub overdue_symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+/// What's kept in the cache
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+struct Counts {
+    due: u64,
+    overdue: u64,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            source: Source::Taskwarrior,
+            command: vec![],
+            ttl_seconds: 60,
+            show_zero: false,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(24),
+
+            overdue_fg: Color::Numbered(15),
+            overdue_bg: Color::Numbered(161),
+
+            // ☑ – ballot box with check
+            symbol: "\u{2611}".into(),
+            // ⏰ – alarm clock
+            overdue_symbol: "\u{23f0}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        if glyphs == GlyphSet::Ascii {
+            swap_default(&mut self.symbol, &default.symbol, "todo");
+            swap_default(&mut self.overdue_symbol, &default.overdue_symbol, "late");
+        }
+    }
+}
+
+impl Tasks {
+    /// Runs `program` and parses the first thing it prints as a number
+    fn count(program: &str, args: &[&str]) -> crate::Result<u64> {
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?;
+
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .next()
+            .ok_or_else(|| anyhow!("{} didn't print anything", program))?
+            .parse::<u64>()
+            .map_err(|e| anyhow!("{} didn't print a number: {}", program, e))
+    }
+
+    fn counts(args: &Args) -> crate::Result<Counts> {
+        match args.source {
+            Source::Taskwarrior => {
+                const QUIET: [&str; 2] = ["rc.verbose=nothing", "rc.hooks=off"];
+
+                Ok(Counts {
+                    // +DUE includes anything that's overdue
+                    due: Self::count(
+                        "task",
+                        &[&QUIET[..], &["+DUE", "-OVERDUE", "count"]].concat(),
+                    )?,
+                    overdue: Self::count("task", &[&QUIET[..], &["+OVERDUE", "count"]].concat())?,
+                })
+            }
+            Source::Command => {
+                let (program, rest) = args
+                    .command
+                    .split_first()
+                    .ok_or_else(|| anyhow!("tasks: command is empty"))?;
+                let rest = rest.iter().map(String::as_str).collect::<Vec<_>>();
+
+                Ok(Counts {
+                    due: Self::count(program, &rest)?,
+                    overdue: 0,
+                })
+            }
+        }
+    }
+}
+
+impl ToSegment for Tasks {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Tasks"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.tasks;
+
+        let key = match args.source {
+            Source::Taskwarrior => String::from("tasks-taskwarrior"),
+            Source::Command => Cache::key("tasks-command", &args.command),
+        };
+
+        let counts = Cache::from_env(&state.env)?.get_or_insert_with(
+            &key,
+            Duration::from_secs(args.ttl_seconds),
+            || Self::counts(&args),
+        )?;
+
+        let metadata = json!({ "due": counts.due, "overdue": counts.overdue });
+
+        let mut segments = vec![];
+
+        if counts.due > 0 || (args.show_zero && counts.overdue == 0) {
+            segments.push(
+                Segment::new(
+                    "Tasks::Due",
+                    theme.fg,
+                    theme.bg,
+                    format!("{} {}", theme.symbol, counts.due),
+                )
+                .with_style(theme.style.iter().copied()),
+            );
+        }
+
+        if counts.overdue > 0 {
+            segments.push(
+                Segment::new(
+                    "Tasks::Overdue",
+                    theme.overdue_fg,
+                    theme.overdue_bg,
+                    format!("{} {}", theme.overdue_symbol, counts.overdue),
+                )
+                .with_style(theme.style.iter().copied()),
+            );
+        }
+
+        Ok(segments
+            .into_iter()
+            .map(|segment| segment.with_metadata(metadata.clone()))
+            .collect())
+    }
+}
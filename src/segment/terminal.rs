@@ -0,0 +1,216 @@
+//! The `Terminal` segment identifies the terminal emulator that's hosting the shell
+//!
+//! There's no reliable way to ask a terminal what it is.  Some set `TERM_PROGRAM`, for the rest we
+//! walk up the process tree from the shell until we find a process whose name we recognize.  The
+//! walk won't find anything if we're inside a multiplexer (the server process is detached from the
+//! terminal) or logged in via ssh.
+
+use std::collections::HashMap;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::ansi::{Color, TextStyle};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Terminal {}
+
+/// Terminal emulators we know how to recognize by process name
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Emulator {
+    Alacritty,
+    AppleTerminal,
+    Foot,
+    GnomeTerminal,
+    ITerm2,
+    Kitty,
+    Konsole,
+    WezTerm,
+    Xterm,
+}
+
+/// Arguments for the [`Terminal`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the name of the terminal emulator
+    pub show_name: bool,
+
+    /// Show [`theme.terminal.symbol`](`Theme`) before the name
+    pub show_icon: bool,
+}
+
+/// Theme for the [`Terminal`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the terminal name
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            show_name: true,
+            show_icon: true,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(250),
+            bg: Color::Numbered(238),
+
+            // 💻 – personal computer
+            symbol: "\u{1f4bb}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Emulator {
+    /// Stop walking the process tree after this many ancestors, just in case.
+    const MAX_DEPTH: usize = 32;
+
+    /// Maps a process name to a terminal emulator
+    pub fn from_process_name(name: &str) -> Option<Self> {
+        match name {
+            "alacritty" | "Alacritty" => Some(Self::Alacritty),
+            "Terminal" => Some(Self::AppleTerminal),
+            "foot" | "footclient" => Some(Self::Foot),
+            "gnome-terminal-server" | "gnome-terminal-" => Some(Self::GnomeTerminal),
+            "iTerm2" | "iTerm" => Some(Self::ITerm2),
+            "kitty" => Some(Self::Kitty),
+            "konsole" => Some(Self::Konsole),
+            "wezterm-gui" | "wezterm" => Some(Self::WezTerm),
+            "xterm" | "uxterm" => Some(Self::Xterm),
+            _ => None,
+        }
+    }
+
+    /// Maps the `TERM_PROGRAM` a terminal sets to an emulator.  Multiplexers set it too, e.g.
+    /// `tmux`, which doesn't tell us anything.
+    pub fn from_term_program(env: &HashMap<String, String>) -> Option<Self> {
+        match env.get("TERM_PROGRAM").map(String::as_str) {
+            Some("Apple_Terminal") => Some(Self::AppleTerminal),
+            Some("iTerm.app") => Some(Self::ITerm2),
+            Some("WezTerm") => Some(Self::WezTerm),
+            _ => None,
+        }
+    }
+
+    /// Checks `TERM_PROGRAM` first as it's cheap, then walks the process tree
+    pub fn detect(env: &HashMap<String, String>) -> Option<Self> {
+        Self::from_term_program(env).or_else(Self::find)
+    }
+
+    /// Human-friendly name for the terminal emulator
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Alacritty => "alacritty",
+            Self::AppleTerminal => "Terminal.app",
+            Self::Foot => "foot",
+            Self::GnomeTerminal => "gnome-terminal",
+            Self::ITerm2 => "iTerm2",
+            Self::Kitty => "kitty",
+            Self::Konsole => "konsole",
+            Self::WezTerm => "wezterm",
+            Self::Xterm => "xterm",
+        }
+    }
+
+    /// Walks up the process tree from our parent looking for a terminal emulator we recognize
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "linux"
+    ))]
+    pub fn find() -> Option<Self> {
+        // pid_t is u32 in rust but POSIX defies it as a signed integer…
+        let mut pid = std::os::unix::process::parent_id() as i64;
+
+        for _ in 0..Self::MAX_DEPTH {
+            if pid <= 1 {
+                break;
+            }
+
+            let name = crate::ffi::get_process_name(pid);
+            let name = std::path::Path::new(name.as_str())
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or(name);
+
+            if let Some(emulator) = Self::from_process_name(name.as_str()) {
+                return Some(emulator);
+            }
+
+            pid = crate::ffi::get_parent_pid(pid);
+        }
+
+        None
+    }
+
+    /// No way to walk the process tree on this platform
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "linux"
+    )))]
+    pub fn find() -> Option<Self> {
+        None
+    }
+}
+
+impl ToSegment for Terminal {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Terminal"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.terminal;
+
+        let emulator = match Emulator::detect(&state.env) {
+            Some(emulator) => emulator,
+            None => return Ok(vec![]),
+        };
+
+        let text = match (args.show_icon, args.show_name) {
+            (true, true) => format!("{} {}", theme.symbol, emulator.name()),
+            (true, false) => theme.symbol.clone(),
+            (false, true) => emulator.name().to_string(),
+            (false, false) => return Ok(vec![]),
+        };
+
+        Ok(vec![Segment::new("Terminal", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())])
+    }
+}
@@ -0,0 +1,121 @@
+//! The `Thermal` segment shows how hot the machine is running
+//!
+//! By default the hottest sensor is shown, set [`Args::zone`] to pick one by its type (e.g.
+//! `x86_pkg_temp` or `acpitz`).  The temperature in degrees Celsius is checked against
+//! [`Args::thresholds`], see [`threshold`](`crate::segment::threshold`).  Nothing is shown outside
+//! of Linux.
+
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::TextStyle;
+use crate::platform;
+use crate::segment::threshold::{ThresholdColors, ThresholdDisplay};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Thermal {}
+
+/// Arguments for the [`Thermal`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Type of the sensor to show, the hottest one is shown if this isn't set
+    pub zone: Option<String>,
+
+    /// When to show the segment and when to change colors, in degrees Celsius
+    pub thresholds: ThresholdDisplay,
+}
+
+/// Theme for the [`Thermal`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Colors for each threshold
+    pub colors: ThresholdColors,
+
+    /// Icon to display before the temperature
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            zone: None,
+            thresholds: ThresholdDisplay {
+                show_above: None,
+                warn_at: Some(80.0),
+                crit_at: Some(95.0),
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            colors: Default::default(),
+
+            // 🌡 – thermometer
+            symbol: "\u{1f321}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Thermal {
+    /// Picks the sensor named `zone` out of `zones`, or the hottest one if no zone was given
+    pub fn pick<'a>(zones: &'a [(String, f64)], zone: Option<&str>) -> Option<&'a (String, f64)> {
+        match zone {
+            Some(zone) => zones.iter().find(|(kind, _)| kind == zone),
+            None => zones.iter().max_by(|(_, a), (_, b)| a.total_cmp(b)),
+        }
+    }
+}
+
+impl ToSegment for Thermal {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Thermal"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.thermal;
+
+        let zones = platform::temperatures(Path::new(platform::THERMAL_DIR));
+
+        let (zone, celsius) = match Self::pick(&zones, args.zone.as_deref()) {
+            Some((zone, celsius)) => (zone, *celsius),
+            None => return Ok(vec![]),
+        };
+
+        if !args.thresholds.visible(celsius) {
+            return Ok(vec![]);
+        }
+
+        let (fg, bg) = theme.colors.colors(args.thresholds.level(celsius));
+
+        Ok(vec![Segment::new(
+            "Thermal",
+            fg,
+            bg,
+            format!("{} {:.0}\u{b0}C", theme.symbol, celsius),
+        )
+        .with_style(theme.style.iter().copied())
+        .with_metadata(json!({ "zone": zone, "celsius": celsius }))])
+    }
+}
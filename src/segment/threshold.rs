@@ -0,0 +1,99 @@
+//! There are no segments here, just the knobs shared by segments that show a number which gets
+//! worse as it goes up, like the system load, memory or disk in use, or the temperature.
+//!
+//! Each of those segments takes a [`ThresholdDisplay`] in its arguments to decide whether to show
+//! up at all and how alarmed to look, and a [`ThresholdColors`] in its theme for the colors:
+//! ```json
+//! { "name": "load", "args": { "thresholds": { "show_above": 1.0, "warn_at": 4.0, "crit_at": 8.0 } } }
+//! ```
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
+use crate::ansi::Color;
+use promptr_macros::SerializeNonDefault;
+
+/// When a value is worth showing and when it's worth worrying about
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdDisplay {
+    /// Hide the segment unless the value is above this
+    pub show_above: Option<f64>,
+
+    /// Switch to the warning colors at or above this value
+    pub warn_at: Option<f64>,
+
+    /// Switch to the critical colors at or above this value
+    pub crit_at: Option<f64>,
+}
+
+/// How alarming a value is, from least to most
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Normal,
+    Warn,
+    Crit,
+}
+
+/// Colors for each [`Level`]
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdColors {
+    /// Foreground color below the warning threshold
+    pub normal_fg: Color,
+    /// Background color below the warning threshold
+    pub normal_bg: Color,
+
+    /// Foreground color at or above the warning threshold
+    pub warn_fg: Color,
+    /// Background color at or above the warning threshold
+    pub warn_bg: Color,
+
+    /// Foreground color at or above the critical threshold
+    pub crit_fg: Color,
+    /// Background color at or above the critical threshold
+    pub crit_bg: Color,
+}
+
+impl Default for ThresholdColors {
+    fn default() -> Self {
+        Self {
+            normal_fg: Color::Numbered(250),
+            normal_bg: Color::Numbered(238),
+
+            warn_fg: Color::Numbered(0),
+            warn_bg: Color::Numbered(208),
+
+            crit_fg: Color::Numbered(15),
+            crit_bg: Color::Numbered(160),
+        }
+    }
+}
+
+impl ThresholdDisplay {
+    /// Returns true if `value` clears [`ThresholdDisplay::show_above`]
+    pub fn visible(&self, value: f64) -> bool {
+        self.show_above.is_none_or(|show_above| value > show_above)
+    }
+
+    /// Returns how alarming `value` is
+    pub fn level(&self, value: f64) -> Level {
+        let reached = |threshold: Option<f64>| threshold.is_some_and(|at| value >= at);
+
+        match (reached(self.crit_at), reached(self.warn_at)) {
+            (true, _) => Level::Crit,
+            (false, true) => Level::Warn,
+            (false, false) => Level::Normal,
+        }
+    }
+}
+
+impl ThresholdColors {
+    /// Returns the foreground and background colors for `level`
+    pub fn colors(&self, level: Level) -> (Color, Color) {
+        match level {
+            Level::Normal => (self.normal_fg, self.normal_bg),
+            Level::Warn => (self.warn_fg, self.warn_bg),
+            Level::Crit => (self.crit_fg, self.crit_bg),
+        }
+    }
+}
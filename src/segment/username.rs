@@ -3,15 +3,16 @@
 use anyhow::anyhow;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
-use crate::ansi::Color;
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
 use crate::segment::{Segment, ToSegment};
-use crate::{ApplicationState, Separator};
+use crate::ApplicationState;
 use promptr_macros::SerializeNonDefault;
 
 pub struct Username {}
 
 /// The format in which we would like sudo shells to be represented
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SudoIndicator {
     /// `≈ effective_user`
@@ -22,7 +23,7 @@ pub enum SudoIndicator {
     None,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct Args {
     pub sudo_indicator: SudoIndicator,
@@ -39,6 +40,9 @@ pub struct Theme {
 
     /// Separator between the user and effective user if `Args.sudo_indicator` is set to `Username`
     pub sudo_separator: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
 }
 
 impl Default for Args {
@@ -59,6 +63,20 @@ impl Default for Theme {
             sudo_indicator: "\u{2248}".into(),
             // user → effective user
             sudo_separator: "\u{2192}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        if glyphs == GlyphSet::Ascii {
+            swap_default(&mut self.sudo_indicator, &default.sudo_indicator, "~");
+            swap_default(&mut self.sudo_separator, &default.sudo_separator, "->");
         }
     }
 }
@@ -98,12 +116,7 @@ impl ToSegment for Username {
             },
         };
 
-        Ok(vec![Segment {
-            fg: theme.fg,
-            bg: theme.bg,
-            separator: Separator::Thick,
-            text,
-            source: "Username",
-        }])
+        Ok(vec![Segment::new("Username", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())])
     }
 }
@@ -0,0 +1,17 @@
+//! There are no segments here, just helpers for poking around the filesystem that more than one
+//! segment needs.
+
+use std::fs::metadata;
+use std::path::{Path, PathBuf};
+
+/// Looks for `target` in `pwd` and each of its ancestors, like git does for `.git`.  A copy of
+/// `target` in any of the `skip` directories doesn't count, but the search carries on above them.
+///
+/// This is loosely based on `rvm`'s `scripts/functions/rvmrc_project`.
+pub fn find_ancestor(target: &str, pwd: &str, skip: &[&Path]) -> Option<PathBuf> {
+    Path::new(pwd)
+        .ancestors()
+        .filter(|dir| !skip.contains(dir))
+        .map(|dir| dir.join(target))
+        .find(|file| metadata(file).is_ok())
+}
@@ -1,10 +1,68 @@
-//! There are no segments here, just theme related structs.
+//! The `Vcs` segment shows whichever version control segment suits the current directory
+//!
+//! Starting at `$PWD` each directory up to the root is checked for the markers in [`MARKERS`],
+//! the first one found says which segment is drawn.  `$GIT_DIR` means git regardless.  Arguments
+//! for the segments it hands off to go under their names, so one stanza covers them all:
+//! ```json
+//! {
+//!     "name": "vcs",
+//!     "args": { "git": { "show_tag": "describe" }, "sapling": { "show_status": false } }
+//! }
+//! ```
+//!
+//! Mercurial, Subversion, Jujutsu, and Fossil are recognized but have no segment yet, so they
+//! draw nothing rather than falling through to a repository further up.  The theme shared by the
+//! version control segments lives here too.
 
-use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
-use crate::ansi::Color;
+use serde::{ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
+use crate::registry::{self, Lookup};
+use crate::segment::threshold::ThresholdColors;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
 use promptr_macros::SerializeNonDefault;
 
+pub struct Vcs {}
+
+/// Arguments for the [`Vcs`] segment, each is passed along as is to that segment
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Arguments for the [`git`](`crate::segment::git`) segment
+    pub git: Option<serde_json::Value>,
+
+    /// Arguments for the [`sapling`](`crate::segment::sapling`) segment
+    pub sapling: Option<serde_json::Value>,
+}
+
+/// Version control systems the [`Vcs`] segment knows how to recognize
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Git,
+    Mercurial,
+    Subversion,
+    Jujutsu,
+    Fossil,
+    Sapling,
+}
+
+/// Files or directories that mark the top of a working copy, checked in this order in each
+/// directory.  Jujutsu and Sapling come before git as they can share a directory with a `.git`.
+pub const MARKERS: &[(&str, Backend)] = &[
+    (".jj", Backend::Jujutsu),
+    (".sl", Backend::Sapling),
+    (".git", Backend::Git),
+    (".hg", Backend::Mercurial),
+    (".svn", Backend::Subversion),
+    (".fslckout", Backend::Fossil),
+    ("_FOSSIL_", Backend::Fossil),
+];
+
 #[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
 #[serde(default, deny_unknown_fields)]
 pub struct Symbols {
@@ -18,43 +76,150 @@ pub struct Symbols {
     pub stash: String,
 
     pub git: String,
+    /// Badges used instead of [`Self::git`] when `origin` is on one of these hosts.  Entries in
+    /// the configuration are added to the defaults, an empty badge turns one off.
+    #[serde(deserialize_with = "merge_remotes")]
+    pub remotes: BTreeMap<String, String>,
     pub cherry_pick: String,
+    /// Marks counts reused from an earlier prompt
+    pub cached: String,
+    /// Shown before the nearest tag
+    pub tag: String,
+    /// Shown when only part of the repository is checked out
+    pub sparse: String,
+    /// Shown before the email commits will be made with
+    pub email: String,
+    /// Used instead of [`Self::git`] by the Sapling segment
+    pub sapling: String,
 }
 
+/// Colors for the single segment of counts drawn when the git segment's `compact_counts` is set
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Compact {
+    pub fg: Color,
+    pub bg: Color,
+
+    pub staged: Color,
+    pub changed: Color,
+    pub untracked: Color,
+    pub stashed: Color,
+}
+
+/// Theme shared by the version control segments.  The clean and dirty colors and the symbols
+/// are common to them all, each backend has its own section for the rest.
+///
+/// Before git had its own section its colors were all in here with a `git_` prefix (e.g.
+/// `git_ahead_fg`), those are still read into [`Self::git`], see [`deserialize_theme`].
 #[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
 #[serde(default, deny_unknown_fields)]
 pub struct Theme {
-    pub git_ahead_fg: Color,
-    pub git_ahead_bg: Color,
+    pub repo_clean_fg: Color,
+    pub repo_clean_bg: Color,
+
+    pub repo_dirty_fg: Color,
+    pub repo_dirty_bg: Color,
 
-    pub git_behind_fg: Color,
-    pub git_behind_bg: Color,
+    /// Text attributes for a clean working copy, e.g. `["italic"]`
+    pub repo_clean_style: Vec<TextStyle>,
 
-    pub git_staged_fg: Color,
-    pub git_staged_bg: Color,
+    /// Text attributes for a dirty working copy, e.g. `["bold"]` to make it stand out
+    pub repo_dirty_style: Vec<TextStyle>,
 
-    pub git_changed_fg: Color,
-    pub git_changed_bg: Color,
+    pub symbols: Symbols,
 
-    pub git_untracked_fg: Color,
-    pub git_untracked_bg: Color,
+    /// Theme for the [`git`](`crate::segment::git`) segment
+    pub git: GitTheme,
 
-    pub git_conflict_fg: Color,
-    pub git_conflict_bg: Color,
+    /// Theme for the [`sapling`](`crate::segment::sapling`) segment
+    pub sapling: BackendTheme,
+}
 
-    pub git_in_progress_fg: Color,
-    pub git_in_progress_bg: Color,
+/// Clean and dirty colors for one backend, any that aren't set are taken from [`Theme`]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendTheme {
+    pub clean_fg: Option<Color>,
+    pub clean_bg: Option<Color>,
 
-    pub git_stashed_fg: Color,
-    pub git_stashed_bg: Color,
+    pub dirty_fg: Option<Color>,
+    pub dirty_bg: Option<Color>,
+}
 
-    pub repo_clean_fg: Color,
-    pub repo_clean_bg: Color,
+/// Theme for the [`git`](`crate::segment::git`) segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitTheme {
+    /// Colors for the branch when there's nothing to commit, [`Theme::repo_clean_fg`] and
+    /// [`Theme::repo_clean_bg`] if not set
+    pub clean_fg: Option<Color>,
+    pub clean_bg: Option<Color>,
 
-    pub repo_dirty_fg: Color,
-    pub repo_dirty_bg: Color,
+    /// Colors for the branch when there is, [`Theme::repo_dirty_fg`] and
+    /// [`Theme::repo_dirty_bg`] if not set
+    pub dirty_fg: Option<Color>,
+    pub dirty_bg: Option<Color>,
 
-    pub symbols: Symbols,
+    pub ahead_fg: Color,
+    pub ahead_bg: Color,
+
+    pub behind_fg: Color,
+    pub behind_bg: Color,
+
+    /// Colors for how far ahead and behind another ref, see the git segment's `compare_ref`
+    pub compare_fg: Color,
+    pub compare_bg: Color,
+
+    pub staged_fg: Color,
+    pub staged_bg: Color,
+
+    pub changed_fg: Color,
+    pub changed_bg: Color,
+
+    pub untracked_fg: Color,
+    pub untracked_bg: Color,
+
+    pub conflict_fg: Color,
+    pub conflict_bg: Color,
+
+    pub in_progress_fg: Color,
+    pub in_progress_bg: Color,
+
+    pub stashed_fg: Color,
+    pub stashed_bg: Color,
+
+    /// Colors for the badge shown when the counts came from the cache
+    pub cached_fg: Color,
+    pub cached_bg: Color,
+
+    /// Colors for the nearest tag, see the git segment's `show_tag`
+    pub tag_fg: Color,
+    pub tag_bg: Color,
+
+    /// Colors for the upstream branch, see the git segment's `show_upstream`
+    pub upstream_fg: Color,
+    pub upstream_bg: Color,
+
+    /// Colors for an upstream branch that's been deleted
+    pub upstream_gone_fg: Color,
+    pub upstream_gone_bg: Color,
+
+    /// Colors for the sparse checkout badge
+    pub sparse_fg: Color,
+    pub sparse_bg: Color,
+
+    /// Colors for the email commits will be made with
+    pub email_fg: Color,
+    pub email_bg: Color,
+
+    /// Colors for an email that isn't the one expected, see the git segment's `expected_emails`
+    pub email_mismatch_fg: Color,
+    pub email_mismatch_bg: Color,
+
+    /// Colors for the time since the last commit, see the git segment's `commit_age_thresholds`
+    pub commit_age: ThresholdColors,
+
+    pub compact: Compact,
 }
 
 impl Default for Symbols {
@@ -79,46 +244,360 @@ impl Default for Symbols {
 
             //  – ?
             git: "\u{e0a0}".into(),
+            remotes: [
+                //  – nf-fa-bitbucket
+                ("bitbucket.org", "\u{f171}"),
+                //  – nf-fa-github
+                ("github.com", "\u{f09b}"),
+                //  – nf-fa-gitlab
+                ("gitlab.com", "\u{f296}"),
+            ]
+            .iter()
+            .map(|(host, badge)| (host.to_string(), badge.to_string()))
+            .collect(),
             // 🍒 - duh
             cherry_pick: "\u{1f352}".into(),
+            // ⌛ – hourglass
+            cached: "\u{231b}".into(),
+            // 🏷 – label
+            tag: "\u{1f3f7}".into(),
+            // ◌ – dotted circle
+            sparse: "\u{25cc}".into(),
+            // ✉ – envelope
+            email: "\u{2709}".into(),
+            // 🌱 – seedling
+            sapling: "\u{1f331}".into(),
         }
     }
 }
 
-impl Default for Theme {
-    fn default() -> Self {
-        Self {
-            git_ahead_fg: Color::Numbered(250),
-            git_ahead_bg: Color::Numbered(240),
+impl Symbols {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        match glyphs {
+            GlyphSet::Auto | GlyphSet::NerdFont => return,
+            // ⎇ – alternative key symbol, the usual stand-in for a branch
+            GlyphSet::Emoji => swap_default(&mut self.git, &default.git, "\u{2387}"),
+            GlyphSet::Ascii => {
+                swap_default(&mut self.git, &default.git, "git");
+                swap_default(&mut self.ahead, &default.ahead, "^");
+                swap_default(&mut self.behind, &default.behind, "v");
+                swap_default(&mut self.changed, &default.changed, "*");
+                swap_default(&mut self.conflicted, &default.conflicted, "!");
+                swap_default(&mut self.detached, &default.detached, "@");
+                swap_default(&mut self.staged, &default.staged, "+");
+                // Not `$`, bash would take `$3` for a positional parameter
+                swap_default(&mut self.stash, &default.stash, "s");
+                swap_default(&mut self.cherry_pick, &default.cherry_pick, "pick");
+                swap_default(&mut self.cached, &default.cached, "~");
+                swap_default(&mut self.tag, &default.tag, "tag:");
+                swap_default(&mut self.sparse, &default.sparse, "sparse");
+                swap_default(&mut self.email, &default.email, "@");
+                swap_default(&mut self.sapling, &default.sapling, "sl");
+            }
+        }
+
+        // Only the Nerd Fonts have logos, the hosts that haven't been changed get the plain badge
+        for (host, badge) in &default.remotes {
+            if self.remotes.get(host) == Some(badge) {
+                self.remotes.remove(host);
+            }
+        }
+    }
+
+    /// Returns the badge for a repository whose `origin` is at `url`
+    pub fn badge(&self, url: Option<&str>) -> &str {
+        match url
+            .and_then(remote_host)
+            .and_then(|host| self.remotes.get(host))
+        {
+            Some(badge) if !badge.is_empty() => badge,
+            _ => &self.git,
+        }
+    }
+}
+
+/// Returns the host in a remote URL, e.g. `github.com` for `https://github.com/a/b.git`,
+/// `ssh://git@github.com:22/a/b.git`, or the scp-like `git@github.com:a/b.git`
+pub fn remote_host(url: &str) -> Option<&str> {
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.split('/').next()?,
+        // scp-like syntax needs the colon before any slash, otherwise it's a local path
+        None => match url.split_once(':') {
+            Some((host, _)) if !host.contains('/') => host,
+            _ => return None,
+        },
+    };
+
+    let host = rest.rsplit_once('@').map_or(rest, |(_, host)| host);
+    let host = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next()?,
+        None => host.split(':').next()?,
+    };
+
+    match host.is_empty() {
+        true => None,
+        false => Some(host),
+    }
+}
+
+/// Entries in the configuration are added to the default table rather than replacing it
+fn merge_remotes<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut remotes = Symbols::default().remotes;
+    remotes.extend(BTreeMap::<String, String>::deserialize(deserializer)?);
+    Ok(remotes)
+}
+
+impl Backend {
+    /// Name of the segment that draws this backend
+    pub fn segment(&self) -> &'static str {
+        match self {
+            Self::Git => "git",
+            Self::Mercurial => "hg",
+            Self::Subversion => "svn",
+            Self::Jujutsu => "jj",
+            Self::Fossil => "fossil",
+            Self::Sapling => "sapling",
+        }
+    }
+}
 
-            git_behind_fg: Color::Numbered(250),
-            git_behind_bg: Color::Numbered(240),
+impl Vcs {
+    /// Returns the version control system that owns `$PWD`
+    pub fn detect(env: &HashMap<String, String>) -> Option<Backend> {
+        if env.contains_key("GIT_DIR") {
+            return Some(Backend::Git);
+        }
 
-            git_staged_fg: Color::Numbered(15),
-            git_staged_bg: Color::Numbered(22),
+        Path::new(env.get("PWD")?).ancestors().find_map(|dir| {
+            MARKERS
+                .iter()
+                .find(|(marker, _)| dir.join(marker).exists())
+                .map(|(_, backend)| *backend)
+        })
+    }
+}
 
-            git_changed_fg: Color::Numbered(15),
-            git_changed_bg: Color::Numbered(130),
+impl ToSegment for Vcs {
+    type Args = Args;
+    type Theme = Theme;
 
-            git_untracked_fg: Color::Numbered(15),
-            git_untracked_bg: Color::Numbered(52),
+    fn error_context() -> &'static str {
+        "segment::Vcs"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
 
-            git_conflict_fg: Color::Numbered(15),
-            git_conflict_bg: Color::Numbered(9),
+        let backend = match Self::detect(&state.env) {
+            Some(backend) => backend,
+            None => return Ok(vec![]),
+        };
 
-            git_in_progress_fg: Color::Numbered(15),
-            git_in_progress_bg: Color::Numbered(208),
+        let backend_args = match backend {
+            Backend::Git => args.git,
+            Backend::Sapling => args.sapling,
+            _ => None,
+        };
 
-            git_stashed_fg: Color::Numbered(0),
-            git_stashed_bg: Color::Numbered(221),
+        // Not every backend has a segment, or it might have been compiled out
+        match registry::lookup(backend.segment()) {
+            Lookup::Found(render) => render(backend_args, state),
+            Lookup::Disabled(_) | Lookup::Unknown => Ok(vec![]),
+        }
+    }
+}
+
+impl Default for Compact {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(250),
+            bg: Color::Numbered(236),
+
+            staged: Color::Numbered(76),
+            changed: Color::Numbered(214),
+            untracked: Color::Numbered(203),
+            stashed: Color::Numbered(221),
+        }
+    }
+}
 
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
             repo_clean_fg: Color::Numbered(0),
             repo_clean_bg: Color::Numbered(148),
 
             repo_dirty_fg: Color::Numbered(15),
             repo_dirty_bg: Color::Numbered(161),
 
+            repo_clean_style: vec![],
+            repo_dirty_style: vec![],
+
             symbols: Symbols::default(),
+
+            git: GitTheme::default(),
+
+            sapling: BackendTheme::default(),
+        }
+    }
+}
+
+impl Default for GitTheme {
+    fn default() -> Self {
+        Self {
+            clean_fg: None,
+            clean_bg: None,
+
+            dirty_fg: None,
+            dirty_bg: None,
+
+            ahead_fg: Color::Numbered(250),
+            ahead_bg: Color::Numbered(240),
+
+            behind_fg: Color::Numbered(250),
+            behind_bg: Color::Numbered(240),
+
+            compare_fg: Color::Numbered(250),
+            compare_bg: Color::Numbered(238),
+
+            staged_fg: Color::Numbered(15),
+            staged_bg: Color::Numbered(22),
+
+            changed_fg: Color::Numbered(15),
+            changed_bg: Color::Numbered(130),
+
+            untracked_fg: Color::Numbered(15),
+            untracked_bg: Color::Numbered(52),
+
+            conflict_fg: Color::Numbered(15),
+            conflict_bg: Color::Numbered(9),
+
+            in_progress_fg: Color::Numbered(15),
+            in_progress_bg: Color::Numbered(208),
+
+            stashed_fg: Color::Numbered(0),
+            stashed_bg: Color::Numbered(221),
+
+            cached_fg: Color::Numbered(250),
+            cached_bg: Color::Numbered(238),
+
+            tag_fg: Color::Numbered(250),
+            tag_bg: Color::Numbered(238),
+
+            upstream_fg: Color::Numbered(250),
+            upstream_bg: Color::Numbered(240),
+
+            upstream_gone_fg: Color::Numbered(15),
+            upstream_gone_bg: Color::Numbered(124),
+
+            sparse_fg: Color::Numbered(250),
+            sparse_bg: Color::Numbered(238),
+
+            email_fg: Color::Numbered(250),
+            email_bg: Color::Numbered(238),
+
+            email_mismatch_fg: Color::Numbered(15),
+            email_mismatch_bg: Color::Numbered(160),
+
+            commit_age: ThresholdColors::default(),
+
+            compact: Compact::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// Returns the foreground and background for a clean or dirty working copy, `own` being the
+    /// backend's colors for each (foreground, background) where it has any
+    fn repo_colors(&self, dirty: bool, own: [Option<Color>; 4]) -> (Color, Color) {
+        let [clean_fg, clean_bg, dirty_fg, dirty_bg] = own;
+
+        match dirty {
+            false => (
+                clean_fg.unwrap_or(self.repo_clean_fg),
+                clean_bg.unwrap_or(self.repo_clean_bg),
+            ),
+            true => (
+                dirty_fg.unwrap_or(self.repo_dirty_fg),
+                dirty_bg.unwrap_or(self.repo_dirty_bg),
+            ),
+        }
+    }
+
+    /// Text attributes for a clean or dirty working copy, shared by every backend
+    pub fn repo_style(&self, dirty: bool) -> &[TextStyle] {
+        match dirty {
+            false => &self.repo_clean_style,
+            true => &self.repo_dirty_style,
         }
     }
+
+    /// Colors for a clean or dirty git working copy
+    pub fn git_colors(&self, dirty: bool) -> (Color, Color) {
+        let git = &self.git;
+        self.repo_colors(
+            dirty,
+            [git.clean_fg, git.clean_bg, git.dirty_fg, git.dirty_bg],
+        )
+    }
+
+    /// Colors for a clean or dirty working copy of a backend without a theme of its own beyond
+    /// `backend`
+    pub fn backend_colors(&self, backend: &BackendTheme, dirty: bool) -> (Color, Color) {
+        self.repo_colors(
+            dirty,
+            [
+                backend.clean_fg,
+                backend.clean_bg,
+                backend.dirty_fg,
+                backend.dirty_bg,
+            ],
+        )
+    }
+}
+
+/// Reads a [`Theme`], moving any git colors from before they had their own section (e.g.
+/// `git_ahead_fg`, `compact`) into [`Theme::git`].  Where both are set the new one wins.
+pub fn deserialize_theme<'de, D>(deserializer: D) -> Result<Theme, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut raw = serde_json::Map::<String, serde_json::Value>::deserialize(deserializer)?;
+
+    let legacy = raw
+        .keys()
+        .filter(|key| key.starts_with("git_") || matches!(key.as_str(), "compact" | "commit_age"))
+        .cloned()
+        .collect::<Vec<_>>();
+    let legacy = legacy
+        .into_iter()
+        .filter_map(|key| {
+            let value = raw.remove(&key)?;
+            Some((key.strip_prefix("git_").unwrap_or(&key).to_string(), value))
+        })
+        .collect::<Vec<_>>();
+
+    if !legacy.is_empty() {
+        let git = raw
+            .entry("git")
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+
+        // Anything but an object is left for deserializing to complain about
+        if let Some(git) = git.as_object_mut() {
+            for (key, value) in legacy {
+                git.entry(key).or_insert(value);
+            }
+        }
+    }
+
+    Theme::deserialize(serde_json::Value::Object(raw)).map_err(serde::de::Error::custom)
 }
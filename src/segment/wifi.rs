@@ -0,0 +1,161 @@
+//! The `Wifi` segment shows the name of the Wi-Fi network we're connected to
+//!
+//! On Linux the kernel is asked directly, or `iw` if it was built without the wireless
+//! extensions.  macOS and the BSDs go through the C++ bridge so they need the `ffi` feature too.
+//! Recent versions of macOS only tell programs that have been given location access, otherwise
+//! the segment stays hidden.  This is gated by the `segment-wifi` feature.
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Wifi {}
+
+/// Arguments for the [`Wifi`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Only look at this interface (e.g. `wlan0`), otherwise the first one that's connected
+    pub interface: Option<String>,
+
+    /// Show the name of the network after the symbol
+    pub show_ssid: bool,
+}
+
+/// Theme for the [`Wifi`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the network name
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            interface: None,
+            show_ssid: true,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(25),
+
+            //  – nf-fa-wifi
+            symbol: "\u{f1eb}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        match glyphs {
+            GlyphSet::Auto | GlyphSet::NerdFont => {}
+            // 📶 – antenna with bars
+            GlyphSet::Emoji => swap_default(&mut self.symbol, &default.symbol, "\u{1f4f6}"),
+            GlyphSet::Ascii => swap_default(&mut self.symbol, &default.symbol, "wifi"),
+        }
+    }
+}
+
+impl Wifi {
+    /// Returns the SSID of the network `interface` is connected to, an empty `interface` means
+    /// whichever one is connected
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "linux"
+    ))]
+    pub fn ssid(interface: &str) -> Option<String> {
+        let ssid = crate::ffi::get_wifi_ssid(interface);
+
+        match ssid.is_empty() {
+            true => None,
+            false => Some(ssid.to_string()),
+        }
+    }
+
+    /// No way to ask about wireless networks on this platform
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "illumos",
+        target_os = "solaris",
+        target_os = "linux"
+    )))]
+    pub fn ssid(_interface: &str) -> Option<String> {
+        None
+    }
+
+    /// Returns the segment for a connection to `ssid`.  Anyone nearby can pick the name of an
+    /// access point so it's escaped like any other text we didn't write.
+    pub fn segment(args: &Args, ssid: &str, state: &ApplicationState) -> Segment {
+        let theme = &state.theme.wifi;
+
+        let text = match args.show_ssid {
+            true => format!(
+                "{} {}",
+                theme.symbol,
+                state.escape_style.escape_literal(ssid)
+            ),
+            false => theme.symbol.clone(),
+        };
+
+        Segment::new("Wifi", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({
+                "ssid": ssid,
+                "interface": args.interface,
+            }))
+    }
+}
+
+impl ToSegment for Wifi {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Wifi"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        match Self::ssid(args.interface.as_deref().unwrap_or_default()) {
+            Some(ssid) => Ok(vec![Self::segment(&args, &ssid, state)]),
+            None => Ok(vec![]),
+        }
+    }
+}
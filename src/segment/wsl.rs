@@ -0,0 +1,134 @@
+//! The `Wsl` segment shows which distribution is running when the shell is inside the Windows
+//! Subsystem for Linux
+//!
+//! WSL sets `WSL_DISTRO_NAME` for everything it starts.  Shells that lost their environment
+//! along the way (e.g. `sudo` or `su -`) are still caught by the kernel version in
+//! [`platform::PROC_VERSION`], but then there's no name to show, only the symbol.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+use serde_json::json;
+
+use crate::ansi::{Color, TextStyle};
+use crate::glyphs::{swap_default, GlyphSet};
+use crate::platform;
+use crate::segment::{Segment, ToSegment};
+use crate::ApplicationState;
+use promptr_macros::SerializeNonDefault;
+
+pub struct Wsl {}
+
+/// Arguments for the [`Wsl`] segment
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Args {
+    /// Show the name of the distribution after the symbol
+    pub show_distro: bool,
+}
+
+/// Theme for the [`Wsl`] segment
+#[derive(Clone, Debug, Deserialize, PartialEq, SerializeNonDefault)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Foreground color
+    pub fg: Color,
+
+    /// Background color
+    pub bg: Color,
+
+    /// Icon to display before the distribution
+    pub symbol: String,
+
+    /// Text attributes the segment is drawn with, e.g. `["bold"]`
+    pub style: Vec<TextStyle>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self { show_distro: true }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            fg: Color::Numbered(15),
+            bg: Color::Numbered(32),
+
+            //  – nf-fa-windows
+            symbol: "\u{f17a}".into(),
+
+            style: vec![],
+        }
+    }
+}
+
+impl Theme {
+    /// Swaps any default symbols for their equivalents from `glyphs`
+    pub fn apply_glyph_set(&mut self, glyphs: GlyphSet) {
+        let default = Self::default();
+
+        match glyphs {
+            GlyphSet::Auto | GlyphSet::NerdFont => {}
+            // 🪟 – window
+            GlyphSet::Emoji => swap_default(&mut self.symbol, &default.symbol, "\u{1fa9f}"),
+            GlyphSet::Ascii => swap_default(&mut self.symbol, &default.symbol, "WSL"),
+        }
+    }
+}
+
+impl Wsl {
+    /// Returns the name of the distribution if we're running under WSL, or an empty string if we
+    /// are but there's no telling which one.  `version` is read in the format of
+    /// [`platform::PROC_VERSION`].
+    pub fn detect(env: &HashMap<String, String>, version: &Path) -> Option<String> {
+        if let Some(distro) = env.get("WSL_DISTRO_NAME").filter(|name| !name.is_empty()) {
+            return Some(distro.clone());
+        }
+
+        // WSL 1 says Microsoft, WSL 2 kernels are named like 5.15.90.1-microsoft-standard-WSL2
+        match fs::read_to_string(version) {
+            Ok(version) if version.to_lowercase().contains("microsoft") => Some(String::new()),
+            _ => None,
+        }
+    }
+}
+
+impl ToSegment for Wsl {
+    type Args = Args;
+    type Theme = Theme;
+
+    fn error_context() -> &'static str {
+        "segment::Wsl"
+    }
+
+    fn to_segment(
+        args: Option<Self::Args>,
+        state: &ApplicationState,
+    ) -> crate::Result<Vec<Segment>> {
+        let args = args.unwrap_or_default();
+
+        let theme = &state.theme.wsl;
+
+        let distro = match Self::detect(&state.env, Path::new(platform::PROC_VERSION)) {
+            Some(distro) => distro,
+            None => return Ok(vec![]),
+        };
+
+        let text = match args.show_distro && !distro.is_empty() {
+            true => format!(
+                "{} {}",
+                theme.symbol,
+                state.escape_style.escape_literal(&distro)
+            ),
+            false => theme.symbol.clone(),
+        };
+
+        Ok(vec![Segment::new("Wsl", theme.fg, theme.bg, text)
+            .with_style(theme.style.iter().copied())
+            .with_metadata(json!({ "distro": distro }))])
+    }
+}
@@ -1,17 +1,43 @@
 //! Command shell identification and initialization.
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::env;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use indoc::indoc;
 
+use crate::ansi::EscapeStyle;
+use crate::platform;
+
+/// Exit status of the last command, saved at the very start of `PROMPT_COMMAND` before anything
+/// else in there has a chance to clobber `$?`.  Preferred over `code`, see [`last_exit_code`].
+pub const LAST_EXIT_VAR: &str = "PROMPTR_LAST_EXIT";
+
+/// Holds the process ID of `promptr` while it renders a prompt, see [`active_marker`].  Anything
+/// it runs (hooks, segment commands) inherits it, so a shell started from there that loads
+/// `promptr` again gets a bare prompt instead of nesting another full render, see
+/// [`nested_render`].
+pub const ACTIVE_VAR: &str = "PROMPTR_ACTIVE";
+
+/// Comment that marks the line `promptr install-hook` adds to a shell startup file, the hook line
+/// itself follows right after it
+pub const HOOK_MARKER: &str =
+    "# Added by `promptr install-hook`, remove with `promptr install-hook --remove`";
+
 /// Initialization and identification of the command shell that's running promptr.
 ///
 /// TODO: Add support for other common shells
 #[derive(Copy, Clone, Debug)]
 pub enum Shell {
     Bash,
+    /// PowerShell, both Windows PowerShell and `pwsh` on Unix
+    PowerShell,
+    /// tcsh and csh
+    Tcsh,
+    /// Strictly POSIX shells, e.g. `dash`, `ash`, or `busybox sh`
+    PosixSh,
 }
 
 impl Shell {
@@ -20,18 +46,46 @@ impl Shell {
     /// Note: `wc` is not a builtin so we're probably better off splitting the string rust-side.
     ///
     /// Note: `dirs -p` prints each item on the stack on a separate line, sidestepping the paths with spaces issue
-    const CAPTURE_VARS: &'static str =
-        r#"uid="${UID}" hostname=${HOSTNAME} code=${?} dirs=$(dirs -p) jobs=$(jobs -p | wc -l)"#;
+    ///
+    /// Note: `$?` is saved by the hook before anything else runs, see [`Self::generate_loader`].
+    const CAPTURE_VARS: &'static str = concat!(
+        r#"uid="${UID}" hostname=${HOSTNAME} code=${promptr_code} dirs=$(dirs -p) jobs=$(jobs -p | wc -l) "#,
+        r#"cpu_user=${promptr_cpu_user} cpu_sys=${promptr_cpu_sys} duration_us=${promptr_duration} "#,
+        r#"cpu_user_prev=${promptr_cpu_user_prev} cpu_sys_prev=${promptr_cpu_sys_prev} "#,
+        r#"codes="${promptr_codes[*]}" COLUMNS=${COLUMNS} PROMPT_DIRTRIM=${PROMPT_DIRTRIM}"#,
+    );
+
+    /// Number of exit codes the shell hook remembers, passed along as `codes`
+    pub const EXIT_CODE_HISTORY: usize = 16;
 
     /// Returns an [`anyhow::Result`] with the invoking shell or an error if the shell cannot be identified.
     pub fn get_current_shell() -> Result<Self> {
         let shell: String = env::var("PROMPTR_SHELL")
             .or_else::<anyhow::Error, _>(|_| {
                 // pid_t is u32 in rust but POSIX defies it as a signed integer…
-                #[cfg(any(target_os = "macos", target_os = "freebsd"))]
+                #[cfg(any(
+                    target_os = "macos",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd",
+                    target_os = "illumos",
+                    target_os = "solaris",
+                    target_os = "linux"
+                ))]
                 let shell_via_parent =
                     crate::ffi::get_process_name(std::os::unix::process::parent_id() as i64);
-                #[cfg(not(any(target_os = "macos", target_os = "freebsd")))]
+                #[cfg(windows)]
+                let shell_via_parent = "pwsh".to_string();
+                #[cfg(not(any(
+                    target_os = "macos",
+                    target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd",
+                    target_os = "illumos",
+                    target_os = "solaris",
+                    target_os = "linux",
+                    windows
+                )))]
                 let shell_via_parent = "bash".to_string();
 
                 match shell_via_parent {
@@ -50,6 +104,9 @@ impl Shell {
 
         match shell.as_str() {
             "bash" => Ok(Shell::Bash),
+            "pwsh" | "pwsh.exe" | "powershell" | "powershell.exe" => Ok(Shell::PowerShell),
+            "tcsh" | "csh" => Ok(Shell::Tcsh),
+            "sh" | "dash" | "ash" | "busybox" => Ok(Shell::PosixSh),
             other_shell => Err(anyhow!(
                 "This shell is incompatible with promptr: {}",
                 other_shell
@@ -57,10 +114,84 @@ impl Shell {
         }
     }
 
+    /// Returns the [`EscapeStyle`] the shell's prompt expects
+    pub fn escape_style(&self) -> EscapeStyle {
+        match self {
+            Self::Bash => EscapeStyle::Bash,
+            // PowerShell measures the prompt itself and handles escapes fine
+            Self::PowerShell => EscapeStyle::Raw,
+            Self::Tcsh => EscapeStyle::Zsh,
+            // There's no way to tell a POSIX shell that something doesn't print, hopefully the
+            // line editor copes
+            Self::PosixSh => EscapeStyle::Raw,
+        }
+    }
+
+    /// Returns what the shell needs to see to start a new line in the prompt
+    pub fn newline(&self) -> &'static str {
+        match self {
+            // A literal newline would be fine too, but it's easy to lose in a command substitution
+            Self::Bash | Self::Tcsh => r"\n",
+            Self::PowerShell | Self::PosixSh => "\n",
+        }
+    }
+
+    /// Prepares segment text for the shell.
+    ///
+    /// The default theme uses `bash` prompt escapes in a couple of places (e.g. `\$`) so those
+    /// are translated for other shells.
+    pub fn escape_text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Bash => Cow::Borrowed(text),
+            Self::PowerShell | Self::PosixSh => Cow::Owned(text.replace("\\$", "$")),
+            // % introduces a prompt sequence and ! is the history event number
+            Self::Tcsh => Cow::Owned(
+                text.replace("\\$", "$")
+                    .replace('%', "%%")
+                    .replace('!', "\\!"),
+            ),
+        }
+    }
+
+    /// Returns the line that loads `promptr` from the shell's startup file.  This relies on
+    /// `promptr` being in the `PATH` so it keeps working if the binary is upgraded or moved.
+    pub fn hook_line(&self) -> &'static str {
+        match self {
+            Self::Bash => "source <(promptr load)",
+            Self::PowerShell => "promptr load | Out-String | Invoke-Expression",
+            Self::Tcsh => "eval \"`promptr load`\"",
+            Self::PosixSh => r#"eval "$(promptr load)""#,
+        }
+    }
+
+    /// Returns the startup file interactive instances of the shell read, if there's a good guess.
+    /// Note that on macOS `bash` starts as a login shell and reads `.bash_profile` instead.
+    pub fn rc_file(&self, env: &HashMap<String, String>) -> Option<PathBuf> {
+        let home = env.get("HOME").map(PathBuf::from);
+
+        match self {
+            Self::Bash => home.map(|home| home.join(".bashrc")),
+            Self::Tcsh => home.map(|home| home.join(".tcshrc")),
+            Self::PosixSh => env
+                .get("ENV")
+                .map(PathBuf::from)
+                .or_else(|| home.map(|home| home.join(".shrc"))),
+            // $PROFILE isn't exported
+            Self::PowerShell => None,
+        }
+    }
+
+    /// Prints the shell code that loads `promptr`, see [`Self::loader`]
     pub fn generate_loader(&self, self_exe: &str) {
+        println!("{}", self.loader(self_exe));
+    }
+
+    /// Returns the shell code that hooks `promptr` into the shell, `self_exe` is the path to the
+    /// `promptr` binary
+    pub fn loader(&self, self_exe: &str) -> String {
         match self {
             Self::Bash => {
-                println!(
+                format!(
                     indoc!(
                     r##"
                         if [[ $- == *i* ]]; then
@@ -74,17 +205,230 @@ impl Shell {
                             unset promptr_conf_dir
                             unset promptr_conf_file
 
-                            PROMPT_COMMAND=promptr_prompt
+                            # The loadable builtin from promptr-bash renders without starting a process
+                            if [[ -n ${{PROMPTR_BUILTIN}} ]] && enable -f "${{PROMPTR_BUILTIN}}" promptr_render; then
+                                promptr_builtin=1
+                            fi
+
+                            # `times` has to run in this shell, a command substitution would only see
+                            # the subshell's (empty) children.  The file goes away with the shell, any
+                            # EXIT trap that was already set still runs.
+                            if [[ -z ${{promptr_times_file}} ]]; then
+                                promptr_times_file=$(mktemp "${{XDG_RUNTIME_DIR:-${{TMPDIR:-/tmp}}}}/promptr-times.XXXXXX")
+                                eval "promptr_exit_trap=($(trap -p EXIT))"
+                                trap -- 'rm -f -- "${{promptr_times_file}}"; '"${{promptr_exit_trap[2]}}" EXIT
+                                unset promptr_exit_trap
+                            fi
+
+                            # PS0 is expanded right before a command runs, use it to note the start
+                            # time.  EPOCHREALTIME needs bash 5.  Sourcing this again mustn't add it twice.
+                            if [[ -n ${{EPOCHREALTIME}} && ${{PS0}} != *promptr_cmd_start* ]]; then
+                                PS0+='${{PS1:$((promptr_cmd_start=${{EPOCHREALTIME/./}}, 0)):0}}'
+                            fi
+
+                            # Other PROMPT_COMMAND entries can clobber $? so it's saved before any of
+                            # them get to run.  Newlines keep a trailing ; from making a syntax error.
+                            if [[ ${{PROMPT_COMMAND[*]}} != *promptr_prompt* ]]; then
+                                if [[ $(declare -p PROMPT_COMMAND 2>/dev/null) == "declare -a"* ]]; then
+                                    PROMPT_COMMAND=('PROMPTR_LAST_EXIT=$?' "${{PROMPT_COMMAND[@]}}" promptr_prompt)
+                                else
+                                    PROMPT_COMMAND='PROMPTR_LAST_EXIT=$?'$'\n'"${{PROMPT_COMMAND}}"$'\n'promptr_prompt
+                                fi
+                            fi
                             promptr_prompt() {{
-                                PS1="$({capture_vars} {promptr} prompt)"
+                                local promptr_code=${{PROMPTR_LAST_EXIT:-$?}} promptr_cpu_user promptr_cpu_sys promptr_duration
+                                # A stale value would be worse than none if PROMPT_COMMAND gets replaced
+                                unset PROMPTR_LAST_EXIT
+                                # Without a start time we can't tell an empty line from a command
+                                if [[ -n ${{promptr_cmd_start}} || -z ${{EPOCHREALTIME}} ]]; then
+                                    promptr_codes+=("${{promptr_code}}")
+                                    if (( ${{#promptr_codes[@]}} > {history_len} )); then
+                                        promptr_codes=("${{promptr_codes[@]: -{history_len}}}")
+                                    fi
+                                fi
+                                if [[ -n ${{promptr_cmd_start}} ]]; then
+                                    promptr_duration=$(( ${{EPOCHREALTIME/./}} - promptr_cmd_start ))
+                                fi
+                                if [[ -n ${{promptr_times_file}} ]]; then
+                                    times > "${{promptr_times_file}}"
+                                    {{ read -r _ _; read -r promptr_cpu_user promptr_cpu_sys; }} < "${{promptr_times_file}}"
+                                fi
+                                if [[ -n ${{promptr_builtin}} ]]; then
+                                    {capture_vars} promptr_render -v PS1
+                                else
+                                    PS1="$({capture_vars} {promptr} prompt)"
+                                fi
+                                promptr_cpu_user_prev=${{promptr_cpu_user}}
+                                promptr_cpu_sys_prev=${{promptr_cpu_sys}}
+                                unset promptr_cmd_start
                             }}
                         fi
                     "##
                     ),
                     capture_vars = Self::CAPTURE_VARS,
+                    history_len = Self::EXIT_CODE_HISTORY,
+                    promptr = self_exe,
+                )
+            }
+            Self::PowerShell => {
+                format!(
+                    indoc!(
+                    r##"
+                        [Console]::OutputEncoding = [System.Text.Encoding]::UTF8
+
+                        $promptr_conf_file = Join-Path (& '{promptr}' location) "promptr.json"
+                        if (-not (Test-Path $promptr_conf_file)) {{
+                            Write-Host "Couldn't find an existing configuration file, using the defaults"
+                        }}
+                        Remove-Variable promptr_conf_file
+
+                        function global:prompt {{
+                            # This has to come first, anything else will clobber it
+                            $promptr_success = $?
+                            $promptr_exit = $global:LASTEXITCODE
+                            $env:code = if ($promptr_success) {{ 0 }} elseif ($promptr_exit) {{ $promptr_exit }} else {{ 1 }}
+
+                            $env:PROMPTR_SHELL = "pwsh"
+                            $env:hostname = [System.Net.Dns]::GetHostName()
+                            $env:PWD = $ExecutionContext.SessionState.Path.CurrentFileSystemLocation.ProviderPath
+                            if (-not $env:HOME) {{ $env:HOME = $HOME }}
+                            if (-not $env:USER) {{ $env:USER = [Environment]::UserName }}
+
+                            $promptr_out = (& '{promptr}' prompt) -join ""
+
+                            Remove-Item Env:code, Env:hostname -ErrorAction SilentlyContinue
+                            $global:LASTEXITCODE = $promptr_exit
+                            $promptr_out
+                        }}
+                    "##
+                    ),
+                    promptr = self_exe.replace('\'', "''"),
+                )
+            }
+            Self::Tcsh => {
+                // This gets run through eval which flattens newlines, so it all needs to live on
+                // one line.
+                format!(
+                    concat!(
+                        r#"if ( $?prompt && ! -f "`{promptr} location`/promptr.json" ) echo "Couldn't find an existing configuration file, using the defaults"; "#,
+                        r#"if ( $?prompt ) alias precmd 'set promptr_code=$status; set prompt="`env code=$promptr_code uid=$uid hostname=$HOST PROMPTR_SHELL=tcsh {promptr} prompt`"'"#,
+                    ),
                     promptr = self_exe,
                 )
             }
+            Self::PosixSh => {
+                // PS1 only gets parameter expansion and command substitution, there's no hook
+                // to run before the prompt is drawn so everything happens inside PS1 itself.
+                format!(
+                    indoc!(
+                    r##"
+                        case $- in
+                            *i*)
+                                if [ ! -f "$('{promptr}' location)/promptr.json" ]; then
+                                    echo "Couldn't find an existing configuration file, using the defaults"
+                                fi
+
+                                PS1='$(code=$? uid=$(id -u) hostname=$(uname -n) PWD="${{PWD}}" PROMPTR_SHELL=sh '"'{promptr}'"' prompt)'
+                                ;;
+                        esac
+                    "##
+                    ),
+                    promptr = self_exe.replace('\'', r#"'\''"#),
+                )
+            }
+        }
+    }
+}
+
+/// Returns what [`ACTIVE_VAR`] is set to while this process renders a prompt: the process ID
+/// and, where it's known, when the process started, e.g. `4242:1234567`
+pub fn active_marker() -> String {
+    let pid = std::process::id() as i32;
+
+    match platform::process_start_time(pid) {
+        Some(start) => format!("{}:{}", pid, start),
+        None => pid.to_string(),
+    }
+}
+
+/// Returns true if another `promptr` further up the process tree is in the middle of rendering a
+/// prompt.  A long lived shell started from a render (e.g. a terminal opened by a hook) inherits
+/// [`ACTIVE_VAR`] as well, so it only counts while the process it names is still running.  When
+/// the marker has a start time the process has to match it too, the pid may have been handed out
+/// again since.  Where there's no telling whether a process is running it never counts.
+pub fn nested_render(env: &HashMap<String, String>) -> bool {
+    let marker = match env.get(ACTIVE_VAR) {
+        Some(marker) => marker,
+        None => return false,
+    };
+
+    let (pid, start) = match marker.split_once(':') {
+        Some((pid, start)) => match start.parse::<u64>() {
+            Ok(start) => (pid, Some(start)),
+            Err(_) => return false,
+        },
+        None => (marker.as_str(), None),
+    };
+
+    let pid = match pid.parse::<i32>() {
+        Ok(pid) if pid != std::process::id() as i32 => pid,
+        _ => return false,
+    };
+
+    platform::process_alive(pid)
+        && start.is_none_or(|start| platform::process_start_time(pid) == Some(start))
+}
+
+/// Returns the exit status of the last command, from [`LAST_EXIT_VAR`] if it's set and `code`
+/// otherwise
+pub fn last_exit_code(env: &HashMap<String, String>) -> Option<&str> {
+    env.get(LAST_EXIT_VAR)
+        .filter(|code| !code.is_empty())
+        .or_else(|| env.get("code"))
+        .map(String::as_str)
+}
+
+/// Returns `rc` with the hook line added after [`HOOK_MARKER`], or `None` if it's already there
+pub fn install_hook(rc: &str, hook_line: &str) -> Option<String> {
+    if rc.lines().any(|line| line == HOOK_MARKER) {
+        return None;
+    }
+
+    let mut installed = rc.to_string();
+
+    if !installed.is_empty() {
+        if !installed.ends_with('\n') {
+            installed.push('\n');
         }
+        installed.push('\n');
     }
+
+    installed.push_str(HOOK_MARKER);
+    installed.push('\n');
+    installed.push_str(hook_line);
+    installed.push('\n');
+
+    Some(installed)
+}
+
+/// Returns `rc` without [`HOOK_MARKER`], the line after it, and the blank line
+/// [`install_hook`] put before it, or `None` if there's nothing to remove
+pub fn remove_hook(rc: &str) -> Option<String> {
+    let lines = rc.lines().collect::<Vec<_>>();
+    let marker = lines.iter().position(|line| *line == HOOK_MARKER)?;
+
+    let start = match marker {
+        0 => 0,
+        _ if lines[marker - 1].is_empty() => marker - 1,
+        _ => marker,
+    };
+    let end = (marker + 2).min(lines.len());
+
+    let removed = lines[..start]
+        .iter()
+        .chain(&lines[end..])
+        .map(|line| format!("{}\n", line))
+        .collect();
+
+    Some(removed)
 }
@@ -0,0 +1,220 @@
+//! Keeps track of how long each prompt took to render so `promptr stats` can report on it.
+//!
+//! With `latency_history` set in the config every prompt appends a [`Sample`] to `latency.jsonl`
+//! in the [cache](`crate::cache`) directory.  The file is trimmed back to the most recent
+//! `latency_history` samples every so often.  The report is in the Prometheus text format so it
+//! can be read by eye or fed to a textfile collector:
+//! ```text
+//! # TYPE promptr_render_seconds summary
+//! promptr_render_seconds{segment="git",quantile="0.5"} 0.004211
+//! promptr_render_seconds{segment="git",quantile="0.95"} 0.019870
+//! promptr_render_seconds_count{segment="git"} 200
+//! ```
+//! The whole prompt is reported as the `total` segment.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cache::Cache;
+
+/// Name the time for the whole prompt is reported under
+pub const TOTAL: &str = "total";
+
+/// Render times for a single prompt
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Sample {
+    /// Microseconds for the whole prompt
+    pub total_us: u64,
+
+    /// Microseconds spent in each segment, segments used more than once are added together
+    pub segments: BTreeMap<String, u64>,
+}
+
+/// Percentiles for one segment over the recorded history
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// Number of prompts the segment was rendered in
+    pub count: usize,
+    /// Median in microseconds
+    pub p50: u64,
+    /// 95th percentile in microseconds
+    pub p95: u64,
+}
+
+/// Handle to the latency history file
+#[derive(Clone, Debug)]
+pub struct Log {
+    path: PathBuf,
+}
+
+impl Sample {
+    /// Adds the time spent rendering `segment`
+    pub fn add(&mut self, segment: &str, elapsed: Duration) {
+        *self.segments.entry(segment.to_string()).or_default() += elapsed.as_micros() as u64;
+    }
+}
+
+impl Log {
+    /// Opens the history file at `path`
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Opens the history file in the cache directory
+    pub fn from_env(env: &HashMap<String, String>) -> crate::Result<Self> {
+        Ok(Self::new(Cache::from_env(env)?.dir().join("latency.jsonl")))
+    }
+
+    /// Returns every sample in the history, oldest first.  Lines that can't be read are skipped.
+    pub fn read(&self) -> Vec<Sample> {
+        fs::read_to_string(&self.path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    /// Returns the most recent `keep` samples, oldest first
+    pub fn recent(&self, keep: usize) -> Vec<Sample> {
+        let mut samples = self.read();
+        samples.drain(..samples.len().saturating_sub(keep));
+        samples
+    }
+
+    /// Appends `sample` to the history.  Once the file holds more than twice `keep` samples it's
+    /// rewritten with only the most recent `keep`, so most prompts only pay for a single append.
+    pub fn record(&self, sample: &Sample, keep: usize) -> crate::Result<()> {
+        let mut line = serde_json::to_string(sample)?;
+        line.push('\n');
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // One write per sample so lines from concurrent prompts don't get mixed together
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+
+        // Samples are all about the same size so there's no need to count lines until the file
+        // looks big enough to be worth trimming
+        let limit = 2 * keep as u64 * line.len() as u64;
+        if file.metadata()?.len() > limit {
+            self.compact(keep)?;
+        }
+
+        Ok(())
+    }
+
+    /// Rewrites the history with only the most recent `keep` samples
+    fn compact(&self, keep: usize) -> crate::Result<()> {
+        let samples = self.read();
+        if samples.len() <= 2 * keep {
+            return Ok(());
+        }
+
+        let mut contents = String::new();
+        for sample in &samples[samples.len() - keep..] {
+            contents.push_str(&serde_json::to_string(sample)?);
+            contents.push('\n');
+        }
+
+        // Write somewhere else first so a concurrent prompt never sees half a file
+        let tmp = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp, contents)?;
+        fs::rename(tmp, &self.path)?;
+
+        Ok(())
+    }
+}
+
+/// Escapes `value` for use as a Prometheus label value
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Nearest-rank percentile of already sorted `values`
+pub fn percentile(values: &[u64], percent: f64) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+
+    let rank = (percent / 100.0 * values.len() as f64).ceil() as usize;
+
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+/// Works out the percentiles for each segment, and for the whole prompt as [`TOTAL`]
+pub fn summarize(samples: &[Sample]) -> BTreeMap<String, Summary> {
+    let mut times: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+
+    for sample in samples {
+        times
+            .entry(TOTAL.to_string())
+            .or_default()
+            .push(sample.total_us);
+
+        for (segment, micros) in &sample.segments {
+            times.entry(segment.clone()).or_default().push(*micros);
+        }
+    }
+
+    times
+        .into_iter()
+        .map(|(segment, mut values)| {
+            values.sort_unstable();
+
+            let summary = Summary {
+                count: values.len(),
+                p50: percentile(&values, 50.0),
+                p95: percentile(&values, 95.0),
+            };
+
+            (segment, summary)
+        })
+        .collect()
+}
+
+/// Formats `summaries` in the Prometheus text format
+pub fn to_prometheus(summaries: &BTreeMap<String, Summary>) -> String {
+    let seconds = |micros: u64| micros as f64 / 1_000_000.0;
+
+    let mut out = String::from(
+        "# HELP promptr_render_seconds Time spent rendering the prompt, by segment\n\
+         # TYPE promptr_render_seconds summary\n",
+    );
+
+    for (segment, summary) in summaries {
+        for (quantile, micros) in [("0.5", summary.p50), ("0.95", summary.p95)] {
+            let _ = writeln!(
+                out,
+                "promptr_render_seconds{{segment=\"{}\",quantile=\"{}\"}} {:.6}",
+                label(segment),
+                quantile,
+                seconds(micros)
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "promptr_render_seconds_count{{segment=\"{}\"}} {}",
+            label(segment),
+            summary.count
+        );
+    }
+
+    out
+}
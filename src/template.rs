@@ -0,0 +1,90 @@
+//! Swaps the values `bash` can fill in by itself for its prompt escapes.
+//!
+//! With `template` set in the config the username, hostname, and working directory are written
+//! into `PS1` as `\u`, `\h` (or `\H`), and `\w` instead of their current values.  If the prompt
+//! hook ever fails to run again those stay up to date, e.g. after `cd` in a shell where
+//! `PROMPT_COMMAND` has been clobbered.
+//!
+//! The [`path`](`crate::segment::path`) segment's breadcrumbs are folded into a single segment
+//! since `\w` can't be split up.  Widths are measured before `bash` expands anything so
+//! `max_width` and `reserve_columns` are only a rough guide in this mode.  Other shells don't
+//! share `bash`'s escapes so this only applies to [`EscapeStyle::Bash`](`crate::ansi::EscapeStyle::Bash`).
+
+use std::collections::HashMap;
+
+use crate::segment::Segment;
+
+/// Prompt escape for the username
+pub const USER: &str = r"\u";
+/// Prompt escape for the hostname up to the first `.`
+pub const SHORT_HOST: &str = r"\h";
+/// Prompt escape for the full hostname
+pub const HOST: &str = r"\H";
+/// Prompt escape for the working directory with `$HOME` abbreviated to `~`
+pub const CWD: &str = r"\w";
+
+/// Replaces the baked in values in `segments` with prompt escapes where it can
+pub fn apply(segments: &mut Vec<Segment>, env: &HashMap<String, String>) {
+    let user = env.get("USER").map(String::as_str);
+    let host = env.get("hostname").map(String::as_str);
+
+    for segment in segments.iter_mut() {
+        match segment.source {
+            "Username" => segment.text = swap_user(&segment.text, user),
+            "Hostname" => segment.text = swap_host(&segment.text, host),
+            "Identity" => {
+                segment.text = swap_host(&swap_user(&segment.text, user), host);
+            }
+            _ => {}
+        }
+    }
+
+    fold_path(segments);
+}
+
+/// Swaps a leading or trailing username, as written by the username and identity segments
+fn swap_user(text: &str, user: Option<&str>) -> String {
+    match user.filter(|user| !user.is_empty()) {
+        Some(user) if text.starts_with(user) => format!("{}{}", USER, &text[user.len()..]),
+        Some(user) if text.ends_with(user) => {
+            format!("{}{}", &text[..text.len() - user.len()], USER)
+        }
+        _ => text.to_string(),
+    }
+}
+
+/// Swaps the full or short hostname wherever it shows up first
+fn swap_host(text: &str, host: Option<&str>) -> String {
+    let host = match host.filter(|host| !host.is_empty()) {
+        Some(host) => host,
+        None => return text.to_string(),
+    };
+    let short = host.split('.').next().unwrap_or(host);
+
+    match (text.contains(host), host == short) {
+        (true, false) => text.replacen(host, HOST, 1),
+        _ => text.replacen(short, SHORT_HOST, 1),
+    }
+}
+
+/// Folds the breadcrumbs from the path segment into a single `\w`, colored like the last one
+fn fold_path(segments: &mut Vec<Segment>) {
+    let is_breadcrumb = |segment: &Segment| {
+        segment.source.starts_with("Path::") && segment.source != "Path::BashDirStack"
+    };
+
+    let first = match segments.iter().position(is_breadcrumb) {
+        Some(first) => first,
+        None => return,
+    };
+    let count = segments[first..]
+        .iter()
+        .take_while(|segment| is_breadcrumb(segment))
+        .count();
+
+    let last = first + count - 1;
+    segments[last].source = "Path::Template";
+    segments[last].text = CWD.to_string();
+
+    segments.drain(first..last);
+}
@@ -1,4 +1,23 @@
 use std::collections::HashMap;
 type AppEnv = HashMap<String, String>;
 
+mod ansi;
+mod bundle;
+mod cache;
+mod config;
+mod crash;
+mod exec;
+mod glyphs;
+mod hooks;
+mod lint;
+mod notify;
+mod platform;
+mod plugin;
+mod redact;
+mod registry;
+mod render;
+mod schedule;
 mod segment;
+mod shell;
+mod stats;
+mod template;
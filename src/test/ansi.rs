@@ -0,0 +1,314 @@
+use crate::ansi::{osc, Background, Color, ColorDepth, EscapeStyle, OscCommand, TextStyle};
+use crate::segment::Segment;
+use crate::test::AppEnv;
+
+#[test]
+fn osc_outside_multiplexer() {
+    let env = AppEnv::new();
+
+    assert_eq!(
+        "\x1b]2;title\x07",
+        osc(EscapeStyle::Raw, &env, OscCommand::SetTitle, "title")
+    );
+    assert_eq!(
+        r"\[\e]2;title\a\]",
+        osc(EscapeStyle::Bash, &env, OscCommand::SetTitle, "title")
+    );
+}
+
+#[test]
+fn osc_inside_tmux() {
+    let env = AppEnv::from([(
+        String::from("TMUX"),
+        String::from("/tmp/tmux-1000/default,1,0"),
+    )]);
+
+    assert_eq!(
+        "\x1bPtmux;\x1b\x1b]2;title\x07\x1b\\",
+        osc(EscapeStyle::Raw, &env, OscCommand::SetTitle, "title")
+    );
+}
+
+#[test]
+fn osc_inside_screen() {
+    let env = AppEnv::from([(String::from("STY"), String::from("1234.ttyNN.hostname"))]);
+
+    assert_eq!(
+        r"\[\eP\e]7;file://host/tmp\a\e\\\]",
+        osc(
+            EscapeStyle::Bash,
+            &env,
+            OscCommand::CurrentDirectory,
+            "file://host/tmp"
+        )
+    );
+}
+
+#[test]
+fn escape_styles() {
+    let color = Color::Numbered(31);
+
+    assert_eq!(r"\[\e[38;5;31m\]", color.set_fg(EscapeStyle::Bash));
+    assert_eq!("%{\x1b[38;5;31m%}", color.set_fg(EscapeStyle::Zsh));
+    assert_eq!("\x1b[38;5;31m", color.set_fg(EscapeStyle::Raw));
+    assert_eq!("#[fg=colour31]", color.set_fg(EscapeStyle::Tmux));
+}
+
+#[test]
+fn tmux_style() {
+    let color = Color::Rgb {
+        r: 255,
+        g: 80,
+        b: 95,
+    };
+
+    assert_eq!("#[bg=#ff505f]", color.set_bg(EscapeStyle::Tmux));
+    assert_eq!("#[default]", Color::reset_colors(EscapeStyle::Tmux));
+    assert_eq!(
+        "",
+        osc(
+            EscapeStyle::Tmux,
+            &AppEnv::new(),
+            OscCommand::SetTitle,
+            "title"
+        )
+    );
+    assert_eq!("## $", EscapeStyle::Tmux.escape_text(r"# \$"));
+}
+
+#[test]
+fn text_styles() {
+    assert_eq!("\x1b[3m", TextStyle::Italic.on(EscapeStyle::Raw));
+    assert_eq!("\x1b[24m", TextStyle::Underline.off(EscapeStyle::Raw));
+    assert_eq!("\x1b[22m", TextStyle::Dim.off(EscapeStyle::Raw));
+    assert_eq!("#[italics]", TextStyle::Italic.on(EscapeStyle::Tmux));
+    assert_eq!(
+        "#[nounderscore]",
+        TextStyle::Underline.off(EscapeStyle::Tmux)
+    );
+
+    let styles: Vec<TextStyle> = serde_json::from_str(r#"["bold", "underline"]"#).unwrap();
+    assert_eq!(vec![TextStyle::Bold, TextStyle::Underline], styles);
+}
+
+#[test]
+fn background_from_colorfgbg() {
+    assert_eq!(Some(Background::Dark), Background::from_colorfgbg("15;0"));
+    assert_eq!(
+        Some(Background::Light),
+        Background::from_colorfgbg("0;default;15")
+    );
+    assert_eq!(Some(Background::Light), Background::from_colorfgbg("0;7"));
+    assert_eq!(None, Background::from_colorfgbg("default;default"));
+
+    // The environment is consulted before the terminal
+    let env = AppEnv::from([(String::from("COLORFGBG"), String::from("12;8"))]);
+    assert_eq!(
+        Some(Background::Dark),
+        Background::detect(&env, std::time::Duration::ZERO)
+    );
+}
+
+#[test]
+fn background_query() {
+    assert_eq!("\x1b]11;?\x07\x1b[c", Background::query(&AppEnv::new()));
+
+    let env = AppEnv::from([(String::from("TMUX"), String::from("/tmp/tmux-1000/default"))]);
+    assert_eq!(
+        "\x1bPtmux;\x1b\x1b]11;?\x07\x1b\x1b[c\x1b\\",
+        Background::query(&env)
+    );
+
+    let env = AppEnv::from([(String::from("STY"), String::from("1234.pts-0.host"))]);
+    assert_eq!("\x1bP\x1b]11;?\x07\x1b[c\x1b\\", Background::query(&env));
+}
+
+#[test]
+fn background_from_reply() {
+    assert_eq!(
+        Some(Background::Light),
+        Background::from_reply("\x1b]11;rgb:ffff/ffff/dddd\x07")
+    );
+    assert_eq!(
+        Some(Background::Dark),
+        Background::from_reply("\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\")
+    );
+    assert_eq!(
+        Some(Background::Light),
+        Background::from_reply("\x1b]11;rgb:f/e/d\x07")
+    );
+    assert_eq!(None, Background::from_reply("\x1b]11;rgb:ffff/ffff\x07"));
+    assert_eq!(None, Background::from_reply(""));
+}
+
+#[test]
+fn interpolate_colors() {
+    let white = Color::Rgb {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    assert_eq!(
+        Some(Color::Rgb { r: 0, g: 0, b: 0 }),
+        Color::Numbered(16).interpolate(white, 0.0)
+    );
+    assert_eq!(
+        Some(Color::Rgb {
+            r: 128,
+            g: 128,
+            b: 128
+        }),
+        Color::Numbered(16).interpolate(white, 0.5)
+    );
+    assert_eq!(Some(white), Color::Numbered(16).interpolate(white, 2.0));
+    assert_eq!(None, Color::Auto.interpolate(white, 0.5));
+}
+
+#[test]
+fn parse_escape_style() {
+    assert_eq!(EscapeStyle::Zsh, "zsh".parse().unwrap());
+    assert!("fish".parse::<EscapeStyle>().is_err());
+}
+
+#[test]
+fn auto_color() {
+    let color: Color = serde_json::from_str(r#""auto""#).unwrap();
+    assert_eq!(Color::Auto, color);
+    assert_eq!(r#""auto""#, serde_json::to_string(&color).unwrap());
+
+    assert!(serde_json::from_str::<Color>(r#""bluish""#).is_err());
+    assert_eq!(
+        Color::Numbered(31),
+        serde_json::from_str::<Color>("31").unwrap()
+    );
+}
+
+#[test]
+fn named_colors() {
+    let color = |json: &str| serde_json::from_str::<Color>(json).ok();
+
+    assert_eq!(Some(Color::Numbered(4)), color(r#""blue""#));
+    assert_eq!(Some(Color::Numbered(12)), color(r#""Bright_Blue""#));
+    assert_eq!(Some(Color::Numbered(12)), color(r#""bright-blue""#));
+    assert_eq!(Some(Color::Numbered(208)), color(r#""ORANGE""#));
+    assert_eq!(None, color(r#""ultraviolet""#));
+
+    // Names are only for reading, they're written out as numbers
+    assert_eq!(
+        "1",
+        serde_json::to_string(&Color::named("red").unwrap()).unwrap()
+    );
+}
+
+#[test]
+fn contrasting_colors() {
+    assert_eq!(Color::Numbered(15), Color::Numbered(0).contrasting());
+    assert_eq!(Color::Numbered(0), Color::Numbered(15).contrasting());
+    // Dark blue from the cube, light yellow from the cube, and both ends of the gray ramp
+    assert_eq!(Color::Numbered(15), Color::Numbered(24).contrasting());
+    assert_eq!(Color::Numbered(0), Color::Numbered(221).contrasting());
+    assert_eq!(Color::Numbered(15), Color::Numbered(236).contrasting());
+    assert_eq!(Color::Numbered(0), Color::Numbered(252).contrasting());
+    assert_eq!(
+        Color::Numbered(0),
+        Color::Rgb {
+            r: 255,
+            g: 200,
+            b: 0
+        }
+        .contrasting()
+    );
+}
+
+#[test]
+fn segments_resolve_auto() {
+    let seg = Segment::new("Test", Color::Auto, Color::Numbered(221), "x");
+    assert_eq!(Color::Numbered(0), seg.fg);
+}
+
+#[test]
+fn color_depth_detection() {
+    let detect = |vars: &[(&str, &str)]| {
+        let env = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<AppEnv>();
+        ColorDepth::Auto.resolve(&env)
+    };
+
+    assert_eq!(
+        ColorDepth::TrueColor,
+        detect(&[("COLORTERM", "truecolor"), ("TERM", "xterm-256color")])
+    );
+    assert_eq!(
+        ColorDepth::Palette256,
+        detect(&[("TERM", "xterm-256color")])
+    );
+    assert_eq!(
+        ColorDepth::Palette256,
+        detect(&[
+            ("TERM_PROGRAM", "Apple_Terminal"),
+            ("TERM", "xterm-256color")
+        ])
+    );
+    assert_eq!(ColorDepth::Palette16, detect(&[("TERM", "linux")]));
+    assert_eq!(ColorDepth::TrueColor, detect(&[]));
+
+    // Pinned in the config
+    let env = AppEnv::from([(String::from("TERM"), String::from("linux"))]);
+    assert_eq!(ColorDepth::Palette256, ColorDepth::Palette256.resolve(&env));
+}
+
+#[test]
+fn colors_are_downgraded() {
+    let rgb = Color::Rgb {
+        r: 255,
+        g: 80,
+        b: 95,
+    };
+
+    assert_eq!(rgb, rgb.downgrade(ColorDepth::TrueColor));
+    assert_eq!(Color::Numbered(203), rgb.downgrade(ColorDepth::Palette256));
+    assert_eq!(Color::Numbered(9), rgb.downgrade(ColorDepth::Palette16));
+
+    // Grays go to the gray ramp, not the system colors
+    let gray = Color::Rgb {
+        r: 18,
+        g: 18,
+        b: 18,
+    };
+    assert_eq!(Color::Numbered(233), gray.downgrade(ColorDepth::Palette256));
+
+    assert_eq!(
+        Color::Numbered(240),
+        Color::Numbered(240).downgrade(ColorDepth::Palette256)
+    );
+    assert_eq!(
+        Color::Numbered(8),
+        Color::Numbered(240).downgrade(ColorDepth::Palette16)
+    );
+    assert_eq!(
+        Color::Numbered(4),
+        Color::Numbered(4).downgrade(ColorDepth::Palette16)
+    );
+    assert_eq!(Color::Auto, Color::Auto.downgrade(ColorDepth::Palette16));
+}
+
+#[test]
+fn segment_colors_are_downgraded() {
+    let rgb = Color::Rgb { r: 0, g: 0, b: 0 };
+
+    let mut segment =
+        Segment::new("Test", Color::Numbered(255), rgb, "ab").with_spans([crate::segment::Span {
+            text: "ab".into(),
+            fg: Some(rgb),
+            bg: None,
+            style: None,
+        }]);
+    segment.downgrade_colors(ColorDepth::Palette16);
+
+    assert_eq!(Color::Numbered(7), segment.fg);
+    assert_eq!(Color::Numbered(0), segment.bg);
+    assert_eq!(Some(Color::Numbered(0)), segment.spans[0].fg);
+}
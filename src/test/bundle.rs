@@ -0,0 +1,123 @@
+use serde_json::json;
+
+use crate::ansi::Color;
+use crate::bundle::{Bundle, BUNDLE_VERSION};
+use crate::glyphs::GlyphSet;
+use crate::PromptrConfig;
+
+fn config() -> PromptrConfig {
+    #[allow(unused_mut)]
+    let mut theme = json!({ "path": { "bg": 236 } });
+
+    #[cfg(feature = "segment-battery")]
+    {
+        theme["battery"] = json!({ "normal_bg": 52 });
+    }
+
+    serde_json::from_value(json!({
+        "promptr_config": 12,
+        "glyph_set": "ascii",
+        "theme": theme
+    }))
+    .unwrap()
+}
+
+#[test]
+fn export() {
+    let bundle = Bundle::export(&config(), Some("dusk".into())).unwrap();
+
+    assert_eq!(BUNDLE_VERSION, bundle.promptr_theme_bundle);
+    assert_eq!(Some("dusk"), bundle.name.as_deref());
+    assert_eq!(GlyphSet::Ascii, bundle.glyph_set);
+    #[cfg(feature = "segment-battery")]
+    assert_eq!(vec!["segment-battery"], bundle.features);
+
+    let json = serde_json::to_string(&bundle).unwrap();
+    assert_eq!(bundle, Bundle::parse(&json).unwrap());
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    assert!(Bundle::parse(r#"{ "promptr_theme_bundle": 1 }"#).is_ok());
+    assert!(Bundle::parse(r#"{ "promptr_theme_bundle": 2 }"#).is_err());
+    assert!(Bundle::parse(r#"{ "theme": {} }"#).is_err());
+    assert!(Bundle::parse(r#"{ "promptr_theme_bundle": 1, "extra": true }"#).is_err());
+}
+
+#[test]
+fn apply_as_base_theme() {
+    let bundle = Bundle::export(&config(), None).unwrap();
+
+    let mut raw = json!({ "promptr_config": 12, "segments": [{ "name": "path" }] });
+    bundle.apply(&mut raw, None).unwrap();
+
+    assert_eq!(json!("ascii"), raw["glyph_set"]);
+    assert_eq!(json!(236), raw["theme"]["path"]["bg"]);
+    assert_eq!(json!("path"), raw["segments"][0]["name"]);
+}
+
+#[test]
+fn apply_as_named_theme() {
+    let bundle = Bundle::export(&config(), None).unwrap();
+
+    let mut raw = json!({
+        "promptr_config": 12,
+        "theme": { "path": { "bg": 1 } },
+        "themes": { "old": {} }
+    });
+    bundle.apply(&mut raw, Some("dusk")).unwrap();
+
+    assert_eq!(json!(1), raw["theme"]["path"]["bg"]);
+    assert_eq!(None, raw.get("glyph_set"));
+    assert_eq!(json!({}), raw["themes"]["old"]);
+    assert_eq!(json!(236), raw["themes"]["dusk"]["path"]["bg"]);
+    #[cfg(feature = "segment-battery")]
+    assert_eq!(json!(52), raw["themes"]["dusk"]["battery"]["normal_bg"]);
+
+    assert!(serde_json::from_value::<PromptrConfig>(raw).is_ok());
+}
+
+#[test]
+fn unsupported_keys_are_left_out() {
+    let bundle = Bundle::parse(
+        r#"{
+            "promptr_theme_bundle": 1,
+            "features": ["segment-battery"],
+            "theme": { "path": { "bg": 236 }, "battery": { "normal_bg": 52 } }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(Color::Numbered(236), bundle.to_theme().unwrap().path.bg);
+
+    let mut raw = json!({ "promptr_config": 12 });
+    bundle.apply(&mut raw, None).unwrap();
+
+    if cfg!(feature = "segment-battery") {
+        assert!(bundle.unsupported_keys().is_empty());
+        assert_eq!(json!(52), raw["theme"]["battery"]["normal_bg"]);
+    } else {
+        assert_eq!(vec!["battery"], bundle.unsupported_keys());
+        assert_eq!(None, raw["theme"].get("battery"));
+    }
+
+    assert!(serde_json::from_value::<PromptrConfig>(raw).is_ok());
+
+    // Anything else wrong with the theme is still an error
+    assert!(Bundle::parse(r#"{ "promptr_theme_bundle": 1, "theme": { "nope": {} } }"#).is_err());
+}
+
+#[test]
+fn key_order_is_kept() {
+    let bundle = Bundle::export(&config(), None).unwrap();
+
+    let mut raw: serde_json::Value =
+        serde_json::from_str(r#"{ "segments": [], "promptr_config": 12, "redact": [] }"#).unwrap();
+    bundle.apply(&mut raw, None).unwrap();
+
+    let keys = raw.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(
+        vec!["segments", "promptr_config", "redact", "theme", "glyph_set"],
+        keys
+    );
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use crate::cache::Cache;
+
+#[test]
+fn round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path());
+
+    assert_eq!(None, cache.get::<u64>("answer", Duration::from_secs(60)));
+
+    cache.set("answer", &42u64).unwrap();
+    assert_eq!(
+        Some(42),
+        cache.get::<u64>("answer", Duration::from_secs(60))
+    );
+
+    // Nothing is ever younger than zero seconds
+    assert_eq!(None, cache.get::<u64>("answer", Duration::ZERO));
+}
+
+#[test]
+fn insert_only_on_miss() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path().join("nested"));
+    let ttl = Duration::from_secs(60);
+
+    assert_eq!(1, cache.get_or_insert_with("key", ttl, || Ok(1u8)).unwrap());
+    assert_eq!(1, cache.get_or_insert_with("key", ttl, || Ok(2u8)).unwrap());
+    assert_eq!(
+        3,
+        cache
+            .get_or_insert_with("key", Duration::ZERO, || Ok(3u8))
+            .unwrap()
+    );
+}
+
+#[test]
+fn failures_are_cached() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::new(dir.path());
+    let ttl = Duration::from_secs(60);
+
+    let err = cache
+        .get_or_insert_with::<u8, _>("key", ttl, || Err(anyhow::anyhow!("broken")))
+        .unwrap_err();
+    assert_eq!("broken", err.to_string());
+
+    // The producer isn't run again until the failure expires
+    let err = cache
+        .get_or_insert_with("key", ttl, || Ok(1u8))
+        .unwrap_err();
+    assert_eq!("broken", err.to_string());
+
+    assert_eq!(
+        2,
+        cache
+            .get_or_insert_with("key", Duration::ZERO, || Ok(2u8))
+            .unwrap()
+    );
+}
+
+#[test]
+fn keys_are_file_names() {
+    let key = Cache::key("prefix", &vec!["some", "/path with spaces"]);
+
+    assert!(key.starts_with("prefix-"));
+    assert!(!key.contains('/'));
+    assert_eq!(
+        key,
+        Cache::key("prefix", &vec!["some", "/path with spaces"])
+    );
+}
@@ -0,0 +1,362 @@
+use crate::ansi::{Color, EscapeStyle};
+use crate::redact::Redactor;
+use crate::segment::Segment;
+use crate::test::AppEnv;
+use crate::{ApplicationState, PromptrConfig, Separator, Theme};
+
+#[test]
+fn fast_profile_from_env() {
+    let config = PromptrConfig::default();
+
+    assert!(!config.use_fast_profile(&AppEnv::new()));
+    assert!(config.use_fast_profile(&AppEnv::from([(
+        String::from("PROMPTR_FAST"),
+        String::from("1")
+    )])));
+}
+
+#[test]
+fn fast_profile_membership() {
+    let mut config = PromptrConfig::default();
+    assert!(config.in_fast_profile("path"));
+    assert!(!config.in_fast_profile("git"));
+
+    config.fast_profile = Some(vec![String::from("git")]);
+    assert!(!config.in_fast_profile("path"));
+    assert!(config.in_fast_profile("git"));
+}
+
+#[test]
+fn lines_take_precedence() {
+    let mut config: PromptrConfig = serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "segments": [{ "name": "username" }],
+            "lines": [
+                { "segments": [{ "name": "path" }, { "name": "git" }] },
+                { "segments": [{ "name": "command_status" }] }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let lines = config.take_lines();
+    assert_eq!(2, lines.len());
+    assert_eq!("git", lines[0][1].name);
+    assert_eq!("command_status", lines[1][0].name);
+}
+
+#[test]
+fn segments_are_a_single_line() {
+    let mut config = PromptrConfig::default();
+
+    let lines = config.take_lines();
+    assert_eq!(1, lines.len());
+    assert_eq!(3, lines[0].len());
+}
+
+#[test]
+fn newline_splits_lines() {
+    let mut config: PromptrConfig = serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "segments": [
+                { "name": "path" },
+                { "name": "git" },
+                { "name": "newline" },
+                { "name": "command_status" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let lines = config.take_lines();
+    assert_eq!(2, lines.len());
+    assert_eq!(2, lines[0].len());
+    assert_eq!("command_status", lines[1][0].name);
+}
+
+#[test]
+fn separator_overrides() {
+    let mut config: PromptrConfig = serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "segments": [
+                { "name": "path", "separator": "thin" },
+                { "name": "git", "separator": { "custom": ">" } },
+                { "name": "command_status", "separator": "none" },
+                { "name": "screen" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let separators = config
+        .take_lines()
+        .remove(0)
+        .into_iter()
+        .map(|stanza| stanza.separator)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        vec![
+            Some(Separator::Thin),
+            Some(Separator::Custom(">".into())),
+            Some(Separator::None),
+            None
+        ],
+        separators
+    );
+}
+
+#[test]
+fn aliases_are_resolved() {
+    let mut config: PromptrConfig = serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "segment_aliases": { "cwd": "paths", "nl": "newline" },
+            "segments": [
+                { "name": "cwd" },
+                { "name": "nl" },
+                { "name": "battery_status" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let lines = config.take_lines();
+    assert_eq!(2, lines.len());
+    assert_eq!("path", lines[0][0].name);
+    assert_eq!("battery", lines[1][0].name);
+}
+
+#[test]
+fn demo_mode_swaps_names() {
+    let theme = crate::Theme::default();
+    let env = AppEnv::from([
+        (String::from("PROMPTR_DEMO"), String::from("1")),
+        (String::from("USER"), String::from("jrandom")),
+        (
+            String::from("hostname"),
+            String::from("build-07.corp.example"),
+        ),
+        (String::from("HOME"), String::from("/home/jrandom")),
+        (String::from("PWD"), String::from("/home/jrandom/src")),
+    ]);
+
+    // Segments still need the real values to find things on disk
+    let state = ApplicationState::new(&theme, env, EscapeStyle::Bash);
+    assert!(state.demo);
+    assert_eq!("jrandom", state.env["USER"]);
+    assert_eq!("/home/jrandom/src", state.env["PWD"]);
+
+    let mut segment = Segment::new(
+        "Test",
+        Color::Numbered(0),
+        Color::Numbered(1),
+        "jrandom@build-07",
+    )
+    .with_metadata(
+        serde_json::json!({ "pwd": "/home/jrandom/src", "host": "build-07.corp.example" }),
+    );
+    Redactor::demo(&state.env).redact_segment(&mut segment);
+
+    assert_eq!("user@host", segment.text);
+    assert_eq!(
+        Some(serde_json::json!({ "pwd": "/home/user/src", "host": "host" })),
+        segment.metadata
+    );
+
+    // Only whole names are swapped
+    assert_eq!("jrandomly", Redactor::demo(&state.env).redact("jrandomly"));
+
+    let env = AppEnv::from([(String::from("USER"), String::from("jrandom"))]);
+    let state = ApplicationState::new(&theme, env, EscapeStyle::Bash);
+    assert!(!state.demo);
+}
+
+#[test]
+fn demo_mode_hides_segments() {
+    let config = PromptrConfig::default();
+    assert!(config.hidden_in_demo("screen"));
+    assert!(!config.hidden_in_demo("path"));
+
+    let config = PromptrConfig {
+        demo_hidden: Some(vec![String::from("git")]),
+        ..Default::default()
+    };
+    assert!(config.hidden_in_demo("git"));
+    assert!(!config.hidden_in_demo("screen"));
+}
+
+#[test]
+fn accessible_from_env() {
+    let mut config = PromptrConfig::default();
+
+    assert!(!config.accessible(&AppEnv::new()));
+    assert!(config.accessible(&AppEnv::from([(
+        String::from("PROMPTR_A11Y"),
+        String::from("1")
+    )])));
+
+    config.accessible = true;
+    assert!(config.accessible(&AppEnv::new()));
+}
+
+#[test]
+fn no_color_from_env() {
+    let env = |value: &str| AppEnv::from([(String::from("NO_COLOR"), String::from(value))]);
+
+    assert!(!crate::prompt::no_color(&AppEnv::new()));
+    assert!(!crate::prompt::no_color(&env("")));
+    assert!(crate::prompt::no_color(&env("1")));
+    assert!(crate::prompt::no_color(&env("yes please")));
+}
+
+#[test]
+fn theme_by_name() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("themes")).unwrap();
+    std::fs::write(
+        dir.path().join("themes/gruvbox.json"),
+        r#"{ "path": { "bg": 237 } }"#,
+    )
+    .unwrap();
+
+    let mut raw = serde_json::json!({ "promptr_config": 12, "theme": "gruvbox" });
+    crate::prompt::load_theme_file(&mut raw, dir.path()).unwrap();
+
+    let config: PromptrConfig = serde_json::from_value(raw).unwrap();
+    assert_eq!(Color::Numbered(237), config.theme.path.bg);
+
+    let mut raw = serde_json::json!({ "promptr_config": 12, "theme": "missing" });
+    assert!(crate::prompt::load_theme_file(&mut raw, dir.path()).is_err());
+
+    // The named themes come first
+    let mut raw = serde_json::json!({
+        "promptr_config": 12,
+        "theme": "gruvbox",
+        "themes": { "gruvbox": { "path": { "bg": 52 } } }
+    });
+    crate::prompt::load_theme_file(&mut raw, dir.path()).unwrap();
+
+    let config: PromptrConfig = serde_json::from_value(raw).unwrap();
+    assert_eq!(Color::Numbered(52), config.theme.path.bg);
+
+    std::fs::write(dir.path().join("themes/notes.txt"), "").unwrap();
+    assert_eq!(vec!["gruvbox"], crate::prompt::theme_files(dir.path()));
+}
+
+#[test]
+fn theme_preview() {
+    let mut theme = Theme::default();
+    theme.path.home_bg = Color::Numbered(52);
+
+    let preview = crate::prompt::preview(&theme);
+    for text in [" user ", " host ", " ~ ", " src ", " promptr "] {
+        assert!(preview.contains(text), "{:?} isn't in {:?}", text, preview);
+    }
+    assert!(preview.contains("\x1b[48;5;52m ~ "));
+}
+
+#[test]
+fn theme_file_under_inline_theme() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("work.json"),
+        r#"{ "path": { "bg": 237, "fg": 250 }, "hostname": { "bg": 22 } }"#,
+    )
+    .unwrap();
+
+    let mut raw = serde_json::json!({
+        "promptr_config": 12,
+        "theme_file": "work.json",
+        "theme": { "path": { "bg": 52 } }
+    });
+    crate::prompt::load_theme_file(&mut raw, dir.path()).unwrap();
+
+    let config: PromptrConfig = serde_json::from_value(raw).unwrap();
+    assert_eq!(Color::Numbered(52), config.theme.path.bg);
+    assert_eq!(Color::Numbered(250), config.theme.path.fg);
+    assert_eq!(Color::Numbered(22), config.theme.hostname.bg);
+    assert_eq!(Some("work.json"), config.theme_file.as_deref());
+
+    let mut raw = serde_json::json!({ "theme_file": "work.json", "theme": "gruvbox" });
+    assert!(crate::prompt::load_theme_file(&mut raw, dir.path()).is_err());
+}
+
+#[test]
+fn palette_references() {
+    let mut raw = serde_json::json!({
+        "promptr_config": 12,
+        "theme": {
+            "palette": { "accent": 208, "base": "blue" },
+            "path": { "home_bg": "$accent", "bg": "$base" },
+            "vcs": { "git": { "ahead_fg": "$accent" } },
+            "aws": { "symbol": "$x" }
+        },
+        "themes": {
+            "night": {
+                "palette": { "base": 17 },
+                "path": { "home_bg": "$accent", "bg": "$base", "fg": "$nope" }
+            }
+        }
+    });
+
+    let err = crate::prompt::resolve_palette(&mut raw).unwrap_err();
+    assert_eq!(
+        "no such colors in the palette: themes.night.path.fg ($nope)",
+        err.to_string()
+    );
+
+    let config: PromptrConfig = serde_json::from_value(raw).unwrap();
+    assert_eq!(Color::Numbered(208), config.theme.path.home_bg);
+    assert_eq!(Color::Numbered(4), config.theme.path.bg);
+    assert_eq!(Color::Numbered(208), config.theme.vcs.git.ahead_fg);
+    assert_eq!("$x", config.theme.aws.symbol);
+
+    let night = &config.themes["night"];
+    assert_eq!(Color::Numbered(208), night.path.home_bg);
+    assert_eq!(Color::Numbered(17), night.path.bg);
+    assert_eq!(PromptrConfig::default().theme.path.fg, night.path.fg);
+}
+
+#[test]
+fn palette_references_in_lists() {
+    let mut raw = serde_json::json!({
+        "promptr_config": 12,
+        "theme": {
+            "palette": { "accent": 208 },
+            "hostname": { "hash_palette": ["$accent", 30, "$nope"] }
+        }
+    });
+
+    let err = crate::prompt::resolve_palette(&mut raw).unwrap_err();
+    assert_eq!(
+        "no such colors in the palette: theme.hostname.hash_palette[2] ($nope)",
+        err.to_string()
+    );
+
+    let config: PromptrConfig = serde_json::from_value(raw).unwrap();
+    assert_eq!(
+        vec![Color::Numbered(208), Color::Numbered(30)],
+        config.theme.hostname.hash_palette
+    );
+}
+
+#[test]
+fn palette_references_without_fg_or_bg() {
+    let mut raw = serde_json::json!({
+        "promptr_config": 12,
+        "theme": {
+            "palette": { "accent": 208, "calm": "green" },
+            "vcs": { "git": { "compact": { "staged": "$accent", "stashed": "$calm" } } }
+        }
+    });
+
+    crate::prompt::resolve_palette(&mut raw).unwrap();
+
+    let config: PromptrConfig = serde_json::from_value(raw).unwrap();
+    assert_eq!(Color::Numbered(208), config.theme.vcs.git.compact.staged);
+    assert_eq!(Color::Numbered(2), config.theme.vcs.git.compact.stashed);
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::crash;
+
+#[test]
+fn log_goes_in_the_state_dir() {
+    let env = HashMap::from([("PROMPTR_STATE_DIR".to_string(), "/tmp/state".to_string())]);
+
+    assert_eq!(
+        PathBuf::from("/tmp/state/crash.log"),
+        crash::log_path(&env).unwrap()
+    );
+}
+
+#[test]
+fn panic_messages() {
+    let payload = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+    assert_eq!("static", crash::message(payload.as_ref()));
+
+    let payload = std::panic::catch_unwind(|| panic!("formatted {}", 42)).unwrap_err();
+    assert_eq!("formatted 42", crash::message(payload.as_ref()));
+
+    let payload = std::panic::catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+    assert_eq!("Box<dyn Any>", crash::message(payload.as_ref()));
+}
+
+#[test]
+fn report_has_the_details() {
+    let report = crash::report("oops", Some("src/render.rs:1:2".into()), "0: main");
+
+    assert!(report.contains("panicked at src/render.rs:1:2:\noops\n"));
+    assert!(report.ends_with("stack backtrace:\n0: main"));
+
+    let report = crash::report("oops", None, "");
+    assert!(report.contains("panicked at an unknown location:"));
+}
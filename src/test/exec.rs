@@ -0,0 +1,39 @@
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::exec;
+
+fn sh(script: &str) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .args(["-c", script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped());
+    command
+}
+
+#[test]
+fn output_is_captured() {
+    let finished = exec::run(&mut sh("echo hi; exit 3"), Duration::from_secs(5), None)
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(b"hi\n", finished.stdout.as_slice());
+    assert_eq!(Some(3), finished.status.code());
+}
+
+#[test]
+fn slow_programs_are_killed() {
+    let finished = exec::run(&mut sh("sleep 5"), Duration::from_millis(20), None).unwrap();
+
+    assert!(finished.is_none());
+}
+
+#[test]
+fn output_is_capped() {
+    let timeout = Duration::from_secs(5);
+
+    assert!(exec::run(&mut sh("printf 1234"), timeout, Some(4)).is_ok());
+    assert!(exec::run(&mut sh("printf 12345"), timeout, Some(4)).is_err());
+    assert!(exec::run(&mut sh("yes"), timeout, Some(4)).is_err());
+}
@@ -0,0 +1,113 @@
+use crate::glyphs::GlyphSet;
+use crate::test::AppEnv;
+
+#[test]
+fn pinned_glyph_set_is_untouched() {
+    let env = AppEnv::from([(String::from("TERM"), String::from("linux"))]);
+
+    assert_eq!(GlyphSet::Emoji, GlyphSet::Emoji.resolve(&env));
+}
+
+#[test]
+fn linux_console_is_ascii() {
+    let env = AppEnv::from([(String::from("TERM"), String::from("linux"))]);
+
+    assert_eq!(GlyphSet::Ascii, GlyphSet::Auto.resolve(&env));
+}
+
+#[test]
+fn apple_terminal_is_emoji() {
+    let env = AppEnv::from([
+        (String::from("TERM"), String::from("xterm-256color")),
+        (String::from("TERM_PROGRAM"), String::from("Apple_Terminal")),
+    ]);
+
+    assert_eq!(GlyphSet::Emoji, GlyphSet::Auto.resolve(&env));
+}
+
+#[cfg(feature = "segment-git")]
+#[test]
+fn overridden_symbols_are_kept() {
+    let mut theme = crate::Theme::default();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert_eq!("git", theme.vcs.symbols.git);
+
+    let mut theme = crate::Theme::default();
+    theme.vcs.symbols.git = "(git)".to_string();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert_eq!("(git)", theme.vcs.symbols.git);
+}
+
+#[test]
+fn separators_follow_the_glyph_set() {
+    let mut theme = crate::Theme::default();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert_eq!(">", theme.separator_thick);
+    assert_eq!("|", theme.separator_thin);
+
+    let mut theme = crate::Theme {
+        separator_thick: "\u{e0b4}".to_string(),
+        ..Default::default()
+    };
+    theme.apply_glyph_set(GlyphSet::Emoji);
+    assert_eq!("\u{e0b4}", theme.separator_thick);
+    assert_eq!("\u{276f}", theme.separator_thin);
+}
+
+#[test]
+fn samples_show_both_separators() {
+    //  – thick and  – thin powerline separators
+    let sample = GlyphSet::NerdFont.sample();
+    assert!(sample.contains('\u{e0b0}'));
+    assert!(sample.contains('\u{e0b1}'));
+
+    let sample = GlyphSet::Ascii.sample();
+    assert!(sample.is_ascii());
+    assert!(sample.contains('>'));
+    assert!(sample.contains('|'));
+}
+
+#[test]
+fn choosing_a_glyph_set() {
+    assert_eq!(None, GlyphSet::choose("\n").unwrap());
+    assert_eq!(Some(GlyphSet::NerdFont), GlyphSet::choose("1\n").unwrap());
+    assert_eq!(Some(GlyphSet::Ascii), GlyphSet::choose(" 3 ").unwrap());
+    assert_eq!(Some(GlyphSet::Emoji), GlyphSet::choose("Emoji").unwrap());
+
+    assert!(GlyphSet::choose("0").is_err());
+    assert!(GlyphSet::choose("4").is_err());
+    assert!(GlyphSet::choose("auto").is_err());
+}
+
+#[test]
+fn ascii_defaults_are_ascii() {
+    fn walk(path: &str, value: &serde_json::Value, found: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(object) => object
+                .iter()
+                .for_each(|(key, value)| walk(&format!("{}.{}", path, key), value, found)),
+            serde_json::Value::Array(array) => {
+                array.iter().for_each(|value| walk(path, value, found))
+            }
+            serde_json::Value::String(text) if !text.is_ascii() => found.push(path.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut theme = crate::Theme::default();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+
+    let mut found = vec![];
+    walk(
+        "theme",
+        &crate::to_json_with_defaults(&theme).unwrap(),
+        &mut found,
+    );
+    assert!(found.is_empty(), "{:?}", found);
+
+    let mut theme = crate::Theme::default();
+    theme.username.sudo_indicator = "\u{2248} ".to_string();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert_eq!("\u{2248} ", theme.username.sudo_indicator);
+    assert_eq!("->", theme.username.sudo_separator);
+}
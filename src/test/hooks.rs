@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+use crate::hooks::{Config, Hook, Stage};
+use crate::test::AppEnv;
+
+fn hook(command: &[&str]) -> Hook {
+    Hook {
+        command: command.iter().map(|arg| arg.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn hooks_see_the_exit_code_and_directory() {
+    let env = AppEnv::from([
+        (String::from("code"), String::from("3")),
+        (String::from("PWD"), String::from("/tmp")),
+    ]);
+
+    let check = hook(&[
+        "sh",
+        "-c",
+        r#"test "$PROMPTR_EXIT_CODE" = 3 && test "$PROMPTR_PWD" = /tmp && test "$PROMPTR_HOOK" = post_render"#,
+    ]);
+
+    assert!(check.run(Stage::PostRender, &env).is_ok());
+    assert!(check.run(Stage::PreRender, &env).is_err());
+}
+
+#[test]
+fn slow_hooks_are_killed() {
+    let slow = Hook {
+        timeout_ms: 50,
+        ..hook(&["sleep", "5"])
+    };
+
+    let start = Instant::now();
+    let err = slow.run(Stage::PreRender, &AppEnv::new()).unwrap_err();
+
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(err.to_string().contains("timed out"));
+}
+
+#[test]
+fn every_hook_runs() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("ran");
+
+    let config = Config {
+        pre_render: vec![hook(&["false"]), hook(&["touch", marker.to_str().unwrap()])],
+        post_render: vec![],
+    };
+
+    assert!(config.run(Stage::PreRender, &AppEnv::new()).is_err());
+    assert!(marker.exists());
+    assert!(config.run(Stage::PostRender, &AppEnv::new()).is_ok());
+}
+
+#[test]
+fn post_render_hooks_are_not_waited_on() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("ran");
+
+    let config = Config {
+        pre_render: vec![],
+        post_render: vec![Hook {
+            timeout_ms: 5000,
+            ..hook(&[
+                "sh",
+                "-c",
+                &format!("sleep 1; touch '{}'", marker.display()),
+            ])
+        }],
+    };
+
+    let start = Instant::now();
+    assert!(config.run(Stage::PostRender, &AppEnv::new()).is_ok());
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert!(!marker.exists());
+
+    // It still gets to finish
+    while !marker.exists() && start.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    assert!(marker.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn post_render_hooks_are_timed_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("ran");
+
+    let slow = Hook {
+        timeout_ms: 50,
+        ..hook(&[
+            "sh",
+            "-c",
+            &format!("sleep 2; touch '{}'", marker.display()),
+        ])
+    };
+
+    assert!(slow.spawn(Stage::PostRender, &AppEnv::new()).is_ok());
+
+    // Timeouts are rounded up to a second
+    std::thread::sleep(Duration::from_secs(3));
+    assert!(!marker.exists());
+}
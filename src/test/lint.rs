@@ -0,0 +1,106 @@
+use crate::ansi::Color;
+use crate::glyphs::GlyphSet;
+use crate::lint;
+use crate::{PromptrConfig, Theme};
+
+#[test]
+fn contrast() {
+    let black_on_white = lint::contrast_ratio(Color::Numbered(0), Color::Numbered(15)).unwrap();
+    assert!((black_on_white - 21.0).abs() < 0.01);
+
+    assert_eq!(
+        Some(1.0),
+        lint::contrast_ratio(Color::Numbered(4), Color::Numbered(4))
+    );
+    assert_eq!(None, lint::contrast_ratio(Color::Auto, Color::Numbered(4)));
+}
+
+#[test]
+fn unreadable_theme_colors() {
+    let mut theme = Theme::default();
+    theme.hostname.fg = theme.hostname.bg;
+    theme.load.colors.warn_fg = Color::Numbered(236);
+    theme.load.colors.warn_bg = Color::Numbered(238);
+
+    let warnings = lint::lint_theme_colors(&theme);
+    assert_eq!(2, warnings.len(), "{:?}", warnings);
+    assert!(warnings[0].starts_with("theme.hostname.fg:"));
+    assert!(warnings[1].starts_with("theme.load.colors.warn_fg:"));
+}
+
+#[test]
+fn default_theme_colors() {
+    let warnings = lint::lint_theme_colors(&Theme::default());
+    assert!(warnings.is_empty(), "{:?}", warnings);
+}
+
+#[test]
+fn separators_outside_the_set() {
+    let mut theme = Theme::default();
+    assert!(lint::lint_theme(&theme, GlyphSet::NerdFont).is_empty());
+    assert_eq!(2, lint::lint_theme(&theme, GlyphSet::Emoji).len());
+
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert!(lint::lint_theme(&theme, GlyphSet::Ascii).is_empty());
+}
+
+#[test]
+fn segment_args() {
+    let config: PromptrConfig = serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "segments": [
+                { "name": "paths", "args": { "nope": 1 } },
+                { "name": "hostname" },
+                { "name": "bogus" },
+                { "name": "weather", "args": { "anything": true } }
+            ],
+            "plugins": { "weather": { "command": ["true"] } }
+        }"#,
+    )
+    .unwrap();
+
+    let warnings = lint::lint_segment_args(&config);
+    assert_eq!(2, warnings.len(), "{:?}", warnings);
+    assert!(warnings[0].starts_with("paths: unknown field `nope`"));
+    assert_eq!("bogus: Unknown segment: bogus", warnings[1]);
+}
+
+#[test]
+fn duplicate_segments() {
+    let config: PromptrConfig = serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "segments": [
+                { "name": "paths" },
+                { "name": "path" },
+                { "name": "hostname" },
+                { "name": "git", "args": { "show_stash": false } },
+                { "name": "git" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec!["path: the same segment is listed twice in a row"],
+        lint::lint_config(&config)
+    );
+}
+
+#[test]
+fn themes_for_missing_features() {
+    let raw = serde_json::json!({
+        "theme": { "vcs": {}, "path": {} },
+        "themes": { "dim": { "battery": {} } }
+    });
+
+    let warnings = lint::lint_raw(&raw);
+    // Shared by the git and sapling segments, the latter is always there
+    assert!(!warnings.iter().any(|w| w.starts_with("theme.vcs")));
+    assert_eq!(
+        !cfg!(feature = "segment-battery"),
+        warnings.iter().any(|w| w.starts_with("themes.dim.battery"))
+    );
+    assert!(!warnings.iter().any(|w| w.contains("path")));
+}
@@ -0,0 +1,56 @@
+use crate::notify::{format_duration, Config, Method};
+use crate::test::AppEnv;
+
+fn env(duration_us: &str, code: &str) -> AppEnv {
+    AppEnv::from([
+        (String::from("duration_us"), String::from(duration_us)),
+        (String::from("code"), String::from(code)),
+    ])
+}
+
+#[test]
+fn durations() {
+    assert_eq!("4.2s", format_duration(4.25));
+    assert_eq!("2m5s", format_duration(125.0));
+    assert_eq!("1h1m1s", format_duration(3661.0));
+}
+
+#[test]
+fn below_threshold() {
+    let config = Config::default();
+
+    assert_eq!(None, config.message(&env("9999999", "0")));
+    assert_eq!(None, config.message(&AppEnv::new()));
+}
+
+#[test]
+fn messages() {
+    let config = Config::default();
+
+    assert_eq!(
+        Some(String::from("Command finished after 12.0s")),
+        config.message(&env("12000000", "0"))
+    );
+    assert_eq!(
+        Some(String::from("Command failed with status 2 after 1m30s")),
+        config.message(&env("90000000", "2"))
+    );
+}
+
+#[test]
+fn sequences() {
+    let mut config = Config::default();
+    assert_eq!(
+        Some(String::from("\x1b]9;hi\x07")),
+        config.sequence(&AppEnv::new(), "hi")
+    );
+
+    config.method = Method::Osc777;
+    assert_eq!(
+        Some(String::from("\x1b]777;notify;promptr;hi\x07")),
+        config.sequence(&AppEnv::new(), "hi")
+    );
+
+    config.method = Method::Command;
+    assert_eq!(None, config.sequence(&AppEnv::new(), "hi"));
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::Path;
+
+use crate::platform::{self, ApmBattery};
+
+fn supply(dir: &Path, name: &str, kind: &str, online: &str) {
+    let dir = dir.join(name);
+    fs::create_dir(&dir).unwrap();
+    fs::write(dir.join("type"), format!("{}\n", kind)).unwrap();
+    fs::write(dir.join("online"), format!("{}\n", online)).unwrap();
+}
+
+#[test]
+fn on_ac_power() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // Nothing to ask
+    assert_eq!(None, platform::on_ac_power(&dir.path().join("missing")));
+    assert_eq!(None, platform::on_ac_power(dir.path()));
+
+    // Batteries don't count as chargers
+    supply(dir.path(), "BAT0", "Battery", "1");
+    assert_eq!(None, platform::on_ac_power(dir.path()));
+
+    supply(dir.path(), "AC", "Mains", "0");
+    assert_eq!(Some(false), platform::on_ac_power(dir.path()));
+
+    supply(dir.path(), "ADP1", "Mains", "1");
+    assert_eq!(Some(true), platform::on_ac_power(dir.path()));
+}
+
+#[test]
+fn apm_output() {
+    assert_eq!(
+        Some((87.0, ApmBattery::High)),
+        platform::parse_apm("87\n", "0\n")
+    );
+    assert_eq!(
+        Some((12.0, ApmBattery::Charging)),
+        platform::parse_apm("12\n", "3\n")
+    );
+    assert_eq!(
+        Some((40.0, ApmBattery::Unknown)),
+        platform::parse_apm("40\n", "255\n")
+    );
+
+    // No battery, the percentage is whatever apm feels like
+    assert_eq!(
+        Some((0.0, ApmBattery::Absent)),
+        platform::parse_apm("-1\n", "4\n")
+    );
+
+    assert_eq!(None, platform::parse_apm("-1\n", "255\n"));
+    assert_eq!(None, platform::parse_apm("87\n", ""));
+}
+
+#[test]
+fn memory() {
+    let dir = tempfile::tempdir().unwrap();
+    let meminfo = dir.path().join("meminfo");
+
+    assert_eq!(None, platform::memory(&meminfo));
+
+    fs::write(
+        &meminfo,
+        "MemTotal:       16000000 kB\nMemFree:         1000000 kB\nMemAvailable:    4000000 kB\n",
+    )
+    .unwrap();
+    assert_eq!(
+        Some((16000000 * 1024, 4000000 * 1024)),
+        platform::memory(&meminfo)
+    );
+
+    // Old kernels don't report what's available
+    fs::write(&meminfo, "MemTotal:       16000000 kB\n").unwrap();
+    assert_eq!(None, platform::memory(&meminfo));
+}
+
+#[test]
+fn temperatures() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let zone = |name: &str, kind: &str, temp: &str| {
+        let dir = dir.path().join(name);
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("type"), format!("{}\n", kind)).unwrap();
+        fs::write(dir.join("temp"), format!("{}\n", temp)).unwrap();
+    };
+
+    assert!(platform::temperatures(&dir.path().join("missing")).is_empty());
+
+    zone("thermal_zone1", "x86_pkg_temp", "61500");
+    zone("thermal_zone0", "acpitz", "45000");
+    // Not a thermal zone
+    zone("cooling_device0", "Processor", "0");
+    // Sensors that can't be read are skipped
+    zone("thermal_zone2", "iwlwifi_1", "");
+
+    assert_eq!(
+        vec![
+            ("acpitz".to_string(), 45.0),
+            ("x86_pkg_temp".to_string(), 61.5)
+        ],
+        platform::temperatures(dir.path())
+    );
+}
+
+#[test]
+fn iw_link_output() {
+    let link = "Connected to aa:bb:cc:dd:ee:ff (on wlp2s0)\n\tSSID: Coffee Shop\n\tfreq: 5180\n";
+    assert_eq!(
+        Some("Coffee Shop".to_string()),
+        platform::iw_link_ssid(link)
+    );
+
+    assert_eq!(None, platform::iw_link_ssid("Not connected.\n"));
+}
+
+#[test]
+fn device_attributes_replies() {
+    let reply = b"\x1b]11;rgb:ffff/ffff/ffff\x07\x1b[?62;22c";
+    assert_eq!(Some(24), platform::device_attributes_reply(reply));
+    assert_eq!(Some(0), platform::device_attributes_reply(b"\x1b[?1;2c"));
+
+    // Not all there yet
+    assert_eq!(
+        None,
+        platform::device_attributes_reply(b"\x1b]11;rgb:ffff/ffff/ffff\x07")
+    );
+    assert_eq!(None, platform::device_attributes_reply(b"\x1b[?62;2"));
+}
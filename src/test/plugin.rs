@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use crate::ansi::{Color, EscapeStyle};
+use crate::plugin::Plugin;
+
+fn plugin(script: &str) -> Plugin {
+    Plugin {
+        command: vec!["sh".into(), "-c".into(), script.into()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn parse_one_or_many() {
+    let one = Plugin::parse(br#"{ "text": "hi", "fg": 0, "bg": 220 }"#, EscapeStyle::Raw).unwrap();
+    assert_eq!(1, one.len());
+    assert_eq!("hi", one[0].text);
+    assert_eq!(Color::Numbered(220), one[0].bg);
+
+    let many = Plugin::parse(
+        br#"[{ "text": "a", "fg": 0, "bg": 1, "metadata": { "n": 1 } }, { "text": "b", "fg": 0, "bg": 2 }]"#,
+        EscapeStyle::Raw,
+    )
+    .unwrap();
+    assert_eq!(2, many.len());
+    assert_eq!(
+        serde_json::json!({ "n": 1 }),
+        many[0].metadata.clone().unwrap()
+    );
+
+    assert!(Plugin::parse(b"\n", EscapeStyle::Raw).unwrap().is_empty());
+}
+
+#[test]
+fn parse_rejects_bad_output() {
+    assert!(Plugin::parse(b"plain text", EscapeStyle::Raw).is_err());
+    assert!(Plugin::parse(br#"{ "text": "hi" }"#, EscapeStyle::Raw).is_err());
+    assert!(Plugin::parse(
+        br#"{ "text": "hi", "fg": 0, "bg": 1, "blink": true }"#,
+        EscapeStyle::Raw
+    )
+    .is_err());
+}
+
+#[test]
+fn text_is_not_expanded_by_bash() {
+    let seg = Plugin::parse(
+        br#"{ "text": "$(id)", "fg": 0, "bg": 1 }"#,
+        EscapeStyle::Bash,
+    )
+    .unwrap();
+    assert_eq!(r"\\$(id)", seg[0].text);
+}
+
+#[test]
+fn only_allowed_env_is_passed() {
+    let env = HashMap::from([
+        (String::from("PATH"), std::env::var("PATH").unwrap()),
+        (String::from("SECRET"), String::from("hunter2")),
+    ]);
+
+    let check = plugin(r#"printf '{ "text": "%s", "fg": 0, "bg": 1 }' "${SECRET:-none}""#);
+    let seg = check.run("check", &env, EscapeStyle::Raw).unwrap();
+    assert_eq!("none", seg[0].text);
+
+    let allowed = Plugin {
+        env: vec!["PATH".into(), "SECRET".into()],
+        ..check
+    };
+    let seg = allowed.run("check", &env, EscapeStyle::Raw).unwrap();
+    assert_eq!("hunter2", seg[0].text);
+}
+
+#[test]
+fn limits_are_enforced() {
+    let env = HashMap::from([(String::from("PATH"), std::env::var("PATH").unwrap())]);
+
+    let slow = Plugin {
+        timeout_ms: 20,
+        ..plugin("sleep 5")
+    };
+    let err = slow.run("slow", &env, EscapeStyle::Raw).unwrap_err();
+    assert!(err.to_string().contains("timed out"));
+
+    let chatty = Plugin {
+        max_output_bytes: 16,
+        ..plugin(r#"printf '{ "text": "far too long for this", "fg": 0, "bg": 1 }'"#)
+    };
+    assert!(chatty.run("chatty", &env, EscapeStyle::Raw).is_err());
+
+    assert!(plugin("exit 1")
+        .run("broken", &env, EscapeStyle::Raw)
+        .is_err());
+}
@@ -0,0 +1,99 @@
+use serde_json::json;
+
+use crate::ansi::Color;
+use crate::redact::Redactor;
+use crate::segment::{Segment, Span};
+use crate::test::AppEnv;
+use crate::PromptrConfig;
+
+#[test]
+fn matches_are_masked() {
+    let redactor = Redactor::new(&[r"\d{12}", "prod-[a-z]+"]).unwrap();
+
+    assert_eq!("aws:************", redactor.redact("aws:123456789012"));
+    assert_eq!("*******/web", redactor.redact("prod-eu/web"));
+    assert_eq!("dev-eu", redactor.redact("dev-eu"));
+}
+
+#[test]
+fn metadata_is_masked() {
+    let redactor = Redactor::new(&["secret"]).unwrap();
+
+    let mut segment = Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "a secret")
+        .with_metadata(json!({ "name": "secret", "list": ["secret"], "count": 1 }));
+    redactor.redact_segment(&mut segment);
+
+    assert_eq!("a ******", segment.text);
+    assert_eq!(
+        Some(json!({ "name": "******", "list": ["******"], "count": 1 })),
+        segment.metadata
+    );
+}
+
+#[test]
+fn spans_are_masked() {
+    let redactor = Redactor::new(&["secret"]).unwrap();
+
+    let span = |text: &str| Span {
+        text: text.to_string(),
+        fg: Some(Color::Numbered(2)),
+        ..Default::default()
+    };
+
+    let mut segment = Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "")
+        .with_spans([span("a "), span("secret")]);
+    redactor.redact_segment(&mut segment);
+
+    assert_eq!("a ******", segment.text);
+    assert_eq!(vec![span("a "), span("******")], segment.spans);
+
+    let json = serde_json::to_value(&segment).unwrap();
+    assert_eq!(json!("******"), json["spans"][1]["text"]);
+
+    // Split across two spans there's no telling which color each star should get
+    let mut segment = Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "")
+        .with_spans([span("a sec"), span("ret")]);
+    redactor.redact_segment(&mut segment);
+
+    assert_eq!("a ******", segment.text);
+    assert!(segment.spans.is_empty());
+    assert!(!serde_json::to_string(&segment).unwrap().contains("sec"));
+}
+
+#[test]
+fn toggled_by_the_environment() {
+    let config = PromptrConfig {
+        redact: vec![String::from("secret")],
+        ..Default::default()
+    };
+
+    assert!(config.redactor(&AppEnv::new()).0.is_none());
+
+    let env = AppEnv::from([(String::from("PROMPTR_REDACT"), String::from("1"))]);
+    assert!(config.redactor(&env).0.is_some());
+
+    let config = PromptrConfig {
+        redact_always: true,
+        ..config
+    };
+    assert!(config.redactor(&AppEnv::new()).0.is_some());
+}
+
+#[test]
+fn bad_patterns() {
+    assert!(Redactor::new(&["("]).is_err());
+
+    // The rest still get masked
+    let (redactor, errors) = Redactor::skipping_invalid(&["(", "secret"]);
+    assert_eq!(1, errors.len());
+    assert_eq!("a ******", redactor.redact("a secret"));
+
+    let config = PromptrConfig {
+        redact: vec![String::from("("), String::from("secret")],
+        redact_always: true,
+        ..Default::default()
+    };
+    let (redactor, errors) = config.redactor(&AppEnv::new());
+    assert_eq!(1, errors.len());
+    assert_eq!("a ******", redactor.unwrap().redact("a secret"));
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+use serde_json::json;
+
+use crate::registry::{self, Lookup, ALIASES, BUILTINS};
+
+#[test]
+fn builtins_are_sorted_and_unique() {
+    let names = BUILTINS
+        .iter()
+        .map(|builtin| builtin.name)
+        .collect::<Vec<_>>();
+    let mut sorted = names.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    assert_eq!(sorted, names);
+}
+
+#[test]
+fn unknown_segment() {
+    assert!(matches!(registry::lookup("nope"), Lookup::Unknown));
+    assert!(matches!(registry::lookup("path"), Lookup::Found(_)));
+}
+
+#[test]
+fn features_match_the_build() {
+    for builtin in BUILTINS {
+        let expected = match builtin.feature {
+            Some("segment-battery") => cfg!(feature = "segment-battery"),
+            Some("segment-git") => cfg!(feature = "segment-git"),
+            Some("segment-rvm") => cfg!(feature = "segment-rvm"),
+            Some("segment-tasks") => cfg!(feature = "segment-tasks"),
+            Some("segment-wifi") => cfg!(feature = "segment-wifi"),
+            Some(feature) => panic!(
+                "{} is gated behind unknown feature {}",
+                builtin.name, feature
+            ),
+            None => true,
+        };
+
+        assert_eq!(expected, builtin.enabled(), "{}", builtin.name);
+    }
+}
+
+#[cfg(not(feature = "segment-git"))]
+#[test]
+fn disabled_segment() {
+    assert!(matches!(
+        registry::lookup("git"),
+        Lookup::Disabled("segment-git")
+    ));
+}
+
+#[test]
+fn aliases() {
+    let aliases = BTreeMap::from([
+        (String::from("cwd"), String::from("path")),
+        (String::from("power"), String::from("battery_status")),
+    ]);
+
+    assert_eq!("path", registry::resolve("cwd", &aliases));
+    assert_eq!("battery", registry::resolve("power", &aliases));
+    assert_eq!("path", registry::resolve("paths", &aliases));
+    assert_eq!("git", registry::resolve("git", &aliases));
+}
+
+#[test]
+fn builtin_aliases_point_at_builtins() {
+    for (alias, name) in ALIASES {
+        assert!(
+            BUILTINS.iter().any(|builtin| builtin.name == *name),
+            "{} -> {}",
+            alias,
+            name
+        );
+    }
+}
+
+#[test]
+fn descriptors_cover_every_builtin() {
+    let descriptors = registry::describe_all();
+    assert_eq!(BUILTINS.len(), descriptors.len());
+
+    for descriptor in &descriptors {
+        assert!(!descriptor.doc.is_empty(), "{}", descriptor.name);
+        assert!(!descriptor.doc.starts_with(' '), "{}", descriptor.name);
+        assert_eq!(descriptor.enabled, descriptor.default_args.is_some());
+        assert_eq!(descriptor.enabled, descriptor.default_theme.is_some());
+    }
+}
+
+#[test]
+fn descriptors_write_out_defaults() {
+    let path = registry::describe_all()
+        .into_iter()
+        .find(|descriptor| descriptor.name == "path")
+        .unwrap();
+
+    assert_eq!(vec!["paths"], path.aliases);
+    assert_eq!(None, path.feature);
+    assert_eq!(
+        Some(&json!(true)),
+        path.default_args.as_ref().unwrap().get("show_dir_stack")
+    );
+    assert_eq!(
+        Some(&json!("~")),
+        path.default_theme
+            .as_ref()
+            .unwrap()
+            .get("home_dir_replacement")
+    );
+
+    // Nothing sticks around for regular serialization
+    assert_eq!(
+        json!({}),
+        serde_json::to_value(crate::segment::path::Theme::default()).unwrap()
+    );
+}
+
+#[test]
+fn nested_defaults_are_written_out() {
+    let load = registry::describe_all()
+        .into_iter()
+        .find(|descriptor| descriptor.name == "load")
+        .unwrap();
+
+    // The colors are their own struct inside the theme
+    assert!(load.default_theme.unwrap()["colors"]
+        .get("crit_bg")
+        .is_some());
+}
+
+#[test]
+fn docs_come_from_the_module() {
+    let doc = |name| {
+        registry::describe_all()
+            .into_iter()
+            .find(|descriptor| descriptor.name == name)
+            .unwrap()
+            .doc
+    };
+
+    // "The `Clock` segment shows the time the prompt was drawn"
+    assert_eq!("Shows the time the prompt was drawn", doc("clock"));
+    // The first paragraph runs over more than one line
+    assert!(doc("screen").ends_with("name of the screen"));
+}
@@ -0,0 +1,589 @@
+use crate::ansi::{Color, EscapeStyle, TextStyle};
+use crate::glyphs::GlyphSet;
+use crate::render::{
+    clamp, expand_fills, fit, place_right, prompt_width, render_accessible, render_lines,
+    render_plain, render_prompt, text_width, Stanza,
+};
+use crate::segment::{Kind, Segment, Span};
+use crate::shell::Shell;
+use crate::{Separator, Theme};
+
+#[test]
+fn empty_prompt_width() {
+    assert_eq!(1, prompt_width(&[], &Theme::default()));
+}
+
+#[test]
+fn padding_and_separators_are_counted() {
+    let segments = vec![
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "user"),
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(2), r"\$"),
+    ];
+
+    // " user " + sep + " $ " + sep + " "
+    assert_eq!(
+        6 + 1 + 3 + 1 + 1,
+        prompt_width(&segments, &Theme::default())
+    );
+}
+
+#[test]
+fn end_cap_is_measured() {
+    let segments = vec![
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "user"),
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(2), r"\$"),
+    ];
+
+    // 🔥 – fire is two columns wide
+    let theme = Theme {
+        separator_end: Some("\u{1f525}".into()),
+        ..Default::default()
+    };
+
+    // " user " + sep + " $ " + cap + " "
+    assert_eq!(6 + 1 + 3 + 2 + 1, prompt_width(&segments, &theme));
+}
+
+#[test]
+fn wide_characters() {
+    // 📚 – stack of books is two columns wide
+    assert_eq!(4, text_width("2 \u{1f4da}"));
+    // é as e + combining acute
+    assert_eq!(4, text_width("cafe\u{301}"));
+}
+
+fn ascii_theme() -> Theme {
+    let mut theme = Theme::default();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    theme
+}
+
+#[test]
+fn lines_are_joined_per_shell() {
+    let lines = vec![
+        vec![Segment::new(
+            "Test",
+            Color::Numbered(0),
+            Color::Numbered(1),
+            "~",
+        )],
+        vec![Segment::new(
+            "Test",
+            Color::Numbered(0),
+            Color::Numbered(2),
+            r"\$",
+        )],
+    ];
+
+    let bash = render_lines(&lines, &ascii_theme(), Shell::Bash, EscapeStyle::Bash);
+    assert_eq!(1, bash.matches(r"\n").count());
+    assert!(!bash.contains('\n'));
+
+    let sh = render_lines(&lines, &ascii_theme(), Shell::PosixSh, EscapeStyle::Raw);
+    assert_eq!(1, sh.matches('\n').count());
+    assert!(sh.ends_with(" $ \x1b[0m\x1b[38;5;2m>\x1b[0m "));
+
+    // An overridden escape style decides how lines are joined, not the shell
+    let raw = render_lines(&lines, &ascii_theme(), Shell::Bash, EscapeStyle::Raw);
+    assert_eq!(1, raw.matches('\n').count());
+    assert!(!raw.contains(r"\n"));
+
+    let tmux = render_lines(&lines, &ascii_theme(), Shell::Bash, EscapeStyle::Tmux);
+    assert!(!tmux.contains('\n'));
+    assert!(!tmux.contains(r"\n"));
+}
+
+#[test]
+fn empty_prompt_falls_back() {
+    let lines = vec![vec![], vec![]];
+
+    assert_eq!(
+        "\\[\\e[0m\\]\\$ ",
+        render_lines(&lines, &Theme::default(), Shell::Bash, EscapeStyle::Bash)
+    );
+    assert_eq!(
+        "\x1b[0m$ ",
+        render_lines(&lines, &Theme::default(), Shell::PosixSh, EscapeStyle::Raw)
+    );
+
+    // A blank line is fine as long as something else is shown
+    let lines = vec![
+        vec![],
+        vec![Segment::new(
+            "Test",
+            Color::Numbered(0),
+            Color::Numbered(1),
+            "~",
+        )],
+    ];
+    let prompt = render_lines(&lines, &Theme::default(), Shell::PosixSh, EscapeStyle::Raw);
+    assert!(prompt.starts_with("\x1b[0m \n"));
+}
+
+#[test]
+fn separator_override_wins() {
+    let mut first = Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "a");
+    first.separator_override = Some(Separator::Custom("=>".into()));
+    let mut second = Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "b");
+    second.separator_override = Some(Separator::None);
+    let segments = vec![first, second];
+
+    // " a " + "=>" + " b " + nothing + " "
+    assert_eq!(3 + 2 + 3 + 1, prompt_width(&segments, &Theme::default()));
+
+    // Same background would normally get a thin separator
+    let prompt = render_lines(
+        &[segments],
+        &ascii_theme(),
+        Shell::PosixSh,
+        EscapeStyle::Raw,
+    );
+    assert!(prompt.contains(" a \x1b[48;5;1m\x1b[38;5;1m=>"));
+    assert!(!prompt.contains('|'));
+    assert!(prompt.ends_with(" b \x1b[0m\x1b[38;5;1m\x1b[0m "));
+}
+
+#[test]
+fn theme_separators_and_end_cap() {
+    let segments = vec![
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "a"),
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "b"),
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(2), "c"),
+    ];
+
+    let theme = Theme {
+        separator_thick: "}".into(),
+        separator_thin: ")".into(),
+        separator_end: Some("]".into()),
+        ..Default::default()
+    };
+
+    let prompt = render_lines(&[segments], &theme, Shell::PosixSh, EscapeStyle::Raw);
+    assert!(prompt.contains(" a \x1b[48;5;1m\x1b[38;5;244m)"));
+    assert!(prompt.contains(" b \x1b[48;5;2m\x1b[38;5;1m}"));
+    assert!(prompt.ends_with(" c \x1b[0m\x1b[38;5;2m]\x1b[0m "));
+}
+
+#[test]
+fn prefix_suffix_and_padding() {
+    let segments = vec![Segment::new(
+        "Test",
+        Color::Numbered(0),
+        Color::Numbered(1),
+        "~",
+    )];
+
+    let theme = Theme {
+        prefix: "[".into(),
+        suffix: "] ".into(),
+        padding: 2,
+        ..ascii_theme()
+    };
+
+    // "[" + "  ~  " + ">" + "] "
+    assert_eq!(1 + 5 + 1 + 2, prompt_width(&segments, &theme));
+
+    let prompt = render_lines(&[segments], &theme, Shell::PosixSh, EscapeStyle::Raw);
+    assert!(prompt.starts_with("[\x1b[38;5;0m\x1b[48;5;1m  ~  \x1b[0m"));
+    assert!(prompt.ends_with(">\x1b[0m] "));
+}
+
+#[test]
+fn capsules() {
+    let lines = vec![vec![Segment::new(
+        "Test",
+        Color::Numbered(0),
+        Color::Numbered(1),
+        "~",
+    )]];
+
+    let theme = Theme {
+        separator_start: Some("(".into()),
+        separator_end: Some(")".into()),
+        ..Default::default()
+    };
+
+    // "(" + " ~ " + ")" + " "
+    assert_eq!(1 + 3 + 1 + 1, prompt_width(&lines[0], &theme));
+
+    let prompt = render_lines(&lines, &theme, Shell::PosixSh, EscapeStyle::Raw);
+    assert!(prompt.starts_with("\x1b[0m\x1b[38;5;1m(\x1b[38;5;0m\x1b[48;5;1m ~ "));
+    assert!(prompt.ends_with(" ~ \x1b[0m\x1b[38;5;1m)\x1b[0m "));
+
+    let theme = Theme {
+        bar_bg: Some(Color::Numbered(236)),
+        ..theme
+    };
+    let prompt = render_lines(&lines, &theme, Shell::PosixSh, EscapeStyle::Raw);
+    assert!(prompt.starts_with("\x1b[0m\x1b[48;5;236m\x1b[38;5;1m("));
+    assert!(prompt.ends_with(" ~ \x1b[0m\x1b[48;5;236m\x1b[38;5;1m)\x1b[0m "));
+}
+
+#[test]
+fn accessible_output() {
+    let seg = |source, text| Segment::new(source, Color::Numbered(0), Color::Numbered(1), text);
+
+    let lines = vec![
+        vec![
+            seg("Path::First::Home", "~"),
+            seg("Path::Middle::Normal", "src"),
+            seg("Path::Last::Normal", "promptr"),
+            //  – branch
+            seg("Git::Branch", "\u{e0a0} main"),
+            // ✎ – pencil
+            seg("Git::Changed", "2\u{270e}"),
+            seg("Git::Merge", "\u{270e}"),
+        ],
+        vec![seg("CommandStatus", r"\$")],
+    ];
+
+    assert_eq!(
+        r"[dir ~/src/promptr] [git main, 2 changed, merge]\n[status \$] ",
+        render_accessible(&lines, &Theme::default(), Shell::Bash, EscapeStyle::Bash)
+    );
+    assert_eq!(
+        "[dir ~/src/promptr] [git main, 2 changed, merge]\n[status $] ",
+        render_accessible(&lines, &Theme::default(), Shell::PosixSh, EscapeStyle::Raw)
+    );
+
+    // Only paths have their slashes squeezed together
+    let lines = vec![vec![
+        seg("Path::First::Root", "/"),
+        seg("Path::Last::Normal", "etc"),
+        seg("Command", "https://example.com"),
+    ]];
+    assert_eq!(
+        "[dir /etc] [command https://example.com] ",
+        render_accessible(&lines, &Theme::default(), Shell::PosixSh, EscapeStyle::Raw)
+    );
+
+    assert_eq!(
+        "$ ",
+        render_accessible(
+            &[vec![]],
+            &Theme::default(),
+            Shell::PosixSh,
+            EscapeStyle::Raw
+        )
+    );
+}
+
+#[test]
+fn plain_prompt() {
+    let seg = |text: &str| Segment::new("Test", Color::Numbered(0), Color::Numbered(1), text);
+
+    let lines = vec![
+        vec![
+            seg("12:00").with_kind(Kind::Right),
+            seg("~/src"),
+            seg("---").with_kind(Kind::Fill),
+            seg(""),
+            seg("main").with_style([TextStyle::Bold]),
+        ],
+        vec![seg(r"\$")],
+    ];
+
+    let prompt = render_plain(&lines, &Theme::default(), Shell::Bash, EscapeStyle::Bash);
+    assert_eq!(r"~/src | main | 12:00\n\$ ", prompt);
+    assert!(!prompt.contains('\x1b'));
+
+    assert_eq!(
+        "~/src | main | 12:00\n$ ",
+        render_plain(&lines, &Theme::default(), Shell::PosixSh, EscapeStyle::Raw)
+    );
+    assert_eq!(
+        "$ ",
+        render_plain(
+            &[vec![]],
+            &Theme::default(),
+            Shell::PosixSh,
+            EscapeStyle::Raw
+        )
+    );
+}
+
+#[test]
+fn spans_are_colored() {
+    let spans = vec![
+        Span {
+            text: String::from("+2"),
+            fg: Some(Color::Numbered(76)),
+            ..Default::default()
+        },
+        Span::from(" "),
+        Span {
+            text: String::from("!"),
+            bg: Some(Color::Numbered(1)),
+            style: Some(TextStyle::Bold),
+            ..Default::default()
+        },
+    ];
+    let mut segments = vec![
+        Segment::new("Test", Color::Numbered(250), Color::Numbered(236), "").with_spans(spans),
+    ];
+    assert_eq!("+2 !", segments[0].text);
+
+    let prompt = render_prompt(&segments, &ascii_theme(), Shell::PosixSh, EscapeStyle::Raw);
+    assert!(prompt.contains(concat!(
+        " \x1b[38;5;76m+2\x1b[38;5;250m \x1b[38;5;250m\x1b[48;5;1m",
+        "\x1b[1m!\x1b[22m\x1b[48;5;236m "
+    )));
+
+    // Rewriting the text drops the colors
+    segments[0].text = String::from("+…");
+    let prompt = render_prompt(&segments, &ascii_theme(), Shell::PosixSh, EscapeStyle::Raw);
+    assert!(!prompt.contains("\x1b[38;5;76m"));
+    assert!(prompt.contains(" +… "));
+}
+
+#[test]
+fn segment_style() {
+    let segments = vec![
+        Segment::new("Test", Color::Numbered(250), Color::Numbered(236), "")
+            .with_spans([
+                Span::from("main "),
+                Span {
+                    text: String::from("!"),
+                    style: Some(TextStyle::Dim),
+                    ..Default::default()
+                },
+            ])
+            .with_style([TextStyle::Bold, TextStyle::Underline]),
+    ];
+
+    let prompt = render_prompt(&segments, &ascii_theme(), Shell::PosixSh, EscapeStyle::Raw);
+    assert!(prompt.contains(concat!(
+        "\x1b[1m\x1b[4m\x1b[38;5;250mmain \x1b[38;5;250m\x1b[2m!\x1b[22m",
+        "\x1b[1m\x1b[4m\x1b[22m\x1b[24m "
+    )));
+}
+
+fn stanza(priority: i32, truncate: bool, text: &str) -> Stanza {
+    Stanza {
+        priority,
+        truncate,
+        segments: vec![Segment::new(
+            "Test",
+            Color::Numbered(0),
+            Color::Numbered(1),
+            text,
+        )],
+    }
+}
+
+fn texts(segments: &[Segment]) -> Vec<&str> {
+    segments.iter().map(|seg| seg.text.as_str()).collect()
+}
+
+#[test]
+fn fit_leaves_narrow_prompts_alone() {
+    let stanzas = vec![stanza(0, false, "user"), stanza(0, false, "~")];
+
+    assert_eq!(
+        vec!["user", "~"],
+        texts(&fit(stanzas, 80, &Theme::default()))
+    );
+}
+
+#[test]
+fn fit_drops_lowest_priority_from_the_right() {
+    // Each stanza is 8 columns wide once padded, plus a trailing space
+    let stanzas = vec![
+        stanza(0, false, "aaaa"),
+        stanza(1, false, "bbbb"),
+        stanza(0, false, "cccc"),
+    ];
+
+    assert_eq!(
+        vec!["aaaa", "bbbb"],
+        texts(&fit(stanzas.clone(), 20, &Theme::default()))
+    );
+    assert_eq!(
+        vec!["bbbb"],
+        texts(&fit(stanzas.clone(), 10, &Theme::default()))
+    );
+    // The highest priority stanza is kept no matter what
+    assert_eq!(vec!["bbbb"], texts(&fit(stanzas, 1, &Theme::default())));
+}
+
+#[test]
+fn fit_truncates_before_dropping() {
+    let stanzas = vec![stanza(0, true, "/usr/local/src"), stanza(1, false, "$")];
+
+    // 17 + 4 + 1 = 22, two columns over
+    assert_eq!(
+        vec!["/usr/local/\u{2026}", "$"],
+        texts(&fit(stanzas.clone(), 20, &Theme::default()))
+    );
+    // Can't truncate enough, so it's dropped
+    assert_eq!(vec!["$"], texts(&fit(stanzas, 6, &Theme::default())));
+}
+
+#[test]
+fn fit_truncates_wide_characters() {
+    // 😀 is two columns wide, so cutting one column drops it and the character after
+    let stanzas = vec![stanza(0, true, "ab\u{1f600}c"), stanza(1, false, "$$$$")];
+
+    // 8 + 7 + 1 = 16, one column over
+    assert_eq!(
+        vec!["ab\u{2026}", "$$$$"],
+        texts(&fit(stanzas, 15, &Theme::default()))
+    );
+}
+
+#[test]
+fn fills_pad_to_the_terminal_width() {
+    let mut segments = vec![
+        // 📚 – stack of books is two columns wide
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "\u{1f4da}"),
+        Segment::fill("Test", Color::Numbered(0), Color::Numbered(2), '-'),
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(3), r"\$"),
+    ];
+
+    expand_fills(&mut segments, 40, &Theme::default());
+
+    assert_eq!(40, prompt_width(&segments, &Theme::default()));
+    assert!(segments[1].text.chars().all(|c| c == '-'));
+}
+
+#[test]
+fn fills_share_the_room() {
+    let mut segments = vec![
+        Segment::fill("Test", Color::Numbered(0), Color::Numbered(1), ' '),
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(2), "x"),
+        Segment::fill("Test", Color::Numbered(0), Color::Numbered(1), ' '),
+    ];
+
+    expand_fills(&mut segments, 20, &Theme::default());
+
+    assert_eq!(20, prompt_width(&segments, &Theme::default()));
+    assert_eq!(5, segments[0].text.len());
+    assert_eq!(4, segments[2].text.len());
+}
+
+#[test]
+fn fills_never_shrink_below_nothing() {
+    let mut segments = vec![
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "wide"),
+        Segment::fill("Test", Color::Numbered(0), Color::Numbered(2), ' '),
+    ];
+
+    expand_fills(&mut segments, 2, &Theme::default());
+
+    assert_eq!("", segments[1].text);
+}
+
+#[test]
+fn clamp_cuts_from_the_left() {
+    let theme = Theme::default();
+    let segments = vec![
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "user"),
+        Segment::new(
+            "Test",
+            Color::Numbered(0),
+            Color::Numbered(2),
+            "~/src/promptr",
+        ),
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(3), r"\$"),
+    ];
+
+    // " user " + sep + " ~/src/promptr " + sep + " $ " + sep + " "
+    assert_eq!(7 + 16 + 4 + 1, prompt_width(&segments, &theme));
+
+    let mut clamped = segments.clone();
+    clamp(&mut clamped, 40, &theme);
+    assert_eq!(segments, clamped);
+
+    let mut clamped = segments.clone();
+    clamp(&mut clamped, 15, &theme);
+    assert_eq!(vec!["\u{2026}romptr", r"\$"], texts(&clamped));
+    assert_eq!(15, prompt_width(&clamped, &theme));
+
+    // The prompt indicator is never dropped
+    let mut clamped = segments;
+    clamp(&mut clamped, 2, &theme);
+    assert_eq!(vec![r"\$"], texts(&clamped));
+}
+
+fn right_edge_line() -> Vec<Segment> {
+    vec![
+        Segment::new("Test", Color::Numbered(0), Color::Numbered(1), "~"),
+        Segment::new("Clock", Color::Numbered(2), Color::Numbered(3), "12:00")
+            .with_kind(Kind::Right),
+    ]
+}
+
+#[test]
+fn right_edge_takes_no_room() {
+    let theme = ascii_theme();
+    let segments = right_edge_line();
+
+    let prompt = render_prompt(&segments, &theme, Shell::Bash, EscapeStyle::Bash);
+    assert!(prompt.starts_with(r"\[\e7\e[999C\e[6D\e[38;5;2m\e[48;5;3m 12:00 \e[0m\e8\]"));
+
+    // The rest of the line is drawn as if the clock weren't there
+    let left = render_prompt(&segments[..1], &theme, Shell::Bash, EscapeStyle::Bash);
+    assert!(prompt.ends_with(&left));
+
+    // tmux can't move the cursor around in its status line
+    let prompt = render_prompt(&segments, &theme, Shell::Bash, EscapeStyle::Tmux);
+    assert!(!prompt.contains("12:00"));
+}
+
+#[test]
+fn right_edge_needs_room() {
+    let theme = ascii_theme();
+
+    // " ~ > " and " 12:00 " leave one column free
+    let mut segments = right_edge_line();
+    place_right(&mut segments, Some(13), &theme);
+    assert_eq!(2, segments.len());
+
+    let mut segments = right_edge_line();
+    place_right(&mut segments, Some(12), &theme);
+    assert_eq!(vec!["~"], texts(&segments));
+
+    let mut segments = right_edge_line();
+    place_right(&mut segments, None, &theme);
+    assert_eq!(vec!["~"], texts(&segments));
+}
+
+#[test]
+fn right_edge_is_not_measured() {
+    let theme = ascii_theme();
+
+    // A right edge segment between two others doesn't change the separator the first one gets
+    let mut segments = right_edge_line();
+    segments.push(Segment::new(
+        "Test",
+        Color::Numbered(0),
+        Color::Numbered(1),
+        "$",
+    ));
+
+    let left = segments
+        .iter()
+        .filter(|seg| seg.kind != Kind::Right)
+        .cloned()
+        .collect::<Vec<_>>();
+    assert_eq!(prompt_width(&left, &theme), prompt_width(&segments, &theme));
+
+    // Only the last segment on the left is always kept, the clock is left to place_right
+    let mut clamped = segments;
+    clamp(&mut clamped, 1, &theme);
+    assert_eq!(vec!["12:00", "$"], texts(&clamped));
+}
+
+#[test]
+fn fills_leave_room_for_the_right_edge() {
+    let mut segments = right_edge_line();
+    segments.insert(
+        1,
+        Segment::fill("Test", Color::Numbered(0), Color::Numbered(2), ' '),
+    );
+
+    expand_fills(&mut segments, 20, &ascii_theme());
+
+    // " ~ >" + "  >" + " 12:00 " + " "
+    assert_eq!(5, segments[1].text.len());
+}
@@ -0,0 +1,178 @@
+use crate::ansi::{Background, Color};
+use crate::cache::Cache;
+use crate::prompt::detect_background;
+use crate::schedule::TimeOfDay;
+use crate::test::AppEnv;
+use crate::PromptrConfig;
+
+fn config() -> PromptrConfig {
+    serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "theme": { "path": { "bg": 31 } },
+            "themes": { "dim": { "path": { "bg": 236 } } },
+            "schedule": [
+                { "from": "20:00", "theme": "dim" },
+                { "from": "00:00", "theme": "dim" },
+                { "from": "07:30", "theme": "default" }
+            ]
+        }"#,
+    )
+    .unwrap()
+}
+
+fn at(time: &str) -> TimeOfDay {
+    TimeOfDay::try_from(time.to_string()).unwrap()
+}
+
+#[test]
+fn times_of_day() {
+    assert_eq!(TimeOfDay(7 * 60 + 5), at("07:05"));
+    assert_eq!("07:05", at("7:05").to_string());
+    assert!(TimeOfDay::try_from(String::from("24:00")).is_err());
+    assert!(TimeOfDay::try_from(String::from("noon")).is_err());
+}
+
+#[test]
+fn latest_entry_wins() {
+    let path_bg = |time| {
+        let mut config = config();
+        config.apply_schedule(at(time)).unwrap();
+        config.theme.path.bg
+    };
+
+    assert_eq!(path_bg("03:00"), config().themes["dim"].path.bg);
+    assert_eq!(path_bg("12:00"), config().theme.path.bg);
+    assert_eq!(path_bg("23:59"), config().themes["dim"].path.bg);
+}
+
+#[test]
+fn unknown_theme() {
+    let mut config = config();
+    config.themes.clear();
+
+    assert!(config.apply_schedule(at("21:00")).is_err());
+}
+
+fn dim_from(schedule: &str, time: &str) -> bool {
+    let mut config: PromptrConfig = serde_json::from_str(&format!(
+        r#"{{
+            "promptr_config": 12,
+            "theme": {{ "path": {{ "bg": 31 }} }},
+            "themes": {{ "dim": {{ "path": {{ "bg": 236 }} }} }},
+            "schedule": {}
+        }}"#,
+        schedule
+    ))
+    .unwrap();
+
+    config.apply_schedule(at(time)).unwrap();
+    config.theme.path.bg == config.themes["dim"].path.bg
+}
+
+#[test]
+fn entries_run_past_midnight() {
+    let schedule = r#"[{ "from": "20:00", "theme": "dim" }]"#;
+
+    assert!(dim_from(schedule, "23:59"));
+    assert!(dim_from(schedule, "00:01"));
+    // Nothing else takes over, so it never ends
+    assert!(dim_from(schedule, "12:00"));
+}
+
+#[test]
+fn entries_end_at_to() {
+    let schedule = r#"[{ "from": "20:00", "to": "07:30", "theme": "dim" }]"#;
+
+    assert!(!dim_from(schedule, "19:59"));
+    assert!(dim_from(schedule, "23:59"));
+    assert!(dim_from(schedule, "00:01"));
+    assert!(!dim_from(schedule, "07:30"));
+    assert!(!dim_from(schedule, "12:00"));
+}
+
+#[test]
+fn latest_start_wins_overlaps() {
+    let schedule = r#"[
+        { "from": "18:00", "to": "02:00", "theme": "dim" },
+        { "from": "22:00", "to": "23:00", "theme": "default" }
+    ]"#;
+
+    assert!(dim_from(schedule, "21:59"));
+    assert!(!dim_from(schedule, "22:30"));
+    assert!(dim_from(schedule, "23:30"));
+    assert!(dim_from(schedule, "00:01"));
+    assert!(!dim_from(schedule, "02:00"));
+}
+
+#[test]
+fn theme_for_background() {
+    let mut config: PromptrConfig = serde_json::from_str(
+        r#"{
+            "promptr_config": 12,
+            "theme": { "path": { "bg": 31 } },
+            "themes": { "paper": { "path": { "bg": 254 } } },
+            "theme_light": "paper",
+            "theme_dark": "default"
+        }"#,
+    )
+    .unwrap();
+
+    config.apply_background(Background::Dark).unwrap();
+    assert_eq!(Color::Numbered(31), config.theme.path.bg);
+
+    config.apply_background(Background::Light).unwrap();
+    assert_eq!(Color::Numbered(254), config.theme.path.bg);
+
+    config.theme_light = Some(String::from("missing"));
+    assert!(config.apply_background(Background::Light).is_err());
+}
+
+#[test]
+fn background_themes() {
+    let mut config = PromptrConfig::default();
+    assert!(!config.has_background_themes());
+
+    config.theme_dark = Some(String::from("default"));
+    assert!(!config.has_background_themes());
+
+    config.theme_light = Some(String::from("paper"));
+    assert!(config.has_background_themes());
+}
+
+#[cfg(unix)]
+#[test]
+fn background_is_asked_once_per_session() {
+    let dir = tempfile::tempdir().unwrap();
+    let env = AppEnv::from([(
+        String::from("PROMPTR_CACHE_DIR"),
+        dir.path().display().to_string(),
+    )]);
+
+    let key = Cache::key("background", &crate::platform::session_id().unwrap());
+    Cache::new(dir.path())
+        .set(&key, &Some(Background::Light))
+        .unwrap();
+
+    assert_eq!(Some(Background::Light), detect_background(&env));
+
+    // The environment still wins
+    let mut env = env;
+    env.insert(String::from("COLORFGBG"), String::from("15;0"));
+    assert_eq!(Some(Background::Dark), detect_background(&env));
+}
+
+#[test]
+fn theme_from_env() {
+    let env = |name: &str| AppEnv::from([(String::from("PROMPTR_THEME"), String::from(name))]);
+
+    // Beats the schedule, whatever the time
+    let mut dim = config();
+    crate::prompt::select_theme(&mut dim, &env("dim"));
+    assert_eq!(Color::Numbered(236), dim.theme.path.bg);
+
+    let mut base = config();
+    base.theme_dark = Some(String::from("dim"));
+    crate::prompt::select_theme(&mut base, &env("default"));
+    assert_eq!(Color::Numbered(31), base.theme.path.bg);
+}
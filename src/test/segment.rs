@@ -1,10 +1,38 @@
+mod aws;
+#[cfg(feature = "segment-battery")]
+mod battery_status;
+mod bookmark;
+mod clock;
+mod command;
 mod command_status;
+mod container;
+mod cpu_time;
+mod disk;
+mod exit_code;
+mod fill;
+mod frecency;
 #[cfg(feature = "segment-git")]
 mod git;
 mod hostname;
+mod identity;
+mod instance;
+mod load;
+mod memory;
+mod nix;
+mod os;
 mod path;
+mod rbenv;
+mod sapling;
 mod screen;
+#[cfg(feature = "segment-tasks")]
+mod tasks;
+mod terminal;
+mod thermal;
 mod username;
+mod vcs;
+#[cfg(feature = "segment-wifi")]
+mod wifi;
+mod wsl;
 
 /// Expand a JSON string literal into a strongly typed object or None if we pass None.
 macro_rules! test_args {
@@ -78,6 +106,9 @@ macro_rules! declare_segement_test {
                             let state = ApplicationState {
                                 theme: &Theme::default(),
                                 env,
+                                columns: None,
+                                escape_style: crate::ansi::EscapeStyle::Bash,
+                                demo: false,
                             };
                             $body(args, state);
                         }
@@ -0,0 +1,79 @@
+use crate::segment::{aws::Aws, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("AWS_PROFILE", "staging"), ("AWS_REGION", "us-west-2"),]);
+
+#[test]
+fn timestamps() {
+    assert_eq!(Some(0), Aws::parse_timestamp("1970-01-01T00:00:00Z"));
+    assert_eq!(
+        Some(1714566896),
+        Aws::parse_timestamp("2024-05-01T12:34:56Z")
+    );
+    assert_eq!(
+        Some(1714566896),
+        Aws::parse_timestamp("2024-05-01T05:34:56.123-07:00")
+    );
+    assert_eq!(None, Aws::parse_timestamp("2024-05-01"));
+    assert_eq!(None, Aws::parse_timestamp("2024-13-01T00:00:00Z"));
+
+    // Nothing that overflows
+    assert_eq!(
+        None,
+        Aws::parse_timestamp("9223372036854775807-01-01T00:00:00Z")
+    );
+    assert_eq!(
+        None,
+        Aws::parse_timestamp("-9223372036854775808-01-01T00:00:00Z")
+    );
+    assert_eq!(
+        None,
+        Aws::parse_timestamp("2024-05-01T00:00:00+9223372036854775807:00")
+    );
+    assert_eq!(None, Aws::parse_timestamp("2024-05-01T-1:00:00Z"));
+}
+
+segment_test! {
+    fn profile() {
+        |args, mut state : ApplicationState| {
+            let seg = Aws::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} staging", state.theme.aws.symbol), seg[0].text);
+            assert_eq!(state.theme.aws.bg, seg[0].bg);
+
+            // aws-vault wins
+            state.env.insert(String::from("AWS_VAULT"), String::from("prod"));
+            let seg = Aws::to_segment_generic(None, &state).unwrap();
+            assert_eq!(format!("{} prod", state.theme.aws.symbol), seg[0].text);
+
+            state.env.clear();
+            assert!(Aws::to_segment_generic(None, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn region() {
+        let args = r#"{"show_region": true}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Aws::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} staging us-west-2", state.theme.aws.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn expiring_credentials() {
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("AWS_SESSION_EXPIRATION"), String::from("2999-01-01T00:00:00Z"));
+            let seg = Aws::to_segment_generic(None, &state).unwrap();
+            assert_eq!(state.theme.aws.bg, seg[0].bg);
+
+            state.env.insert(String::from("AWS_SESSION_EXPIRATION"), String::from("2000-01-01T00:00:00Z"));
+            let seg = Aws::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.aws.expiring_bg, seg[0].bg);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use crate::segment::battery_status::{Args, BatteryStatus, Cell, Charge, Reading, ShowTime};
+
+/// 50Wh and 20Wh packs, in joules
+const BIG: f32 = 50.0 * 3600.0;
+const SMALL: f32 = 20.0 * 3600.0;
+
+fn cell(energy: f32, energy_full: f32, energy_rate: f32, charge: Charge) -> Cell {
+    Cell {
+        energy,
+        energy_full,
+        energy_rate,
+        charge,
+    }
+}
+
+#[test]
+fn no_batteries() {
+    assert_eq!(None, Reading::combine(&[]));
+}
+
+#[test]
+fn unreadable_batteries_are_skipped() {
+    let reading = Reading::combine_readable(vec![
+        Err(anyhow::anyhow!("unreadable")),
+        Ok(cell(BIG / 2.0, BIG, 10.0, Charge::Discharging)),
+    ])
+    .unwrap()
+    .unwrap();
+    assert_eq!(50.0, reading.percent);
+    assert_eq!(1, reading.batteries);
+
+    assert!(Reading::combine_readable(vec![Err(anyhow::anyhow!("unreadable"))]).is_err());
+    assert_eq!(None, Reading::combine_readable(vec![]).unwrap());
+}
+
+#[test]
+fn one_battery() {
+    let reading = Reading::combine(&[cell(BIG / 2.0, BIG, 10.0, Charge::Discharging)]).unwrap();
+
+    assert_eq!(50.0, reading.percent);
+    assert_eq!(Charge::Discharging, reading.charge);
+    assert_eq!(Some(9000), reading.time_left.map(|t| t.as_secs()));
+    assert_eq!(1, reading.batteries);
+}
+
+#[test]
+fn weighted_by_capacity() {
+    // The empty internal battery is being used up first, the full external one is idle
+    let reading = Reading::combine(&[
+        cell(0.0, SMALL, 0.0, Charge::Empty),
+        cell(BIG, BIG, 14.0, Charge::Discharging),
+    ])
+    .unwrap();
+
+    assert_eq!(Charge::Discharging, reading.charge);
+    assert_eq!(2, reading.batteries);
+    assert!((reading.percent - 71.43).abs() < 0.01);
+    assert_eq!(Some(12857), reading.time_left.map(|t| t.as_secs()));
+}
+
+#[test]
+fn charging_wins() {
+    let reading = Reading::combine(&[
+        cell(SMALL, SMALL, 0.0, Charge::Full),
+        cell(BIG / 2.0, BIG, 25.0, Charge::Charging),
+    ])
+    .unwrap();
+
+    assert_eq!(Charge::Charging, reading.charge);
+    assert_eq!(Some(3600), reading.time_left.map(|t| t.as_secs()));
+}
+
+#[test]
+fn full_only_when_all_are() {
+    let full = Reading::combine(&[
+        cell(SMALL, SMALL, 0.0, Charge::Full),
+        cell(BIG, BIG, 0.0, Charge::Full),
+    ])
+    .unwrap();
+    assert_eq!(Charge::Full, full.charge);
+    assert_eq!(None, full.time_left);
+
+    let mixed = Reading::combine(&[
+        cell(SMALL, SMALL, 0.0, Charge::Full),
+        cell(BIG * 0.8, BIG, 0.0, Charge::Unknown),
+    ])
+    .unwrap();
+    assert_eq!(Charge::Unknown, mixed.charge);
+}
+
+#[test]
+fn no_rate_no_estimate() {
+    let reading = Reading::combine(&[cell(BIG / 2.0, BIG, 0.0, Charge::Discharging)]).unwrap();
+
+    assert_eq!(None, reading.time_left);
+}
+
+#[test]
+fn time_formats() {
+    let time_left = Duration::from_secs(2 * 3600 + 5 * 60 + 59);
+
+    assert_eq!("2:05", BatteryStatus::format_time("%h:%M", time_left));
+    assert_eq!("2h5m", BatteryStatus::format_time("%hh%mm", time_left));
+    assert_eq!("125 min", BatteryStatus::format_time("%t min", time_left));
+    assert_eq!("100% %x", BatteryStatus::format_time("100%% %x", time_left));
+}
+
+#[test]
+fn when_time_is_shown() {
+    let discharging = Reading {
+        percent: 40.0,
+        charge: Charge::Discharging,
+        time_left: Some(Duration::from_secs(90 * 60)),
+        batteries: 1,
+    };
+    let charging = Reading {
+        charge: Charge::Charging,
+        ..discharging
+    };
+
+    let args = Args::default();
+    assert_eq!(None, BatteryStatus::time_text(&args, &discharging));
+
+    let args = Args {
+        show_time: ShowTime::Discharging,
+        ..Default::default()
+    };
+    assert_eq!(
+        Some("1:30".into()),
+        BatteryStatus::time_text(&args, &discharging)
+    );
+    assert_eq!(None, BatteryStatus::time_text(&args, &charging));
+
+    let args = Args {
+        show_time: ShowTime::Always,
+        ..Default::default()
+    };
+    assert_eq!(
+        Some("1:30".into()),
+        BatteryStatus::time_text(&args, &charging)
+    );
+}
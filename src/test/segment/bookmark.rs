@@ -0,0 +1,69 @@
+use crate::segment::{bookmark::Bookmark, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("HOME", "/home/user"), ("PWD", "/home/user/src/billing"),]);
+
+segment_test! {
+    fn no_bookmarks() {
+        |args, state : ApplicationState| {
+            let seg = Bookmark::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
+
+segment_test! {
+    fn exact_match() {
+        let args = r#"{"bookmarks": {"~/src/billing/": {"label": "billing", "icon": "$"}}}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Bookmark::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!("$ billing", seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn exact_match_beats_glob() {
+        let args = r#"{"bookmarks": {
+            "/home/user/src/billing": {"label": "exact"},
+            "/home/**": {"label": "glob"}
+        }}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Bookmark::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(format!("{} exact", state.theme.bookmark.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn longest_glob_wins() {
+        let args = r#"{"bookmarks": {
+            "~/**": {"label": "home"},
+            "~/src/*": {"label": "src"},
+            "~/src/bill?": {"label": "nope"}
+        }}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Bookmark::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(format!("{} src", state.theme.bookmark.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn single_star_stays_in_directory() {
+        let args = r#"{"bookmarks": {"/home/*": {"label": "home", "icon": "h"}}}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Bookmark::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::segment::{clock::Clock, Kind, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+#[test]
+fn format_directives() {
+    assert_eq!("09:05:03", Clock::format("%H:%M:%S", 9, 5, 3));
+    assert_eq!("12:30 AM", Clock::format("%I:%M %p", 0, 30, 0));
+    assert_eq!("01:30 PM", Clock::format("%I:%M %p", 13, 30, 0));
+    assert_eq!("100% %Y%", Clock::format("100%% %Y%", 13, 30, 0));
+}
+
+segment_test! {
+    fn inline_by_default() {
+        let args = r#"{"format": "%H:%M"}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Clock::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(Kind::Text, seg[0].kind);
+            let time = seg[0].text.strip_prefix(&format!("{} ", state.theme.clock.symbol));
+            assert_eq!(Some(5), time.map(str::len));
+            assert_eq!(Some(2), time.and_then(|time| time.find(':')));
+        }
+    }
+}
+
+segment_test! {
+    fn right_edge() {
+        let args = r#"{"right_edge": true}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Clock::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(Kind::Right, seg[0].kind);
+            assert!(seg[0].text.starts_with("\u{1f552} "));
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::segment::{command::Command, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("PWD", "/"),]);
+
+segment_test! {
+    fn first_line_of_output() {
+        let args = r#"{ "command": "printf ' hello \\nworld\\n'" }"#;
+
+        |args, state : ApplicationState| {
+            let seg = Command::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!("hello", seg[0].text);
+            assert_eq!(state.theme.command.bg, seg[0].bg);
+        }
+    }
+}
+
+segment_test! {
+    fn runs_in_the_current_directory() {
+        let args = r#"{ "command": "pwd" }"#;
+
+        |args, state : ApplicationState| {
+            let seg = Command::to_segment_generic(args, &state).unwrap();
+            assert_eq!("/", seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn failure_or_silence_is_hidden() {
+        |_args: Option<serde_json::Value>, state : ApplicationState| {
+            let args = Some(serde_json::json!({ "command": "echo nope; exit 1" }));
+            assert!(Command::to_segment_generic(args, &state).unwrap().is_empty());
+
+            let args = Some(serde_json::json!({ "command": "true" }));
+            assert!(Command::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn missing_command_is_an_error() {
+        |args, state : ApplicationState| {
+            assert!(Command::to_segment_generic(args, &state).is_err());
+        }
+    }
+}
+
+segment_test! {
+    fn slow_commands_time_out() {
+        let args = r#"{ "command": "sleep 5", "timeout_ms": 20 }"#;
+
+        |args, state : ApplicationState| {
+            let error = Command::to_segment_generic(args, &state).unwrap_err();
+            assert!(format!("{:#}", error).contains("timed out"));
+        }
+    }
+}
+
+segment_test! {
+    fn cached_output() {
+        let args = r#"{ "command": "date +%N", "ttl_seconds": 60 }"#;
+
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            state.env.insert(
+                String::from("PROMPTR_CACHE_DIR"),
+                dir.path().to_string_lossy().into(),
+            );
+
+            let first = Command::to_segment_generic(args.clone(), &state).unwrap();
+            let second = Command::to_segment_generic(args, &state).unwrap();
+            assert_eq!(first[0].text, second[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn output_is_not_expanded_by_bash() {
+        let args = r#"{ "command": "echo '$(rm -rf ~) `id` \\w'" }"#;
+
+        |args, state : ApplicationState| {
+            let seg = Command::to_segment_generic(args, &state).unwrap();
+            assert_eq!(r"\\$(rm -rf ~) \\`id\\` \\\\w", seg[0].text);
+            assert_eq!("$(rm -rf ~) `id` \\w", seg[0].metadata.as_ref().unwrap()["output"]);
+        }
+    }
+}
+
+segment_test! {
+    fn timeouts_are_cached() {
+        let args = r#"{ "command": "sleep 5", "timeout_ms": 20, "ttl_seconds": 60 }"#;
+
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            state.env.insert(
+                String::from("PROMPTR_CACHE_DIR"),
+                dir.path().to_string_lossy().into(),
+            );
+
+            assert!(Command::to_segment_generic(args.clone(), &state).is_err());
+
+            let start = std::time::Instant::now();
+            assert!(Command::to_segment_generic(args, &state).is_err());
+            assert!(start.elapsed() < std::time::Duration::from_millis(20));
+        }
+    }
+}
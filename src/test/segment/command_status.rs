@@ -81,3 +81,44 @@ segment_test! {
         }
     }
 }
+
+segment_test! {
+    fn history_strip() {
+        let args = r#"{"history": 4}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("code"), String::from("0"));
+            state.env.insert(String::from("codes"), String::from("1 0 0 1 0"));
+
+            let seg = CommandStatus::to_segment_generic(args, &state).unwrap();
+            assert_eq!(2, seg.len());
+
+            assert_eq!("\u{25cf}\u{25cf}\u{25cb}\u{25cf}", seg[0].text);
+            assert_eq!(r"\$", seg[1].text);
+        }
+    }
+}
+
+segment_test! {
+    fn history_strip_empty() {
+        let args = r#"{"history": 4}"#;
+
+        |args, state : ApplicationState| {
+            let seg = CommandStatus::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+        }
+    }
+}
+
+segment_test! {
+    fn last_exit_beats_code() {
+        |args, mut state : ApplicationState| {
+            // Something later in PROMPT_COMMAND succeeded after the user's command failed
+            state.env.insert(String::from("code"), String::from("0"));
+            state.env.insert(String::from("PROMPTR_LAST_EXIT"), String::from("1"));
+
+            let seg = CommandStatus::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.command_status.failure_bg, seg[0].bg);
+        }
+    }
+}
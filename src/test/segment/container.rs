@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::segment::container::{Container, Detected, Runtime};
+
+const ID: &str = "3f4e8a9b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f";
+
+fn root(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, contents) in files {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+fn detected(runtime: Runtime, name: Option<&str>) -> Option<Detected> {
+    Some(Detected {
+        runtime,
+        name: name.map(String::from),
+    })
+}
+
+#[test]
+fn nothing_to_find() {
+    let dir = root(&[("proc/self/cgroup", "0::/user.slice/user-1000.slice\n")]);
+    assert_eq!(None, Container::detect(dir.path(), &HashMap::new()));
+    assert_eq!(
+        None,
+        Container::detect(Path::new("/does/not/exist"), &HashMap::new())
+    );
+}
+
+#[test]
+fn docker() {
+    let cgroup = format!("12:pids:/docker/{}\n0::/\n", ID);
+    let dir = root(&[(".dockerenv", ""), ("proc/self/cgroup", &cgroup)]);
+    assert_eq!(
+        detected(Runtime::Docker, Some(ID)),
+        Container::detect(dir.path(), &HashMap::new())
+    );
+
+    // cgroup v2 with a private namespace doesn't give anything away
+    let dir = root(&[(".dockerenv", ""), ("proc/self/cgroup", "0::/\n")]);
+    assert_eq!(
+        detected(Runtime::Docker, None),
+        Container::detect(dir.path(), &HashMap::new())
+    );
+}
+
+#[test]
+fn podman() {
+    let dir = root(&[(
+        "run/.containerenv",
+        "engine=\"podman-4.3.1\"\nname=\"web\"\nid=\"abc\"\nrootless=0\n",
+    )]);
+    assert_eq!(
+        detected(Runtime::Podman, Some("web")),
+        Container::detect(dir.path(), &HashMap::new())
+    );
+
+    // Rootless containers leave the name out
+    let cgroup = format!("0::/user.slice/libpod-{}.scope/container\n", ID);
+    let dir = root(&[
+        ("run/.containerenv", "engine=\"podman-4.3.1\"\n"),
+        ("proc/self/cgroup", &cgroup),
+    ]);
+    assert_eq!(
+        detected(Runtime::Podman, Some(ID)),
+        Container::detect(dir.path(), &HashMap::new())
+    );
+}
+
+#[test]
+fn cgroups() {
+    let scope = format!("0::/system.slice/docker-{}.scope\n", ID);
+    assert_eq!(
+        detected(Runtime::Docker, Some(ID)),
+        Container::from_cgroup(&scope)
+    );
+    assert_eq!(
+        detected(Runtime::Lxc, Some("builder")),
+        Container::from_cgroup("0::/lxc.payload.builder/init.scope\n")
+    );
+    assert_eq!(
+        detected(Runtime::Lxc, Some("builder")),
+        Container::from_cgroup("4:memory:/lxc/builder\n")
+    );
+    assert_eq!(None, Container::from_cgroup("0::/docker\n"));
+}
+
+#[test]
+fn environment() {
+    let dir = root(&[]);
+
+    let env = HashMap::from([("container".to_string(), "systemd-nspawn".to_string())]);
+    assert_eq!(
+        detected(Runtime::Nspawn, None),
+        Container::detect(dir.path(), &env)
+    );
+
+    let env = HashMap::from([("container".to_string(), "flatpak".to_string())]);
+    assert_eq!(None, Container::detect(dir.path(), &env));
+}
+
+#[test]
+fn debian_chroot() {
+    let dir = root(&[("etc/debian_chroot", "sid-amd64\n")]);
+    assert_eq!(
+        detected(Runtime::Chroot, Some("sid-amd64")),
+        Container::detect(dir.path(), &HashMap::new())
+    );
+
+    let env = HashMap::from([("debian_chroot".to_string(), "bookworm".to_string())]);
+    assert_eq!(
+        detected(Runtime::Chroot, Some("bookworm")),
+        Container::detect(dir.path(), &env)
+    );
+}
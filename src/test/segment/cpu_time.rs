@@ -0,0 +1,71 @@
+use crate::segment::{
+    cpu_time::{parse_times, CpuTime},
+    ToSegment,
+};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([
+    ("cpu_user", "0m3.250s"),
+    ("cpu_sys", "0m0.500s"),
+    ("cpu_user_prev", "0m1.000s"),
+    ("cpu_sys_prev", "0m0.250s"),
+]);
+
+#[test]
+fn parse_bash_times() {
+    assert_eq!(Some(0.5), parse_times("0m0.500s"));
+    assert_eq!(Some(61.25), parse_times("1m1.250s"));
+    assert_eq!(None, parse_times("0.500"));
+    assert_eq!(None, parse_times(""));
+}
+
+segment_test! {
+    fn user_and_sys() {
+        |args, state : ApplicationState| {
+            let seg = CpuTime::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            let seg = &seg[0];
+
+            assert_eq!(format!("{} 2.25u 0.25s", state.theme.cpu_time.symbol), seg.text);
+        }
+    }
+}
+
+segment_test! {
+    fn user_only() {
+        let args = r#"{"show_sys": false}"#;
+
+        |args, state : ApplicationState| {
+            let seg = CpuTime::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            let seg = &seg[0];
+
+            assert_eq!(format!("{} 2.25u", state.theme.cpu_time.symbol), seg.text);
+        }
+    }
+}
+
+segment_test! {
+    fn below_threshold() {
+        let args = r#"{"min_seconds": 5}"#;
+
+        |args, state : ApplicationState| {
+            let seg = CpuTime::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
+
+segment_test! {
+    fn first_prompt() {
+        |args, mut state : ApplicationState| {
+            state.env.remove("cpu_user_prev");
+            state.env.remove("cpu_sys_prev");
+
+            let seg = CpuTime::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::segment::disk::{Args, Disk, Units};
+use crate::segment::threshold::Level;
+use crate::segment::ToSegment;
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("PWD", "/"),]);
+
+segment_test! {
+    #[cfg(unix)]
+    fn shows_disk_in_use() {
+        |args, state : ApplicationState| {
+            let seg = Disk::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert!(seg[0].text.starts_with(&state.theme.disk.symbol));
+            assert_eq!("/", seg[0].metadata.as_ref().unwrap()["path"]);
+        }
+    }
+}
+
+segment_test! {
+    #[cfg(unix)]
+    fn path_overrides_pwd() {
+        let args = r#"{"path": "/tmp"}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Disk::to_segment_generic(args, &state).unwrap();
+            assert_eq!("/tmp", seg[0].metadata.as_ref().unwrap()["path"]);
+        }
+    }
+}
+
+segment_test! {
+    fn missing_path_is_hidden() {
+        let args = r#"{"path": "/does/not/exist"}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Disk::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn hidden_below_show_above() {
+        let args = r#"{"thresholds": {"show_above": 1e9}}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Disk::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    #[cfg(unix)]
+    fn critical_colors() {
+        let args = r#"{"thresholds": {"crit_at": 0.0}}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Disk::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.disk.colors.crit_bg, seg[0].bg);
+        }
+    }
+}
+
+#[test]
+fn free_space_units() {
+    assert_eq!("512B", Units::Auto.format(512));
+    assert_eq!("1.5K", Units::Auto.format(1536));
+    assert_eq!("12G", Units::Auto.format(12 << 30));
+    assert_eq!("3.4T", Units::Auto.format(3_738_339_534_848));
+
+    assert_eq!("12288M", Units::MiB.format(12 << 30));
+    assert_eq!("0.5G", Units::GiB.format(512 << 20));
+    assert_eq!("0.0T", Units::TiB.format(1 << 20));
+}
+
+#[test]
+fn low_space_band() {
+    let args = Args {
+        warn_below_gib: Some(10.0),
+        crit_below_gib: Some(2.0),
+        ..Default::default()
+    };
+
+    assert_eq!(Level::Normal, args.level(50.0, 20 << 30));
+    assert_eq!(Level::Warn, args.level(50.0, 5 << 30));
+    assert_eq!(Level::Crit, args.level(50.0, 1 << 30));
+
+    // The percentage still counts when it's the worse of the two
+    assert_eq!(Level::Crit, args.level(99.0, 5 << 30));
+}
+
+segment_test! {
+    #[cfg(unix)]
+    fn shows_free_space() {
+        let args = r#"{"show": "free", "units": "GiB", "warn_below_gib": 1e12}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Disk::to_segment_generic(args, &state).unwrap();
+            assert!(seg[0].text.ends_with('G'));
+            assert!(!seg[0].text.ends_with('%'));
+            assert_eq!(state.theme.disk.colors.warn_bg, seg[0].bg);
+        }
+    }
+}
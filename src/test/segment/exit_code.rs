@@ -0,0 +1,59 @@
+use crate::segment::{exit_code::ExitCode, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+segment_test! {
+    fn success_is_hidden() {
+        |args, mut state : ApplicationState| {
+            assert!(ExitCode::to_segment_generic(None, &state).unwrap().is_empty());
+
+            state.env.insert(String::from("code"), String::from("0"));
+            assert!(ExitCode::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn failure_shows_the_code() {
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("code"), String::from("2"));
+
+            let seg = ExitCode::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(format!("{} 2", state.theme.exit_code.symbol), seg[0].text);
+            assert_eq!(state.theme.exit_code.bg, seg[0].bg);
+        }
+    }
+}
+
+segment_test! {
+    fn signals_by_name() {
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("code"), String::from("130"));
+
+            let seg = ExitCode::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} SIGINT", state.theme.exit_code.symbol), seg[0].text);
+            assert_eq!(state.theme.exit_code.signal_bg, seg[0].bg);
+
+            state.env.insert(String::from("code"), String::from("139"));
+            let seg = ExitCode::to_segment_generic(None, &state).unwrap();
+            assert_eq!(format!("{} SIGSEGV", state.theme.exit_code.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn signal_names_off() {
+        let args = r#"{"signal_names": false}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("code"), String::from("130"));
+
+            let seg = ExitCode::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} 130", state.theme.exit_code.symbol), seg[0].text);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::segment::{fill::Fill, Kind, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+segment_test! {
+    fn unknown_width() {
+        |args, state : ApplicationState| {
+            let seg = Fill::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
+
+segment_test! {
+    fn custom_character() {
+        let args = r#"{"character": "."}"#;
+
+        |args, mut state : ApplicationState| {
+            state.columns = Some(80);
+
+            let seg = Fill::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(Kind::Fill, seg[0].kind);
+            assert_eq!(".", seg[0].text);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::cache::Cache;
+use crate::segment::{frecency::Frecency, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("PWD", "/home/me/src"),]);
+
+/// Points the cache somewhere disposable and seeds it with a ranking of the `top` directories
+fn with_ranking(state: &mut ApplicationState, top: usize, ranking: &[&str]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    state.env.insert(
+        String::from("PROMPTR_CACHE_DIR"),
+        dir.path().to_string_lossy().into(),
+    );
+
+    Cache::new(dir.path())
+        .set(&format!("frecency-zoxide-{}", top), &ranking)
+        .unwrap();
+
+    dir
+}
+
+#[test]
+fn parse_zoxide() {
+    let output = "  40.5 /home/me/src\n   2.0 /tmp/some dir\n";
+
+    assert_eq!(
+        vec![
+            (40.5, String::from("/home/me/src")),
+            (2.0, String::from("/tmp/some dir"))
+        ],
+        Frecency::parse_zoxide(output)
+    );
+}
+
+#[test]
+fn parse_autojump() {
+    let output = "10.0:\t/tmp\n22.4:\t/home/me/src\n________________________________________\n\n32:\t total weight\n2:\t number of entries\n";
+
+    assert_eq!(
+        vec![String::from("/home/me/src"), String::from("/tmp")],
+        Frecency::ranking(Frecency::parse_autojump(output), 10)
+    );
+}
+
+#[test]
+fn visit_hook() {
+    let args = serde_json::json!({ "tool": "autojump", "feed": true });
+    let hook = Frecency::visit_hook(Some(args), "/tmp").unwrap().unwrap();
+    assert_eq!(vec!["autojump", "--add", "/tmp"], hook.command);
+
+    assert_eq!(None, Frecency::visit_hook(None, "/tmp").unwrap());
+}
+
+segment_test! {
+    fn ranked() {
+        |args, mut state : ApplicationState| {
+            let _dir = with_ranking(&mut state, 10, &["/home", "/home/me/src"]);
+
+            let seg = Frecency::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(format!("{} 2", state.theme.frecency.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn outside_the_top() {
+        let args = r#"{"top": 1}"#;
+
+        |args, mut state : ApplicationState| {
+            let _dir = with_ranking(&mut state, 1, &["/home", "/home/me/src"]);
+
+            let seg = Frecency::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
+
+segment_test! {
+    fn top_is_part_of_the_cache_key() {
+        let args = r#"{"top": 2}"#;
+
+        |args, mut state : ApplicationState| {
+            let dir = with_ranking(&mut state, 2, &["/home", "/home/me/src"]);
+            Cache::new(dir.path())
+                .set("frecency-zoxide-10", &["/home/me/src"])
+                .unwrap();
+
+            let seg = Frecency::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} 2", state.theme.frecency.symbol), seg[0].text);
+        }
+    }
+}
@@ -5,7 +5,11 @@ use lzma_rs::xz_decompress;
 use tar::Archive;
 use tempfile::{tempdir, TempDir};
 
+use crate::ansi::EscapeStyle;
+use crate::glyphs::GlyphSet;
+use crate::render::render_prompt;
 use crate::segment::{git::Git, ToSegment};
+use crate::shell::Shell;
 use crate::test::segment::declare_segement_test;
 use crate::test::AppEnv;
 use crate::{ApplicationState, Theme};
@@ -32,7 +36,6 @@ fn get_testcase_from_tarball(name: &'static str, state: &mut ApplicationState) -
 }
 
 segment_test! {
-    #[ignore = "libgit2 doesn't support unborn branches"]
     fn empty_git_repo() {
         |args, mut state : ApplicationState| {
             let _temp_dir = get_testcase_from_tarball("empty", &mut state);
@@ -48,13 +51,23 @@ segment_test! {
                 assert_eq!(1, segments.len());
 
                 assert_eq!(
-                    crate::segment::Segment {
-                        bg: theme.vcs.repo_clean_bg,
-                        fg: theme.vcs.repo_clean_fg,
-                        text: String::from(" master (unborn)"),
-                        separator: crate::Separator::Thick,
-                        source: "Git::Branch",
-                    },
+                    crate::segment::Segment::new(
+                        "Git::Branch",
+                        theme.vcs.repo_clean_fg,
+                        theme.vcs.repo_clean_bg,
+                        " master (unborn)",
+                    )
+                    .with_metadata(serde_json::json!({
+                        "branch": "master",
+                        "unborn": true,
+                        "bare": false,
+                        "dirty": false,
+                        "changed": 0,
+                        "conflicted": 0,
+                        "staged": 0,
+                        "untracked": 0,
+                        "stashed": 0,
+                    })),
                     segments[0]
                 );
             }
@@ -78,24 +91,34 @@ segment_test! {
                 assert_eq!(2, segments.len());
 
                 assert_eq!(
-                    crate::segment::Segment {
-                        bg: theme.vcs.repo_dirty_bg,
-                        fg: theme.vcs.repo_dirty_fg,
-                        text: String::from(" master"),
-                        separator: crate::Separator::Thick,
-                        source: "Git::Branch",
-                    },
+                    crate::segment::Segment::new(
+                        "Git::Branch",
+                        theme.vcs.repo_dirty_fg,
+                        theme.vcs.repo_dirty_bg,
+                        " master",
+                    )
+                    .with_metadata(serde_json::json!({
+                        "branch": "master",
+                        "unborn": false,
+                        "bare": false,
+                        "dirty": true,
+                        "changed": 0,
+                        "conflicted": 0,
+                        "staged": 0,
+                        "untracked": 1,
+                        "stashed": 0,
+                    })),
                     segments[0]
                 );
 
                 assert_eq!(
-                    crate::segment::Segment {
-                        bg: theme.vcs.git_untracked_bg,
-                        fg: theme.vcs.git_untracked_fg,
-                        text: String::from("1?"),
-                        separator: crate::Separator::Thick,
-                        source: "Git::Untracked",
-                    },
+                    crate::segment::Segment::new(
+                        "Git::Untracked",
+                        theme.vcs.git.untracked_fg,
+                        theme.vcs.git.untracked_bg,
+                        "1?",
+                    )
+                    .with_metadata(serde_json::json!({ "untracked": 1 })),
                     segments[1]
                 );
             }
@@ -103,6 +126,119 @@ segment_test! {
     }
 }
 
+#[test]
+fn porcelain_counts() {
+    let output =
+        "M  staged.rs\nMM both.rs\n D gone.rs\nR  old.rs -> new.rs\n?? new/\n?? notes.txt\n";
+
+    // (staged, changed, untracked, conflicted)
+    assert_eq!((3, 2, 2, 0), Git::parse_porcelain(output));
+    assert_eq!((0, 0, 0, 0), Git::parse_porcelain(""));
+
+    let output = "UU both.rs\nAA added.rs\nDD deleted.rs\nUD theirs.rs\nM  staged.rs\n";
+    assert_eq!((1, 0, 0, 4), Git::parse_porcelain(output));
+}
+
+segment_test! {
+    fn merge_conflict() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("conflicted", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+
+            let branch = segments.iter().find(|seg| seg.source == "Git::Branch").unwrap();
+            assert_eq!(state.theme.vcs.repo_dirty_bg, branch.bg);
+            assert_eq!(1, branch.metadata.as_ref().unwrap()["conflicted"]);
+
+            assert_eq!(
+                Some(&crate::segment::Segment::new(
+                    "Git::Conflicted",
+                    state.theme.vcs.git.conflict_fg,
+                    state.theme.vcs.git.conflict_bg,
+                    format!("1{}", state.theme.vcs.symbols.conflicted),
+                )
+                .with_metadata(serde_json::json!({ "conflicted": 1 }))),
+                segments.iter().find(|seg| seg.source == "Git::Conflicted")
+            );
+        }
+    }
+}
+
+segment_test! {
+    fn cached_counts() {
+        let args = r#"{"status_cache_seconds": 60}"#;
+
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+            let cache_dir = tempdir().unwrap();
+            state.env.insert(
+                String::from("PROMPTR_CACHE_DIR"),
+                cache_dir.path().to_string_lossy().into(),
+            );
+
+            let first = Git::to_segment_generic(args.clone(), &state).unwrap();
+            assert!(first.iter().all(|seg| seg.source != "Git::Cached"));
+
+            let second = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(first.len() + 1, second.len());
+            assert_eq!(first[1], second[1]);
+            assert_eq!("Git::Cached", second[2].source);
+            assert!(second[2].text.starts_with(&state.theme.vcs.symbols.cached));
+        }
+    }
+}
+
+segment_test! {
+    fn compact_counts() {
+        let args = r#"{"compact_counts": true}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+
+            let theme = &state.theme.vcs;
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+
+            assert_eq!(2, segments.len());
+            assert_eq!("Git::Counts", segments[1].source);
+            assert_eq!("?1", segments[1].text);
+            assert_eq!(theme.git.compact.bg, segments[1].bg);
+            assert_eq!(
+                Some(theme.git.compact.untracked),
+                segments[1].styled_spans().unwrap()[0].fg
+            );
+        }
+    }
+}
+
+segment_test! {
+    fn ascii_compact_counts() {
+        let args = r#"{"compact_counts": true}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+
+            let mut repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+            let signature = git2::Signature::now("promptr", "promptr@example.com").unwrap();
+            repo.stash_save(&signature, "test", Some(git2::StashFlags::INCLUDE_UNTRACKED))
+                .unwrap();
+
+            let mut theme = state.theme.clone();
+            theme.apply_glyph_set(GlyphSet::Ascii);
+            let state = ApplicationState {
+                theme: &theme,
+                ..state
+            };
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!("s1", segments[1].text);
+
+            let prompt = render_prompt(&segments, &theme, Shell::Bash, EscapeStyle::Bash);
+            assert!(prompt.contains("s1"));
+            assert!(!prompt.contains('$'));
+        }
+    }
+}
+
 segment_test! {
     fn rebase_interactive() {
         |args, mut state : ApplicationState| {
@@ -120,24 +256,33 @@ segment_test! {
                 assert_eq!(2, segments.len());
 
                 assert_eq!(
-                    crate::segment::Segment {
-                        bg: theme.vcs.repo_clean_bg,
-                        fg: theme.vcs.repo_clean_fg,
-                        text: String::from(" master"),
-                        separator: crate::Separator::Thick,
-                        source: "Git::Branch",
-                    },
+                    crate::segment::Segment::new(
+                        "Git::Branch",
+                        theme.vcs.repo_clean_fg,
+                        theme.vcs.repo_clean_bg,
+                        " master",
+                    )
+                    .with_metadata(serde_json::json!({
+                        "branch": "master",
+                        "unborn": false,
+                        "bare": false,
+                        "dirty": false,
+                        "changed": 0,
+                        "conflicted": 0,
+                        "staged": 0,
+                        "untracked": 0,
+                        "stashed": 0,
+                    })),
                     segments[0]
                 );
 
                 assert_eq!(
-                    crate::segment::Segment {
-                        bg: theme.vcs.git_in_progress_bg,
-                        fg: theme.vcs.git_in_progress_fg,
-                        text: String::from("int rebase 2/3"),
-                        separator: crate::Separator::Thick,
-                        source: "Git::Rebase",
-                    },
+                    crate::segment::Segment::new(
+                        "Git::Rebase",
+                        theme.vcs.git.in_progress_fg,
+                        theme.vcs.git.in_progress_bg,
+                        "int rebase 2/3",
+                    ),
                     segments[1]
                 );
             }
@@ -160,30 +305,831 @@ segment_test! {
                 let segments = Git::to_segment_generic(args, &state).unwrap();
 
                 eprintln!("Segments: {:#?}", segments);
-                assert_eq!(2, segments.len());
+                assert_eq!(3, segments.len());
 
                 assert_eq!(
-                    crate::segment::Segment {
-                        bg: theme.vcs.repo_clean_bg,
-                        fg: theme.vcs.repo_clean_fg,
-                        text: String::from(" master"),
-                        separator: crate::Separator::Thick,
-                        source: "Git::Branch",
-                    },
+                    crate::segment::Segment::new(
+                        "Git::Branch",
+                        theme.vcs.repo_dirty_fg,
+                        theme.vcs.repo_dirty_bg,
+                        " master",
+                    )
+                    .with_metadata(serde_json::json!({
+                        "branch": "master",
+                        "unborn": false,
+                        "bare": false,
+                        "dirty": true,
+                        "changed": 0,
+                        "conflicted": 1,
+                        "staged": 0,
+                        "untracked": 0,
+                        "stashed": 0,
+                    })),
                     segments[0]
                 );
 
                 assert_eq!(
-                    crate::segment::Segment {
-                        bg: theme.vcs.git_in_progress_bg,
-                        fg: theme.vcs.git_in_progress_fg,
-                        text: String::from("[CHERRY_PICKING]"),
-                        separator: crate::Separator::Thick,
-                        source: "Git::CherryPick",
-                    },
+                    crate::segment::Segment::new(
+                        "Git::CherryPick",
+                        theme.vcs.git.in_progress_fg,
+                        theme.vcs.git.in_progress_bg,
+                        "[CHERRY_PICKING]",
+                    ),
                     segments[1]
                 );
+
+                assert_eq!(
+                    crate::segment::Segment::new(
+                        "Git::Conflicted",
+                        theme.vcs.git.conflict_fg,
+                        theme.vcs.git.conflict_bg,
+                        format!("1{}", theme.vcs.symbols.conflicted),
+                    )
+                    .with_metadata(serde_json::json!({ "conflicted": 1 })),
+                    segments[2]
+                );
+            }
+        }
+    }
+}
+
+segment_test! {
+    fn describe_tag() {
+        let args = r#"{"show_tag": "describe"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let tag = segments.iter().find(|seg| seg.source == "Git::Tag").unwrap();
+
+            assert_eq!(format!("{} v1.0+2", state.theme.vcs.symbols.tag), tag.text);
+            assert_eq!(
+                Some(serde_json::json!({ "tag": "v1.0", "distance": 2 })),
+                tag.metadata
+            );
+        }
+    }
+}
+
+segment_test! {
+    fn exact_tag_only() {
+        let args = r#"{"show_tag": "exact"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert!(segments.iter().all(|seg| seg.source != "Git::Tag"));
+        }
+    }
+}
+
+segment_test! {
+    fn tag_is_escaped() {
+        let args = r#"{"show_tag": "exact"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            let repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+            let head = repo.head().unwrap().peel(git2::ObjectType::Commit).unwrap();
+            repo.tag_lightweight("$(id)`id`", &head, false).unwrap();
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let tag = segments.iter().find(|seg| seg.source == "Git::Tag").unwrap();
+
+            assert_eq!(format!(r"{} \\$(id)\\`id\\`", state.theme.vcs.symbols.tag), tag.text);
+            assert_eq!("$(id)`id`", tag.metadata.as_ref().unwrap()["tag"]);
+        }
+    }
+}
+
+segment_test! {
+    fn compare_ref() {
+        let args = r#"{"compare_ref": "v1.0"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let compare = segments.iter().find(|seg| seg.source == "Git::Compare").unwrap();
+            assert_eq!(format!("v1.0 2{}", state.theme.vcs.symbols.ahead), compare.text);
+            assert_eq!(
+                &serde_json::json!({ "ref": "v1.0", "ahead": 2, "behind": 0 }),
+                compare.metadata.as_ref().unwrap()
+            );
+        }
+    }
+}
+
+segment_test! {
+    fn compare_missing_ref() {
+        let args = r#"{"compare_ref": "origin/nope"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert!(segments.iter().all(|seg| seg.source != "Git::Compare"));
+        }
+    }
+}
+
+#[test]
+fn describe_output() {
+    use crate::segment::git::parse_describe;
+
+    assert_eq!(("v1.0", 2), parse_describe("v1.0-2-g639b2ca"));
+    assert_eq!(
+        ("release-2022-04", 13),
+        parse_describe("release-2022-04-13-gabc1234")
+    );
+    assert_eq!(("release-2022-04", 0), parse_describe("release-2022-04"));
+    assert_eq!(("v1.0", 0), parse_describe("v1.0"));
+}
+
+segment_test! {
+    fn bare_repo() {
+        let args = r#"{"show_tag": "describe"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("bare", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+
+            assert_eq!(2, segments.len());
+            assert_eq!(
+                crate::segment::Segment::new(
+                    "Git::Branch",
+                    state.theme.vcs.repo_clean_fg,
+                    state.theme.vcs.repo_clean_bg,
+                    format!("{} master (bare)", state.theme.vcs.symbols.git),
+                )
+                .with_metadata(serde_json::json!({
+                    "branch": "master",
+                    "unborn": false,
+                    "bare": true,
+                    "dirty": false,
+                    "changed": 0,
+                    "conflicted": 0,
+                    "staged": 0,
+                    "untracked": 0,
+                    "stashed": 0,
+                })),
+                segments[0]
+            );
+            assert_eq!("Git::Tag", segments[1].source);
+        }
+    }
+}
+
+#[test]
+fn remote_hosts() {
+    use crate::segment::vcs::remote_host;
+
+    assert_eq!(
+        Some("github.com"),
+        remote_host("https://github.com/inferiorhumanorgans/promptr.git")
+    );
+    assert_eq!(
+        Some("gitlab.com"),
+        remote_host("ssh://git@gitlab.com:22/a/b.git")
+    );
+    assert_eq!(
+        Some("bitbucket.org"),
+        remote_host("git@bitbucket.org:a/b.git")
+    );
+    assert_eq!(Some("::1"), remote_host("ssh://git@[::1]:22/a/b.git"));
+    assert_eq!(None, remote_host("/srv/git/promptr.git"));
+    assert_eq!(None, remote_host("./a:b"));
+}
+
+#[test]
+fn remote_badges() {
+    let mut symbols = crate::segment::vcs::Symbols::default();
+    symbols
+        .remotes
+        .insert("git.example.com".into(), "EX".into());
+
+    assert_eq!("\u{f09b}", symbols.badge(Some("git@github.com:a/b.git")));
+    assert_eq!("EX", symbols.badge(Some("https://git.example.com/a/b")));
+    assert_eq!(symbols.git, symbols.badge(Some("https://example.org/a/b")));
+    assert_eq!(symbols.git, symbols.badge(None));
+
+    // Only the Nerd Fonts have the logos
+    symbols.apply_glyph_set(crate::glyphs::GlyphSet::Ascii);
+    assert_eq!("git", symbols.badge(Some("git@github.com:a/b.git")));
+    assert_eq!("EX", symbols.badge(Some("https://git.example.com/a/b")));
+}
+
+#[test]
+fn configured_remotes_are_merged() {
+    let symbols: crate::segment::vcs::Symbols =
+        serde_json::from_str(r#"{"remotes": {"git.example.com": "EX", "github.com": ""}}"#)
+            .unwrap();
+
+    assert_eq!("EX", symbols.remotes["git.example.com"]);
+    assert_eq!("\u{f296}", symbols.remotes["gitlab.com"]);
+    assert_eq!(symbols.git, symbols.badge(Some("git@github.com:a/b.git")));
+}
+
+segment_test! {
+    fn origin_badge() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"])
+                .unwrap()
+                .remote("origin", "git@github.com:inferiorhumanorgans/promptr.git")
+                .unwrap();
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!("\u{f09b} master", segments[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn without_badge() {
+        let args = r#"{"show_vcs_badge": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!("master", segments[0].text);
+        }
+    }
+}
+
+/// Has `master` track `origin/main`, and optionally creates `origin/main` so it isn't gone
+fn track_upstream(state: &ApplicationState, exists: bool) {
+    let repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+    repo.remote("origin", "https://example.com/tagged.git")
+        .unwrap();
+
+    let mut config = repo.config().unwrap();
+    config.set_str("branch.master.remote", "origin").unwrap();
+    config
+        .set_str("branch.master.merge", "refs/heads/main")
+        .unwrap();
+
+    if exists {
+        let head = repo.head().unwrap().target().unwrap();
+        repo.reference("refs/remotes/origin/main", head, false, "test")
+            .unwrap();
+    }
+}
+
+segment_test! {
+    fn upstream_beside() {
+        let args = r#"{"show_upstream": "beside"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            track_upstream(&state, true);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+
+            assert_eq!(
+                crate::segment::Segment::new(
+                    "Git::Upstream",
+                    state.theme.vcs.git.upstream_fg,
+                    state.theme.vcs.git.upstream_bg,
+                    "origin/main",
+                )
+                .with_metadata(serde_json::json!({ "upstream": "origin/main", "gone": false })),
+                segments[1]
+            );
+        }
+    }
+}
+
+segment_test! {
+    fn upstream_gone() {
+        let args = r#"{"show_upstream": "beside"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            track_upstream(&state, false);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+
+            assert_eq!("Git::Upstream", segments[1].source);
+            assert_eq!(state.theme.vcs.git.upstream_gone_bg, segments[1].bg);
+            assert_eq!(true, segments[1].metadata.as_ref().unwrap()["gone"]);
+        }
+    }
+}
+
+segment_test! {
+    fn upstream_is_escaped() {
+        let args = r#"{"show_upstream": "beside"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            track_upstream(&state, false);
+
+            let repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+            repo.config()
+                .unwrap()
+                .set_str("branch.master.merge", "refs/heads/$(id)")
+                .unwrap();
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+
+            assert_eq!("Git::Upstream", segments[1].source);
+            assert_eq!(r"origin/\\$(id)", segments[1].text);
+            assert_eq!("origin/$(id)", segments[1].metadata.as_ref().unwrap()["upstream"]);
+        }
+    }
+}
+
+segment_test! {
+    fn upstream_instead() {
+        let args = r#"{"show_upstream": "instead", "show_vcs_badge": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            track_upstream(&state, false);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+
+            assert_eq!("Git::Branch", segments[0].source);
+            assert_eq!("origin/main", segments[0].text);
+            assert_eq!(state.theme.vcs.git.upstream_gone_bg, segments[0].bg);
+            assert!(segments.iter().all(|seg| seg.source != "Git::Upstream"));
+        }
+    }
+}
+
+segment_test! {
+    fn no_upstream() {
+        let args = r#"{"show_upstream": "instead", "show_vcs_badge": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!("master", segments[0].text);
+        }
+    }
+}
+
+#[test]
+fn ages() {
+    use crate::segment::git::format_age;
+
+    assert_eq!("42s", format_age(42));
+    assert_eq!("2m", format_age(150));
+    assert_eq!("3h", format_age(3 * 3600 + 59 * 60));
+    assert_eq!("12d", format_age(12 * 86400 + 1));
+}
+
+segment_test! {
+    fn stale_commit() {
+        let args = r#"{"show_commit_age": true}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let age = segments.iter().find(|seg| seg.source == "Git::CommitAge").unwrap();
+
+            // Committed back in April 2022
+            assert!(age.text.ends_with('d'));
+            assert_eq!(state.theme.vcs.git.commit_age.warn_bg, age.bg);
+        }
+    }
+}
+
+segment_test! {
+    fn commit_age_hidden_below_show_above() {
+        let args = r#"{"show_commit_age": true, "commit_age_thresholds": {"show_above": 1e9}}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert!(segments.iter().all(|seg| seg.source != "Git::CommitAge"));
+        }
+    }
+}
+
+/// Stashes the untracked file away
+fn stash_untracked(state: &ApplicationState) {
+    let mut repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+    let signature = git2::Signature::now("promptr", "promptr@example.com").unwrap();
+    repo.stash_save(
+        &signature,
+        "test",
+        Some(git2::StashFlags::INCLUDE_UNTRACKED),
+    )
+    .unwrap();
+}
+
+segment_test! {
+    fn stashes_are_counted() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+            stash_untracked(&state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let stashed = segments.iter().find(|seg| seg.source == "Git::Stashed").unwrap();
+            assert_eq!(format!("1{}", state.theme.vcs.symbols.stash), stashed.text);
+        }
+    }
+}
+
+segment_test! {
+    fn stashes_are_skipped() {
+        let args = r#"{"show_stash": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+            stash_untracked(&state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert!(segments.iter().all(|seg| seg.source != "Git::Stashed"));
+            assert_eq!(0, segments[0].metadata.as_ref().unwrap()["stashed"]);
+        }
+    }
+}
+
+segment_test! {
+    fn branch_without_counts() {
+        let args = r#"{"show_counts": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, segments.len());
+            assert_eq!("Git::Branch", segments[0].source);
+            assert_eq!(state.theme.vcs.repo_clean_bg, segments[0].bg);
+        }
+    }
+}
+
+segment_test! {
+    fn untracked_limit() {
+        let args = r#"{"untracked_limit": 2}"#;
+
+        |args, mut state : ApplicationState| {
+            let temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+            for name in ["another", "and-another"] {
+                std::fs::write(temp_dir.path().join("untracked-file").join(name), "").unwrap();
             }
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let untracked = segments.iter().find(|seg| seg.source == "Git::Untracked").unwrap();
+            assert_eq!(format!("2+{}", state.theme.vcs.symbols.new), untracked.text);
+            assert_eq!(2, untracked.metadata.as_ref().unwrap()["untracked"]);
+        }
+    }
+}
+
+segment_test! {
+    fn untracked_skipped() {
+        let args = r#"{"skip_untracked": true}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, segments.len());
+            assert_eq!(state.theme.vcs.repo_clean_bg, segments[0].bg);
+            assert_eq!(0, segments[0].metadata.as_ref().unwrap()["untracked"]);
+        }
+    }
+}
+
+segment_test! {
+    fn status_timeout() {
+        // Nothing can be counted in no time at all
+        let args = r#"{"status_timeout_ms": 0, "show_tag": "describe"}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            std::fs::write(
+                std::path::Path::new(&state.env["__PROMPTR_GIT_REPO"]).join("untracked"),
+                "",
+            )
+            .unwrap();
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, segments.len());
+            assert_eq!("Git::Branch", segments[0].source);
+        }
+    }
+}
+
+segment_test! {
+    fn status_timeout_with_fsmonitor() {
+        // `git status` gets killed rather than left behind
+        let args = r#"{"status_timeout_ms": 0}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+
+            let repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+            repo.config().unwrap().set_bool("core.fsmonitor", true).unwrap();
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, segments.len());
+            assert_eq!("Git::Branch", segments[0].source);
+        }
+    }
+}
+
+segment_test! {
+    fn status_within_timeout() {
+        let args = r#"{"status_timeout_ms": 60000}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, segments[0].metadata.as_ref().unwrap()["untracked"]);
+            assert!(segments.iter().any(|seg| seg.source == "Git::Untracked"));
+        }
+    }
+}
+
+segment_test! {
+    #[cfg(unix)]
+    fn background_refresh() {
+        let args = r#"{"status_cache_seconds": 0, "status_cache_background": true}"#;
+
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            let temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+            let cache_dir = tempdir().unwrap();
+            state.env.insert(
+                String::from("PROMPTR_CACHE_DIR"),
+                cache_dir.path().to_string_lossy().into(),
+            );
+            let untracked = |segments: &[crate::segment::Segment]| {
+                segments
+                    .iter()
+                    .find(|seg| seg.source == "Git::Untracked")
+                    .map(|seg| seg.metadata.clone().unwrap()["untracked"].clone())
+            };
+
+            // Nothing cached to fall back on
+            let first = Git::to_segment_generic(args.clone(), &state).unwrap();
+            assert_eq!(Some(serde_json::json!(1)), untracked(&first));
+            assert!(first.iter().all(|seg| seg.source != "Git::Cached"));
+
+            std::fs::write(temp_dir.path().join("untracked-file/another"), "").unwrap();
+
+            // The old count is shown while git works out the new one
+            let second = Git::to_segment_generic(args.clone(), &state).unwrap();
+            assert_eq!(Some(serde_json::json!(1)), untracked(&second));
+            assert!(second.iter().any(|seg| seg.source == "Git::Cached"));
+
+            let finished = (0..100).any(|_| {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                std::fs::read_dir(cache_dir.path()).unwrap().any(|entry| {
+                    entry.unwrap().path().extension() == Some("porcelain".as_ref())
+                })
+            });
+            assert!(finished);
+
+            let third = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(Some(serde_json::json!(2)), untracked(&third));
+        }
+    }
+}
+
+/// Writes `files` under `.git` to fake an operation that's in progress
+fn in_progress(state: &ApplicationState, files: &[(&str, &str)]) {
+    let git_dir = std::path::Path::new(&state.env["__PROMPTR_GIT_REPO"]).join(".git");
+
+    for (name, contents) in files {
+        let path = git_dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+}
+
+segment_test! {
+    fn apply_mailbox() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            in_progress(
+                &state,
+                &[
+                    ("rebase-apply/applying", ""),
+                    ("rebase-apply/next", "1\n"),
+                    ("rebase-apply/last", "3\n"),
+                ],
+            );
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let am = segments.iter().find(|seg| seg.source == "Git::Am").unwrap();
+            assert_eq!("am 1/3", am.text);
+        }
+    }
+}
+
+segment_test! {
+    fn apply_rebase() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            in_progress(
+                &state,
+                &[
+                    ("rebase-apply/rebasing", ""),
+                    ("rebase-apply/next", "2\n"),
+                    ("rebase-apply/last", "5\n"),
+                ],
+            );
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let rebase = segments.iter().find(|seg| seg.source == "Git::Rebase").unwrap();
+            assert_eq!("rebase 2/5", rebase.text);
+        }
+    }
+}
+
+segment_test! {
+    fn merge_rebase() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            in_progress(
+                &state,
+                &[("rebase-merge/msgnum", "3\n"), ("rebase-merge/end", "4\n")],
+            );
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let rebase = segments.iter().find(|seg| seg.source == "Git::Rebase").unwrap();
+            assert_eq!("rebase 3/4", rebase.text);
+        }
+    }
+}
+
+segment_test! {
+    fn progress_unknown() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            in_progress(&state, &[("rebase-apply/rebasing", "")]);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            let rebase = segments.iter().find(|seg| seg.source == "Git::Rebase").unwrap();
+            assert_eq!("rebase", rebase.text);
+        }
+    }
+}
+
+segment_test! {
+    fn am_hidden() {
+        let args = r#"{"show_am": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+            in_progress(&state, &[("rebase-apply/applying", "")]);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert!(segments.iter().all(|seg| seg.source != "Git::Am"));
+        }
+    }
+}
+
+segment_test! {
+    fn sparse_checkout() {
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let sparse = |args: Option<serde_json::Value>| {
+                Git::to_segment_generic(args, &state)
+                    .unwrap()
+                    .into_iter()
+                    .find(|seg| seg.source == "Git::Sparse")
+            };
+            assert_eq!(None, sparse(args.clone()));
+
+            let repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+            let mut config = repo.config().unwrap();
+            config.set_bool("core.sparseCheckout", true).unwrap();
+
+            let badge = sparse(args.clone()).unwrap();
+            assert_eq!(state.theme.vcs.symbols.sparse, badge.text);
+            assert_eq!(Some(serde_json::json!({ "sparse": "legacy" })), badge.metadata);
+
+            config.set_bool("core.sparseCheckoutCone", true).unwrap();
+            let badge = sparse(args).unwrap();
+            assert_eq!(Some(serde_json::json!({ "sparse": "cone" })), badge.metadata);
+
+            assert_eq!(
+                None,
+                sparse(Some(serde_json::json!({ "show_sparse": false })))
+            );
+        }
+    }
+}
+
+#[test]
+fn expected_emails() {
+    use crate::segment::git::email_expected;
+
+    let expected = std::collections::BTreeMap::from([(
+        String::from(r"github\.com[:/]acme/"),
+        String::from(r"@acme\.com$"),
+    )]);
+    let work = Some("git@github.com:acme/widgets.git");
+
+    assert!(email_expected(&expected, work, "me@acme.com").unwrap());
+    assert!(!email_expected(&expected, work, "me@example.com").unwrap());
+    assert!(email_expected(
+        &expected,
+        Some("git@github.com:me/dotfiles.git"),
+        "me@example.com"
+    )
+    .unwrap());
+    assert!(email_expected(&expected, None, "me@example.com").unwrap());
+
+    let broken = std::collections::BTreeMap::from([(String::from("("), String::new())]);
+    assert!(email_expected(&broken, work, "me@acme.com").is_err());
+}
+
+segment_test! {
+    fn committer_email() {
+        let args = r#"{
+            "show_email": true,
+            "email_aliases": { "me@example.com": "home" },
+            "expected_emails": { "acme": "@acme\\.com$" }
+        }"#;
+
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("tagged", &mut state);
+
+            let repo = git2::Repository::open(&state.env["__PROMPTR_GIT_REPO"]).unwrap();
+            repo.config().unwrap().set_str("user.email", "me@example.com").unwrap();
+
+            let email = |state: &ApplicationState| {
+                Git::to_segment_generic(args.clone(), state)
+                    .unwrap()
+                    .into_iter()
+                    .find(|seg| seg.source == "Git::Email")
+                    .unwrap()
+            };
+
+            let seg = email(&state);
+            assert_eq!(format!("{} home", state.theme.vcs.symbols.email), seg.text);
+            assert_eq!(state.theme.vcs.git.email_bg, seg.bg);
+
+            repo.remote("origin", "git@github.com:acme/tagged.git").unwrap();
+            let seg = email(&state);
+            assert_eq!(state.theme.vcs.git.email_mismatch_bg, seg.bg);
+            assert_eq!(
+                Some(serde_json::json!({ "email": "me@example.com", "expected": false })),
+                seg.metadata
+            );
+
+            // The environment wins over the configuration, like it does for git
+            state
+                .env
+                .insert(String::from("GIT_AUTHOR_EMAIL"), String::from("me@acme.com"));
+            let seg = email(&state);
+            assert_eq!(format!("{} me@acme.com", state.theme.vcs.symbols.email), seg.text);
+            assert_eq!(state.theme.vcs.git.email_bg, seg.bg);
+        }
+    }
+}
+
+segment_test! {
+    fn found_from_pwd() {
+        |args, mut state : ApplicationState| {
+            let _temp_dir = get_testcase_from_tarball("untracked-file", &mut state);
+            let repo = state.env.remove("__PROMPTR_GIT_REPO").unwrap();
+            state.env.insert(String::from("PWD"), repo);
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!("Git::Untracked", segments[1].source);
+        }
+    }
+}
+
+segment_test! {
+    fn git_dir_and_work_tree() {
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            let temp_dir = get_testcase_from_tarball("bare", &mut state);
+            state.env.remove("__PROMPTR_GIT_REPO");
+            state
+                .env
+                .insert(String::from("PWD"), temp_dir.path().to_string_lossy().into());
+            state.env.insert(String::from("GIT_DIR"), String::from("bare"));
+
+            let segments = Git::to_segment_generic(args.clone(), &state).unwrap();
+            assert_eq!(Some(serde_json::json!(true)), segments[0].metadata.as_ref().map(|meta| meta["bare"].clone()));
+
+            // A bare clone has no index, so everything that's been committed is staged for removal
+            std::fs::create_dir(temp_dir.path().join("home")).unwrap();
+            state.env.insert(String::from("GIT_WORK_TREE"), String::from("home"));
+
+            let segments = Git::to_segment_generic(args, &state).unwrap();
+            assert_eq!(false, segments[0].metadata.as_ref().unwrap()["bare"]);
+            assert_eq!(3, segments[0].metadata.as_ref().unwrap()["staged"]);
         }
     }
 }
@@ -1,3 +1,4 @@
+use crate::ansi::Color;
 use crate::segment::{hostname::Hostname, ToSegment};
 use crate::test::segment::declare_segement_test;
 use crate::test::AppEnv;
@@ -43,3 +44,33 @@ segment_test! {
         }
     }
 }
+
+segment_test! {
+    fn hashed_bg() {
+        |args, state : ApplicationState| {
+            let mut theme = Theme::default();
+            theme.hostname.hash_bg = true;
+            let state = ApplicationState {
+                theme: &theme,
+                ..state
+            };
+
+            let seg = Hostname::to_segment_generic(args, &state).unwrap();
+            assert_eq!(theme.hostname.bg_for("sean.connery.is.zardoz.com"), seg[0].bg);
+            assert!(theme.hostname.hash_palette.contains(&seg[0].bg));
+        }
+    }
+}
+
+#[test]
+fn hashed_bg_is_stable() {
+    let mut theme = Theme::default().hostname;
+    assert_eq!(theme.bg, theme.bg_for("web1"));
+
+    theme.hash_bg = true;
+    assert_eq!(Color::Numbered(24), theme.bg_for("web1"));
+    assert_eq!(Color::Numbered(88), theme.bg_for("web2"));
+
+    theme.hash_palette.clear();
+    assert_eq!(theme.bg, theme.bg_for("web1"));
+}
@@ -0,0 +1,69 @@
+use crate::ansi::TextStyle;
+use crate::segment::{identity::Identity, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([
+    ("USER", "alice"),
+    ("uid", "1000"),
+    ("hostname", "box.example.com"),
+]);
+
+segment_test! {
+    fn default_user_is_hidden() {
+        let args = r#"{"default_user": "alice", "host": "never"}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Identity::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
+
+segment_test! {
+    fn other_user_is_shown() {
+        let args = r#"{"default_user": "bob", "host": "never"}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Identity::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!("alice", seg[0].text);
+            assert_eq!(state.theme.identity.bg, seg[0].bg);
+        }
+    }
+}
+
+segment_test! {
+    fn host_over_ssh() {
+        let args = r#"{"user": "never"}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("SSH_CONNECTION"), String::from("10.0.0.1 22 10.0.0.2 22"));
+
+            let seg = Identity::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!("box", seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn root_is_highlighted() {
+        let args = r#"{"user": "always", "host": "always", "show_domain": true}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("USER"), String::from("root"));
+            state.env.insert(String::from("uid"), String::from("0"));
+
+            let seg = Identity::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!("root@box.example.com", seg[0].text);
+            assert_eq!(state.theme.identity.root_bg, seg[0].bg);
+
+            let spans = seg[0].styled_spans().unwrap();
+            assert_eq!("@", spans[1].text);
+            assert_eq!(Some(TextStyle::Dim), spans[1].style);
+        }
+    }
+}
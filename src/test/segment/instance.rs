@@ -0,0 +1,134 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::segment::instance::{Instance, Provider, Show};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+fn dmi(fields: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, value) in fields {
+        std::fs::write(dir.path().join(name), format!("{}\n", value)).unwrap();
+    }
+    dir
+}
+
+/// Answers each request in turn with the matching response, handing back what was asked
+fn metadata_service(responses: &[&str]) -> (SocketAddr, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let responses = responses.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+
+    let handle = thread::spawn(move || {
+        responses
+            .iter()
+            .map(|response| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0; 4096];
+                let len = stream.read(&mut buf).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+                String::from_utf8_lossy(&buf[..len]).to_string()
+            })
+            .collect()
+    });
+
+    (addr, handle)
+}
+
+#[test]
+fn detects_the_provider() {
+    let ec2 = dmi(&[("sys_vendor", "Amazon EC2"), ("product_name", "t3.micro")]);
+    assert_eq!(Some(Provider::Ec2), Provider::detect(ec2.path()));
+    assert_eq!(
+        Some("t3.micro".to_string()),
+        Instance::from_dmi(ec2.path(), Provider::Ec2, Show::Type)
+    );
+    assert_eq!(
+        None,
+        Instance::from_dmi(ec2.path(), Provider::Ec2, Show::Name)
+    );
+
+    let xen = dmi(&[("sys_vendor", "Xen"), ("bios_version", "4.11.amazon")]);
+    assert_eq!(Some(Provider::Ec2), Provider::detect(xen.path()));
+    assert_eq!(
+        None,
+        Instance::from_dmi(xen.path(), Provider::Ec2, Show::Type)
+    );
+
+    let gce = dmi(&[
+        ("sys_vendor", "Google"),
+        ("product_name", "Google Compute Engine"),
+    ]);
+    assert_eq!(Some(Provider::Gce), Provider::detect(gce.path()));
+
+    let azure = dmi(&[
+        ("sys_vendor", "Microsoft Corporation"),
+        ("chassis_asset_tag", "7783-7084-3265-9085-8269-3286-77"),
+    ]);
+    assert_eq!(Some(Provider::Azure), Provider::detect(azure.path()));
+
+    // Plain Hyper-V isn't Azure
+    let hyperv = dmi(&[("sys_vendor", "Microsoft Corporation")]);
+    assert_eq!(None, Provider::detect(hyperv.path()));
+
+    assert_eq!(None, Provider::detect(Path::new("/does/not/exist")));
+}
+
+#[test]
+fn ec2_asks_for_a_token_first() {
+    let (addr, handle) = metadata_service(&[
+        "HTTP/1.0 200 OK\r\n\r\nsecret",
+        "HTTP/1.0 200 OK\r\n\r\nm5.large",
+    ]);
+
+    let value = Instance::lookup(addr, Provider::Ec2, Show::Type, Duration::from_secs(5));
+    assert_eq!("m5.large", value.unwrap());
+
+    let requests = handle.join().unwrap();
+    assert!(requests[0].starts_with("PUT /latest/api/token HTTP/1.0\r\n"));
+    assert!(requests[1].starts_with("GET /latest/meta-data/instance-type HTTP/1.0\r\n"));
+    assert!(requests[1].contains("X-aws-ec2-metadata-token: secret\r\n"));
+}
+
+#[test]
+fn gce_machine_type_is_trimmed() {
+    let (addr, handle) =
+        metadata_service(&["HTTP/1.0 200 OK\r\n\r\nprojects/1234/machineTypes/e2-medium"]);
+
+    let value = Instance::lookup(addr, Provider::Gce, Show::Type, Duration::from_secs(5));
+    assert_eq!("e2-medium", value.unwrap());
+
+    let requests = handle.join().unwrap();
+    assert!(requests[0].contains("Metadata-Flavor: Google\r\n"));
+}
+
+#[test]
+fn errors_are_reported() {
+    let (addr, handle) = metadata_service(&["HTTP/1.0 404 Not Found\r\n\r\n"]);
+    assert!(Instance::lookup(addr, Provider::Azure, Show::Name, Duration::from_secs(5)).is_err());
+    handle.join().unwrap();
+
+    let (addr, handle) = metadata_service(&["HTTP/1.0 200 OK\r\n\r\n \n"]);
+    assert!(Instance::lookup(addr, Provider::Azure, Show::Name, Duration::from_secs(5)).is_err());
+    handle.join().unwrap();
+}
+
+segment_test! {
+    fn detail_is_escaped() {
+        |_args: Option<serde_json::Value>, state : ApplicationState| {
+            let detail = Some(String::from("$(reboot)"));
+            let seg = Instance::segment(Provider::Gce, detail, &state);
+            assert_eq!(format!(r"{} \\$(reboot)", state.theme.instance.symbol), seg.text);
+            assert_eq!("$(reboot)", seg.metadata.as_ref().unwrap()["detail"]);
+
+            let seg = Instance::segment(Provider::Gce, None, &state);
+            assert_eq!(format!("{} {}", state.theme.instance.symbol, Provider::Gce.name()), seg.text);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::segment::{
+    load::Load,
+    threshold::{Level, ThresholdColors, ThresholdDisplay},
+    ToSegment,
+};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+#[test]
+fn threshold_levels() {
+    let thresholds = ThresholdDisplay {
+        show_above: Some(1.0),
+        warn_at: Some(4.0),
+        crit_at: Some(8.0),
+    };
+
+    assert!(!thresholds.visible(0.5));
+    assert!(!thresholds.visible(1.0));
+    assert!(thresholds.visible(1.5));
+
+    assert_eq!(Level::Normal, thresholds.level(3.9));
+    assert_eq!(Level::Warn, thresholds.level(4.0));
+    assert_eq!(Level::Crit, thresholds.level(12.0));
+
+    // No thresholds means always shown and never alarming
+    let thresholds = ThresholdDisplay::default();
+    assert!(thresholds.visible(0.0));
+    assert_eq!(Level::Normal, thresholds.level(f64::MAX));
+
+    let colors = ThresholdColors::default();
+    assert_eq!((colors.crit_fg, colors.crit_bg), colors.colors(Level::Crit));
+}
+
+segment_test! {
+    #[cfg(unix)]
+    fn shows_the_load() {
+        |args, state : ApplicationState| {
+            let seg = Load::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert!(seg[0].text.starts_with(&state.theme.load.symbol));
+        }
+    }
+}
+
+segment_test! {
+    fn hidden_below_show_above() {
+        let args = r#"{"thresholds": {"show_above": 1e9}}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Load::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    #[cfg(unix)]
+    fn critical_colors() {
+        let args = r#"{"thresholds": {"crit_at": 0.0}}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Load::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.load.colors.crit_bg, seg[0].bg);
+        }
+    }
+}
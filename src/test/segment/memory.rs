@@ -0,0 +1,40 @@
+use crate::segment::{memory::Memory, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+segment_test! {
+    #[cfg(target_os = "linux")]
+    fn shows_memory_in_use() {
+        |args, state : ApplicationState| {
+            let seg = Memory::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert!(seg[0].text.starts_with(&state.theme.memory.symbol));
+            assert!(seg[0].text.ends_with('%'));
+        }
+    }
+}
+
+segment_test! {
+    fn hidden_below_show_above() {
+        let args = r#"{"thresholds": {"show_above": 1e9}}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Memory::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    #[cfg(target_os = "linux")]
+    fn critical_colors() {
+        let args = r#"{"thresholds": {"crit_at": 0.0}}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Memory::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.memory.colors.crit_bg, seg[0].bg);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::segment::{nix::Nix, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+segment_test! {
+    fn outside_nix() {
+        |args, state : ApplicationState| {
+            assert!(Nix::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn nix_shell() {
+        let args = r#"{"show_purity": true}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("IN_NIX_SHELL"), String::from("impure"));
+            state.env.insert(String::from("name"), String::from("promptr-dev"));
+
+            let seg = Nix::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} promptr-dev (impure)", state.theme.nix.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn direnv_flake() {
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            let envrc_dir = format!("-{}", dir.path().display());
+
+            state.env.insert(String::from("DIRENV_DIR"), envrc_dir);
+
+            // Not a flake
+            assert!(Nix::to_segment_generic(None, &state).unwrap().is_empty());
+
+            std::fs::write(dir.path().join("flake.nix"), "{}").unwrap();
+            let seg = Nix::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} flake", state.theme.nix.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn direnv_file() {
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("flake.nix"), "{}").unwrap();
+
+            // Outside of a direnv environment the directory doesn't matter
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+            assert!(Nix::to_segment_generic(None, &state).unwrap().is_empty());
+
+            state.env.insert(
+                String::from("DIRENV_FILE"),
+                dir.path().join(".envrc").display().to_string(),
+            );
+            let seg = Nix::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} flake", state.theme.nix.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn build_shell() {
+        let args = r#"{"show_name": false}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("NIX_BUILD_TOP"), String::from("/build"));
+
+            let seg = Nix::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.nix.symbol, seg[0].text);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::path::Path;
+
+use crate::glyphs::GlyphSet;
+use crate::segment::os::{Os, Release};
+use crate::segment::ToSegment;
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+const POP_OS: &str = r#"NAME="Pop!_OS"
+VERSION="22.04 LTS"
+ID=pop
+ID_LIKE="ubuntu debian"
+PRETTY_NAME="Pop!_OS 22.04 LTS"
+VERSION_ID="22.04"
+"#;
+
+#[test]
+fn reads_os_release() {
+    let release = Release::from_os_release(POP_OS);
+    assert_eq!("pop", release.id);
+    assert_eq!(vec!["ubuntu", "debian", "linux"], release.id_like);
+    assert_eq!("Pop!_OS", release.name);
+    assert_eq!(Some("22.04".to_string()), release.version);
+
+    let release = Release::from_os_release("NAME='Arch Linux'\nID=arch\nBUILD_ID=rolling\n");
+    assert_eq!("arch", release.id);
+    assert_eq!("Arch Linux", release.name);
+    assert_eq!(None, release.version);
+
+    let release = Release::from_os_release(r#"NAME="Say \"hi\" \\ \$HOME""#);
+    assert_eq!(r#"Say "hi" \ $HOME"#, release.name);
+    assert_eq!("linux", release.id);
+    assert!(release.id_like.is_empty());
+}
+
+#[test]
+fn detects_the_os() {
+    let dir = tempfile::tempdir().unwrap();
+    let os_release = dir.path().join("os-release");
+    std::fs::write(&os_release, "NAME=Fedora\nID=fedora\n").unwrap();
+    let paths = [Path::new("/does/not/exist"), &os_release];
+
+    assert_eq!("fedora", Os::detect(Some("Linux"), &paths).id);
+    assert_eq!("linux", Os::detect(Some("Linux"), &paths[..1]).id);
+
+    let macos = Os::detect(Some("Darwin"), &paths);
+    assert_eq!(("macos", "macOS"), (macos.id.as_str(), macos.name.as_str()));
+
+    let freebsd = Os::detect(Some("FreeBSD"), &paths);
+    assert_eq!(
+        ("freebsd", "FreeBSD"),
+        (freebsd.id.as_str(), freebsd.name.as_str())
+    );
+}
+
+#[test]
+fn symbol_falls_back() {
+    let theme = Theme::default().os;
+
+    let pop = Release::from_os_release(POP_OS);
+    assert_eq!(theme.symbols["ubuntu"], theme.symbol_for(&pop));
+
+    let unknown = Release::from_os_release("ID=slackware\n");
+    assert_eq!(theme.symbols["linux"], theme.symbol_for(&unknown));
+
+    let haiku = Os::detect::<&str>(Some("Haiku"), &[]);
+    assert_eq!(theme.symbol, theme.symbol_for(&haiku));
+}
+
+#[test]
+fn configured_symbols_are_merged() {
+    let theme: crate::segment::os::Theme =
+        serde_json::from_str(r#"{"symbols": {"arch": "btw", "haiku": "H"}}"#).unwrap();
+
+    assert_eq!("btw", theme.symbols["arch"]);
+    assert_eq!("H", theme.symbols["haiku"]);
+    assert_eq!(
+        Theme::default().os.symbols["debian"],
+        theme.symbols["debian"]
+    );
+}
+
+#[test]
+fn symbol_follows_the_glyph_set() {
+    let debian = Release::from_os_release("ID=debian\n");
+
+    let mut theme = Theme::default();
+    theme.os.symbols.insert("arch".into(), "btw".into());
+    theme.apply_glyph_set(GlyphSet::Emoji);
+    assert_eq!("\u{1f427}", theme.os.symbol_for(&debian));
+    assert_eq!("btw", theme.os.symbols["arch"]);
+
+    let mut theme = Theme::default();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert_eq!("", theme.os.symbol_for(&debian));
+}
+
+segment_test! {
+    fn shows_something() {
+        |args, state : ApplicationState| {
+            let seg = Os::to_segment_generic(args, &state).unwrap();
+            let metadata = seg[0].metadata.as_ref().unwrap();
+            assert!(!metadata["id"].as_str().unwrap().is_empty());
+            assert!(!seg[0].text.is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn ascii_shows_the_name() {
+        let args = r#"{"show_version": true}"#;
+
+        |args, state : ApplicationState| {
+            let mut theme = state.theme.clone();
+            theme.apply_glyph_set(GlyphSet::Ascii);
+            let state = ApplicationState { theme: &theme, ..state };
+
+            let seg = Os::to_segment_generic(args, &state).unwrap();
+            let metadata = seg[0].metadata.as_ref().unwrap();
+            assert!(seg[0].text.starts_with(metadata["name"].as_str().unwrap()));
+        }
+    }
+}
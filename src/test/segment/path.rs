@@ -1,6 +1,8 @@
+use crate::ansi::{Color, TextStyle};
 use crate::segment::{path::Path, ToSegment};
 use crate::test::segment::declare_segement_test;
 use crate::test::AppEnv;
+use crate::Separator;
 use crate::{ApplicationState, Theme};
 
 declare_segement_test!([
@@ -70,3 +72,88 @@ segment_test! {
         }
     }
 }
+
+segment_test! {
+    fn dir_trim() {
+        let args = r#"{"dir_trim": 2}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("PWD"), String::from("/usr/local/share/doc"));
+            let seg = Path::to_segment_generic(args, &state).unwrap();
+            let texts: Vec<_> = seg.iter().map(|seg| seg.text.as_str()).collect();
+            assert_eq!(vec!["...", "share", "doc"], texts);
+            assert_eq!("Path::Trimmed", seg[0].source);
+        }
+    }
+}
+
+segment_test! {
+    fn dir_trim_keeps_home() {
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("PROMPT_DIRTRIM"), String::from("1"));
+            state.env.insert(String::from("PWD"), String::from("/home/username/a/b"));
+            let seg = Path::to_segment_generic(args, &state).unwrap();
+            let texts: Vec<_> = seg.iter().map(|seg| seg.text.as_str()).collect();
+            assert_eq!(vec!["~", "...", "b"], texts);
+        }
+    }
+}
+
+segment_test! {
+    fn dir_trim_short_paths_and_zero() {
+        let args = r#"{"dir_trim": 0}"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("PROMPT_DIRTRIM"), String::from("1"));
+            let seg = Path::to_segment_generic(args, &state).unwrap();
+            assert_eq!(2, seg.len());
+            assert_eq!("tmp", seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn gradient() {
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("PWD"), String::from("/home/username/src/promptr"));
+
+            let mut theme = Theme::default();
+            theme.path.gradient_start_bg = Some(Color::Rgb { r: 0, g: 100, b: 200 });
+            theme.path.gradient_end_bg = Some(Color::Rgb { r: 100, g: 100, b: 0 });
+            let state = ApplicationState {
+                theme: &theme,
+                ..state
+            };
+
+            let seg = Path::to_segment_generic(args, &state).unwrap();
+            assert_eq!(
+                vec![
+                    Color::Rgb { r: 0, g: 100, b: 200 },
+                    Color::Rgb { r: 50, g: 100, b: 100 },
+                    Color::Rgb { r: 100, g: 100, b: 0 },
+                ],
+                seg.iter().map(|seg| seg.bg).collect::<Vec<_>>()
+            );
+            assert!(seg.iter().all(|seg| seg.separator == Separator::Thick));
+        }
+    }
+}
+
+segment_test! {
+    fn styled() {
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("PWD"), String::from("/home/username/src"));
+
+            let mut theme = Theme::default();
+            theme.path.style = vec![TextStyle::Bold];
+            let state = ApplicationState {
+                theme: &theme,
+                ..state
+            };
+
+            let seg = Path::to_segment_generic(args, &state).unwrap();
+            assert_eq!(2, seg.len());
+            assert!(seg.iter().all(|seg| seg.style == vec![TextStyle::Bold]));
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::fs;
+
+use serde_json::json;
+
+use crate::segment::{rbenv::Rbenv, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("HOME", "/nonexistent"),]);
+
+segment_test! {
+    fn no_ruby() {
+        |args, state : ApplicationState| {
+            assert!(Rbenv::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn env_wins() {
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join(".ruby-version"), "3.1.4\n").unwrap();
+
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+            state.env.insert(String::from("RBENV_VERSION"), String::from("jruby-9.4.0.0"));
+
+            let seg = Rbenv::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} jruby-9.4.0.0", state.theme.rbenv.symbol), seg[0].text);
+            assert_eq!(Some(json!("env")), seg[0].metadata.as_ref().map(|m| m["origin"].clone()));
+        }
+    }
+}
+
+segment_test! {
+    fn version_file_in_an_ancestor() {
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            let nested = dir.path().join("lib/deep");
+            fs::create_dir_all(&nested).unwrap();
+            fs::write(dir.path().join(".ruby-version"), "ruby-3.2.2\n").unwrap();
+
+            state.env.insert(String::from("PWD"), nested.display().to_string());
+
+            let seg = Rbenv::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} 3.2.2", state.theme.rbenv.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn global_only_when_asked() {
+        let args = r#"{"show_global": true}"#;
+
+        |args, mut state : ApplicationState| {
+            let root = tempfile::tempdir().unwrap();
+            fs::write(root.path().join("version"), "3.3.0\n").unwrap();
+
+            state.env.insert(String::from("RBENV_ROOT"), root.path().display().to_string());
+            assert!(Rbenv::to_segment_generic(None, &state).unwrap().is_empty());
+
+            let seg = Rbenv::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} 3.3.0", state.theme.rbenv.symbol), seg[0].text);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::json;
+
+use crate::ansi::TextStyle;
+use crate::segment::{sapling::Sapling, sapling::Status, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("HOME", "/nonexistent"),]);
+
+/// Makes a `.sl` directory in `root` whose working copy is on `node`
+fn repo(root: &Path, node: u8, bookmark: Option<&str>) {
+    let dot_sl = root.join(".sl");
+    fs::create_dir_all(&dot_sl).unwrap();
+
+    let mut dirstate = vec![node; 20];
+    dirstate.extend([0; 20]);
+    dirstate.extend(b"\ntreestate\n\0");
+    fs::write(dot_sl.join("dirstate"), dirstate).unwrap();
+
+    if let Some(bookmark) = bookmark {
+        fs::write(dot_sl.join("bookmarks.current"), bookmark).unwrap();
+    }
+}
+
+segment_test! {
+    fn not_a_repo() {
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+
+            assert!(Sapling::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn active_bookmark() {
+        let args = r#"{"show_status": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            repo(dir.path(), 0xab, Some("main"));
+            let nested = dir.path().join("src/deep");
+            fs::create_dir_all(&nested).unwrap();
+            state.env.insert(String::from("PWD"), nested.display().to_string());
+
+            let seg = Sapling::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} main", state.theme.vcs.symbols.sapling), seg[0].text);
+            assert_eq!(state.theme.vcs.repo_clean_bg, seg[0].bg);
+            assert_eq!(
+                Some(json!("ab".repeat(20))),
+                seg[0].metadata.as_ref().map(|m| m["commit"].clone())
+            );
+        }
+    }
+}
+
+segment_test! {
+    fn bookmark_is_escaped() {
+        let args = r#"{"show_status": false, "show_vcs_badge": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            repo(dir.path(), 0xab, Some("$(id)"));
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+
+            let seg = Sapling::to_segment_generic(args, &state).unwrap();
+            assert_eq!(r"\\$(id)", seg[0].text);
+            assert_eq!(
+                Some(json!("$(id)")),
+                seg[0].metadata.as_ref().map(|m| m["bookmark"].clone())
+            );
+        }
+    }
+}
+
+segment_test! {
+    fn commit_without_bookmark() {
+        let args = r#"{"show_status": false, "show_vcs_badge": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            repo(dir.path(), 0x1f, None);
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+
+            let seg = Sapling::to_segment_generic(Clone::clone(&args), &state).unwrap();
+            assert_eq!("1f1f1f1f1f1f", seg[0].text);
+            assert!(seg[0].style.is_empty());
+
+            let mut theme = state.theme.clone();
+            theme.vcs.repo_clean_style = vec![TextStyle::Italic];
+            theme.vcs.repo_dirty_style = vec![TextStyle::Bold];
+            let state = ApplicationState {
+                theme: &theme,
+                ..state
+            };
+
+            let seg = Sapling::to_segment_generic(args, &state).unwrap();
+            assert_eq!(vec![TextStyle::Italic], seg[0].style);
+        }
+    }
+}
+
+segment_test! {
+    fn empty_repo() {
+        let args = r#"{"show_status": false, "show_vcs_badge": false}"#;
+
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            repo(dir.path(), 0, None);
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+
+            let seg = Sapling::to_segment_generic(args, &state).unwrap();
+            assert_eq!("(empty)", seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn missing_command_is_clean() {
+        let args = r#"{"command": "/nonexistent/sl"}"#;
+
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            repo(dir.path(), 0xab, Some("main"));
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+
+            let seg = Sapling::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.vcs.repo_clean_bg, seg[0].bg);
+            assert_eq!(Some(json!(null)), seg[0].metadata.as_ref().map(|m| m["changed"].clone()));
+        }
+    }
+}
+
+#[test]
+fn status_output() {
+    let status = Sapling::parse_status("M src/lib.rs\nA new.rs\nR old.rs\n! gone.rs\n? notes\n");
+    assert_eq!(
+        Status {
+            changed: 4,
+            untracked: 1
+        },
+        status
+    );
+    assert!(status.dirty());
+
+    assert!(!Sapling::parse_status("").dirty());
+}
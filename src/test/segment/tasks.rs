@@ -0,0 +1,55 @@
+use crate::segment::{tasks::Tasks, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+/// Points the cache somewhere disposable
+fn with_cache(state: &mut ApplicationState) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    state.env.insert(
+        String::from("PROMPTR_CACHE_DIR"),
+        dir.path().to_string_lossy().into(),
+    );
+    dir
+}
+
+segment_test! {
+    fn command_count() {
+        let args = r#"{"source": "command", "command": ["echo", "3"]}"#;
+
+        |args, mut state : ApplicationState| {
+            let _dir = with_cache(&mut state);
+
+            let seg = Tasks::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!(format!("{} 3", state.theme.tasks.symbol), seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn command_zero() {
+        let args = r#"{"source": "command", "command": ["echo", "0"]}"#;
+
+        |args, mut state : ApplicationState| {
+            let _dir = with_cache(&mut state);
+
+            let seg = Tasks::to_segment_generic(args, &state).unwrap();
+            assert_eq!(0, seg.len());
+        }
+    }
+}
+
+segment_test! {
+    fn command_not_a_number() {
+        let args = r#"{"source": "command", "command": ["echo", "lots"]}"#;
+
+        |args, mut state : ApplicationState| {
+            let _dir = with_cache(&mut state);
+
+            assert!(Tasks::to_segment_generic(args, &state).is_err());
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::segment::{
+    terminal::{Emulator, Terminal},
+    ToSegment,
+};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+segment_test! {
+    fn known_term_programs() {
+        |args: Option<serde_json::Value>, mut state : ApplicationState| {
+            for (term_program, name) in [
+                ("Apple_Terminal", "Terminal.app"),
+                ("iTerm.app", "iTerm2"),
+                ("WezTerm", "wezterm"),
+            ] {
+                state.env.insert(String::from("TERM_PROGRAM"), String::from(term_program));
+
+                let seg = Terminal::to_segment_generic(args.clone(), &state).unwrap();
+                assert_eq!(1, seg.len());
+                assert_eq!(format!("{} {}", state.theme.terminal.symbol, name), seg[0].text);
+            }
+        }
+    }
+}
+
+segment_test! {
+    fn name_only() {
+        let args = r#"{ "show_icon": false }"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("TERM_PROGRAM"), String::from("WezTerm"));
+
+            let seg = Terminal::to_segment_generic(args, &state).unwrap();
+            assert_eq!("wezterm", seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn nothing_to_show() {
+        let args = r#"{ "show_icon": false, "show_name": false }"#;
+
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("TERM_PROGRAM"), String::from("WezTerm"));
+
+            assert!(Terminal::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn falls_back_to_the_process_tree() {
+        |args, mut state : ApplicationState| {
+            // Multiplexers set TERM_PROGRAM too, that shouldn't stop the search
+            state.env.insert(String::from("TERM_PROGRAM"), String::from("tmux"));
+
+            assert_eq!(None, Emulator::from_term_program(&state.env));
+            assert_eq!(Emulator::find(), Emulator::detect(&state.env));
+
+            let seg = Terminal::to_segment_generic(args, &state).unwrap();
+            assert_eq!(Emulator::find().is_some(), !seg.is_empty());
+        }
+    }
+}
+
+#[test]
+fn process_names() {
+    assert_eq!(Some(Emulator::Kitty), Emulator::from_process_name("kitty"));
+    assert_eq!(
+        Some(Emulator::Foot),
+        Emulator::from_process_name("footclient")
+    );
+    assert_eq!(None, Emulator::from_process_name("bash"));
+}
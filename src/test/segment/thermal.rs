@@ -0,0 +1,39 @@
+use crate::segment::{thermal::Thermal, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+#[test]
+fn picks_a_zone() {
+    let zones = vec![
+        ("acpitz".to_string(), 45.0),
+        ("x86_pkg_temp".to_string(), 61.5),
+    ];
+
+    assert_eq!(Some(&zones[1]), Thermal::pick(&zones, None));
+    assert_eq!(Some(&zones[0]), Thermal::pick(&zones, Some("acpitz")));
+    assert_eq!(None, Thermal::pick(&zones, Some("iwlwifi_1")));
+    assert_eq!(None, Thermal::pick(&[], None));
+}
+
+segment_test! {
+    fn unknown_zone_is_hidden() {
+        let args = r#"{"zone": "no such sensor"}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Thermal::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn hidden_below_show_above() {
+        let args = r#"{"thresholds": {"show_above": 1e9}}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Thermal::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
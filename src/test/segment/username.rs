@@ -1,3 +1,4 @@
+use crate::ansi::TextStyle;
 use crate::segment::{username::Username, ToSegment};
 use crate::test::segment::declare_segement_test;
 use crate::test::AppEnv;
@@ -29,3 +30,23 @@ segment_test! {
         }
     }
 }
+
+segment_test! {
+    fn styled() {
+        |args, state : ApplicationState| {
+            let seg = Username::to_segment_generic(Clone::clone(&args), &state).unwrap();
+            assert!(seg[0].style.is_empty());
+
+            let theme: Theme =
+                serde_json::from_str(r#"{ "username": { "style": ["italic", "underline"] } }"#)
+                    .unwrap();
+            let state = ApplicationState {
+                theme: &theme,
+                ..state
+            };
+
+            let seg = Username::to_segment_generic(args, &state).unwrap();
+            assert_eq!(vec![TextStyle::Italic, TextStyle::Underline], seg[0].style);
+        }
+    }
+}
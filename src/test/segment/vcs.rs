@@ -0,0 +1,151 @@
+use std::fs;
+
+use crate::ansi::Color;
+use crate::segment::vcs::{Backend, Vcs};
+use crate::segment::ToSegment;
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("HOME", "/nonexistent"),]);
+
+fn env(pwd: &std::path::Path) -> AppEnv {
+    AppEnv::from([(String::from("PWD"), pwd.display().to_string())])
+}
+
+#[test]
+fn nearest_marker_wins() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("vendor/lib");
+    fs::create_dir_all(nested.join("src")).unwrap();
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    fs::create_dir(nested.join(".hg")).unwrap();
+
+    assert_eq!(
+        Some(Backend::Mercurial),
+        Vcs::detect(&env(&nested.join("src")))
+    );
+    assert_eq!(
+        Some(Backend::Git),
+        Vcs::detect(&env(&dir.path().join("vendor")))
+    );
+}
+
+#[test]
+fn colocated_repositories() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    fs::create_dir(dir.path().join(".jj")).unwrap();
+
+    assert_eq!(Some(Backend::Jujutsu), Vcs::detect(&env(dir.path())));
+}
+
+#[test]
+fn fossil_checkout_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join(".fslckout"), "").unwrap();
+
+    assert_eq!(Some(Backend::Fossil), Vcs::detect(&env(dir.path())));
+}
+
+#[test]
+fn git_dir_means_git() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut env = env(dir.path());
+    assert_eq!(None, Vcs::detect(&env));
+
+    env.insert(String::from("GIT_DIR"), String::from("/nonexistent"));
+    assert_eq!(Some(Backend::Git), Vcs::detect(&env));
+}
+
+segment_test! {
+    fn sapling_args_are_passed_along() {
+        let args = r#"{"sapling": {"show_status": false, "show_vcs_badge": false}}"#;
+
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            fs::create_dir(dir.path().join(".sl")).unwrap();
+            fs::write(dir.path().join(".sl/bookmarks.current"), "main").unwrap();
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+
+            let seg = Vcs::to_segment_generic(args, &state).unwrap();
+            assert_eq!(1, seg.len());
+            assert_eq!("Sapling", seg[0].source);
+            assert_eq!("main", seg[0].text);
+        }
+    }
+}
+
+segment_test! {
+    fn no_segment_for_backend() {
+        |args, mut state : ApplicationState| {
+            let dir = tempfile::tempdir().unwrap();
+            fs::create_dir(dir.path().join(".svn")).unwrap();
+            state.env.insert(String::from("PWD"), dir.path().display().to_string());
+
+            assert!(Vcs::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn unknown_args_are_refused() {
+        let args = r#"{"svn": {}}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Vcs::to_segment_generic(args, &state).is_err());
+        }
+    }
+}
+
+#[test]
+fn legacy_git_colors() {
+    let theme: Theme = serde_json::from_value(serde_json::json!({
+        "vcs": {
+            "git_ahead_fg": 1,
+            "git_behind_bg": 2,
+            "commit_age": { "warn_bg": 3 },
+            "repo_clean_bg": 4,
+            "git": { "behind_bg": 5 }
+        }
+    }))
+    .unwrap();
+
+    assert_eq!(Color::Numbered(1), theme.vcs.git.ahead_fg);
+    // The new place wins
+    assert_eq!(Color::Numbered(5), theme.vcs.git.behind_bg);
+    assert_eq!(Color::Numbered(3), theme.vcs.git.commit_age.warn_bg);
+    assert_eq!(Color::Numbered(4), theme.vcs.repo_clean_bg);
+
+    // Written back out in the new layout
+    let json = serde_json::to_value(&theme).unwrap();
+    assert_eq!(serde_json::json!(1), json["vcs"]["git"]["ahead_fg"]);
+    assert_eq!(None, json["vcs"].get("git_ahead_fg"));
+    assert_eq!(theme, serde_json::from_value(json).unwrap());
+
+    assert!(
+        serde_json::from_value::<Theme>(serde_json::json!({ "vcs": { "git_nope": 1 } })).is_err()
+    );
+}
+
+#[test]
+fn backend_colors() {
+    let mut theme = Theme::default();
+    theme.vcs.sapling.dirty_bg = Some(Color::Numbered(1));
+    theme.vcs.git.clean_fg = Some(Color::Numbered(2));
+
+    let vcs = &theme.vcs;
+    assert_eq!(
+        (vcs.repo_dirty_fg, Color::Numbered(1)),
+        vcs.backend_colors(&vcs.sapling, true)
+    );
+    assert_eq!(
+        (vcs.repo_clean_fg, vcs.repo_clean_bg),
+        vcs.backend_colors(&vcs.sapling, false)
+    );
+    assert_eq!(
+        (Color::Numbered(2), vcs.repo_clean_bg),
+        vcs.git_colors(false)
+    );
+    assert_eq!((vcs.repo_dirty_fg, vcs.repo_dirty_bg), vcs.git_colors(true));
+}
@@ -0,0 +1,42 @@
+use crate::glyphs::GlyphSet;
+use crate::segment::{wifi::Args, wifi::Wifi, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([]);
+
+segment_test! {
+    fn hidden_without_a_network() {
+        let args = r#"{"interface": "promptr0"}"#;
+
+        |args, state : ApplicationState| {
+            assert!(Wifi::to_segment_generic(args, &state).unwrap().is_empty());
+        }
+    }
+}
+
+segment_test! {
+    fn ssid_is_escaped() {
+        |_args: Option<serde_json::Value>, state : ApplicationState| {
+            let seg = Wifi::segment(&Args::default(), "$(touch x) `id`", &state);
+            assert_eq!(
+                format!(r"{} \\$(touch x) \\`id\\`", state.theme.wifi.symbol),
+                seg.text
+            );
+            assert_eq!("$(touch x) `id`", seg.metadata.as_ref().unwrap()["ssid"]);
+        }
+    }
+}
+
+#[test]
+fn symbol_follows_the_glyph_set() {
+    let mut theme = Theme::default();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert_eq!("wifi", theme.wifi.symbol);
+
+    let mut theme = Theme::default();
+    theme.wifi.symbol = "net".into();
+    theme.apply_glyph_set(GlyphSet::Emoji);
+    assert_eq!("net", theme.wifi.symbol);
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::glyphs::GlyphSet;
+use crate::segment::{wsl::Wsl, ToSegment};
+use crate::test::segment::declare_segement_test;
+use crate::test::AppEnv;
+use crate::{ApplicationState, Theme};
+
+declare_segement_test!([("WSL_DISTRO_NAME", "Ubuntu-22.04"),]);
+
+#[test]
+fn detected_from_the_kernel() {
+    let dir = tempfile::tempdir().unwrap();
+    let version = dir.path().join("version");
+    let env = HashMap::new();
+
+    assert_eq!(None, Wsl::detect(&env, Path::new("/does/not/exist")));
+
+    std::fs::write(
+        &version,
+        "Linux version 6.1.0-13-amd64 (debian-kernel@lists.debian.org)\n",
+    )
+    .unwrap();
+    assert_eq!(None, Wsl::detect(&env, &version));
+
+    std::fs::write(
+        &version,
+        "Linux version 5.15.90.1-microsoft-standard-WSL2\n",
+    )
+    .unwrap();
+    assert_eq!(Some(String::new()), Wsl::detect(&env, &version));
+}
+
+segment_test! {
+    fn shows_the_distro() {
+        |args, state : ApplicationState| {
+            let seg = Wsl::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!("{} Ubuntu-22.04", state.theme.wsl.symbol), seg[0].text);
+            assert_eq!("Ubuntu-22.04", seg[0].metadata.as_ref().unwrap()["distro"]);
+        }
+    }
+}
+
+segment_test! {
+    fn distro_is_escaped() {
+        |args, mut state : ApplicationState| {
+            state.env.insert(String::from("WSL_DISTRO_NAME"), String::from("$(id)"));
+
+            let seg = Wsl::to_segment_generic(args, &state).unwrap();
+            assert_eq!(format!(r"{} \\$(id)", state.theme.wsl.symbol), seg[0].text);
+            assert_eq!("$(id)", seg[0].metadata.as_ref().unwrap()["distro"]);
+        }
+    }
+}
+
+segment_test! {
+    fn symbol_only() {
+        let args = r#"{"show_distro": false}"#;
+
+        |args, state : ApplicationState| {
+            let seg = Wsl::to_segment_generic(args, &state).unwrap();
+            assert_eq!(state.theme.wsl.symbol, seg[0].text);
+        }
+    }
+}
+
+#[test]
+fn symbol_follows_the_glyph_set() {
+    let mut theme = Theme::default();
+    theme.apply_glyph_set(GlyphSet::Ascii);
+    assert_eq!("WSL", theme.wsl.symbol);
+
+    let mut theme = Theme::default();
+    theme.wsl.symbol = "win".into();
+    theme.apply_glyph_set(GlyphSet::Emoji);
+    assert_eq!("win", theme.wsl.symbol);
+}
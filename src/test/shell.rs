@@ -0,0 +1,173 @@
+use std::process::Command;
+
+use crate::ansi::EscapeStyle;
+use crate::shell::{
+    active_marker, install_hook, last_exit_code, nested_render, remove_hook, Shell, ACTIVE_VAR,
+    HOOK_MARKER, LAST_EXIT_VAR,
+};
+use crate::test::AppEnv;
+
+fn env(pid: impl ToString) -> AppEnv {
+    AppEnv::from([(ACTIVE_VAR.to_string(), pid.to_string())])
+}
+
+#[test]
+fn not_nested() {
+    assert!(!nested_render(&AppEnv::new()));
+    assert!(!nested_render(&env("garbage")));
+    assert!(!nested_render(&env(std::process::id())));
+}
+
+#[test]
+fn nested_while_the_render_is_running() {
+    assert!(nested_render(&env(std::os::unix::process::parent_id())));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn start_time_has_to_match() {
+    let parent = std::os::unix::process::parent_id() as i32;
+    let start = crate::platform::process_start_time(parent).unwrap();
+
+    assert!(nested_render(&env(format!("{}:{}", parent, start))));
+
+    // Same pid, different process
+    assert!(!nested_render(&env(format!("{}:{}", parent, start + 1))));
+    assert!(!nested_render(&env(format!("{}:garbage", parent))));
+}
+
+#[test]
+fn own_marker_is_not_nested() {
+    assert!(!nested_render(&env(active_marker())));
+}
+
+#[test]
+fn stale_guard_is_ignored() {
+    let mut child = Command::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+
+    assert!(!nested_render(&env(pid)));
+}
+
+#[test]
+fn last_exit_code_prefers_the_saved_status() {
+    let mut env = AppEnv::from([(String::from("code"), String::from("0"))]);
+    assert_eq!(Some("0"), last_exit_code(&env));
+
+    // Set but empty when the loader didn't get to save it
+    env.insert(LAST_EXIT_VAR.into(), String::new());
+    assert_eq!(Some("0"), last_exit_code(&env));
+
+    env.insert(LAST_EXIT_VAR.into(), String::from("130"));
+    assert_eq!(Some("130"), last_exit_code(&env));
+}
+
+#[test]
+fn hook_is_installed_once() {
+    let line = Shell::Bash.hook_line();
+    let rc = "alias ll='ls -l'";
+
+    let installed = install_hook(rc, line).unwrap();
+    assert_eq!(
+        format!("alias ll='ls -l'\n\n{}\n{}\n", HOOK_MARKER, line),
+        installed
+    );
+
+    assert_eq!(None, install_hook(&installed, line));
+    assert_eq!(
+        format!("{}\n{}\n", HOOK_MARKER, line),
+        install_hook("", line).unwrap()
+    );
+}
+
+#[test]
+fn hook_is_removed() {
+    let rc = "alias ll='ls -l'\n";
+    let installed = install_hook(rc, Shell::Bash.hook_line()).unwrap();
+    let with_more = format!("{}export EDITOR=vi\n", installed);
+
+    assert_eq!(rc, remove_hook(&installed).unwrap());
+    assert_eq!(
+        "alias ll='ls -l'\nexport EDITOR=vi\n",
+        remove_hook(&with_more).unwrap()
+    );
+    assert_eq!(None, remove_hook(rc));
+}
+
+/// A path that needs quoting in every shell
+const SELF_EXE: &str = "/opt/it's here/promptr";
+
+#[test]
+fn bash_loader() {
+    let loader = Shell::Bash.loader("/usr/local/bin/promptr");
+
+    // The builtin is only used if it could be loaded, otherwise it's the binary as always
+    assert!(loader.contains("enable -f \"${PROMPTR_BUILTIN}\" promptr_render"));
+    assert!(loader.contains(" promptr_render -v PS1\n"));
+    assert!(loader.contains(" /usr/local/bin/promptr prompt)\"\n"));
+
+    let status = Command::new("bash").args(["-n", "-c", &loader]).status();
+    if let Ok(status) = status {
+        assert!(status.success());
+    }
+}
+
+#[test]
+fn powershell_loader() {
+    assert_eq!(
+        include_str!("snapshots/loader.ps1"),
+        Shell::PowerShell.loader(SELF_EXE)
+    );
+}
+
+#[test]
+fn powershell_escapes() {
+    // The prompt function's return value is printed as is, only bash's `\$` needs translating
+    assert_eq!("$ ", Shell::PowerShell.escape_text("\\$ "));
+    assert_eq!(
+        "$(whoami) `n %",
+        Shell::PowerShell.escape_text("$(whoami) `n %")
+    );
+}
+
+#[test]
+fn tcsh_loader() {
+    // eval flattens newlines so the whole thing has to be one line
+    let loader = Shell::Tcsh.loader("/usr/local/bin/promptr");
+
+    assert_eq!(include_str!("snapshots/loader.tcsh"), loader);
+    assert_eq!(1, loader.lines().count());
+}
+
+#[test]
+fn tcsh_escapes() {
+    assert_eq!(EscapeStyle::Zsh, Shell::Tcsh.escape_style());
+
+    // % starts a prompt sequence and ! is the history event number
+    assert_eq!("100%% \\!$ ", Shell::Tcsh.escape_text("100% !\\$ "));
+    assert_eq!("plain", Shell::Tcsh.escape_text("plain"));
+}
+
+#[test]
+fn posix_loader() {
+    let loader = Shell::PosixSh.loader(SELF_EXE);
+
+    assert_eq!(include_str!("snapshots/loader.sh"), loader);
+
+    // No bashisms allowed, dash is as strict as it gets
+    let status = Command::new("dash").args(["-n", "-c", &loader]).status();
+    if let Ok(status) = status {
+        assert!(status.success());
+    }
+}
+
+#[test]
+fn posix_escapes() {
+    // The output of a command substitution in PS1 isn't expanded again
+    assert_eq!("$ ", Shell::PosixSh.escape_text("\\$ "));
+    assert_eq!(
+        "$(id) `id` % !",
+        Shell::PosixSh.escape_text("$(id) `id` % !")
+    );
+}
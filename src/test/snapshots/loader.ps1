@@ -0,0 +1,26 @@
+[Console]::OutputEncoding = [System.Text.Encoding]::UTF8
+
+$promptr_conf_file = Join-Path (& '/opt/it''s here/promptr' location) "promptr.json"
+if (-not (Test-Path $promptr_conf_file)) {
+    Write-Host "Couldn't find an existing configuration file, using the defaults"
+}
+Remove-Variable promptr_conf_file
+
+function global:prompt {
+    # This has to come first, anything else will clobber it
+    $promptr_success = $?
+    $promptr_exit = $global:LASTEXITCODE
+    $env:code = if ($promptr_success) { 0 } elseif ($promptr_exit) { $promptr_exit } else { 1 }
+
+    $env:PROMPTR_SHELL = "pwsh"
+    $env:hostname = [System.Net.Dns]::GetHostName()
+    $env:PWD = $ExecutionContext.SessionState.Path.CurrentFileSystemLocation.ProviderPath
+    if (-not $env:HOME) { $env:HOME = $HOME }
+    if (-not $env:USER) { $env:USER = [Environment]::UserName }
+
+    $promptr_out = (& '/opt/it''s here/promptr' prompt) -join ""
+
+    Remove-Item Env:code, Env:hostname -ErrorAction SilentlyContinue
+    $global:LASTEXITCODE = $promptr_exit
+    $promptr_out
+}
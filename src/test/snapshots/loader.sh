@@ -0,0 +1,9 @@
+case $- in
+    *i*)
+        if [ ! -f "$('/opt/it'\''s here/promptr' location)/promptr.json" ]; then
+            echo "Couldn't find an existing configuration file, using the defaults"
+        fi
+
+        PS1='$(code=$? uid=$(id -u) hostname=$(uname -n) PWD="${PWD}" PROMPTR_SHELL=sh '"'/opt/it'\''s here/promptr'"' prompt)'
+        ;;
+esac
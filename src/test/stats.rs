@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::stats::{self, Log, Sample, Summary, TOTAL};
+
+fn sample(total_us: u64, path_us: u64) -> Sample {
+    let mut sample = Sample {
+        total_us,
+        ..Default::default()
+    };
+    sample.add("path", Duration::from_micros(path_us));
+    sample
+}
+
+#[test]
+fn percentiles() {
+    let values = (1..=20).collect::<Vec<u64>>();
+
+    assert_eq!(10, stats::percentile(&values, 50.0));
+    assert_eq!(19, stats::percentile(&values, 95.0));
+    assert_eq!(1, stats::percentile(&values, 0.0));
+    assert_eq!(0, stats::percentile(&[], 50.0));
+}
+
+#[test]
+fn repeated_segments_add_up() {
+    let mut sample = sample(100, 10);
+    sample.add("path", Duration::from_micros(5));
+
+    assert_eq!(Some(&15), sample.segments.get("path"));
+}
+
+#[test]
+fn history_is_trimmed() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = Log::new(dir.path().join("latency.jsonl"));
+
+    assert!(log.read().is_empty());
+
+    let totals = |samples: Vec<Sample>| samples.iter().map(|s| s.total_us).collect::<Vec<_>>();
+
+    // Appended until there are more than twice as many as should be kept
+    for idx in 1..=6 {
+        log.record(&sample(idx * 100, idx), 3).unwrap();
+    }
+    assert_eq!(6, log.read().len());
+    assert_eq!(vec![400, 500, 600], totals(log.recent(3)));
+
+    log.record(&sample(700, 7), 3).unwrap();
+    assert_eq!(vec![500, 600, 700], totals(log.read()));
+}
+
+#[test]
+fn samples_are_appended() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("latency.jsonl");
+    let log = Log::new(&path);
+
+    // Something another prompt wrote in the meantime is kept
+    log.record(&sample(100, 1), 10).unwrap();
+    std::fs::write(
+        &path,
+        format!(
+            "{}{}\n",
+            std::fs::read_to_string(&path).unwrap(),
+            serde_json::to_string(&sample(200, 2)).unwrap()
+        ),
+    )
+    .unwrap();
+    log.record(&sample(300, 3), 10).unwrap();
+
+    assert_eq!(
+        vec![100, 200, 300],
+        log.read().iter().map(|s| s.total_us).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn prometheus_report() {
+    let samples = (1..=4)
+        .map(|idx| sample(idx * 1000, idx * 100))
+        .collect::<Vec<_>>();
+
+    let summaries = stats::summarize(&samples);
+    assert_eq!(
+        Some(&Summary {
+            count: 4,
+            p50: 2000,
+            p95: 4000
+        }),
+        summaries.get(TOTAL)
+    );
+
+    let report = stats::to_prometheus(&summaries);
+    assert!(report.contains("promptr_render_seconds{segment=\"path\",quantile=\"0.5\"} 0.000200\n"));
+    assert!(report.contains("promptr_render_seconds_count{segment=\"total\"} 4\n"));
+}
+
+#[test]
+fn prometheus_labels_are_escaped() {
+    let mut summaries = BTreeMap::new();
+    summaries.insert(
+        "say \"hi\"\\\n".to_string(),
+        Summary {
+            count: 1,
+            p50: 1,
+            p95: 1,
+        },
+    );
+
+    let report = stats::to_prometheus(&summaries);
+    assert!(report.contains("promptr_render_seconds_count{segment=\"say \\\"hi\\\"\\\\\\n\"} 1\n"));
+}
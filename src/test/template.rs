@@ -0,0 +1,56 @@
+use crate::ansi::Color;
+use crate::segment::Segment;
+use crate::template;
+use crate::test::AppEnv;
+use crate::Separator;
+
+fn env() -> AppEnv {
+    AppEnv::from([
+        (String::from("USER"), String::from("alice")),
+        (String::from("hostname"), String::from("box.example.com")),
+    ])
+}
+
+fn seg(source: &'static str, text: &str) -> Segment {
+    Segment::new(source, Color::Numbered(0), Color::Numbered(1), text)
+}
+
+fn texts(segments: &[Segment]) -> Vec<&str> {
+    segments.iter().map(|seg| seg.text.as_str()).collect()
+}
+
+#[test]
+fn user_and_host() {
+    let mut segments = vec![
+        seg("Username", "alice"),
+        seg("Hostname", "box"),
+        seg("Identity", "alice@box.example.com"),
+        seg("Username", "bob \u{2192} alice"),
+        seg("Git::Branch", "alice"),
+    ];
+
+    template::apply(&mut segments, &env());
+
+    assert_eq!(
+        vec![r"\u", r"\h", r"\u@\H", "bob \u{2192} \\u", "alice"],
+        texts(&segments)
+    );
+}
+
+#[test]
+fn path_is_folded() {
+    let mut segments = vec![
+        seg("Path::BashDirStack", "2"),
+        seg("Path::First::Home", "~").with_separator(Separator::Thin),
+        seg("Path::Middle::Normal", "src").with_separator(Separator::Thin),
+        seg("Path::Last::Normal", "promptr"),
+        seg("CommandStatus", r"\$"),
+    ];
+    segments[3].bg = Color::Numbered(31);
+
+    template::apply(&mut segments, &env());
+
+    assert_eq!(vec!["2", r"\w", r"\$"], texts(&segments));
+    assert_eq!(Color::Numbered(31), segments[1].bg);
+    assert_eq!(Separator::Thick, segments[1].separator);
+}